@@ -0,0 +1,83 @@
+use soroban_sdk::{contractevent, Address};
+
+/// New bond series registered by its issuer
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SeriesRegistered {
+    #[topic]
+    pub series_id: u32,
+    #[topic]
+    pub issuer: Address,
+    pub face_value: i128,
+    pub coupon_rate: u32,
+    pub maturity: u64,
+}
+
+/// Bonds of a series issued to an investor
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondsMinted {
+    #[topic]
+    pub series_id: u32,
+    #[topic]
+    pub to: Address,
+    pub amount: i128,
+    pub supply: i128,
+}
+
+/// Bonds of a series moved between holders
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondsTransferred {
+    #[topic]
+    pub series_id: u32,
+    #[topic]
+    pub from: Address,
+    #[topic]
+    pub to: Address,
+    pub amount: i128,
+}
+
+/// Coupon paid in by the issuer for holders on record
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CouponPaid {
+    #[topic]
+    pub series_id: u32,
+    pub coupon: u32,
+    pub record_ledger: u32,
+    pub amount: i128,
+}
+
+/// Coupons claimed by a holder
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CouponClaimed {
+    #[topic]
+    pub series_id: u32,
+    #[topic]
+    pub holder: Address,
+    pub amount: i128,
+}
+
+/// Principal deposited by the issuer into a series' redemption escrow
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RedemptionFunded {
+    #[topic]
+    pub series_id: u32,
+    pub amount: i128,
+    pub escrow: i128,
+}
+
+/// Matured bonds burned and their face value paid out of escrow
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondsRedeemed {
+    #[topic]
+    pub series_id: u32,
+    #[topic]
+    pub holder: Address,
+    pub amount: i128,
+    pub paid: i128,
+}
@@ -0,0 +1,616 @@
+#![no_std]
+
+mod events;
+mod snapshot;
+
+use events::{
+    BondsMinted, BondsRedeemed, BondsTransferred, CouponClaimed, CouponPaid, RedemptionFunded,
+    SeriesRegistered,
+};
+use snapshot::{latest, value_before, Checkpoint};
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, token, Address, Env, Vec};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    SeriesNotFound = 1,
+    InvalidParameter = 2,
+    InsufficientBalance = 3,
+    SeriesMatured = 4,
+    MathOverflow = 5,
+    CouponNotDue = 6,
+    NotMatured = 7,
+    EscrowUnderfunded = 8,
+    CouponsOutstanding = 9,
+}
+
+/// Terms and issuance state of a bond series
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondSeries {
+    pub issuer: Address,
+    pub payment_token: Address, // token coupons and principal are paid in
+    pub face_value: i128,       // principal per bond, in payment token units
+    pub coupon_rate: u32,       // 500 = 5% of face value per year
+    pub issue_date: u64,
+    pub maturity: u64,
+    pub coupon_interval: u64, // seconds between coupon dates
+    pub supply: i128,         // bonds outstanding
+    pub coupons_paid: u32,
+}
+
+/// A coupon paid in by the issuer, owed to holders as of `record_ledger`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Coupon {
+    pub record_ledger: u32,
+    pub amount: i128,
+    pub supply: i128, // bonds outstanding at the record ledger
+}
+
+#[contracttype]
+pub enum DataKey {
+    SeriesCount,
+    Series(u32),
+    BalanceHistory(u32, Address),
+    SupplyHistory(u32),
+    Coupons(u32),
+    CouponCursor(u32, Address), // index of the holder's next unsettled coupon
+    CouponCredit(u32, Address), // coupons settled but not yet claimed
+    RedemptionEscrow(u32),
+}
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const LIFETIME_THRESHOLD: u32 = BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+fn load_series(env: &Env, series_id: u32) -> Result<BondSeries, Error> {
+    let key = DataKey::Series(series_id);
+    let series = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .ok_or(Error::SeriesNotFound)?;
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+    Ok(series)
+}
+
+fn save_series(env: &Env, series_id: u32, series: &BondSeries) {
+    let key = DataKey::Series(series_id);
+    env.storage().persistent().set(&key, series);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+}
+
+fn load_history(env: &Env, key: &DataKey) -> Vec<Checkpoint> {
+    env.storage().persistent().get(key).unwrap_or(Vec::new(env))
+}
+
+/// Append a checkpoint to the history stored under `key`
+fn write_history(env: &Env, key: &DataKey, value: i128) {
+    let mut history = load_history(env, key);
+    snapshot::push(env, &mut history, value);
+    env.storage().persistent().set(key, &history);
+    env.storage()
+        .persistent()
+        .extend_ttl(key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+}
+
+/// Record a holder's new balance, first settling the coupons owed on the old one
+///
+/// Balance histories are bounded, so coupons are credited before a change can
+/// push out the checkpoints they were recorded against.
+fn write_balance(env: &Env, series_id: u32, holder: &Address, balance: i128) -> Result<(), Error> {
+    let owed = unsettled_coupons(env, series_id, holder)?;
+    let credit = coupon_credit(env, series_id, holder)
+        .checked_add(owed)
+        .ok_or(Error::MathOverflow)?;
+    set_coupon_credit(env, series_id, holder, credit);
+    env.storage().persistent().set(
+        &DataKey::CouponCursor(series_id, holder.clone()),
+        &load_coupons(env, series_id).len(),
+    );
+
+    write_history(
+        env,
+        &DataKey::BalanceHistory(series_id, holder.clone()),
+        balance,
+    );
+    Ok(())
+}
+
+fn coupon_credit(env: &Env, series_id: u32, holder: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::CouponCredit(series_id, holder.clone()))
+        .unwrap_or(0)
+}
+
+fn set_coupon_credit(env: &Env, series_id: u32, holder: &Address, credit: i128) {
+    let key = DataKey::CouponCredit(series_id, holder.clone());
+    if credit == 0 {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, &credit);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+    }
+}
+
+/// Share of the coupons paid since the holder's cursor, read from their balance history
+fn unsettled_coupons(env: &Env, series_id: u32, holder: &Address) -> Result<i128, Error> {
+    let history = load_history(env, &DataKey::BalanceHistory(series_id, holder.clone()));
+    let cursor: u32 = env
+        .storage()
+        .persistent()
+        .get(&DataKey::CouponCursor(series_id, holder.clone()))
+        .unwrap_or(0);
+
+    let mut owed = 0_i128;
+    for coupon in load_coupons(env, series_id).iter().skip(cursor as usize) {
+        let balance = value_before(&history, coupon.record_ledger);
+        if balance == 0 || coupon.supply == 0 {
+            continue;
+        }
+
+        let share = coupon
+            .amount
+            .checked_mul(balance)
+            .ok_or(Error::MathOverflow)?
+            / coupon.supply;
+        owed = owed.checked_add(share).ok_or(Error::MathOverflow)?;
+    }
+
+    Ok(owed)
+}
+
+fn redemption_escrow(env: &Env, series_id: u32) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::RedemptionEscrow(series_id))
+        .unwrap_or(0)
+}
+
+fn set_redemption_escrow(env: &Env, series_id: u32, escrow: i128) {
+    let key = DataKey::RedemptionEscrow(series_id);
+    env.storage().persistent().set(&key, &escrow);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+}
+
+fn load_coupons(env: &Env, series_id: u32) -> Vec<Coupon> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Coupons(series_id))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Date coupon number `coupon` (counting from 1) falls due
+fn coupon_date(series: &BondSeries, coupon: u32) -> u64 {
+    (series.issue_date + coupon as u64 * series.coupon_interval).min(series.maturity)
+}
+
+/// Coupons paid over the life of a series, the last one possibly a short period
+fn coupon_count(series: &BondSeries) -> u32 {
+    (series.maturity - series.issue_date).div_ceil(series.coupon_interval) as u32
+}
+
+/// Registry of bond series whose bonds are issued to and held by investors
+#[contract]
+pub struct BondRegistry;
+
+#[contractimpl]
+impl BondRegistry {
+    /// Register a new bond series, returning its id (issuer only)
+    ///
+    /// Coupons fall due every `coupon_interval` seconds from registration until
+    /// `maturity`, when the face value is repaid.
+    pub fn register_series(
+        env: Env,
+        issuer: Address,
+        payment_token: Address,
+        face_value: i128,
+        coupon_rate: u32,
+        maturity: u64,
+        coupon_interval: u64,
+    ) -> Result<u32, Error> {
+        issuer.require_auth();
+
+        let issue_date = env.ledger().timestamp();
+        if face_value <= 0
+            || coupon_rate > 10000
+            || maturity <= issue_date
+            || coupon_interval == 0
+            || coupon_interval > maturity - issue_date
+        {
+            return Err(Error::InvalidParameter);
+        }
+
+        let series_id: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::SeriesCount)
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::SeriesCount, &(series_id + 1));
+
+        save_series(
+            &env,
+            series_id,
+            &BondSeries {
+                issuer: issuer.clone(),
+                payment_token,
+                face_value,
+                coupon_rate,
+                issue_date,
+                maturity,
+                coupon_interval,
+                supply: 0,
+                coupons_paid: 0,
+            },
+        );
+
+        SeriesRegistered {
+            series_id,
+            issuer,
+            face_value,
+            coupon_rate,
+            maturity,
+        }
+        .publish(&env);
+
+        Ok(series_id)
+    }
+
+    /// Issue bonds of a series to an investor before maturity (issuer only)
+    pub fn mint(env: Env, series_id: u32, to: Address, amount: i128) -> Result<(), Error> {
+        let mut series = load_series(&env, series_id)?;
+        series.issuer.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        if env.ledger().timestamp() >= series.maturity {
+            return Err(Error::SeriesMatured);
+        }
+
+        series.supply = series
+            .supply
+            .checked_add(amount)
+            .ok_or(Error::MathOverflow)?;
+        save_series(&env, series_id, &series);
+        write_history(&env, &DataKey::SupplyHistory(series_id), series.supply);
+
+        let balance = Self::balance(env.clone(), series_id, to.clone());
+        write_balance(&env, series_id, &to, balance + amount)?;
+
+        BondsMinted {
+            series_id,
+            to,
+            amount,
+            supply: series.supply,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Move bonds of a series to another holder
+    pub fn transfer(
+        env: Env,
+        series_id: u32,
+        from: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        from.require_auth();
+        load_series(&env, series_id)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let from_balance = Self::balance(env.clone(), series_id, from.clone());
+        if from_balance < amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        write_balance(&env, series_id, &from, from_balance - amount)?;
+        let to_balance = Self::balance(env.clone(), series_id, to.clone());
+        write_balance(&env, series_id, &to, to_balance + amount)?;
+
+        BondsTransferred {
+            series_id,
+            from,
+            to,
+            amount,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Get the terms and supply of a series
+    pub fn get_series(env: Env, series_id: u32) -> Result<BondSeries, Error> {
+        load_series(&env, series_id)
+    }
+
+    /// Number of series registered so far; ids run from 0 to this value
+    pub fn series_count(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SeriesCount)
+            .unwrap_or(0)
+    }
+
+    /// Bonds of a series held by an address
+    pub fn balance(env: Env, series_id: u32, holder: Address) -> i128 {
+        latest(&load_history(
+            &env,
+            &DataKey::BalanceHistory(series_id, holder),
+        ))
+    }
+
+    /// Bonds of a series held by an address before `ledger` began, read from
+    /// their most recent balance changes
+    pub fn balance_at(env: Env, series_id: u32, holder: Address, ledger: u32) -> i128 {
+        value_before(
+            &load_history(&env, &DataKey::BalanceHistory(series_id, holder)),
+            ledger,
+        )
+    }
+
+    /// Pay the next scheduled coupon of a series from the issuer (issuer only)
+    ///
+    /// Holders are snapshotted at the current ledger: bonds held before it
+    /// began share the coupon pro-rata and can collect it with `claim_coupon`.
+    pub fn pay_coupon(env: Env, series_id: u32) -> Result<i128, Error> {
+        let mut series = load_series(&env, series_id)?;
+        series.issuer.require_auth();
+
+        let number = series.coupons_paid + 1;
+        if number > coupon_count(&series) || env.ledger().timestamp() < coupon_date(&series, number)
+        {
+            return Err(Error::CouponNotDue);
+        }
+
+        let record_ledger = env.ledger().sequence();
+        let supply = value_before(
+            &load_history(&env, &DataKey::SupplyHistory(series_id)),
+            record_ledger,
+        );
+
+        // Accrue the coupon rate over this period, which may be a short final one
+        let period = coupon_date(&series, number) - coupon_date(&series, number - 1);
+        let amount = supply
+            .checked_mul(series.face_value)
+            .and_then(|principal| {
+                principal.checked_mul(series.coupon_rate as i128 * period as i128)
+            })
+            .ok_or(Error::MathOverflow)?
+            / (10000 * SECONDS_PER_YEAR as i128);
+
+        if amount > 0 {
+            token::Client::new(&env, &series.payment_token).transfer(
+                &series.issuer,
+                env.current_contract_address(),
+                &amount,
+            );
+        }
+
+        let mut coupons = load_coupons(&env, series_id);
+        coupons.push_back(Coupon {
+            record_ledger,
+            amount,
+            supply,
+        });
+        env.storage()
+            .persistent()
+            .set(&DataKey::Coupons(series_id), &coupons);
+        env.storage().persistent().extend_ttl(
+            &DataKey::Coupons(series_id),
+            LIFETIME_THRESHOLD,
+            BUMP_AMOUNT,
+        );
+
+        series.coupons_paid = number;
+        save_series(&env, series_id, &series);
+
+        CouponPaid {
+            series_id,
+            coupon: number,
+            record_ledger,
+            amount,
+        }
+        .publish(&env);
+
+        Ok(amount)
+    }
+
+    /// Collect every paid coupon of a series owed to a holder, returning the amount sent
+    pub fn claim_coupon(env: Env, holder: Address, series_id: u32) -> Result<i128, Error> {
+        holder.require_auth();
+
+        let amount = Self::claimable_coupon(env.clone(), holder.clone(), series_id)?;
+        let coupons = load_coupons(&env, series_id);
+        env.storage().persistent().set(
+            &DataKey::CouponCursor(series_id, holder.clone()),
+            &coupons.len(),
+        );
+        set_coupon_credit(&env, series_id, &holder, 0);
+
+        if amount > 0 {
+            let series = load_series(&env, series_id)?;
+            token::Client::new(&env, &series.payment_token).transfer(
+                &env.current_contract_address(),
+                &holder,
+                &amount,
+            );
+
+            CouponClaimed {
+                series_id,
+                holder,
+                amount,
+            }
+            .publish(&env);
+        }
+
+        Ok(amount)
+    }
+
+    /// Coupon payments a holder has yet to claim
+    pub fn claimable_coupon(env: Env, holder: Address, series_id: u32) -> Result<i128, Error> {
+        load_series(&env, series_id)?;
+
+        unsettled_coupons(&env, series_id, &holder)?
+            .checked_add(coupon_credit(&env, series_id, &holder))
+            .ok_or(Error::MathOverflow)
+    }
+
+    /// Deposit principal into a series' redemption escrow (issuer only)
+    pub fn issuer_fund_redemption(env: Env, series_id: u32, amount: i128) -> Result<i128, Error> {
+        let series = load_series(&env, series_id)?;
+        series.issuer.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        token::Client::new(&env, &series.payment_token).transfer(
+            &series.issuer,
+            env.current_contract_address(),
+            &amount,
+        );
+
+        let escrow = redemption_escrow(&env, series_id)
+            .checked_add(amount)
+            .ok_or(Error::MathOverflow)?;
+        set_redemption_escrow(&env, series_id, escrow);
+
+        RedemptionFunded {
+            series_id,
+            amount,
+            escrow,
+        }
+        .publish(&env);
+
+        Ok(escrow)
+    }
+
+    /// Burn matured bonds and pay their face value from the redemption escrow
+    ///
+    /// Redemption opens once every coupon has been paid, so burning bonds can
+    /// never shrink the supply a final coupon is shared over.
+    pub fn redeem_at_maturity(
+        env: Env,
+        holder: Address,
+        series_id: u32,
+        amount: i128,
+    ) -> Result<i128, Error> {
+        holder.require_auth();
+        let mut series = load_series(&env, series_id)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        if env.ledger().timestamp() < series.maturity {
+            return Err(Error::NotMatured);
+        }
+
+        if series.coupons_paid < coupon_count(&series) {
+            return Err(Error::CouponsOutstanding);
+        }
+
+        let balance = Self::balance(env.clone(), series_id, holder.clone());
+        if balance < amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        let paid = amount
+            .checked_mul(series.face_value)
+            .ok_or(Error::MathOverflow)?;
+        let escrow = redemption_escrow(&env, series_id);
+        if escrow < paid {
+            return Err(Error::EscrowUnderfunded);
+        }
+
+        // Burn the bonds
+        write_balance(&env, series_id, &holder, balance - amount)?;
+        series.supply -= amount;
+        save_series(&env, series_id, &series);
+        write_history(&env, &DataKey::SupplyHistory(series_id), series.supply);
+
+        set_redemption_escrow(&env, series_id, escrow - paid);
+
+        token::Client::new(&env, &series.payment_token).transfer(
+            &env.current_contract_address(),
+            &holder,
+            &paid,
+        );
+
+        BondsRedeemed {
+            series_id,
+            holder,
+            amount,
+            paid,
+        }
+        .publish(&env);
+
+        Ok(paid)
+    }
+
+    /// Principal held in a series' redemption escrow
+    pub fn get_redemption_escrow(env: Env, series_id: u32) -> i128 {
+        redemption_escrow(&env, series_id)
+    }
+
+    /// Whether a series has matured with coupons unpaid or without enough
+    /// escrow to redeem every bond
+    pub fn is_in_default(env: Env, series_id: u32) -> Result<bool, Error> {
+        let series = load_series(&env, series_id)?;
+        if env.ledger().timestamp() < series.maturity {
+            return Ok(false);
+        }
+
+        if series.coupons_paid < coupon_count(&series) {
+            return Ok(true);
+        }
+
+        let owed = Self::outstanding_principal(env.clone(), series_id)?;
+        Ok(redemption_escrow(&env, series_id) < owed)
+    }
+
+    /// Principal owed to holders of a series: supply times face value
+    pub fn outstanding_principal(env: Env, series_id: u32) -> Result<i128, Error> {
+        let series = load_series(&env, series_id)?;
+        series
+            .supply
+            .checked_mul(series.face_value)
+            .ok_or(Error::MathOverflow)
+    }
+
+    /// Next coupon date after the current ledger time, or `None` once matured
+    pub fn next_coupon_date(env: Env, series_id: u32) -> Result<Option<u64>, Error> {
+        let series = load_series(&env, series_id)?;
+        let now = env.ledger().timestamp();
+
+        if now >= series.maturity {
+            return Ok(None);
+        }
+
+        let periods = (now - series.issue_date) / series.coupon_interval + 1;
+        let date = series.issue_date + periods * series.coupon_interval;
+        Ok(Some(date.min(series.maturity)))
+    }
+}
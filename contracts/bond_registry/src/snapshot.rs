@@ -0,0 +1,47 @@
+use soroban_sdk::{contracttype, Env, Vec};
+
+/// Checkpoints kept per history before the oldest is overwritten
+pub(crate) const MAX_CHECKPOINTS: u32 = 32;
+
+/// A value as of the end of a ledger
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Checkpoint {
+    pub ledger: u32,
+    pub value: i128,
+}
+
+/// Most recent value in a checkpoint history
+pub(crate) fn latest(history: &Vec<Checkpoint>) -> i128 {
+    history.last().map(|c| c.value).unwrap_or(0)
+}
+
+/// Value held before `ledger` began, ignoring changes made during it
+///
+/// Only the last `MAX_CHECKPOINTS` changes are kept, so ledgers older than the
+/// oldest of them read as 0.
+pub(crate) fn value_before(history: &Vec<Checkpoint>, ledger: u32) -> i128 {
+    for checkpoint in history.iter().rev() {
+        if checkpoint.ledger < ledger {
+            return checkpoint.value;
+        }
+    }
+    0
+}
+
+/// Record a new value at the current ledger, folding same-ledger updates together
+/// and dropping the oldest checkpoint once the history is full
+pub(crate) fn push(env: &Env, history: &mut Vec<Checkpoint>, value: i128) {
+    let ledger = env.ledger().sequence();
+
+    if let Some(last) = history.last() {
+        if last.ledger == ledger {
+            history.pop_back();
+        }
+    }
+    if history.len() >= MAX_CHECKPOINTS {
+        history.pop_front();
+    }
+
+    history.push_back(Checkpoint { ledger, value });
+}
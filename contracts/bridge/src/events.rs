@@ -0,0 +1,55 @@
+use soroban_sdk::{contractevent, Address, Bytes, BytesN, Vec};
+
+/// Tokens locked on Stellar to be minted on another chain
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Locked {
+    #[topic]
+    pub token: Address,
+    #[topic]
+    pub from: Address,
+    pub nonce: u64,
+    pub amount: i128,
+    pub dest_chain: u32,
+    pub dest_address: Bytes,
+}
+
+/// Locked tokens released for a transfer proven on another chain
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Released {
+    #[topic]
+    pub token: Address,
+    #[topic]
+    pub to: Address,
+    pub source_chain: u32,
+    pub nonce: u64,
+    pub amount: i128,
+}
+
+/// Relayer approval recorded for a release request
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Attested {
+    #[topic]
+    pub digest: BytesN<32>,
+    #[topic]
+    pub relayer: Address,
+}
+
+/// Relayer set or approval threshold changed
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RelayerSetUpdated {
+    pub relayers: Vec<Address>,
+    pub threshold: u32,
+}
+
+/// Token allowed or disallowed for locking
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenAllowed {
+    #[topic]
+    pub token: Address,
+    pub allowed: bool,
+}
@@ -0,0 +1,425 @@
+#![no_std]
+
+mod events;
+
+use events::{Attested, Locked, RelayerSetUpdated, Released, TokenAllowed};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, token, xdr::ToXdr, Address, Bytes, BytesN,
+    Env, Vec,
+};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    Unauthorized = 3,
+    AlreadyProcessed = 4,
+    InsufficientLocked = 5,
+    TokenNotAllowed = 6,
+    InvalidParameter = 7,
+    InsufficientApprovals = 8,
+    InvalidThreshold = 9,
+    AlreadyAttested = 10,
+}
+
+/// Identifies a transfer out of another chain that releases tokens here
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReleaseProof {
+    pub source_chain: u32,
+    pub nonce: u64, // nonce of the transfer on the source chain
+    pub token: Address,
+}
+
+/// A release relayers attest to; approvals only count toward an identical request
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReleaseRequest {
+    pub proof: ReleaseProof,
+    pub to: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    Relayers,
+    Threshold, // approvals required to release
+    LockNonce,
+    Locked(Address),
+    Processed(u32, u64),
+    Approvals(BytesN<32>),
+    AllowedToken(Address), // tokens that may be locked
+}
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const PROCESSED_BUMP_AMOUNT: u32 = 365 * DAY_IN_LEDGERS;
+const PROCESSED_LIFETIME_THRESHOLD: u32 = PROCESSED_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+fn locked(env: &Env, token: &Address) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::Locked(token.clone()))
+        .unwrap_or(0)
+}
+
+fn is_token_allowed(env: &Env, token: &Address) -> bool {
+    env.storage()
+        .instance()
+        .get(&DataKey::AllowedToken(token.clone()))
+        .unwrap_or(false)
+}
+
+/// Check that `admin` is the stored admin and has authorized the call
+fn require_admin(env: &Env, admin: &Address) -> Result<(), Error> {
+    admin.require_auth();
+
+    let stored: Address = env
+        .storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .ok_or(Error::NotInitialized)?;
+    if *admin != stored {
+        return Err(Error::Unauthorized);
+    }
+
+    Ok(())
+}
+
+fn relayers(env: &Env) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Relayers)
+        .unwrap_or(Vec::new(env))
+}
+
+fn threshold(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::Threshold)
+        .unwrap_or(0)
+}
+
+/// Store a new relayer set and threshold, requiring `1 <= threshold <= relayers`
+fn store_relayer_set(env: &Env, relayers: &Vec<Address>, threshold: u32) -> Result<(), Error> {
+    if threshold == 0 || threshold > relayers.len() {
+        return Err(Error::InvalidThreshold);
+    }
+
+    env.storage().instance().set(&DataKey::Relayers, relayers);
+    env.storage()
+        .instance()
+        .set(&DataKey::Threshold, &threshold);
+
+    RelayerSetUpdated {
+        relayers: relayers.clone(),
+        threshold,
+    }
+    .publish(env);
+
+    Ok(())
+}
+
+/// Hash identifying a release request
+fn request_digest(env: &Env, request: &ReleaseRequest) -> BytesN<32> {
+    env.crypto().sha256(&request.clone().to_xdr(env)).to_bytes()
+}
+
+fn load_approvals(env: &Env, digest: &BytesN<32>) -> Vec<Address> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Approvals(digest.clone()))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Approvals on a request from addresses that are still relayers
+fn count_approvals(env: &Env, digest: &BytesN<32>) -> u32 {
+    let relayers = relayers(env);
+    load_approvals(env, digest)
+        .iter()
+        .filter(|approver| relayers.contains(approver))
+        .count() as u32
+}
+
+/// Lock/release bridge moving BENJI or bond tokens between Stellar and other chains
+#[contract]
+pub struct Bridge;
+
+#[contractimpl]
+impl Bridge {
+    /// Initialize the bridge with the admin and an m-of-n relayer set
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        relayers: Vec<Address>,
+        threshold: u32,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        store_relayer_set(&env, &relayers, threshold)?;
+
+        Ok(())
+    }
+
+    /// Add a relayer to the set (admin only)
+    pub fn add_relayer(env: Env, admin: Address, relayer: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        let mut relayers = relayers(&env);
+        if !relayers.contains(&relayer) {
+            relayers.push_back(relayer);
+        }
+
+        store_relayer_set(&env, &relayers, threshold(&env))
+    }
+
+    /// Remove a relayer from the set; fails if the threshold would become unreachable (admin only)
+    pub fn remove_relayer(env: Env, admin: Address, relayer: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        let mut relayers = relayers(&env);
+        if let Some(index) = relayers.first_index_of(&relayer) {
+            relayers.remove(index);
+        }
+
+        store_relayer_set(&env, &relayers, threshold(&env))
+    }
+
+    /// Change how many relayer approvals a release needs (admin only)
+    pub fn set_threshold(env: Env, admin: Address, threshold: u32) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        store_relayer_set(&env, &relayers(&env), threshold)
+    }
+
+    /// Allow or stop locking of a token (admin only)
+    ///
+    /// Tokens already locked can still be released once a token is disallowed.
+    pub fn set_token_allowed(
+        env: Env,
+        admin: Address,
+        token: Address,
+        allowed: bool,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        let key = DataKey::AllowedToken(token.clone());
+        if allowed {
+            env.storage().instance().set(&key, &true);
+        } else {
+            env.storage().instance().remove(&key);
+        }
+
+        TokenAllowed { token, allowed }.publish(&env);
+
+        Ok(())
+    }
+
+    /// Lock tokens for minting on `dest_chain`, returning the transfer nonce
+    pub fn lock(
+        env: Env,
+        from: Address,
+        token: Address,
+        amount: i128,
+        dest_chain: u32,
+        dest_address: Bytes,
+    ) -> Result<u64, Error> {
+        from.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        if !env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::NotInitialized);
+        }
+
+        if !is_token_allowed(&env, &token) {
+            return Err(Error::TokenNotAllowed);
+        }
+
+        let nonce: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::LockNonce)
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::LockNonce, &(nonce + 1));
+        env.storage().instance().set(
+            &DataKey::Locked(token.clone()),
+            &(locked(&env, &token) + amount),
+        );
+
+        token::Client::new(&env, &token).transfer(&from, env.current_contract_address(), &amount);
+
+        Locked {
+            token,
+            from,
+            nonce,
+            amount,
+            dest_chain,
+            dest_address,
+        }
+        .publish(&env);
+
+        Ok(nonce)
+    }
+
+    /// Attest to a lock observed on another chain, returning the request's approvals (relayer only)
+    ///
+    /// The relayer's authorization of this call is its signature over the request.
+    pub fn attest(
+        env: Env,
+        relayer: Address,
+        proof: ReleaseProof,
+        to: Address,
+        amount: i128,
+    ) -> Result<u32, Error> {
+        relayer.require_auth();
+
+        if !relayers(&env).contains(&relayer) {
+            return Err(Error::Unauthorized);
+        }
+
+        if env
+            .storage()
+            .persistent()
+            .has(&DataKey::Processed(proof.source_chain, proof.nonce))
+        {
+            return Err(Error::AlreadyProcessed);
+        }
+
+        let digest = request_digest(&env, &ReleaseRequest { proof, to, amount });
+        let mut approvals = load_approvals(&env, &digest);
+        if approvals.contains(&relayer) {
+            return Err(Error::AlreadyAttested);
+        }
+        approvals.push_back(relayer.clone());
+
+        let key = DataKey::Approvals(digest.clone());
+        env.storage().persistent().set(&key, &approvals);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PROCESSED_LIFETIME_THRESHOLD,
+            PROCESSED_BUMP_AMOUNT,
+        );
+
+        Attested {
+            digest: digest.clone(),
+            relayer,
+        }
+        .publish(&env);
+
+        Ok(count_approvals(&env, &digest))
+    }
+
+    /// Release locked tokens once enough relayers have attested to the transfer
+    ///
+    /// Each `(source_chain, nonce)` can be released once.
+    pub fn release(env: Env, proof: ReleaseProof, to: Address, amount: i128) -> Result<(), Error> {
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let key = DataKey::Processed(proof.source_chain, proof.nonce);
+        if env.storage().persistent().has(&key) {
+            return Err(Error::AlreadyProcessed);
+        }
+
+        let request = ReleaseRequest {
+            proof: proof.clone(),
+            to: to.clone(),
+            amount,
+        };
+        let digest = request_digest(&env, &request);
+        if count_approvals(&env, &digest) < threshold(&env) {
+            return Err(Error::InsufficientApprovals);
+        }
+
+        let available = locked(&env, &proof.token);
+        if available < amount {
+            return Err(Error::InsufficientLocked);
+        }
+
+        env.storage().persistent().set(&key, &true);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PROCESSED_LIFETIME_THRESHOLD,
+            PROCESSED_BUMP_AMOUNT,
+        );
+        // Once processed the request can never be attested again
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Approvals(digest));
+        env.storage()
+            .instance()
+            .set(&DataKey::Locked(proof.token.clone()), &(available - amount));
+
+        token::Client::new(&env, &proof.token).transfer(
+            &env.current_contract_address(),
+            &to,
+            &amount,
+        );
+
+        Released {
+            token: proof.token,
+            to,
+            source_chain: proof.source_chain,
+            nonce: proof.nonce,
+            amount,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Whether a transfer from another chain has already been released
+    pub fn is_processed(env: Env, source_chain: u32, nonce: u64) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::Processed(source_chain, nonce))
+    }
+
+    /// Approvals from current relayers on a release request
+    pub fn get_approvals(env: Env, proof: ReleaseProof, to: Address, amount: i128) -> u32 {
+        count_approvals(
+            &env,
+            &request_digest(&env, &ReleaseRequest { proof, to, amount }),
+        )
+    }
+
+    /// Current relayer set
+    pub fn get_relayers(env: Env) -> Vec<Address> {
+        relayers(&env)
+    }
+
+    /// Approvals required to release
+    pub fn get_threshold(env: Env) -> u32 {
+        threshold(&env)
+    }
+
+    /// Whether a token may be locked
+    pub fn is_token_allowed(env: Env, token: Address) -> bool {
+        is_token_allowed(&env, &token)
+    }
+
+    /// Tokens currently locked in the bridge
+    pub fn get_locked(env: Env, token: Address) -> i128 {
+        locked(&env, &token)
+    }
+
+    /// Nonce the next lock will be assigned
+    pub fn get_lock_nonce(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::LockNonce)
+            .unwrap_or(0)
+    }
+}
@@ -0,0 +1,320 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractclient, contractimpl, contracttype, token::TokenInterface, Address, Env,
+    String,
+};
+use soroban_token_sdk::metadata::TokenMetadata;
+
+#[contracttype]
+pub struct AllowanceDataKey {
+    pub from: Address,
+    pub spender: Address,
+}
+
+#[contracttype]
+pub struct AllowanceValue {
+    pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    Metadata,
+    Balance(Address),
+    TotalSupply,
+    Allowance(AllowanceDataKey),
+}
+
+fn require_admin(env: &Env) -> Address {
+    let admin: Address = env
+        .storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .expect("Not initialized");
+    admin.require_auth();
+    admin
+}
+
+/// Hook on the credit line that keeps supplier rewards on bToken balances
+#[contractclient(name = "PoolClient")]
+pub trait Pool {
+    fn sync_supply_rewards(env: Env, holder: Address, shares: i128);
+}
+
+/// Report a holder's balance to the credit line after it changed outside a
+/// supply or withdrawal
+fn sync_rewards(env: &Env, holder: &Address) {
+    let pool: Address = env
+        .storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .expect("Not initialized");
+    PoolClient::new(env, &pool)
+        .sync_supply_rewards(holder, &BToken::balance(env.clone(), holder.clone()));
+}
+
+fn read_allowance(env: &Env, from: Address, spender: Address) -> AllowanceValue {
+    let key = DataKey::Allowance(AllowanceDataKey { from, spender });
+    match env.storage().temporary().get::<_, AllowanceValue>(&key) {
+        Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => allowance,
+        Some(allowance) => AllowanceValue {
+            amount: 0,
+            expiration_ledger: allowance.expiration_ledger,
+        },
+        None => AllowanceValue {
+            amount: 0,
+            expiration_ledger: 0,
+        },
+    }
+}
+
+fn write_allowance(
+    env: &Env,
+    from: Address,
+    spender: Address,
+    amount: i128,
+    expiration_ledger: u32,
+) {
+    if amount > 0 && expiration_ledger < env.ledger().sequence() {
+        panic!("Expiration ledger is in the past");
+    }
+
+    let key = DataKey::Allowance(AllowanceDataKey { from, spender });
+    env.storage().temporary().set(
+        &key,
+        &AllowanceValue {
+            amount,
+            expiration_ledger,
+        },
+    );
+
+    if amount > 0 {
+        let live_for = expiration_ledger - env.ledger().sequence();
+        env.storage()
+            .temporary()
+            .extend_ttl(&key, live_for, live_for);
+    }
+}
+
+fn spend_allowance(env: &Env, from: Address, spender: Address, amount: i128) {
+    let allowance = read_allowance(env, from.clone(), spender.clone());
+    if allowance.amount < amount {
+        panic!("Insufficient allowance");
+    }
+
+    if amount > 0 {
+        write_allowance(
+            env,
+            from,
+            spender,
+            allowance.amount - amount,
+            allowance.expiration_ledger,
+        );
+    }
+}
+
+fn burn_balance(env: &Env, from: Address, amount: i128) {
+    if amount < 0 {
+        panic!("Amount must be non-negative");
+    }
+
+    let balance = BToken::balance(env.clone(), from.clone());
+    if balance < amount {
+        panic!("Insufficient balance");
+    }
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(from), &(balance - amount));
+
+    let total: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalSupply)
+        .unwrap_or(0);
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalSupply, &(total - amount));
+}
+
+fn move_balance(env: &Env, from: Address, to: Address, amount: i128) {
+    if amount < 0 {
+        panic!("Amount must be non-negative");
+    }
+
+    let from_balance = BToken::balance(env.clone(), from.clone());
+    if from_balance < amount {
+        panic!("Insufficient balance");
+    }
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(from), &(from_balance - amount));
+
+    let to_balance = BToken::balance(env.clone(), to.clone());
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(to), &(to_balance + amount));
+}
+
+/// Interest-bearing receipt for USDC supplied to the credit line.
+///
+/// Balances are pool shares: the credit line mints them on `supply` and burns
+/// them on `withdraw_supply`, and each share redeems for a growing amount of
+/// USDC as borrowers pay interest. Transfers and burns report the new balances
+/// to the credit line, which pays supply rewards on them.
+#[contract]
+pub struct BToken;
+
+#[contractimpl]
+impl BToken {
+    /// Initialize the receipt token; `admin` is the credit line that mints and burns it
+    pub fn initialize(env: Env, admin: Address, decimal: u32, name: String, symbol: String) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+
+        if decimal > 18 {
+            panic!("Decimal must not be greater than 18");
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(
+            &DataKey::Metadata,
+            &TokenMetadata {
+                decimal,
+                name,
+                symbol,
+            },
+        );
+        env.storage().instance().set(&DataKey::TotalSupply, &0_i128);
+    }
+
+    /// Mint receipt tokens to a supplier (admin only)
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        require_admin(&env);
+
+        if amount < 0 {
+            panic!("Amount must be non-negative");
+        }
+
+        let balance = Self::balance(env.clone(), to.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::Balance(to.clone()), &(balance + amount));
+
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalSupply)
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalSupply, &(total + amount));
+    }
+
+    /// Burn a supplier's receipt tokens on withdrawal (admin only)
+    pub fn admin_burn(env: Env, from: Address, amount: i128) {
+        require_admin(&env);
+        burn_balance(&env, from, amount);
+    }
+
+    pub fn total_supply(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TotalSupply)
+            .unwrap_or(0)
+    }
+}
+
+#[contractimpl]
+impl TokenInterface for BToken {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        read_allowance(&env, from, spender).amount
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+
+        if amount < 0 {
+            panic!("Amount must be non-negative");
+        }
+
+        write_allowance(&env, from, spender, amount, expiration_ledger);
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Balance(id))
+            .unwrap_or(0)
+    }
+
+    fn transfer(env: Env, from: Address, to_muxed: soroban_sdk::MuxedAddress, amount: i128) {
+        from.require_auth();
+        let to = to_muxed.address();
+        move_balance(&env, from.clone(), to.clone(), amount);
+        sync_rewards(&env, &from);
+        sync_rewards(&env, &to);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+
+        if amount < 0 {
+            panic!("Amount must be non-negative");
+        }
+
+        spend_allowance(&env, from.clone(), spender, amount);
+        move_balance(&env, from.clone(), to.clone(), amount);
+        sync_rewards(&env, &from);
+        sync_rewards(&env, &to);
+    }
+
+    fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+        burn_balance(&env, from.clone(), amount);
+        sync_rewards(&env, &from);
+    }
+
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+
+        if amount < 0 {
+            panic!("Amount must be non-negative");
+        }
+
+        spend_allowance(&env, from.clone(), spender, amount);
+        burn_balance(&env, from.clone(), amount);
+        sync_rewards(&env, &from);
+    }
+
+    fn decimals(env: Env) -> u32 {
+        let metadata: TokenMetadata = env
+            .storage()
+            .instance()
+            .get(&DataKey::Metadata)
+            .expect("Not initialized");
+        metadata.decimal
+    }
+
+    fn name(env: Env) -> String {
+        let metadata: TokenMetadata = env
+            .storage()
+            .instance()
+            .get(&DataKey::Metadata)
+            .expect("Not initialized");
+        metadata.name
+    }
+
+    fn symbol(env: Env) -> String {
+        let metadata: TokenMetadata = env
+            .storage()
+            .instance()
+            .get(&DataKey::Metadata)
+            .expect("Not initialized");
+        metadata.symbol
+    }
+}
@@ -0,0 +1,384 @@
+//! Operator command line for a deployed BondBridge credit line.
+//!
+//! Each subcommand is one contract call over Soroban RPC. The RPC endpoint,
+//! contract id and admin key are taken from flags or the environment, so a
+//! shell session can set them once:
+//!
+//! ```text
+//! export BONDBRIDGE_RPC_URL=https://soroban-testnet.stellar.org
+//! export BONDBRIDGE_CREDIT_LINE=C...
+//! export BONDBRIDGE_SECRET_KEY=S...
+//! bondbridge-cli set-ltv C... 6500 --threshold 7500
+//! bondbridge-cli list-positions
+//! ```
+//!
+//! Amounts are printed in token base units.
+
+use std::process::ExitCode;
+
+use bondbridge_client::{CollateralConfig, CreditLine, Error, LoanTerms, Signer};
+use clap::{Parser, Subcommand};
+
+/// Health factor at which a position becomes liquidatable, as the contract scales it
+const HEALTH_FACTOR_ONE: i128 = 10_000_000;
+
+/// Most users the contract returns per `list_users` page
+const MAX_PAGE_SIZE: u32 = 50;
+
+/// Ledgers `events` looks back by default, about an hour at five seconds each
+const RECENT_LEDGERS: u32 = 720;
+
+#[derive(Parser)]
+#[command(
+    name = "bondbridge-cli",
+    about = "Operate a deployed BondBridge credit line"
+)]
+struct Cli {
+    /// Soroban RPC endpoint
+    #[arg(long, env = "BONDBRIDGE_RPC_URL")]
+    rpc_url: String,
+
+    /// Credit line contract id (`C...`)
+    #[arg(long, env = "BONDBRIDGE_CREDIT_LINE")]
+    contract: String,
+
+    /// Secret seed (`S...`) of the admin account, needed by commands that change state
+    #[arg(long, env = "BONDBRIDGE_SECRET_KEY", hide_env_values = true)]
+    secret_key: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Initialize a freshly deployed credit line, making the signer its admin
+    Init {
+        /// BENJI token contract id
+        #[arg(long)]
+        benji: String,
+        /// USDC token contract id
+        #[arg(long)]
+        usdc: String,
+    },
+    /// Set the loan-to-value ratio of a collateral token, in basis points
+    SetLtv {
+        /// Collateral token contract id
+        token: String,
+        ratio: u32,
+        /// Also set the liquidation threshold, in basis points; this accepts new tokens
+        #[arg(long)]
+        threshold: Option<u32>,
+    },
+    /// Halt the market
+    Pause {
+        /// Halt repayments too
+        #[arg(long)]
+        repay: bool,
+    },
+    /// Resume a paused market
+    Unpause,
+    /// Set the price oracle used to value collateral
+    SetOracle {
+        /// Oracle contract id
+        oracle: String,
+    },
+    /// List positions with their debt and health factor
+    ListPositions {
+        /// Index of the first user to list
+        #[arg(long, default_value_t = 0)]
+        offset: u32,
+        /// Most users to list
+        #[arg(long, default_value_t = MAX_PAGE_SIZE)]
+        limit: u32,
+    },
+    /// Show every account of one user
+    Position {
+        /// User address (`G...` or `C...`)
+        address: String,
+        /// Show only this account
+        #[arg(long)]
+        account: Option<u32>,
+    },
+    /// Print recent credit line events
+    Events {
+        /// First ledger to read from, by default about an hour before the latest
+        #[arg(long)]
+        start_ledger: Option<u32>,
+        /// Most events to print
+        #[arg(long, default_value_t = 100)]
+        limit: u32,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Error> {
+    let credit_line = CreditLine::connect(&cli.rpc_url, &cli.contract)?;
+    let signer = || match &cli.secret_key {
+        Some(secret) => Signer::from_secret(secret),
+        None => Err(Error::InvalidKey(
+            "no secret key; pass --secret-key or set BONDBRIDGE_SECRET_KEY".to_string(),
+        )),
+    };
+
+    match cli.command {
+        Command::Init { benji, usdc } => {
+            let signer = signer()?;
+            credit_line.initialize(&signer, &benji, &usdc)?;
+            println!("initialized with admin {}", signer.address());
+        }
+        Command::SetLtv {
+            token,
+            ratio,
+            threshold,
+        } => {
+            let signer = signer()?;
+            match threshold {
+                Some(liquidation_threshold) => {
+                    let config = CollateralConfig {
+                        ltv_ratio: ratio,
+                        liquidation_threshold,
+                    };
+                    credit_line.set_collateral_config(&signer, &token, config)?;
+                    println!(
+                        "{token}: ltv {ratio} bps, liquidation threshold {} bps",
+                        config.liquidation_threshold
+                    );
+                }
+                None => {
+                    credit_line.set_ltv_ratio(&signer, &token, ratio)?;
+                    println!("{token}: ltv {ratio} bps");
+                }
+            }
+        }
+        Command::Pause { repay } => {
+            credit_line.pause(&signer()?, repay)?;
+            match repay {
+                true => println!("paused, repayments included"),
+                false => println!("paused, repayments still open"),
+            }
+        }
+        Command::Unpause => {
+            credit_line.unpause(&signer()?)?;
+            println!("unpaused");
+        }
+        Command::SetOracle { oracle } => {
+            credit_line.set_oracle(&signer()?, &oracle)?;
+            println!("oracle set to {oracle}");
+        }
+        Command::ListPositions { offset, limit } => {
+            list_positions(&credit_line, offset, limit)?;
+        }
+        Command::Position { address, account } => {
+            let accounts = match account {
+                Some(account_id) => vec![account_id],
+                None => credit_line.accounts(&address)?,
+            };
+            if accounts.is_empty() {
+                println!("{address} has no positions");
+            }
+            for account_id in accounts {
+                show_position(&credit_line, &address, account_id)?;
+            }
+        }
+        Command::Events {
+            start_ledger,
+            limit,
+        } => {
+            let start_ledger = match start_ledger {
+                Some(ledger) => ledger,
+                None => credit_line
+                    .contract()
+                    .rpc()
+                    .latest_ledger()?
+                    .saturating_sub(RECENT_LEDGERS),
+            };
+            for event in credit_line.events(start_ledger, limit)? {
+                println!("{} {} {:?}", event.ledger, event.tx_hash, event.event);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn list_positions(credit_line: &CreditLine, offset: u32, limit: u32) -> Result<(), Error> {
+    println!(
+        "{:<56} {:>7} {:>20} {:>12}",
+        "user", "account", "debt", "health"
+    );
+
+    let end = offset.saturating_add(limit);
+    for page in (offset..end).step_by(MAX_PAGE_SIZE as usize) {
+        let users = credit_line.list_users(page, MAX_PAGE_SIZE.min(end - page))?;
+        if users.is_empty() {
+            break;
+        }
+        for user in users {
+            for account_id in credit_line.accounts(&user)? {
+                let debt = credit_line.current_debt(&user, account_id)?;
+                let health = credit_line.health_factor(&user, account_id)?;
+                println!(
+                    "{user:<56} {account_id:>7} {debt:>20} {:>12}",
+                    format_health(health)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn show_position(credit_line: &CreditLine, user: &str, account_id: u32) -> Result<(), Error> {
+    let position = credit_line.position(user, account_id)?;
+    let debt = credit_line.current_debt(user, account_id)?;
+    let health = credit_line.health_factor(user, account_id)?;
+
+    println!("{user} account {account_id}");
+    for (token, amount) in &position.collateral {
+        println!("  collateral     {amount} of {token}");
+    }
+    println!("  debt           {debt}");
+    println!("  rate mode      {:?}", position.rate_mode);
+    if position.stable_borrowed > 0 {
+        println!(
+            "  stable debt    {} at {} bps",
+            position.stable_borrowed, position.stable_rate
+        );
+    }
+    if let LoanTerms::Fixed(loan) = &position.terms {
+        println!(
+            "  fixed loan     {} over {} installments every {} ledgers",
+            loan.principal, loan.installments, loan.interval
+        );
+    }
+    println!("  health factor  {}", format_health(health));
+
+    Ok(())
+}
+
+/// Health factor as a decimal, or `-` for a position without debt
+fn format_health(health: i128) -> String {
+    if health == i128::MAX {
+        return "-".to_string();
+    }
+    format!(
+        "{}.{:04}",
+        health / HEALTH_FACTOR_ONE,
+        health % HEALTH_FACTOR_ONE / 1_000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    /// Parse a command line, with the connection flags every command needs
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        let base = [
+            "bondbridge-cli",
+            "--rpc-url",
+            "http://localhost:8000",
+            "--contract",
+            "CCREDIT",
+        ];
+        Cli::try_parse_from(base.iter().chain(args))
+    }
+
+    #[test]
+    fn command_definitions_are_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_connection_flags() {
+        let cli = parse(&["--secret-key", "SKEY", "unpause"]).unwrap();
+        assert_eq!(cli.rpc_url, "http://localhost:8000");
+        assert_eq!(cli.contract, "CCREDIT");
+        assert_eq!(cli.secret_key.as_deref(), Some("SKEY"));
+        assert!(matches!(cli.command, Command::Unpause));
+    }
+
+    #[test]
+    fn parses_admin_commands() {
+        let cli = parse(&["set-ltv", "CTOKEN", "6500", "--threshold", "7500"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::SetLtv { token, ratio: 6500, threshold: Some(7500) } if token == "CTOKEN"
+        ));
+
+        let cli = parse(&["set-ltv", "CTOKEN", "6500"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::SetLtv {
+                threshold: None,
+                ..
+            }
+        ));
+
+        let cli = parse(&["init", "--benji", "CBENJI", "--usdc", "CUSDC"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Init { benji, usdc } if benji == "CBENJI" && usdc == "CUSDC"
+        ));
+
+        assert!(matches!(
+            parse(&["pause"]).unwrap().command,
+            Command::Pause { repay: false }
+        ));
+        assert!(matches!(
+            parse(&["pause", "--repay"]).unwrap().command,
+            Command::Pause { repay: true }
+        ));
+    }
+
+    #[test]
+    fn listing_defaults_to_the_first_page() {
+        assert!(matches!(
+            parse(&["list-positions"]).unwrap().command,
+            Command::ListPositions {
+                offset: 0,
+                limit: MAX_PAGE_SIZE
+            }
+        ));
+        assert!(matches!(
+            parse(&["position", "GUSER", "--account", "2"]).unwrap().command,
+            Command::Position { address, account: Some(2) } if address == "GUSER"
+        ));
+        assert!(matches!(
+            parse(&["events"]).unwrap().command,
+            Command::Events {
+                start_ledger: None,
+                limit: 100
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_arguments() {
+        assert!(parse(&["set-ltv", "CTOKEN", "65%"]).is_err());
+        assert!(parse(&["set-ltv", "CTOKEN"]).is_err());
+        assert!(parse(&["init", "--benji", "CBENJI"]).is_err());
+        assert!(parse(&["list-positions", "--limit", "-1"]).is_err());
+        assert!(parse(&["liquidate"]).is_err());
+    }
+
+    #[test]
+    fn formats_health_factors() {
+        assert_eq!(format_health(HEALTH_FACTOR_ONE * 3 / 2), "1.5000");
+        assert_eq!(format_health(9_876_543), "0.9876");
+        assert_eq!(format_health(i128::MAX), "-");
+    }
+}
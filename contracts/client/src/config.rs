@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use stellar_xdr::curr::{ScMap, ScMapEntry, ScVal};
+
+use crate::scval::{field, symbol, to_address, to_i128, to_map, to_option, to_u32, to_u64};
+use crate::Error;
+
+/// Market parameters, as `get_config` returns them
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketConfig {
+    pub benji_token: String,
+    pub usdc_token: String,
+    pub collateral: BTreeMap<String, CollateralConfig>, // token address -> its limits
+    pub oracle: Option<String>,
+    pub treasury: Option<String>,
+    pub btoken: Option<String>,
+    pub debt_token: Option<String>,
+    pub staking: Option<String>,
+    pub interest_rate: u32,
+    pub rate_slope: u32,
+    pub stable_rate_premium: u32,
+    pub liquidation_bonus: u32,
+    pub reserve_factor: u32,
+    pub flash_loan_fee: u32,
+    pub debt_ceiling: Option<i128>,
+    pub min_borrow: i128,
+    pub min_collateral: i128,
+    pub grace_period: u64,
+    pub max_price_age: u64,
+    pub max_price_deviation: u32,
+    pub twap_window: u64,
+    pub interest_free_period: u64,
+    pub late_penalty_rate: u32,
+    pub close_factor: u32,
+    pub compounding: Compounding,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compounding {
+    Linear,
+    PerLedger,
+    Daily,
+}
+
+/// Borrowing and liquidation limits of one collateral token, in basis points
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CollateralConfig {
+    pub ltv_ratio: u32,
+    pub liquidation_threshold: u32,
+}
+
+impl TryFrom<&ScVal> for CollateralConfig {
+    type Error = Error;
+
+    fn try_from(value: &ScVal) -> Result<Self, Error> {
+        Ok(CollateralConfig {
+            ltv_ratio: to_u32(field(value, "ltv_ratio")?)?,
+            liquidation_threshold: to_u32(field(value, "liquidation_threshold")?)?,
+        })
+    }
+}
+
+impl TryFrom<CollateralConfig> for ScVal {
+    type Error = Error;
+
+    fn try_from(config: CollateralConfig) -> Result<Self, Error> {
+        // Struct fields are map entries sorted by name
+        let entries = vec![
+            ScMapEntry {
+                key: symbol("liquidation_threshold")?,
+                val: config.liquidation_threshold.into(),
+            },
+            ScMapEntry {
+                key: symbol("ltv_ratio")?,
+                val: config.ltv_ratio.into(),
+            },
+        ];
+        Ok(ScVal::Map(Some(ScMap(entries.try_into()?))))
+    }
+}
+
+impl TryFrom<&ScVal> for MarketConfig {
+    type Error = Error;
+
+    fn try_from(value: &ScVal) -> Result<Self, Error> {
+        let address = |name| to_option(field(value, name)?, to_address);
+        let collateral = to_map(field(value, "collateral")?)?
+            .iter()
+            .map(|entry| {
+                Ok((
+                    to_address(&entry.key)?,
+                    CollateralConfig::try_from(&entry.val)?,
+                ))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let compounding = match to_u32(field(value, "compounding")?)? {
+            0 => Compounding::Linear,
+            1 => Compounding::PerLedger,
+            2 => Compounding::Daily,
+            mode => return Err(Error::Decode(format!("unknown compounding {mode}"))),
+        };
+
+        Ok(MarketConfig {
+            benji_token: to_address(field(value, "benji_token")?)?,
+            usdc_token: to_address(field(value, "usdc_token")?)?,
+            collateral,
+            oracle: address("oracle")?,
+            treasury: address("treasury")?,
+            btoken: address("btoken")?,
+            debt_token: address("debt_token")?,
+            staking: address("staking")?,
+            interest_rate: to_u32(field(value, "interest_rate")?)?,
+            rate_slope: to_u32(field(value, "rate_slope")?)?,
+            stable_rate_premium: to_u32(field(value, "stable_rate_premium")?)?,
+            liquidation_bonus: to_u32(field(value, "liquidation_bonus")?)?,
+            reserve_factor: to_u32(field(value, "reserve_factor")?)?,
+            flash_loan_fee: to_u32(field(value, "flash_loan_fee")?)?,
+            debt_ceiling: to_option(field(value, "debt_ceiling")?, to_i128)?,
+            min_borrow: to_i128(field(value, "min_borrow")?)?,
+            min_collateral: to_i128(field(value, "min_collateral")?)?,
+            grace_period: to_u64(field(value, "grace_period")?)?,
+            max_price_age: to_u64(field(value, "max_price_age")?)?,
+            max_price_deviation: to_u32(field(value, "max_price_deviation")?)?,
+            twap_window: to_u64(field(value, "twap_window")?)?,
+            interest_free_period: to_u64(field(value, "interest_free_period")?)?,
+            late_penalty_rate: to_u32(field(value, "late_penalty_rate")?)?,
+            close_factor: to_u32(field(value, "close_factor")?)?,
+            compounding,
+        })
+    }
+}
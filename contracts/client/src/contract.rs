@@ -0,0 +1,193 @@
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use stellar_xdr::curr::{
+    AccountId, HostFunction, InvokeContractArgs, InvokeHostFunctionOp, Memo, MuxedAccount,
+    Operation, OperationBody, Preconditions, PublicKey, ScAddress, ScSymbol, ScVal, SequenceNumber,
+    SorobanAuthorizationEntry, TimeBounds, TimePoint, Transaction, TransactionEnvelope,
+    TransactionExt, TransactionV1Envelope, Uint256, VecM,
+};
+
+use crate::rpc::{Rpc, TransactionStatus};
+use crate::{Error, Signer};
+
+/// Inclusion fee offered on top of the resource fee simulation asks for, in stroops
+const BASE_FEE: u32 = 100;
+
+/// Seconds a signed transaction stays valid for
+const TRANSACTION_TIMEOUT: u64 = 300;
+
+/// How often to check whether a submitted transaction has landed
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait for a submitted transaction before giving up
+const WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Any deployed contract, called by function name with raw arguments
+///
+/// Calls that change state are simulated, signed by the given signer, submitted
+/// and awaited. The signer is also the transaction source, so the auth
+/// simulation returns needs no signatures beyond the envelope's own.
+#[derive(Clone)]
+pub struct Contract {
+    rpc: Rpc,
+    id: String,
+    address: ScAddress,
+    network_passphrase: String,
+}
+
+/// A simulated call, ready to sign and submit
+pub struct Prepared {
+    pub transaction: Transaction,
+    pub result: ScVal, // what the call returned in simulation
+    pub fee: u32,      // total fee the transaction offers, in stroops
+}
+
+impl Contract {
+    /// Connect to the contract `id` (`C...`) through the RPC server at `rpc_url`
+    pub fn connect(rpc_url: &str, id: &str) -> Result<Self, Error> {
+        let rpc = Rpc::new(rpc_url);
+        let network_passphrase = rpc.network_passphrase()?;
+        Self::new(rpc, id, network_passphrase)
+    }
+
+    fn new(rpc: Rpc, id: &str, network_passphrase: String) -> Result<Self, Error> {
+        let address = id.parse().map_err(|_| Error::InvalidKey(id.to_string()))?;
+        Ok(Self {
+            rpc,
+            id: id.to_string(),
+            address,
+            network_passphrase,
+        })
+    }
+
+    /// Another contract on the same RPC server
+    pub fn at(&self, id: &str) -> Result<Self, Error> {
+        Self::new(self.rpc.clone(), id, self.network_passphrase.clone())
+    }
+
+    pub fn rpc(&self) -> &Rpc {
+        &self.rpc
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Simulate a call as `signer` to learn its result and fee before submitting
+    pub fn prepare(
+        &self,
+        signer: &Signer,
+        function: &str,
+        args: Vec<ScVal>,
+    ) -> Result<Prepared, Error> {
+        let source = signer.account_id();
+        let sequence = self.rpc.account_sequence(&source)? + 1;
+
+        let unsigned = self.transaction(&source, sequence, function, args.clone(), Vec::new())?;
+        let simulation = self.rpc.simulate(&envelope(unsigned))?;
+
+        let mut transaction =
+            self.transaction(&source, sequence, function, args, simulation.auth)?;
+        transaction.fee = BASE_FEE.saturating_add(simulation.min_resource_fee);
+        transaction.ext = TransactionExt::V1(simulation.transaction_data);
+
+        Ok(Prepared {
+            fee: transaction.fee,
+            transaction,
+            result: simulation.result,
+        })
+    }
+
+    /// Sign and submit a prepared call, returning its result once it lands
+    pub fn submit(&self, signer: &Signer, prepared: Prepared) -> Result<ScVal, Error> {
+        let signed = signer.sign(prepared.transaction, &self.network_passphrase)?;
+        let hash = self.rpc.send(&signed)?;
+        self.wait(&hash)
+    }
+
+    /// Call a contract function in a signed transaction, returning its result
+    pub fn invoke(
+        &self,
+        signer: &Signer,
+        function: &str,
+        args: Vec<ScVal>,
+    ) -> Result<ScVal, Error> {
+        let prepared = self.prepare(signer, function, args)?;
+        self.submit(signer, prepared)
+    }
+
+    /// Call a read-only contract function by simulation alone
+    pub fn view(&self, function: &str, args: Vec<ScVal>) -> Result<ScVal, Error> {
+        // Simulation does not check the source account, so any key will do
+        let source = AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([0; 32])));
+        let transaction = self.transaction(&source, 0, function, args, Vec::new())?;
+        Ok(self.rpc.simulate(&envelope(transaction))?.result)
+    }
+
+    fn transaction(
+        &self,
+        source: &AccountId,
+        sequence: i64,
+        function: &str,
+        args: Vec<ScVal>,
+        auth: Vec<SorobanAuthorizationEntry>,
+    ) -> Result<Transaction, Error> {
+        let AccountId(PublicKey::PublicKeyTypeEd25519(key)) = source;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+
+        Ok(Transaction {
+            source_account: MuxedAccount::Ed25519(key.clone()),
+            fee: BASE_FEE,
+            seq_num: SequenceNumber(sequence),
+            cond: Preconditions::Time(TimeBounds {
+                min_time: TimePoint(0),
+                max_time: TimePoint(now + TRANSACTION_TIMEOUT),
+            }),
+            memo: Memo::None,
+            operations: vec![Operation {
+                source_account: None,
+                body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+                    host_function: HostFunction::InvokeContract(InvokeContractArgs {
+                        contract_address: self.address.clone(),
+                        function_name: ScSymbol(function.try_into()?),
+                        args: args.try_into()?,
+                    }),
+                    auth: auth.try_into()?,
+                }),
+            }]
+            .try_into()?,
+            ext: TransactionExt::V0,
+        })
+    }
+
+    /// Poll until a submitted transaction lands, returning the call's result
+    fn wait(&self, hash: &str) -> Result<ScVal, Error> {
+        let started = Instant::now();
+        loop {
+            match self.rpc.transaction(hash)? {
+                TransactionStatus::Success(result) => return Ok(result),
+                TransactionStatus::Failed => {
+                    return Err(Error::Transaction {
+                        hash: hash.to_string(),
+                        status: "failed".to_string(),
+                    })
+                }
+                TransactionStatus::Pending if started.elapsed() >= WAIT_TIMEOUT => {
+                    return Err(Error::Timeout(hash.to_string()))
+                }
+                TransactionStatus::Pending => thread::sleep(POLL_INTERVAL),
+            }
+        }
+    }
+}
+
+/// An unsigned envelope, which is all simulation needs
+fn envelope(transaction: Transaction) -> TransactionEnvelope {
+    TransactionEnvelope::Tx(TransactionV1Envelope {
+        tx: transaction,
+        signatures: VecM::default(),
+    })
+}
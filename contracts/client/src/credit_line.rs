@@ -0,0 +1,368 @@
+use stellar_xdr::curr::ScVal;
+
+use crate::contract::Prepared;
+use crate::event::Event;
+use crate::scval::{address, to_address, to_bool, to_i128, to_u32, to_vec};
+use crate::{CollateralConfig, Contract, Error, MarketConfig, OracleSettings, Position, Signer};
+
+/// A deployed credit line contract, with typed calls for its common entry points
+///
+/// Anything without a typed wrapper can be called through `contract()`.
+pub struct CreditLine {
+    contract: Contract,
+}
+
+impl CreditLine {
+    /// Connect to the credit line `contract_id` (`C...`) through the RPC server at `rpc_url`
+    pub fn connect(rpc_url: &str, contract_id: &str) -> Result<Self, Error> {
+        Ok(Self::new(Contract::connect(rpc_url, contract_id)?))
+    }
+
+    pub fn new(contract: Contract) -> Self {
+        Self { contract }
+    }
+
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+
+    /// Credit line events from `start_ledger` on, at most `limit` of them
+    pub fn events(&self, start_ledger: u32, limit: u32) -> Result<Vec<Event>, Error> {
+        self.contract
+            .rpc()
+            .events(self.contract.id(), start_ledger, None, limit)?
+            .events
+            .into_iter()
+            .map(Event::try_from)
+            .collect()
+    }
+
+    /// Initialize a freshly deployed credit line with the signer as admin
+    pub fn initialize(
+        &self,
+        signer: &Signer,
+        benji_token: &str,
+        usdc_token: &str,
+    ) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "initialize",
+            vec![
+                address(&signer.address())?,
+                address(benji_token)?,
+                address(usdc_token)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Accept a collateral token or replace its limits (admin only)
+    pub fn set_collateral_config(
+        &self,
+        signer: &Signer,
+        token: &str,
+        config: CollateralConfig,
+    ) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "set_collateral_config",
+            vec![
+                address(&signer.address())?,
+                address(token)?,
+                config.try_into()?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Set the loan-to-value ratio of a collateral token in basis points (admin only)
+    pub fn set_ltv_ratio(&self, signer: &Signer, token: &str, ratio: u32) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "set_ltv_ratio",
+            vec![address(&signer.address())?, address(token)?, ratio.into()],
+        )?;
+        Ok(())
+    }
+
+    /// Set the price oracle used to value collateral (admin only)
+    pub fn set_oracle(&self, signer: &Signer, oracle: &str) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "set_oracle",
+            vec![address(&signer.address())?, address(oracle)?],
+        )?;
+        Ok(())
+    }
+
+    /// Accrue pool interest and keep the market's storage alive
+    pub fn poke(&self, signer: &Signer) -> Result<(), Error> {
+        self.contract.invoke(signer, "poke", vec![])?;
+        Ok(())
+    }
+
+    /// Halt the market, optionally still allowing repayments (admin only)
+    pub fn pause(&self, signer: &Signer, pause_repay: bool) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "pause",
+            vec![address(&signer.address())?, pause_repay.into()],
+        )?;
+        Ok(())
+    }
+
+    /// Resume the market (admin only)
+    pub fn unpause(&self, signer: &Signer) -> Result<(), Error> {
+        self.contract
+            .invoke(signer, "unpause", vec![address(&signer.address())?])?;
+        Ok(())
+    }
+
+    /// Deposit collateral into one of the signer's accounts
+    pub fn deposit_collateral(
+        &self,
+        signer: &Signer,
+        account_id: u32,
+        token: &str,
+        amount: i128,
+    ) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "deposit_collateral",
+            vec![
+                address(&signer.address())?,
+                account_id.into(),
+                address(token)?,
+                amount.into(),
+                ScVal::Void,
+                ScVal::Void,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Deposit collateral the signer has approved the credit line to spend
+    ///
+    /// The contract pulls the tokens with `transfer_from`, so the signer
+    /// authorizes only the deposit. Approve first with [`Token::approve`],
+    /// naming the credit line's id as spender; without enough allowance the
+    /// call fails with contract error 39 (`InsufficientAllowance`).
+    ///
+    /// [`Token::approve`]: crate::Token::approve
+    pub fn deposit_collateral_from(
+        &self,
+        signer: &Signer,
+        account_id: u32,
+        token: &str,
+        amount: i128,
+    ) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "deposit_collateral_from",
+            vec![
+                address(&signer.address())?,
+                account_id.into(),
+                address(token)?,
+                amount.into(),
+                ScVal::Void,
+                ScVal::Void,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Borrow USDC against one of the signer's accounts
+    pub fn borrow(&self, signer: &Signer, account_id: u32, amount: i128) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "borrow",
+            vec![
+                address(&signer.address())?,
+                account_id.into(),
+                amount.into(),
+                ScVal::Void,
+                ScVal::Void,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Repay debt on one of the signer's accounts
+    pub fn repay(&self, signer: &Signer, account_id: u32, amount: i128) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "repay",
+            vec![
+                address(&signer.address())?,
+                account_id.into(),
+                amount.into(),
+                ScVal::Void,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Repay debt on one of the signer's accounts with USDC the signer has
+    /// approved the credit line to spend
+    ///
+    /// Like [`CreditLine::deposit_collateral_from`], the signer authorizes only
+    /// the repayment; without enough allowance the call fails with contract
+    /// error 39 (`InsufficientAllowance`).
+    pub fn repay_from(&self, signer: &Signer, account_id: u32, amount: i128) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "repay_from",
+            vec![
+                address(&signer.address())?,
+                account_id.into(),
+                amount.into(),
+                ScVal::Void,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Withdraw collateral from one of the signer's accounts
+    pub fn withdraw_collateral(
+        &self,
+        signer: &Signer,
+        account_id: u32,
+        token: &str,
+        amount: i128,
+    ) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "withdraw_collateral",
+            vec![
+                address(&signer.address())?,
+                account_id.into(),
+                address(token)?,
+                amount.into(),
+                ScVal::Void,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Simulate liquidating a position as `signer`; the prepared result is the
+    /// collateral the call would seize
+    pub fn prepare_liquidate(
+        &self,
+        signer: &Signer,
+        user: &str,
+        account_id: u32,
+        token: &str,
+        repay_amount: i128,
+    ) -> Result<Prepared, Error> {
+        self.contract.prepare(
+            signer,
+            "liquidate",
+            vec![
+                address(&signer.address())?,
+                address(user)?,
+                account_id.into(),
+                address(token)?,
+                repay_amount.into(),
+            ],
+        )
+    }
+
+    /// Repay part of an underwater position's debt, returning the collateral seized
+    pub fn liquidate(
+        &self,
+        signer: &Signer,
+        user: &str,
+        account_id: u32,
+        token: &str,
+        repay_amount: i128,
+    ) -> Result<i128, Error> {
+        let prepared = self.prepare_liquidate(signer, user, account_id, token, repay_amount)?;
+        to_i128(&self.contract.submit(signer, prepared)?)
+    }
+
+    pub fn config(&self) -> Result<MarketConfig, Error> {
+        MarketConfig::try_from(&self.contract.view("get_config", Vec::new())?)
+    }
+
+    pub fn oracle_settings(&self) -> Result<OracleSettings, Error> {
+        OracleSettings::try_from(&self.contract.view("get_oracle_settings", Vec::new())?)
+    }
+
+    pub fn position(&self, user: &str, account_id: u32) -> Result<Position, Error> {
+        let value = self
+            .contract
+            .view("get_position", vec![address(user)?, account_id.into()])?;
+        Position::try_from(&value)
+    }
+
+    /// Debt with interest accrued to the latest ledger
+    pub fn current_debt(&self, user: &str, account_id: u32) -> Result<i128, Error> {
+        to_i128(
+            &self
+                .contract
+                .view("get_current_debt", vec![address(user)?, account_id.into()])?,
+        )
+    }
+
+    /// Health factor, where `HEALTH_FACTOR_ONE` (1e7) is the liquidation line
+    pub fn health_factor(&self, user: &str, account_id: u32) -> Result<i128, Error> {
+        to_i128(
+            &self
+                .contract
+                .view("get_health_factor", vec![address(user)?, account_id.into()])?,
+        )
+    }
+
+    /// Borrow rate less the yield of the BENJI collateral, in basis points of
+    /// the debt; negative when the collateral out-earns the debt
+    pub fn net_rate(&self, user: &str, account_id: u32) -> Result<i128, Error> {
+        to_i128(
+            &self
+                .contract
+                .view("get_net_rate", vec![address(user)?, account_id.into()])?,
+        )
+    }
+
+    pub fn is_paused(&self) -> Result<bool, Error> {
+        to_bool(&self.contract.view("is_paused", Vec::new())?)
+    }
+
+    /// Account ids the user has opened
+    pub fn accounts(&self, user: &str) -> Result<Vec<u32>, Error> {
+        to_vec(&self.contract.view("get_accounts", vec![address(user)?])?)?
+            .iter()
+            .map(to_u32)
+            .collect()
+    }
+
+    /// Number of users that have opened a position
+    pub fn user_count(&self) -> Result<u32, Error> {
+        to_u32(&self.contract.view("get_user_count", Vec::new())?)
+    }
+
+    /// Users with positions, `limit` of them from `offset`, in the order they first deposited
+    pub fn list_users(&self, offset: u32, limit: u32) -> Result<Vec<String>, Error> {
+        to_vec(
+            &self
+                .contract
+                .view("list_users", vec![offset.into(), limit.into()])?,
+        )?
+        .iter()
+        .map(to_address)
+        .collect()
+    }
+
+    /// Liquidatable `(user, account id)` pairs among `limit` users from `offset`
+    pub fn list_liquidatable(&self, offset: u32, limit: u32) -> Result<Vec<(String, u32)>, Error> {
+        let value = self
+            .contract
+            .view("list_liquidatable", vec![offset.into(), limit.into()])?;
+        to_vec(&value)?
+            .iter()
+            .map(|pair| match to_vec(pair)?.as_slice() {
+                [user, account_id] => Ok((to_address(user)?, to_u32(account_id)?)),
+                _ => Err(Error::Decode("expected (user, account id)".to_string())),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+
+/// Everything that can go wrong talking to a deployed contract
+#[derive(Debug)]
+pub enum Error {
+    /// The RPC server could not be reached or sent back something unreadable
+    Http(String),
+    /// The RPC server rejected the request
+    Rpc { code: i64, message: String },
+    /// XDR that failed to encode or decode
+    Xdr(stellar_xdr::curr::Error),
+    /// A secret key or address that is not a valid strkey
+    InvalidKey(String),
+    /// The contract returned one of its `Error` codes
+    Contract(u32),
+    /// Simulation failed for a reason other than a contract error
+    Simulation(String),
+    /// The transaction was rejected on submission or failed on chain
+    Transaction { hash: String, status: String },
+    /// The transaction was still pending when the client stopped waiting
+    Timeout(String),
+    /// A contract value did not have the shape the client expected
+    Decode(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(message) => write!(f, "rpc transport error: {message}"),
+            Error::Rpc { code, message } => write!(f, "rpc error {code}: {message}"),
+            Error::Xdr(error) => write!(f, "xdr error: {error}"),
+            Error::InvalidKey(key) => write!(f, "invalid key or address: {key}"),
+            Error::Contract(code) => write!(f, "contract error #{code}"),
+            Error::Simulation(message) => write!(f, "simulation failed: {message}"),
+            Error::Transaction { hash, status } => write!(f, "transaction {hash} {status}"),
+            Error::Timeout(hash) => write!(f, "timed out waiting for transaction {hash}"),
+            Error::Decode(message) => write!(f, "unexpected contract value: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<stellar_xdr::curr::Error> for Error {
+    fn from(error: stellar_xdr::curr::Error) -> Self {
+        Error::Xdr(error)
+    }
+}
+
+impl From<ureq::Error> for Error {
+    fn from(error: ureq::Error) -> Self {
+        Error::Http(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Http(error.to_string())
+    }
+}
+
+impl From<bondbridge_events::DecodeError> for Error {
+    fn from(error: bondbridge_events::DecodeError) -> Self {
+        match error {
+            bondbridge_events::DecodeError::Xdr(error) => Error::Xdr(error),
+            error => Error::Decode(error.to_string()),
+        }
+    }
+}
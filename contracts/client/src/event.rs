@@ -0,0 +1,26 @@
+use bondbridge_events::{CreditLineEvent, DecodeEvent};
+
+use crate::rpc::RawEvent;
+use crate::Error;
+
+/// A credit line event and where it was emitted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub id: String, // paging token, unique per event
+    pub ledger: u32,
+    pub tx_hash: String,
+    pub event: CreditLineEvent,
+}
+
+impl TryFrom<RawEvent> for Event {
+    type Error = Error;
+
+    fn try_from(event: RawEvent) -> Result<Self, Error> {
+        Ok(Event {
+            event: CreditLineEvent::decode(&event.topics, &event.value)?,
+            id: event.id,
+            ledger: event.ledger,
+            tx_hash: event.tx_hash,
+        })
+    }
+}
@@ -0,0 +1,43 @@
+//! Off-chain client for the BondBridge contracts, for bots and backends.
+//!
+//! [`CreditLine`] wraps a deployed credit line behind typed calls: state
+//! changes are simulated, signed with a [`Signer`], submitted and awaited over
+//! Soroban RPC, and views are answered by simulation alone. Positions, config
+//! and events come back decoded into plain Rust types. [`Oracle`] and [`Token`]
+//! do the same for the price oracle and the tokens the market uses, and
+//! [`Contract`] calls any other contract by function name.
+//!
+//! ```no_run
+//! use bondbridge_client::{CreditLine, Signer};
+//!
+//! let credit_line = CreditLine::connect("https://soroban-testnet.stellar.org", "C...")?;
+//! let signer = Signer::from_secret("S...")?;
+//!
+//! credit_line.borrow(&signer, 0, 100_0000000)?;
+//! let position = credit_line.position(&signer.address(), 0)?;
+//! # Ok::<(), bondbridge_client::Error>(())
+//! ```
+
+mod config;
+mod contract;
+mod credit_line;
+mod error;
+mod event;
+mod oracle;
+mod position;
+pub mod rpc;
+pub mod scval;
+mod signer;
+mod token;
+
+pub use bondbridge_events::{self as events, CreditLineEvent};
+pub use config::{CollateralConfig, Compounding, MarketConfig};
+pub use contract::{Contract, Prepared};
+pub use credit_line::CreditLine;
+pub use error::Error;
+pub use event::Event;
+pub use oracle::{Asset, Oracle, OracleSettings, PriceData, QuoteAsset};
+pub use position::{FixedLoan, LoanTerms, Position, RateMode, Tranche};
+pub use signer::Signer;
+pub use stellar_xdr::curr as xdr;
+pub use token::Token;
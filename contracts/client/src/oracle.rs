@@ -0,0 +1,105 @@
+use stellar_xdr::curr::ScVal;
+
+use crate::scval::{
+    address, field, symbol, to_address, to_bool, to_i128, to_option, to_symbol, to_u32, to_u64,
+    to_variant,
+};
+use crate::{Contract, Error};
+
+/// A SEP-40 price oracle, such as the one the credit line prices collateral with
+pub struct Oracle {
+    contract: Contract,
+}
+
+/// A price record, with `decimals` of precision
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+/// An asset as SEP-40 price feeds identify it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    Stellar(String), // token contract address
+    Other(String),   // symbol, such as `USD`
+}
+
+/// Asset the credit line's oracle quotes token prices in
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum QuoteAsset {
+    Usdc,
+    Other(Asset), // with USDC priced in it under this entry
+}
+
+/// How the credit line reads its oracle's prices, as `get_oracle_settings` returns them
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OracleSettings {
+    pub decimals: Option<u32>, // overrides the feed's `decimals`
+    pub quote: QuoteAsset,
+    pub invert: bool, // the feed reports the quote asset per token
+}
+
+impl TryFrom<&ScVal> for Asset {
+    type Error = Error;
+
+    fn try_from(value: &ScVal) -> Result<Self, Error> {
+        match to_variant(value)? {
+            (name, [token]) if name == "Stellar" => Ok(Asset::Stellar(to_address(token)?)),
+            (name, [symbol]) if name == "Other" => Ok(Asset::Other(to_symbol(symbol)?)),
+            (name, _) => Err(Error::Decode(format!("unknown asset {name}"))),
+        }
+    }
+}
+
+impl TryFrom<&Asset> for ScVal {
+    type Error = Error;
+
+    fn try_from(asset: &Asset) -> Result<Self, Error> {
+        let fields = match asset {
+            Asset::Stellar(token) => vec![symbol("Stellar")?, address(token)?],
+            Asset::Other(name) => vec![symbol("Other")?, symbol(name)?],
+        };
+        Ok(ScVal::Vec(Some(fields.try_into()?)))
+    }
+}
+
+impl TryFrom<&ScVal> for OracleSettings {
+    type Error = Error;
+
+    fn try_from(value: &ScVal) -> Result<Self, Error> {
+        let quote = match to_variant(field(value, "quote")?)? {
+            (name, []) if name == "Usdc" => QuoteAsset::Usdc,
+            (name, [asset]) if name == "Other" => QuoteAsset::Other(Asset::try_from(asset)?),
+            (name, _) => return Err(Error::Decode(format!("unknown quote asset {name}"))),
+        };
+
+        Ok(OracleSettings {
+            decimals: to_option(field(value, "decimals")?, to_u32)?,
+            quote,
+            invert: to_bool(field(value, "invert")?)?,
+        })
+    }
+}
+
+impl Oracle {
+    pub fn new(contract: Contract) -> Self {
+        Self { contract }
+    }
+
+    /// Number of decimals prices are reported with
+    pub fn decimals(&self) -> Result<u32, Error> {
+        to_u32(&self.contract.view("decimals", Vec::new())?)
+    }
+
+    /// Latest price of an asset, if the oracle has one
+    pub fn lastprice(&self, asset: &Asset) -> Result<Option<PriceData>, Error> {
+        let value = self.contract.view("lastprice", vec![asset.try_into()?])?;
+        to_option(&value, |record| {
+            Ok(PriceData {
+                price: to_i128(field(record, "price")?)?,
+                timestamp: to_u64(field(record, "timestamp")?)?,
+            })
+        })
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use stellar_xdr::curr::ScVal;
+
+use crate::scval::{field, to_address, to_i128, to_map, to_u32, to_u64, to_variant, to_vec};
+use crate::Error;
+
+/// One of a user's credit line accounts, as `get_position` returns it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Position {
+    pub collateral: BTreeMap<String, i128>, // token address -> amount deposited
+    pub borrowed: i128,
+    pub last_update: u64,
+    pub borrow_index: i128,
+    pub rate_mode: RateMode,
+    pub stable_borrowed: i128, // part of `borrowed` at `stable_rate`
+    pub stable_rate: u32,
+    pub free_tranches: Vec<Tranche>,
+    pub terms: LoanTerms,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RateMode {
+    Variable,
+    Stable,
+}
+
+/// Borrowed principal that accrues no interest until `free_until`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tranche {
+    pub amount: i128,
+    pub free_until: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LoanTerms {
+    Open,
+    Fixed(FixedLoan),
+}
+
+/// Repayment schedule of a fixed-term loan
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FixedLoan {
+    pub principal: i128,
+    pub start_ledger: u32,
+    pub installments: u32,
+    pub interval: u32,
+    pub outstanding: i128, // principal not yet repaid
+}
+
+impl TryFrom<&ScVal> for Position {
+    type Error = Error;
+
+    fn try_from(value: &ScVal) -> Result<Self, Error> {
+        let collateral = to_map(field(value, "collateral")?)?
+            .iter()
+            .map(|entry| Ok((to_address(&entry.key)?, to_i128(&entry.val)?)))
+            .collect::<Result<_, Error>>()?;
+
+        let rate_mode = match to_u32(field(value, "rate_mode")?)? {
+            0 => RateMode::Variable,
+            1 => RateMode::Stable,
+            mode => return Err(Error::Decode(format!("unknown rate mode {mode}"))),
+        };
+
+        let free_tranches = to_vec(field(value, "free_tranches")?)?
+            .iter()
+            .map(|tranche| {
+                Ok(Tranche {
+                    amount: to_i128(field(tranche, "amount")?)?,
+                    free_until: to_u64(field(tranche, "free_until")?)?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let terms = match to_variant(field(value, "terms")?)? {
+            (name, []) if name == "Open" => LoanTerms::Open,
+            (name, [loan]) if name == "Fixed" => LoanTerms::Fixed(FixedLoan {
+                principal: to_i128(field(loan, "principal")?)?,
+                start_ledger: to_u32(field(loan, "start_ledger")?)?,
+                installments: to_u32(field(loan, "installments")?)?,
+                interval: to_u32(field(loan, "interval")?)?,
+                outstanding: to_i128(field(loan, "outstanding")?)?,
+            }),
+            (name, _) => return Err(Error::Decode(format!("unknown loan terms {name}"))),
+        };
+
+        Ok(Position {
+            collateral,
+            borrowed: to_i128(field(value, "borrowed")?)?,
+            last_update: to_u64(field(value, "last_update")?)?,
+            borrow_index: to_i128(field(value, "borrow_index")?)?,
+            rate_mode,
+            stable_borrowed: to_i128(field(value, "stable_borrowed")?)?,
+            stable_rate: to_u32(field(value, "stable_rate")?)?,
+            free_tranches,
+            terms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stellar_xdr::curr::{Limits, ReadXdr, ScMap, ScMapEntry, ScVec, WriteXdr};
+
+    use super::*;
+    use crate::scval::{address, symbol};
+
+    fn map(entries: Vec<(ScVal, ScVal)>) -> ScVal {
+        let entries: Vec<_> = entries
+            .into_iter()
+            .map(|(key, val)| ScMapEntry { key, val })
+            .collect();
+        ScVal::Map(Some(ScMap(entries.try_into().unwrap())))
+    }
+
+    fn record(fields: Vec<(&str, ScVal)>) -> ScVal {
+        map(fields
+            .into_iter()
+            .map(|(name, value)| (symbol(name).unwrap(), value))
+            .collect())
+    }
+
+    fn list(items: Vec<ScVal>) -> ScVal {
+        ScVal::Vec(Some(ScVec(items.try_into().unwrap())))
+    }
+
+    /// A position as the contract encodes it, with the given rate mode and terms
+    fn position(token: &str, rate_mode: u32, terms: ScVal) -> ScVal {
+        record(vec![
+            (
+                "collateral",
+                map(vec![(address(token).unwrap(), 1_000_i128.into())]),
+            ),
+            ("borrowed", 500_i128.into()),
+            ("last_update", 1_700_000_000_u64.into()),
+            ("borrow_index", 10_i128.pow(27).into()),
+            ("rate_mode", rate_mode.into()),
+            ("stable_borrowed", 200_i128.into()),
+            ("stable_rate", 700_u32.into()),
+            (
+                "free_tranches",
+                list(vec![record(vec![
+                    ("amount", 50_i128.into()),
+                    ("free_until", 1_700_086_400_u64.into()),
+                ])]),
+            ),
+            ("terms", terms),
+        ])
+    }
+
+    #[test]
+    fn decodes_a_position_from_xdr() {
+        let token = stellar_strkey::Contract([3; 32]).to_string();
+        let terms = list(vec![
+            symbol("Fixed").unwrap(),
+            record(vec![
+                ("principal", 200_i128.into()),
+                ("start_ledger", 100_u32.into()),
+                ("installments", 4_u32.into()),
+                ("interval", 17_280_u32.into()),
+                ("outstanding", 150_i128.into()),
+            ]),
+        ]);
+        let encoded = position(&token, 1, terms)
+            .to_xdr_base64(Limits::none())
+            .unwrap();
+        let value = ScVal::from_xdr_base64(encoded, Limits::none()).unwrap();
+
+        assert_eq!(
+            Position::try_from(&value).unwrap(),
+            Position {
+                collateral: BTreeMap::from([(token, 1_000)]),
+                borrowed: 500,
+                last_update: 1_700_000_000,
+                borrow_index: 10_i128.pow(27),
+                rate_mode: RateMode::Stable,
+                stable_borrowed: 200,
+                stable_rate: 700,
+                free_tranches: vec![Tranche {
+                    amount: 50,
+                    free_until: 1_700_086_400,
+                }],
+                terms: LoanTerms::Fixed(FixedLoan {
+                    principal: 200,
+                    start_ledger: 100,
+                    installments: 4,
+                    interval: 17_280,
+                    outstanding: 150,
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn refuses_unknown_modes_and_terms() {
+        let token = stellar_strkey::Contract([3; 32]).to_string();
+        let open = list(vec![symbol("Open").unwrap()]);
+        assert_eq!(
+            Position::try_from(&position(&token, 0, open.clone()))
+                .unwrap()
+                .terms,
+            LoanTerms::Open
+        );
+        assert!(matches!(
+            Position::try_from(&position(&token, 2, open)),
+            Err(Error::Decode(_))
+        ));
+        assert!(matches!(
+            Position::try_from(&position(&token, 0, list(vec![symbol("Bullet").unwrap()]))),
+            Err(Error::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,350 @@
+//! The handful of Soroban RPC methods the client needs, over JSON-RPC.
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use stellar_xdr::curr::{
+    AccountId, LedgerEntryData, LedgerKey, LedgerKeyAccount, Limits, ReadXdr, ScVal,
+    SorobanAuthorizationEntry, SorobanTransactionData, TransactionEnvelope, TransactionMeta,
+    WriteXdr,
+};
+
+use crate::Error;
+
+/// A Soroban RPC endpoint
+#[derive(Clone)]
+pub struct Rpc {
+    url: String,
+    agent: ureq::Agent,
+}
+
+/// What a simulated contract call returned and needs to be submitted
+pub struct Simulation {
+    pub result: ScVal,
+    pub auth: Vec<SorobanAuthorizationEntry>,
+    pub transaction_data: SorobanTransactionData,
+    pub min_resource_fee: u32,
+}
+
+/// Where a submitted transaction stands
+pub enum TransactionStatus {
+    /// Not yet in a closed ledger
+    Pending,
+    /// Applied, with the contract call's return value
+    Success(ScVal),
+    Failed,
+}
+
+/// A contract event as `getEvents` reports it
+pub struct RawEvent {
+    pub id: String,
+    pub ledger: u32,
+    pub tx_hash: String,
+    pub contract_id: String,
+    pub topics: Vec<ScVal>,
+    pub value: ScVal,
+}
+
+/// A page of events and the cursor to continue from
+pub struct EventPage {
+    pub events: Vec<RawEvent>,
+    pub latest_ledger: u32,
+    pub cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<ResponseError>,
+}
+
+#[derive(Deserialize)]
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Network {
+    passphrase: String,
+}
+
+#[derive(Deserialize)]
+struct LatestLedger {
+    sequence: u32,
+}
+
+#[derive(Deserialize)]
+struct LedgerEntries {
+    entries: Option<Vec<LedgerEntry>>,
+}
+
+#[derive(Deserialize)]
+struct LedgerEntry {
+    xdr: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulateResult {
+    error: Option<String>,
+    transaction_data: Option<String>,
+    min_resource_fee: Option<String>,
+    results: Option<Vec<SimulateHostFunctionResult>>,
+}
+
+#[derive(Deserialize)]
+struct SimulateHostFunctionResult {
+    auth: Vec<String>,
+    xdr: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendResult {
+    status: String,
+    hash: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetTransactionResult {
+    status: String,
+    result_meta_xdr: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventsResult {
+    events: Vec<EventInfo>,
+    latest_ledger: u32,
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventInfo {
+    id: String,
+    ledger: u32,
+    tx_hash: String,
+    contract_id: String,
+    topic: Vec<String>,
+    value: String,
+}
+
+impl Rpc {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+        let response: Response<T> = self
+            .agent
+            .post(&self.url)
+            .send_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))?
+            .into_json()?;
+
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(Error::Rpc {
+                code: error.code,
+                message: error.message,
+            }),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(Error::Http(format!("empty response to {method}"))),
+        }
+    }
+
+    /// Passphrase of the network the server is on, which transactions sign over
+    pub fn network_passphrase(&self) -> Result<String, Error> {
+        let network: Network = self.request("getNetwork", json!({}))?;
+        Ok(network.passphrase)
+    }
+
+    pub fn latest_ledger(&self) -> Result<u32, Error> {
+        let ledger: LatestLedger = self.request("getLatestLedger", json!({}))?;
+        Ok(ledger.sequence)
+    }
+
+    /// Current sequence number of a classic account
+    pub fn account_sequence(&self, account: &AccountId) -> Result<i64, Error> {
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: account.clone(),
+        });
+        let entries: LedgerEntries = self.request(
+            "getLedgerEntries",
+            json!({ "keys": [key.to_xdr_base64(Limits::none())?] }),
+        )?;
+
+        let entry = entries
+            .entries
+            .and_then(|entries| entries.into_iter().next())
+            .ok_or_else(|| Error::InvalidKey(format!("account {account} not found")))?;
+        match LedgerEntryData::from_xdr_base64(entry.xdr, Limits::none())? {
+            LedgerEntryData::Account(account) => Ok(account.seq_num.0),
+            _ => Err(Error::Decode("ledger entry is not an account".to_string())),
+        }
+    }
+
+    /// Simulate a transaction holding a single contract call
+    ///
+    /// A contract error is reported as `Error::Contract` with its code.
+    pub fn simulate(&self, transaction: &TransactionEnvelope) -> Result<Simulation, Error> {
+        let result: SimulateResult = self.request(
+            "simulateTransaction",
+            json!({ "transaction": transaction.to_xdr_base64(Limits::none())? }),
+        )?;
+
+        if let Some(error) = result.error {
+            return Err(contract_error(&error).map_or(Error::Simulation(error), Error::Contract));
+        }
+
+        let call = result
+            .results
+            .and_then(|results| results.into_iter().next())
+            .ok_or_else(|| Error::Simulation("no result".to_string()))?;
+        let transaction_data = result
+            .transaction_data
+            .ok_or_else(|| Error::Simulation("no transaction data".to_string()))?;
+        let min_resource_fee = result
+            .min_resource_fee
+            .and_then(|fee| fee.parse().ok())
+            .ok_or_else(|| Error::Simulation("no resource fee".to_string()))?;
+
+        Ok(Simulation {
+            result: ScVal::from_xdr_base64(call.xdr, Limits::none())?,
+            auth: call
+                .auth
+                .into_iter()
+                .map(|entry| SorobanAuthorizationEntry::from_xdr_base64(entry, Limits::none()))
+                .collect::<Result<_, _>>()?,
+            transaction_data: SorobanTransactionData::from_xdr_base64(
+                transaction_data,
+                Limits::none(),
+            )?,
+            min_resource_fee,
+        })
+    }
+
+    /// Submit a signed transaction, returning its hash
+    pub fn send(&self, transaction: &TransactionEnvelope) -> Result<String, Error> {
+        let result: SendResult = self.request(
+            "sendTransaction",
+            json!({ "transaction": transaction.to_xdr_base64(Limits::none())? }),
+        )?;
+
+        match result.status.as_str() {
+            "PENDING" | "DUPLICATE" => Ok(result.hash),
+            _ => Err(Error::Transaction {
+                hash: result.hash,
+                status: result.status.to_lowercase(),
+            }),
+        }
+    }
+
+    pub fn transaction(&self, hash: &str) -> Result<TransactionStatus, Error> {
+        let result: GetTransactionResult =
+            self.request("getTransaction", json!({ "hash": hash }))?;
+
+        match result.status.as_str() {
+            "SUCCESS" => {
+                let meta = result
+                    .result_meta_xdr
+                    .ok_or_else(|| Error::Decode("no transaction meta".to_string()))?;
+                Ok(TransactionStatus::Success(return_value(
+                    TransactionMeta::from_xdr_base64(meta, Limits::none())?,
+                )))
+            }
+            "NOT_FOUND" => Ok(TransactionStatus::Pending),
+            _ => Ok(TransactionStatus::Failed),
+        }
+    }
+
+    /// Events emitted by `contract_id`, from `start_ledger` or after `cursor`
+    pub fn events(
+        &self,
+        contract_id: &str,
+        start_ledger: u32,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> Result<EventPage, Error> {
+        let filters = json!([{ "type": "contract", "contractIds": [contract_id] }]);
+        let params = match cursor {
+            Some(cursor) => json!({
+                "filters": filters,
+                "pagination": { "cursor": cursor, "limit": limit },
+            }),
+            None => json!({
+                "startLedger": start_ledger,
+                "filters": filters,
+                "pagination": { "limit": limit },
+            }),
+        };
+        let result: EventsResult = self.request("getEvents", params)?;
+
+        let events = result
+            .events
+            .into_iter()
+            .map(|event| {
+                Ok(RawEvent {
+                    id: event.id,
+                    ledger: event.ledger,
+                    tx_hash: event.tx_hash,
+                    contract_id: event.contract_id,
+                    topics: event
+                        .topic
+                        .into_iter()
+                        .map(|topic| ScVal::from_xdr_base64(topic, Limits::none()))
+                        .collect::<Result<_, _>>()?,
+                    value: ScVal::from_xdr_base64(event.value, Limits::none())?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(EventPage {
+            events,
+            latest_ledger: result.latest_ledger,
+            cursor: result.cursor,
+        })
+    }
+}
+
+/// Contract error code in a host error message such as `Error(Contract, #9)`
+fn contract_error(message: &str) -> Option<u32> {
+    let start = message.find("Error(Contract, #")? + "Error(Contract, #".len();
+    let end = start + message[start..].find(')')?;
+    message[start..end].parse().ok()
+}
+
+fn return_value(meta: TransactionMeta) -> ScVal {
+    let value = match meta {
+        TransactionMeta::V3(meta) => meta.soroban_meta.map(|soroban| soroban.return_value),
+        TransactionMeta::V4(meta) => meta.soroban_meta.and_then(|soroban| soroban.return_value),
+        _ => None,
+    };
+    value.unwrap_or(ScVal::Void)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_contract_error_codes() {
+        assert_eq!(
+            contract_error("HostError: Error(Contract, #28)\n\nEvent log: ..."),
+            Some(28)
+        );
+        assert_eq!(
+            contract_error("HostError: Error(Auth, InvalidAction)"),
+            None
+        );
+        assert_eq!(contract_error("Error(Contract, #"), None);
+    }
+}
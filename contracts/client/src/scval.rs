@@ -0,0 +1,176 @@
+//! Conversions between contract values and plain Rust types.
+//!
+//! Contract structs arrive as maps keyed by field name, unit enums as `u32`
+//! and enums with data as a vector of the variant name and its fields.
+
+use stellar_xdr::curr::{ScAddress, ScMap, ScSymbol, ScVal, ScVec};
+
+use crate::Error;
+
+/// An address argument from its strkey (`G...` or `C...`)
+pub fn address(address: &str) -> Result<ScVal, Error> {
+    let address: ScAddress = address
+        .parse()
+        .map_err(|_| Error::InvalidKey(address.to_string()))?;
+    Ok(ScVal::Address(address))
+}
+
+pub fn symbol(symbol: &str) -> Result<ScVal, Error> {
+    Ok(ScVal::Symbol(ScSymbol(symbol.try_into()?)))
+}
+
+pub fn to_address(value: &ScVal) -> Result<String, Error> {
+    match value {
+        ScVal::Address(address) => Ok(address.to_string()),
+        _ => Err(unexpected("address", value)),
+    }
+}
+
+pub fn to_i128(value: &ScVal) -> Result<i128, Error> {
+    match value {
+        ScVal::I128(parts) => Ok(parts.into()),
+        _ => Err(unexpected("i128", value)),
+    }
+}
+
+pub fn to_u32(value: &ScVal) -> Result<u32, Error> {
+    match value {
+        ScVal::U32(value) => Ok(*value),
+        _ => Err(unexpected("u32", value)),
+    }
+}
+
+pub fn to_u64(value: &ScVal) -> Result<u64, Error> {
+    match value {
+        ScVal::U64(value) => Ok(*value),
+        _ => Err(unexpected("u64", value)),
+    }
+}
+
+pub fn to_bool(value: &ScVal) -> Result<bool, Error> {
+    match value {
+        ScVal::Bool(value) => Ok(*value),
+        _ => Err(unexpected("bool", value)),
+    }
+}
+
+pub fn to_symbol(value: &ScVal) -> Result<String, Error> {
+    match value {
+        ScVal::Symbol(symbol) => Ok(symbol.0.to_utf8_string_lossy()),
+        _ => Err(unexpected("symbol", value)),
+    }
+}
+
+pub fn to_vec(value: &ScVal) -> Result<&ScVec, Error> {
+    match value {
+        ScVal::Vec(Some(vec)) => Ok(vec),
+        _ => Err(unexpected("vec", value)),
+    }
+}
+
+pub fn to_map(value: &ScVal) -> Result<&ScMap, Error> {
+    match value {
+        ScVal::Map(Some(map)) => Ok(map),
+        _ => Err(unexpected("map", value)),
+    }
+}
+
+/// `None` for a void value, otherwise the value decoded by `decode`
+pub fn to_option<T>(
+    value: &ScVal,
+    decode: impl FnOnce(&ScVal) -> Result<T, Error>,
+) -> Result<Option<T>, Error> {
+    match value {
+        ScVal::Void => Ok(None),
+        value => decode(value).map(Some),
+    }
+}
+
+/// A named field of a contract struct
+pub fn field<'a>(value: &'a ScVal, name: &str) -> Result<&'a ScVal, Error> {
+    to_map(value)?
+        .iter()
+        .find(|entry| matches!(&entry.key, ScVal::Symbol(key) if key.0.as_vec() == name.as_bytes()))
+        .map(|entry| &entry.val)
+        .ok_or_else(|| Error::Decode(format!("missing field `{name}`")))
+}
+
+/// The variant name and fields of a contract enum with data
+pub fn to_variant(value: &ScVal) -> Result<(String, &[ScVal]), Error> {
+    let items = to_vec(value)?;
+    let (name, fields) = items
+        .split_first()
+        .ok_or_else(|| unexpected("enum variant", value))?;
+    Ok((to_symbol(name)?, fields))
+}
+
+fn unexpected(expected: &str, value: &ScVal) -> Error {
+    Error::Decode(format!("expected {expected}, got {value:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use stellar_xdr::curr::{Limits, ReadXdr, ScMapEntry, WriteXdr};
+
+    use super::*;
+
+    fn round_trip(value: &ScVal) -> ScVal {
+        let encoded = value.to_xdr_base64(Limits::none()).unwrap();
+        ScVal::from_xdr_base64(encoded, Limits::none()).unwrap()
+    }
+
+    #[test]
+    fn addresses_survive_xdr() {
+        let account = stellar_strkey::ed25519::PublicKey([7; 32]).to_string();
+        let contract = stellar_strkey::Contract([9; 32]).to_string();
+
+        for strkey in [account, contract] {
+            let value = round_trip(&address(&strkey).unwrap());
+            assert_eq!(to_address(&value).unwrap(), strkey);
+        }
+        assert!(matches!(address("G123"), Err(Error::InvalidKey(_))));
+    }
+
+    #[test]
+    fn numbers_survive_xdr() {
+        for amount in [0, -1, i128::MAX, i128::MIN, 12_345 * 10_000_000] {
+            assert_eq!(to_i128(&round_trip(&amount.into())).unwrap(), amount);
+        }
+        assert_eq!(to_u32(&round_trip(&u32::MAX.into())).unwrap(), u32::MAX);
+        assert_eq!(to_u64(&round_trip(&u64::MAX.into())).unwrap(), u64::MAX);
+        assert!(to_bool(&round_trip(&true.into())).unwrap());
+
+        // Values of another type are refused rather than converted
+        assert!(matches!(to_i128(&7_u32.into()), Err(Error::Decode(_))));
+        assert!(matches!(to_u64(&7_u32.into()), Err(Error::Decode(_))));
+    }
+
+    #[test]
+    fn structs_and_enums_decode_by_name() {
+        let entry = |key: &str, val: ScVal| ScMapEntry {
+            key: symbol(key).unwrap(),
+            val,
+        };
+        let value = round_trip(&ScVal::Map(Some(ScMap(
+            vec![entry("amount", 5_i128.into()), entry("expiry", ScVal::Void)]
+                .try_into()
+                .unwrap(),
+        ))));
+        assert_eq!(to_i128(field(&value, "amount").unwrap()).unwrap(), 5);
+        assert_eq!(
+            to_option(field(&value, "expiry").unwrap(), to_u64).unwrap(),
+            None
+        );
+        assert!(matches!(field(&value, "missing"), Err(Error::Decode(_))));
+
+        let variant = round_trip(&ScVal::Vec(Some(ScVec(
+            vec![symbol("Fixed").unwrap(), 3_u32.into()]
+                .try_into()
+                .unwrap(),
+        ))));
+        let (name, fields) = to_variant(&variant).unwrap();
+        assert_eq!(name, "Fixed");
+        assert_eq!(to_u32(&fields[0]).unwrap(), 3);
+        assert!(to_variant(&ScVal::Vec(Some(ScVec::default()))).is_err());
+    }
+}
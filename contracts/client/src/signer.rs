@@ -0,0 +1,58 @@
+use ed25519_dalek::{Signer as _, SigningKey};
+use sha2::{Digest, Sha256};
+use stellar_xdr::curr::{
+    AccountId, DecoratedSignature, PublicKey, Signature, SignatureHint, Transaction,
+    TransactionEnvelope, TransactionV1Envelope, Uint256,
+};
+
+use crate::Error;
+
+/// An ed25519 key that signs and pays for transactions
+pub struct Signer {
+    key: SigningKey,
+}
+
+impl Signer {
+    /// Load a signer from its secret seed (`S...`)
+    pub fn from_secret(secret: &str) -> Result<Self, Error> {
+        let seed = stellar_strkey::ed25519::PrivateKey::from_string(secret)
+            .map_err(|_| Error::InvalidKey("secret seed".to_string()))?;
+        Ok(Self {
+            key: SigningKey::from_bytes(&seed.0),
+        })
+    }
+
+    pub fn account_id(&self) -> AccountId {
+        AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+            self.key.verifying_key().to_bytes(),
+        )))
+    }
+
+    /// The signer's account address (`G...`)
+    pub fn address(&self) -> String {
+        self.account_id().to_string()
+    }
+
+    /// Wrap a transaction in an envelope signed for `network_passphrase`
+    pub(crate) fn sign(
+        &self,
+        transaction: Transaction,
+        network_passphrase: &str,
+    ) -> Result<TransactionEnvelope, Error> {
+        let network_id = Sha256::digest(network_passphrase.as_bytes()).into();
+        let signature = self.key.sign(&transaction.hash(network_id)?);
+
+        let public_key = self.key.verifying_key().to_bytes();
+        let mut hint = [0; 4];
+        hint.copy_from_slice(&public_key[28..]);
+
+        Ok(TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: transaction,
+            signatures: vec![DecoratedSignature {
+                hint: SignatureHint(hint),
+                signature: Signature(signature.to_bytes().to_vec().try_into()?),
+            }]
+            .try_into()?,
+        }))
+    }
+}
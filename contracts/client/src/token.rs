@@ -0,0 +1,51 @@
+use crate::scval::{address, to_i128, to_u32};
+use crate::{Contract, Error, Signer};
+
+/// A SEP-41 token, such as USDC or a collateral token
+pub struct Token {
+    contract: Contract,
+}
+
+impl Token {
+    pub fn new(contract: Contract) -> Self {
+        Self { contract }
+    }
+
+    pub fn balance(&self, owner: &str) -> Result<i128, Error> {
+        to_i128(&self.contract.view("balance", vec![address(owner)?])?)
+    }
+
+    /// Amount `spender` may still move out of `owner`'s balance
+    pub fn allowance(&self, owner: &str, spender: &str) -> Result<i128, Error> {
+        to_i128(
+            &self
+                .contract
+                .view("allowance", vec![address(owner)?, address(spender)?])?,
+        )
+    }
+
+    /// Let `spender` move up to `amount` of the signer's tokens until `expiration_ledger`
+    pub fn approve(
+        &self,
+        signer: &Signer,
+        spender: &str,
+        amount: i128,
+        expiration_ledger: u32,
+    ) -> Result<(), Error> {
+        self.contract.invoke(
+            signer,
+            "approve",
+            vec![
+                address(&signer.address())?,
+                address(spender)?,
+                amount.into(),
+                expiration_ledger.into(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn decimals(&self) -> Result<u32, Error> {
+        to_u32(&self.contract.view("decimals", Vec::new())?)
+    }
+}
@@ -0,0 +1,82 @@
+use soroban_sdk::{contractclient, Address, Env};
+
+use crate::{DataKey, MarketConfig};
+
+/// Admin surface of the bToken receipt minted to pool suppliers
+///
+/// The credit line is the token's admin: it mints bTokens on `supply` and
+/// burns them on `withdraw_supply`, so balances always equal pool shares.
+#[contractclient(name = "BTokenClient")]
+pub trait BToken {
+    fn mint(env: Env, to: Address, amount: i128);
+    fn admin_burn(env: Env, from: Address, amount: i128);
+    fn balance(env: Env, id: Address) -> i128;
+    fn total_supply(env: Env) -> i128;
+}
+
+fn btoken_client<'a>(env: &'a Env, config: &MarketConfig) -> Option<BTokenClient<'a>> {
+    config
+        .btoken
+        .as_ref()
+        .map(|btoken| BTokenClient::new(env, btoken))
+}
+
+/// Pool shares outstanding across all suppliers
+pub(crate) fn total_supply_shares(env: &Env, config: &MarketConfig) -> i128 {
+    match btoken_client(env, config) {
+        Some(client) => client.total_supply(),
+        None => env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalSupplyShares)
+            .unwrap_or(0),
+    }
+}
+
+/// Pool shares held by a supplier
+pub(crate) fn supply_shares(env: &Env, config: &MarketConfig, lender: &Address) -> i128 {
+    match btoken_client(env, config) {
+        Some(client) => client.balance(lender),
+        None => env
+            .storage()
+            .persistent()
+            .get(&DataKey::SupplyShares(lender.clone()))
+            .unwrap_or(0),
+    }
+}
+
+/// Issue new pool shares to a supplier
+pub(crate) fn mint_supply_shares(env: &Env, config: &MarketConfig, lender: &Address, shares: i128) {
+    if let Some(client) = btoken_client(env, config) {
+        client.mint(lender, &shares);
+        return;
+    }
+
+    let lender_shares = supply_shares(env, config, lender);
+    let total_shares = total_supply_shares(env, config);
+    env.storage().persistent().set(
+        &DataKey::SupplyShares(lender.clone()),
+        &(lender_shares + shares),
+    );
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalSupplyShares, &(total_shares + shares));
+}
+
+/// Cancel a supplier's pool shares on withdrawal
+pub(crate) fn burn_supply_shares(env: &Env, config: &MarketConfig, lender: &Address, shares: i128) {
+    if let Some(client) = btoken_client(env, config) {
+        client.admin_burn(lender, &shares);
+        return;
+    }
+
+    let lender_shares = supply_shares(env, config, lender);
+    let total_shares = total_supply_shares(env, config);
+    env.storage().persistent().set(
+        &DataKey::SupplyShares(lender.clone()),
+        &(lender_shares - shares),
+    );
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalSupplyShares, &(total_shares - shares));
+}
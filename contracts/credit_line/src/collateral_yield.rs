@@ -0,0 +1,163 @@
+use soroban_sdk::{contracttype, token, Address, Env};
+
+use crate::math::{mul_div, Rounding};
+use crate::{protocol_balance, DataKey, Error, POSITION_BUMP_AMOUNT, POSITION_LIFETIME_THRESHOLD};
+
+/// Accumulated yield per unit of collateral of 1.0
+const YIELD_INDEX_ONE: i128 = 1_000_000_000;
+
+/// A depositor's share of yield earned by one collateral token
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct YieldCheckpoint {
+    pub index: i128,
+    pub claimable: i128,
+}
+
+/// Collateral of a token currently held on behalf of depositors
+pub(crate) fn collateral_total(env: &Env, token: &Address) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::CollateralTotal(token.clone()))
+        .unwrap_or(0)
+}
+
+/// Adjust the collateral held on behalf of depositors for a token
+pub(crate) fn update_collateral_total(env: &Env, token: &Address, delta: i128) {
+    let total = collateral_total(env, token);
+    env.storage()
+        .instance()
+        .set(&DataKey::CollateralTotal(token.clone()), &(total + delta));
+}
+
+/// Fold any growth in the contract's token balance into the yield index
+pub(crate) fn update_yield_index(env: &Env, token: &Address) -> Result<i128, Error> {
+    let index: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::YieldIndex(token.clone()))
+        .unwrap_or(0);
+    let reserved: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::YieldReserved(token.clone()))
+        .unwrap_or(0);
+    let total = collateral_total(env, token);
+
+    if total == 0 {
+        return Ok(index);
+    }
+
+    // Anything above deposits, unclaimed yield and the protocol's own BENJI is
+    // new yield
+    let balance = token::Client::new(env, token).balance(&env.current_contract_address());
+    let growth = balance - total - reserved - protocol_balance(env, token);
+    if growth <= 0 {
+        return Ok(index);
+    }
+
+    let index = index
+        .checked_add(
+            mul_div(growth, YIELD_INDEX_ONE, total, Rounding::Down).ok_or(Error::MathOverflow)?,
+        )
+        .ok_or(Error::MathOverflow)?;
+    env.storage()
+        .instance()
+        .set(&DataKey::YieldIndex(token.clone()), &index);
+    env.storage()
+        .instance()
+        .set(&DataKey::YieldReserved(token.clone()), &(reserved + growth));
+
+    Ok(index)
+}
+
+/// Credit one of a depositor's accounts with yield earned on `collateral` since its
+/// last checkpoint
+pub(crate) fn settle_yield(
+    env: &Env,
+    user: &Address,
+    account_id: u32,
+    token: &Address,
+    collateral: i128,
+) -> Result<YieldCheckpoint, Error> {
+    let index = update_yield_index(env, token)?;
+    let key = DataKey::YieldCheckpoint(user.clone(), account_id, token.clone());
+
+    let mut checkpoint = load_checkpoint(env, &key).unwrap_or(YieldCheckpoint {
+        index,
+        claimable: 0,
+    });
+
+    let earned = mul_div(
+        collateral,
+        index - checkpoint.index,
+        YIELD_INDEX_ONE,
+        Rounding::Down,
+    )
+    .ok_or(Error::MathOverflow)?;
+    checkpoint.claimable = checkpoint
+        .claimable
+        .checked_add(earned)
+        .ok_or(Error::MathOverflow)?;
+    checkpoint.index = index;
+
+    save_checkpoint(env, &key, &checkpoint);
+    Ok(checkpoint)
+}
+
+fn load_checkpoint(env: &Env, key: &DataKey) -> Option<YieldCheckpoint> {
+    let checkpoint = env.storage().persistent().get(key);
+
+    if checkpoint.is_some() {
+        env.storage().persistent().extend_ttl(
+            key,
+            POSITION_LIFETIME_THRESHOLD,
+            POSITION_BUMP_AMOUNT,
+        );
+    }
+
+    checkpoint
+}
+
+fn save_checkpoint(env: &Env, key: &DataKey, checkpoint: &YieldCheckpoint) {
+    env.storage().persistent().set(key, checkpoint);
+    env.storage()
+        .persistent()
+        .extend_ttl(key, POSITION_LIFETIME_THRESHOLD, POSITION_BUMP_AMOUNT);
+}
+
+/// Pay out a depositor's settled yield, returning the amount sent
+pub(crate) fn claim_yield(
+    env: &Env,
+    user: &Address,
+    account_id: u32,
+    token: &Address,
+    collateral: i128,
+) -> Result<i128, Error> {
+    let mut checkpoint = settle_yield(env, user, account_id, token, collateral)?;
+    let amount = checkpoint.claimable;
+
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    checkpoint.claimable = 0;
+    save_checkpoint(
+        env,
+        &DataKey::YieldCheckpoint(user.clone(), account_id, token.clone()),
+        &checkpoint,
+    );
+
+    let reserved: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::YieldReserved(token.clone()))
+        .unwrap_or(0);
+    env.storage()
+        .instance()
+        .set(&DataKey::YieldReserved(token.clone()), &(reserved - amount));
+
+    token::Client::new(env, token).transfer(&env.current_contract_address(), user, &amount);
+
+    Ok(amount)
+}
@@ -0,0 +1,33 @@
+use soroban_sdk::{contractclient, Address, Env};
+
+use crate::load_config;
+
+/// Admin surface of the non-transferable debt token tracking borrower debt
+///
+/// The credit line is the token's admin and keeps each borrower's balance
+/// equal to the `borrowed` of their positions whenever a position is saved.
+#[contractclient(name = "DebtTokenClient")]
+pub trait DebtToken {
+    fn mint(env: Env, to: Address, amount: i128);
+    fn admin_burn(env: Env, from: Address, amount: i128);
+    fn balance(env: Env, id: Address) -> i128;
+}
+
+/// Mint or burn debt tokens as a borrower's debt on one of their accounts changes
+///
+/// A borrower's balance is the sum of the debt across all of their accounts.
+pub(crate) fn sync_debt_token(env: &Env, user: &Address, delta: i128) {
+    if delta == 0 {
+        return;
+    }
+    let Some(debt_token) = load_config(env).ok().and_then(|config| config.debt_token) else {
+        return;
+    };
+
+    let client = DebtTokenClient::new(env, &debt_token);
+    if delta > 0 {
+        client.mint(user, &delta);
+    } else {
+        client.admin_burn(user, &-delta);
+    }
+}
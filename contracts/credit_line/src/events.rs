@@ -0,0 +1,442 @@
+use soroban_sdk::{contractevent, Address, BytesN, Symbol};
+
+/// Collateral deposited
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Deposit {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    #[topic]
+    pub token: Address,
+    pub payer: Address,
+    pub muxed_id: Option<u64>, // set when the payer is a muxed account
+    pub amount: i128,
+    pub collateral: i128,
+    pub borrowed: i128,
+}
+
+/// USDC borrowed against collateral
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Borrow {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub recipient: Address,
+    pub to: Address,           // paid the USDC, the recipient unless redirected
+    pub muxed_id: Option<u64>, // set when the USDC went to a muxed account
+    pub amount: i128,
+    pub borrowed: i128,
+}
+
+/// Borrowing allowance granted against a user's position
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DelegationApproved {
+    #[topic]
+    pub delegator: Address,
+    #[topic]
+    pub delegatee: Address,
+    pub account_id: u32,
+    pub amount: i128,
+}
+
+/// USDC debt repaid
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Repay {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub payer: Address,
+    pub muxed_id: Option<u64>, // set when the payer is a muxed account
+    pub amount: i128,
+    pub borrowed: i128,
+}
+
+/// Referrer named with a user's first deposit
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Referred {
+    #[topic]
+    pub user: Address,
+    #[topic]
+    pub referrer: Address,
+}
+
+/// Referral fees paid out to a referrer
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferralFeesClaimed {
+    #[topic]
+    pub referrer: Address,
+    pub amount: i128,
+}
+
+/// USDC debt repaid in BENJI, which the protocol keeps
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RepayWithCollateral {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub amount: i128,
+    pub repaid: i128,
+    pub borrowed: i128,
+}
+
+/// USDC debt repaid by surrendering BENJI collateral to the protocol
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RepayFromCollateral {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub amount: i128,
+    pub repaid: i128,
+    pub borrowed: i128,
+}
+
+/// BENJI taken in repayment of debt sold for USDC
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolCollateralSold {
+    #[topic]
+    pub buyer: Address,
+    pub amount: i128,
+    pub cost: i128,
+}
+
+/// Collateral withdrawn
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Withdraw {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    #[topic]
+    pub token: Address,
+    pub to: Address,           // paid the collateral, the user unless redirected
+    pub muxed_id: Option<u64>, // set when the collateral went to a muxed account
+    pub amount: i128,
+    pub collateral: i128,
+    pub borrowed: i128,
+}
+
+/// Underwater position partially repaid by a liquidator
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Liquidate {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    #[topic]
+    pub liquidator: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub seized: i128,       // paid to the liquidator
+    pub protocol_fee: i128, // share of the bonus sent to the treasury
+    pub collateral: i128,
+    pub borrowed: i128,
+}
+
+/// Loan-to-value ratio of a collateral token changed by the admin
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LtvUpdated {
+    #[topic]
+    pub token: Address,
+    pub old_ratio: u32,
+    pub new_ratio: u32,
+}
+
+/// Market paused or resumed by the admin
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PauseUpdated {
+    pub paused: bool,
+    pub pause_repay: bool,
+}
+
+/// Collateral token accepted or its risk parameters changed
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CollateralConfigUpdated {
+    #[topic]
+    pub token: Address,
+    pub ltv_ratio: u32,
+    pub liquidation_threshold: u32,
+}
+
+/// USDC supplied to the pool
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Supply {
+    #[topic]
+    pub lender: Address,
+    pub muxed_id: Option<u64>, // set when the lender is a muxed account
+    pub amount: i128,
+    pub shares: i128,
+}
+
+/// USDC withdrawn from the pool
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawSupply {
+    #[topic]
+    pub lender: Address,
+    pub muxed_id: Option<u64>, // set when the lender is a muxed account
+    pub amount: i128,
+    pub shares: i128,
+}
+
+/// Protocol reserves sent to the treasury
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReservesWithdrawn {
+    #[topic]
+    pub treasury: Address,
+    pub amount: i128,
+}
+
+/// Tokens sent to the contract by mistake returned by the admin
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenRescued {
+    #[topic]
+    pub token: Address,
+    #[topic]
+    pub to: Address,
+    pub amount: i128,
+}
+
+/// Yield earned by held collateral paid out to its depositor
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CollateralYieldClaimed {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    #[topic]
+    pub token: Address,
+    pub amount: i128,
+}
+
+/// Contract code replaced by the admin
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Upgraded {
+    pub new_wasm_hash: BytesN<32>,
+}
+
+/// New admin proposed by the current admin
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminProposed {
+    #[topic]
+    pub new_admin: Address,
+    pub eta: u64,
+}
+
+/// Admin role handed over to a previously proposed admin
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminAccepted {
+    #[topic]
+    pub old_admin: Address,
+    #[topic]
+    pub new_admin: Address,
+}
+
+/// Pool USDC lent and repaid within a single invocation
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FlashLoan {
+    #[topic]
+    pub initiator: Address,
+    #[topic]
+    pub receiver: Address,
+    pub amount: i128,
+    pub fee: i128,
+}
+
+/// Position left below the health warning threshold by an operation, but
+/// not yet liquidatable
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HealthWarning {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub health_factor: i128,
+    pub threshold: i128,
+}
+
+/// Position handed to the successor market
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PositionMigrated {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    #[topic]
+    pub successor: Address,
+    pub borrowed: i128, // debt the successor took over and paid for
+}
+
+/// Position taken over from the predecessor market
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PositionReceived {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    #[topic]
+    pub predecessor: Address,
+    pub borrowed: i128, // account's debt here once the migrated debt is added
+}
+
+/// Pool interest accrued and storage kept alive by a `poke`
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Heartbeat {
+    pub borrow_index: i128,
+    pub total_borrowed: i128,
+    pub total_supplied: i128,
+}
+
+/// Borrowing frozen and emergency withdrawals opened by the admin
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmergencyModeEnabled {
+    pub timestamp: u64,
+}
+
+/// Collateral withdrawn in emergency mode with the user's debt written off
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmergencyWithdraw {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    #[topic]
+    pub token: Address,
+    pub amount: i128,
+    pub written_off: i128,
+}
+
+/// Debt left on a position with no collateral moved to bad debt
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BadDebtRecorded {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub amount: i128,
+}
+
+/// Bad debt paid off out of protocol reserves
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BadDebtCovered {
+    pub amount: i128,
+}
+
+/// Bad debt written off against suppliers
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BadDebtSocialized {
+    pub amount: i128,
+}
+
+/// Position switched between variable and stable rate
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RateModeSwapped {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub rate_mode: u32,
+    pub stable_rate: u32,
+}
+
+/// Reward tokens paid out to a supplier or borrower
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RewardsClaimed {
+    #[topic]
+    pub user: Address,
+    pub amount: i128,
+}
+
+/// Reward emission rate of a pool changed
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmissionRateUpdated {
+    #[topic]
+    pub pool: u32,
+    pub rate_per_second: i128,
+}
+
+/// Fixed-term loan taken on an account
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FixedLoanOpened {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub amount: i128,
+    pub rate: u32,
+    pub installments: u32,
+    pub maturity_ledger: u32,
+}
+
+/// Protector registered to repay a position's debt before liquidation
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtectionSet {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub protector: Address,
+    pub trigger_health_factor: i128,
+    pub max_repay: i128,
+}
+
+/// Protector unregistered from a position
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtectionRemoved {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+}
+
+/// Debt repaid by a position's protector
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Protected {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub protector: Address,
+    pub amount: i128,
+    pub borrowed: i128,
+}
+
+/// Admin parameter changed, as recorded in the parameter log under `sequence`
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParamChanged {
+    #[topic]
+    pub param: Symbol,
+    #[topic]
+    pub admin: Address,
+    pub sequence: u32,
+    pub subject: Option<Address>,
+    pub old_value: Option<i128>,
+    pub new_value: Option<i128>,
+    pub old_address: Option<Address>,
+    pub new_address: Option<Address>,
+}
@@ -0,0 +1,106 @@
+//! Fixed-term loans repaid in equal-principal installments.
+//!
+//! A fixed loan takes over an account with no other debt. The account accrues
+//! interest at a rate locked in at origination, like stable-rate debt, and at
+//! each due ledger its outstanding principal must be down to the principal not
+//! yet scheduled for repayment. Repayments settle accrued interest before
+//! principal. Repaying early counts toward later installments; missing a due
+//! ledger adds penalty interest and makes the position liquidatable whatever
+//! its health.
+
+use soroban_sdk::{contracttype, Env};
+
+use crate::math::{mul_div, Rounding};
+use crate::{Error, DAY_IN_LEDGERS};
+
+/// Ledgers between installments of a fixed loan, about a month
+const INSTALLMENT_LEDGERS: u32 = 30 * DAY_IN_LEDGERS;
+
+/// Repayment schedule of a fixed-term loan
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FixedLoan {
+    pub principal: i128,
+    pub start_ledger: u32,
+    pub installments: u32,
+    pub interval: u32,     // ledgers between due dates
+    pub outstanding: i128, // principal not yet repaid
+}
+
+/// How a position's debt is to be repaid
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LoanTerms {
+    /// Open credit line, repaid whenever the borrower likes
+    Open,
+    /// Fixed-term loan following its installment schedule
+    Fixed(FixedLoan),
+}
+
+/// Schedule for `principal` repaid over `term_ledgers`, starting now
+pub(crate) fn new_fixed_loan(env: &Env, principal: i128, term_ledgers: u32) -> FixedLoan {
+    let installments = (term_ledgers / INSTALLMENT_LEDGERS).max(1);
+    FixedLoan {
+        principal,
+        start_ledger: env.ledger().sequence(),
+        installments,
+        interval: term_ledgers / installments,
+        outstanding: principal,
+    }
+}
+
+/// Ledger installment `number` (counting from 1) falls due
+fn due_ledger(loan: &FixedLoan, number: u32) -> u32 {
+    loan.start_ledger + number * loan.interval
+}
+
+/// Installments whose due ledger has passed
+fn installments_due(env: &Env, loan: &FixedLoan) -> u32 {
+    let elapsed = env.ledger().sequence().saturating_sub(loan.start_ledger);
+    (elapsed / loan.interval).min(loan.installments)
+}
+
+/// Balance the loan may still carry once installment `number` is paid
+fn scheduled_balance(loan: &FixedLoan, number: u32) -> Result<i128, Error> {
+    mul_div(
+        loan.principal,
+        (loan.installments - number) as i128,
+        loan.installments as i128,
+        Rounding::Down,
+    )
+    .ok_or(Error::MathOverflow)
+}
+
+/// Principal still owed on a loan with `borrowed` outstanding, interest included
+///
+/// Repayments go to interest first, so principal only falls once the balance
+/// drops below what was left of it.
+pub(crate) fn outstanding_principal(loan: &FixedLoan, borrowed: i128) -> i128 {
+    loan.outstanding.min(borrowed)
+}
+
+/// Whether a due installment is unpaid on a loan with `borrowed` outstanding
+pub(crate) fn is_overdue(env: &Env, loan: &FixedLoan, borrowed: i128) -> Result<bool, Error> {
+    let due = installments_due(env, loan);
+    Ok(due > 0 && outstanding_principal(loan, borrowed) > scheduled_balance(loan, due)?)
+}
+
+/// Amount that settles the next unpaid installment, and the ledger it is due by
+///
+/// An overdue installment is already past its ledger. Interest accrued since
+/// origination is paid along with the principal.
+pub(crate) fn installment_due(
+    env: &Env,
+    loan: &FixedLoan,
+    borrowed: i128,
+) -> Result<(i128, u32), Error> {
+    // Skip installments already covered by early repayments
+    let principal = outstanding_principal(loan, borrowed);
+    let mut number = installments_due(env, loan).max(1);
+    while number < loan.installments && principal <= scheduled_balance(loan, number)? {
+        number += 1;
+    }
+
+    let amount = (borrowed - scheduled_balance(loan, number)?).max(0);
+    Ok((amount, due_ledger(loan, number)))
+}
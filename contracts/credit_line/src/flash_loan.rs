@@ -0,0 +1,23 @@
+use soroban_sdk::{contractclient, Address, Env};
+
+/// Callback implemented by contracts that take flash loans from the pool
+///
+/// `exec_op` is invoked after the USDC has been sent to the receiver and must
+/// return `amount + fee` to the credit line before it returns. It cannot call
+/// the credit line itself, which is still on the call stack.
+#[contractclient(name = "FlashLoanReceiverClient")]
+pub trait FlashLoanReceiver {
+    fn exec_op(env: Env, token: Address, amount: i128, fee: i128);
+}
+
+/// Callback implemented by contracts that take the collateral of a flash
+/// liquidation
+///
+/// `exec_liquidation` is invoked after `amount` of the seized collateral
+/// `token` has been sent to the receiver and must send `owed` USDC, the debt
+/// repaid plus the flash loan fee, to the credit line before it returns. Like
+/// `exec_op`, it cannot call the credit line itself.
+#[contractclient(name = "FlashLiquidationReceiverClient")]
+pub trait FlashLiquidationReceiver {
+    fn exec_liquidation(env: Env, token: Address, amount: i128, owed: i128);
+}
@@ -0,0 +1,65 @@
+//! Optional on-chain record of each account's most recent states.
+//!
+//! With a history length set, every write of a position also appends a
+//! snapshot of it to that account's ring buffer, dropping the oldest once the
+//! buffer is full. Statements and disputes can then be reconstructed from
+//! contract storage alone, without an indexer.
+
+use soroban_sdk::{contracttype, Address, Env, Map, Vec};
+
+use crate::{DataKey, UserPosition, POSITION_BUMP_AMOUNT, POSITION_LIFETIME_THRESHOLD};
+
+/// Most snapshots kept per account, bounding the size of one storage entry
+pub const MAX_HISTORY_LENGTH: u32 = 50;
+
+/// A position as it was written at `timestamp`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snapshot {
+    pub timestamp: u64,
+    pub collateral: Map<Address, i128>,
+    pub borrowed: i128,
+    pub borrow_index: i128,
+}
+
+/// Snapshots kept per account, 0 while history is off
+pub(crate) fn history_length(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::HistoryLength)
+        .unwrap_or(0)
+}
+
+/// Recorded snapshots of one of a user's accounts, oldest first
+pub(crate) fn history(env: &Env, user: &Address, account_id: u32) -> Vec<Snapshot> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::History(user.clone(), account_id))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Append a position's new state to its account's history, if history is on
+pub(crate) fn record_snapshot(env: &Env, user: &Address, account_id: u32, position: &UserPosition) {
+    let length = history_length(env);
+    if length == 0 {
+        return;
+    }
+
+    let mut snapshots = history(env, user, account_id);
+    snapshots.push_back(Snapshot {
+        timestamp: env.ledger().timestamp(),
+        collateral: position.collateral.clone(),
+        borrowed: position.borrowed,
+        borrow_index: position.borrow_index,
+    });
+    // The length may have been lowered since the last write
+    while snapshots.len() > length {
+        snapshots.pop_front();
+    }
+
+    let key = DataKey::History(user.clone(), account_id);
+    env.storage().persistent().set(&key, &snapshots);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POSITION_LIFETIME_THRESHOLD, POSITION_BUMP_AMOUNT);
+}
@@ -0,0 +1,90 @@
+//! Pool accounting checked against the USDC the contract actually holds.
+//!
+//! Every USDC owed to suppliers is either idle in the contract or lent out, so
+//! the contract's USDC balance plus outstanding debt covers total supplied.
+//! Debt repaid in BENJI counts too, as USDC owed to the pool until the
+//! protocol sells that BENJI.
+//! Protocol reserves make up the difference and absorb bad debt first; a
+//! shortfall larger than the bad debt on record means USDC left the pool
+//! without the totals following it.
+
+use soroban_sdk::{contracttype, token, Env};
+
+use crate::referral::unclaimed_referral_fees;
+use crate::{
+    bad_debt, load_config, protocol_collateral, total_supplied, DataKey, Error, MarketConfig,
+};
+
+/// Shortfall `check` lets pass, in USDC base units, for the rounding share
+/// and interest arithmetic leaves in the totals
+pub const ROUNDING_TOLERANCE: i128 = 100;
+
+/// The pool's USDC against what it owes suppliers
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Solvency {
+    pub usdc_balance: i128,
+    pub total_borrowed: i128,
+    pub protocol_collateral_owed: i128, // USDC owed for BENJI taken in repayment
+    pub total_supplied: i128,           // USDC owed to suppliers as recorded, including bad debt
+    pub bad_debt: i128,
+    pub gap: i128, // usdc_balance + total_borrowed + protocol_collateral_owed - total_supplied
+}
+
+pub(crate) fn solvency(env: &Env) -> Result<Solvency, Error> {
+    Ok(solvency_with(env, &load_config(env)?))
+}
+
+fn solvency_with(env: &Env, config: &MarketConfig) -> Solvency {
+    let usdc_balance =
+        token::Client::new(env, &config.usdc_token).balance(&env.current_contract_address());
+    let total_borrowed: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalBorrowed)
+        .unwrap_or(0);
+    let protocol_collateral_owed = protocol_collateral(env).owed;
+    let total_supplied = total_supplied(env);
+
+    Solvency {
+        usdc_balance,
+        total_borrowed,
+        protocol_collateral_owed,
+        total_supplied,
+        bad_debt: bad_debt(env),
+        gap: usdc_balance + total_borrowed + protocol_collateral_owed - total_supplied,
+    }
+}
+
+/// USDC the contract holds beyond what it owes suppliers, the protocol's
+/// reserves and referrers, such as USDC sent to it by mistake
+///
+/// Counts against recorded total supplied rather than pool assets, which
+/// would hand such USDC to suppliers. Outstanding bad debt is made up before
+/// any USDC counts as surplus.
+pub(crate) fn usdc_surplus(env: &Env) -> Result<i128, Error> {
+    let solvency = solvency(env)?;
+    let reserves: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalReserves)
+        .unwrap_or(0);
+
+    let owed = solvency.total_supplied + reserves + unclaimed_referral_fees(env);
+    let held = solvency.usdc_balance + solvency.total_borrowed + solvency.protocol_collateral_owed;
+    Ok((held - owed).max(0))
+}
+
+/// Fail if the pool is short by more than its recorded bad debt, give or
+/// take `ROUNDING_TOLERANCE`
+///
+/// Called by every entry point that moves USDC or changes the totals, just
+/// before it returns successfully.
+pub(crate) fn check(env: &Env, config: &MarketConfig) -> Result<(), Error> {
+    let solvency = solvency_with(env, config);
+    if solvency.gap + solvency.bad_debt < -ROUNDING_TOLERANCE {
+        return Err(Error::SolvencyCheckFailed);
+    }
+
+    Ok(())
+}
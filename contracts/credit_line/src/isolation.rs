@@ -0,0 +1,80 @@
+//! Isolation mode for riskier collateral.
+//!
+//! An isolated token cannot share a position with any other collateral, and
+//! all positions backed by it together may owe at most its debt ceiling. The
+//! debt they owe is tracked per token as positions are saved, interest
+//! included.
+
+use soroban_sdk::{contracttype, Address, Env, Map};
+
+use crate::{DataKey, Error};
+
+/// Debt ceiling of an isolated collateral token and the debt counted against it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IsolationUsage {
+    pub debt_ceiling: i128,
+    pub debt: i128,
+}
+
+/// Debt ceiling of a collateral token, if it is isolated
+pub(crate) fn debt_ceiling(env: &Env, token: &Address) -> Option<i128> {
+    env.storage()
+        .instance()
+        .get(&DataKey::IsolationCeiling(token.clone()))
+}
+
+/// USDC owed by positions backed by an isolated token
+pub(crate) fn isolated_debt(env: &Env, token: &Address) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::IsolatedDebt(token.clone()))
+        .unwrap_or(0)
+}
+
+/// The isolated token among a position's collateral, if any
+pub(crate) fn isolated_token(env: &Env, collateral: &Map<Address, i128>) -> Option<Address> {
+    collateral
+        .keys()
+        .iter()
+        .find(|token| debt_ceiling(env, token).is_some())
+}
+
+/// Adjust the debt counted against an isolated token
+pub(crate) fn update_isolated_debt(env: &Env, token: &Address, delta: i128) {
+    let debt = isolated_debt(env, token);
+    env.storage().instance().set(
+        &DataKey::IsolatedDebt(token.clone()),
+        &(debt + delta).max(0),
+    );
+}
+
+/// Debt the isolated token backing a position may still take on before its
+/// ceiling, if the position is isolated
+pub(crate) fn isolation_headroom(env: &Env, collateral: &Map<Address, i128>) -> Option<i128> {
+    let token = isolated_token(env, collateral)?;
+    debt_ceiling(env, &token).map(|ceiling| ceiling - isolated_debt(env, &token))
+}
+
+/// Fail if a position mixes an isolated token with other collateral
+pub(crate) fn check_collateral_mix(
+    env: &Env,
+    collateral: &Map<Address, i128>,
+) -> Result<(), Error> {
+    if collateral.len() > 1 && isolated_token(env, collateral).is_some() {
+        return Err(Error::IsolatedCollateral);
+    }
+    Ok(())
+}
+
+/// Fail if the isolated token backing a position is over its debt ceiling
+pub(crate) fn check_debt_ceiling(env: &Env, collateral: &Map<Address, i128>) -> Result<(), Error> {
+    let Some(token) = isolated_token(env, collateral) else {
+        return Ok(());
+    };
+
+    if debt_ceiling(env, &token).is_some_and(|ceiling| isolated_debt(env, &token) > ceiling) {
+        return Err(Error::IsolationCeilingExceeded);
+    }
+    Ok(())
+}
@@ -1,6 +1,77 @@
 #![no_std]
 
-use soroban_sdk::{contract, contracterror, contractimpl, contracttype, token, Address, Env};
+pub mod btoken;
+mod collateral_yield;
+pub mod debt_token;
+mod events;
+pub mod fixed_loan;
+pub mod flash_loan;
+pub mod history;
+pub mod invariant;
+pub mod isolation;
+mod liquidation_fee;
+pub mod math;
+pub mod migration;
+pub mod oracle;
+pub mod param_log;
+pub mod param_update;
+pub mod preview;
+pub mod protection;
+mod referral;
+pub mod rewards;
+pub mod staking;
+mod user_index;
+
+use btoken::{burn_supply_shares, mint_supply_shares, supply_shares, total_supply_shares};
+use collateral_yield::{claim_yield, collateral_total, settle_yield, update_collateral_total};
+use debt_token::sync_debt_token;
+use events::{
+    AdminAccepted, AdminProposed, BadDebtCovered, BadDebtRecorded, BadDebtSocialized, Borrow,
+    CollateralConfigUpdated, CollateralYieldClaimed, DelegationApproved, Deposit,
+    EmergencyModeEnabled, EmergencyWithdraw, EmissionRateUpdated, FixedLoanOpened, FlashLoan,
+    HealthWarning, Heartbeat, Liquidate, PauseUpdated, PositionMigrated, PositionReceived,
+    Protected, ProtectionRemoved, ProtectionSet, ProtocolCollateralSold, RateModeSwapped,
+    ReferralFeesClaimed, Repay, RepayFromCollateral, RepayWithCollateral, ReservesWithdrawn,
+    RewardsClaimed, Supply, TokenRescued, Upgraded, Withdraw, WithdrawSupply,
+};
+use fixed_loan::{installment_due, is_overdue, new_fixed_loan, FixedLoan, LoanTerms};
+use flash_loan::{FlashLiquidationReceiverClient, FlashLoanReceiverClient};
+use history::{history, record_snapshot, Snapshot, MAX_HISTORY_LENGTH};
+use invariant::{solvency, usdc_surplus, Solvency};
+use isolation::{
+    check_collateral_mix, check_debt_ceiling, debt_ceiling, isolated_debt, isolated_token,
+    isolation_headroom, update_isolated_debt, IsolationUsage,
+};
+use liquidation_fee::{
+    liquidation_fee_share, liquidation_fees, set_liquidation_fee_share, take_liquidation_fee,
+};
+use math::{bps_mul, compound, mul_div, ray_div, ray_mul, Rounding, BPS, RAY, WAD};
+use migration::{predecessor, set_predecessor, set_successor, successor, SuccessorClient};
+use oracle::{
+    oracle_settings, oriented_price, quote_log_value, time_weighted_price, Asset, OracleSettings,
+    PriceOracleClient, QuoteAsset,
+};
+use param_log::{
+    param_change_count, param_changes, record_address_change, record_change, record_config_changes,
+    ParamChange,
+};
+use param_update::{apply_update, ParamUpdate};
+use preview::{preview, preview_borrow, preview_repay, preview_withdraw, Preview};
+use protection::{Protection, ProtectorClient};
+use referral::{
+    credit_referrer, name_referrer, referral_fees, referral_share, referrer, set_referral_share,
+    take_referral_fees, unclaimed_referral_fees,
+};
+use rewards::{
+    claim_rewards, claimable_rewards, set_emission_rate, set_reward_balance, update_reward_balance,
+    RewardPool, MAX_EMISSION_RATE,
+};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, token, Address, Bytes, BytesN, Env, Map,
+    MuxedAddress, Vec,
+};
+use staking::ltv_boost;
+use user_index::{accounts, add_account, user_count, users};
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -11,269 +82,5532 @@ pub enum Error {
     InsufficientCollateral = 3,
     ExceedsCreditLimit = 4,
     InsufficientBalance = 5,
+    Unauthorized = 6,
+    PositionHealthy = 7,
+    PriceUnavailable = 8,
+    InvalidParameter = 9,
+    ContractPaused = 10,
+    UnsupportedCollateral = 11,
+    InsufficientLiquidity = 12,
+    TreasuryNotSet = 13,
+    GlobalDebtCeilingReached = 14,
+    UserBorrowCapReached = 15,
+    NoPendingAdmin = 16,
+    TimelockNotElapsed = 17,
+    MathOverflow = 18,
+    FlashLoanNotRepaid = 19,
+    ReentrantCall = 20,
+    InvalidAmount = 21,
+    InvalidToken = 22,
+    NotAllowlisted = 23,
+    BelowMinimum = 24,
+    InsufficientDelegation = 25,
+    EmergencyMode = 26,
+    NotEmergencyMode = 27,
+    ExceedsBadDebt = 28,
+    TooManyAccounts = 29,
+    OracleStale = 30,
+    FixedLoanActive = 31,
+    NoFixedLoan = 32,
+    DebtOutstanding = 33,
+    NoProtection = 34,
+    ProtectorNotPaid = 35,
+    SupplyCapExceeded = 36,
+    CloseFactorExceeded = 37,
+    SolvencyCheckFailed = 38,
+    InsufficientAllowance = 39,
+    IsolatedCollateral = 40,
+    IsolationCeilingExceeded = 41,
+    ProtectedToken = 42,
+    MaxAmountExceeded = 43,
+    MigrationNotPaid = 44,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct UserPosition {
-    pub collateral: i128,
+    pub collateral: Map<Address, i128>,
     pub borrowed: i128,
     pub last_update: u64,
+    pub borrow_index: i128, // global borrow index `borrowed` was last brought up to
+    pub rate_mode: RateMode, // how open-line borrows accrue interest
+    pub stable_borrowed: i128, // part of `borrowed` accruing at `stable_rate`, the rest is variable
+    pub stable_rate: u32,   // APR locked in on the stable debt, 0 while there is none
+    pub free_tranches: Vec<Tranche>, // recent borrows still inside the interest-free period
+    pub terms: LoanTerms,   // open credit line, or a fixed-term loan and its schedule
+}
+
+/// Borrowed principal that accrues no interest until `free_until`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tranche {
+    pub amount: i128,
+    pub free_until: u64,
+}
+
+/// How a position's debt accrues interest
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum RateMode {
+    /// Follows the market rate, which rises with utilization
+    Variable = 0,
+    /// Fixed at the rate in force when the debt was taken
+    Stable = 1,
+}
+
+/// How often accrued interest starts earning interest itself
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum Compounding {
+    /// Simple interest between updates, compounding only when debt is touched
+    Linear = 0,
+    /// Compounds every ledger close
+    PerLedger = 1,
+    /// Compounds once a day
+    Daily = 2,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CollateralConfig {
+    pub ltv_ratio: u32,             // 7000 = 70%
+    pub liquidation_threshold: u32, // 8000 = 80%
+}
+
+/// Liquidation threshold a collateral token had before the admin lowered it,
+/// honoured for liquidations until `deadline`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ThresholdGrace {
+    pub liquidation_threshold: u32,
+    pub deadline: u64,
+}
+
+/// BENJI the protocol took in repayment of debt, and the USDC the pool is
+/// still owed for it until the BENJI is sold
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolCollateral {
+    pub amount: i128,
+    pub owed: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingAdmin {
+    pub admin: Address,
+    pub eta: u64,
+}
+
+/// A new admin timelock, waiting out the delay it replaces
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingTimelock {
+    pub delay: u64,
+    pub eta: u64,
+}
+
+/// Market-wide parameters, stored together under a single key
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketConfig {
+    pub benji_token: Address,
+    pub usdc_token: Address,
+    pub collateral: Map<Address, CollateralConfig>,
+    pub oracle: Option<Address>,
+    pub treasury: Option<Address>,
+    pub btoken: Option<Address>,
+    pub debt_token: Option<Address>,
+    pub staking: Option<Address>, // BENJI lock-up contract granting LTV boosts
+    pub interest_rate: u32,       // 500 = 5% APR at zero utilization
+    pub rate_slope: u32,          // 1500 = 15% APR added at full utilization
+    pub stable_rate_premium: u32, // 200 = stable borrowers pay 2% over the variable rate
+    pub liquidation_bonus: u32,   // 500 = 5% extra collateral to liquidator
+    pub reserve_factor: u32,      // 1000 = 10% of interest
+    pub flash_loan_fee: u32,      // 9 = 0.09% of the loan
+    pub debt_ceiling: Option<i128>,
+    pub min_borrow: i128, // smallest borrow, and debt below which a position is dust
+    pub min_collateral: i128, // smallest USDC value of collateral a deposit may leave
+    pub grace_period: u64, // seconds a lowered liquidation threshold is not enforced
+    pub max_price_age: u64, // seconds after which an oracle price is refused; 0 = no limit
+    pub max_price_deviation: u32, // 2000 = refuse a price 20% off the one before it; 0 = no limit
+    pub twap_window: u64, // seconds oracle prices are averaged over; 0 = latest price
+    pub interest_free_period: u64, // seconds a new borrow accrues no interest; 0 = none
+    pub late_penalty_rate: u32, // 500 = 5% APR added while a fixed loan installment is overdue
+    pub close_factor: u32, // 5000 = one liquidation repays at most 50% of the debt
+    pub compounding: Compounding,
+}
+
+/// Change to an optional address in `MarketConfigUpdate`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AddressChange {
+    Keep,
+    Set(Address),
+    Clear,
+}
+
+impl AddressChange {
+    fn apply(self, current: Option<Address>) -> Option<Address> {
+        match self {
+            AddressChange::Keep => current,
+            AddressChange::Set(address) => Some(address),
+            AddressChange::Clear => None,
+        }
+    }
+}
+
+/// Change to an optional amount in `MarketConfigUpdate`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AmountChange {
+    Keep,
+    Set(i128),
+    Clear,
+}
+
+impl AmountChange {
+    fn apply(self, current: Option<i128>) -> Option<i128> {
+        match self {
+            AmountChange::Keep => current,
+            AmountChange::Set(amount) => Some(amount),
+            AmountChange::Clear => None,
+        }
+    }
+}
+
+/// Parameters to change with `update_config`; `None` or `Keep` leaves a
+/// parameter as is
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketConfigUpdate {
+    pub oracle: AddressChange,
+    pub treasury: AddressChange,
+    pub staking: AddressChange,
+    pub interest_rate: Option<u32>,
+    pub rate_slope: Option<u32>,
+    pub stable_rate_premium: Option<u32>,
+    pub liquidation_bonus: Option<u32>,
+    pub reserve_factor: Option<u32>,
+    pub flash_loan_fee: Option<u32>,
+    pub debt_ceiling: AmountChange,
+    pub min_borrow: Option<i128>,
+    pub min_collateral: Option<i128>,
+    pub grace_period: Option<u64>,
+    pub max_price_age: Option<u64>,
+    pub max_price_deviation: Option<u32>,
+    pub twap_window: Option<u64>,
+    pub interest_free_period: Option<u64>,
+    pub late_penalty_rate: Option<u32>,
+    pub close_factor: Option<u32>,
+}
+
+/// Collateral of one token held across all positions, against its supply cap
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CollateralUsage {
+    pub total: i128,
+    pub supply_cap: Option<i128>,
+}
+
+/// Market-wide totals and rates, for dashboards and indexers
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketSummary {
+    pub total_collateral: Map<Address, i128>, // per accepted collateral token
+    pub total_borrowed: i128,
+    pub total_supplied: i128, // USDC owned by suppliers, including outstanding debt
+    pub total_reserves: i128,
+    pub available_liquidity: i128,
+    pub utilization: u32,        // 8000 = 80% of supplied USDC lent out
+    pub borrow_rate: u32,        // 500 = 5% APR
+    pub stable_borrow_rate: u32, // rate a stable-mode borrow would lock in now
+    pub supply_rate: u32,        // APR earned by suppliers after the reserve factor
 }
 
+/// Storage keys of the contract
+///
+/// A contract type enum may have at most 50 variants and this one is close to
+/// that, so newer modules keep their keys in enums of their own.
 #[contracttype]
 pub enum DataKey {
     Admin,
+    Config,
+    UserPosition(Address, u32), // (user, account id)
+    Paused,
+    RepayPaused,
+    TotalBorrowed,
+    TotalSupplied, // USDC owed to suppliers as the contract has recorded it
+    TotalSupplyShares,
+    SupplyShares(Address),
+    TotalReserves,
+    BorrowCap(Address), // applies to the user's accounts together
+    CollateralTotal(Address),
+    SupplyCap(Address), // most of a collateral token all positions may hold together
+    CollateralHaircut(Address), // 200 = 2% off the token's LTV for credit limits
+    IsolationCeiling(Address), // set while the token is isolated
+    IsolatedDebt(Address), // USDC owed by positions backed by an isolated token
+    RepaySpread,        // 300 = BENJI repaying debt is valued 3% under its price
+    ProtocolCollateral,
+    OracleSettings,
+    HealthWarning, // health factor below which saved positions emit a warning
+    YieldIndex(Address),
+    YieldReserved(Address),
+    YieldCheckpoint(Address, u32, Address),
+    Version,
+    PendingAdmin,
+    AdminTimelock,
+    PendingTimelock,
+    TokenDecimals(Address),
+    AllowlistEnabled,
+    Allowlisted(Address),
+    UserCount,
+    UserList(u32),     // chunk of users in the order they opened a position
+    Accounts(Address), // sub-account ids a user has opened
+    Entered,           // set while a state-changing call is in progress
+    ThresholdGrace(Address),
+    BorrowIndex,
+    BorrowIndexUpdated,
+    Delegation(Address, u32, Address), // (delegator, account id, delegatee) -> remaining allowance
+    EmergencyMode,
+    TotalStableBorrowed,
+    BadDebt, // debt left on positions with no collateral, not yet covered or socialized
+    RewardToken,
+    RewardState(RewardPool),
+    RewardCheckpoint(RewardPool, Address),
+    Protection(Address, u32), // (user, account id) -> liquidation protection
+    CollateralYield,          // 400 = BENJI earns 4% a year while held as collateral
+    HistoryLength,            // snapshots kept per account; 0 = history off
+    History(Address, u32),    // (user, account id) -> recent snapshots, oldest first
+}
+
+/// Keys of versions 0 and 1 whose entries `migrate` and `migrate_positions`
+/// move to the current layout
+///
+/// Keys encode only the variant, so these match the entries those versions
+/// wrote under `DataKey`.
+#[contracttype]
+enum LegacyKey {
+    UserPosition(Address), // version 0 position, before sub-accounts
+    LtvRatio,              // version 0 LTV of BENJI, its only collateral
     BenjiToken,
     UsdcToken,
-    UserPosition(Address),
-    LtvRatio, // 7000 = 70%
+    CollateralConfig(Address),
+    CollateralTokens,
+    InterestRate,
+    LiquidationBonus,
+    Oracle,
+    Treasury,
+    ReserveFactor,
+    DebtCeiling,
+    FlashLoanFee,
+    BToken,
+    DebtToken,
 }
 
-#[contract]
-pub struct CreditLineContract;
+/// A position as version 0 stored it, with BENJI as the only collateral and no
+/// interest
+#[contracttype]
+struct LegacyPosition {
+    collateral: i128,
+    borrowed: i128,
+    last_update: u64,
+}
 
-#[contractimpl]
-impl CreditLineContract {
-    /// Initialize the contract
-    pub fn initialize(
-        env: Env,
-        admin: Address,
-        benji_token: Address,
-        usdc_token: Address,
-    ) -> Result<(), Error> {
-        if env.storage().instance().has(&DataKey::Admin) {
-            return Err(Error::AlreadyInitialized);
-        }
+/// Storage layout version written by this code; bump alongside a `migrate` step
+const CONTRACT_VERSION: u32 = 3;
 
-        env.storage().instance().set(&DataKey::Admin, &admin);
-        env.storage()
-            .instance()
-            .set(&DataKey::BenjiToken, &benji_token);
-        env.storage()
-            .instance()
-            .set(&DataKey::UsdcToken, &usdc_token);
-        env.storage().instance().set(&DataKey::LtvRatio, &7000_u32); // 70%
+pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
+pub(crate) const POSITION_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub(crate) const POSITION_LIFETIME_THRESHOLD: u32 = POSITION_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
 
-        Ok(())
+/// Read one of a user's positions, extending its TTL so it is not archived
+fn load_position(env: &Env, user: &Address, account_id: u32) -> Option<UserPosition> {
+    let key = DataKey::UserPosition(user.clone(), account_id);
+    let position = env.storage().persistent().get(&key);
+
+    if position.is_some() {
+        env.storage().persistent().extend_ttl(
+            &key,
+            POSITION_LIFETIME_THRESHOLD,
+            POSITION_BUMP_AMOUNT,
+        );
     }
 
-    /// Deposit BENJI tokens as collateral
-    pub fn deposit_collateral(env: Env, user: Address, amount: i128) -> Result<(), Error> {
-        user.require_auth();
+    position
+}
 
-        if amount <= 0 {
-            panic!("Amount must be positive");
+/// Write one of a user's positions, extend its TTL and mirror its debt on the debt
+/// token and in the borrow reward pool
+fn save_position(
+    env: &Env,
+    config: &MarketConfig,
+    user: &Address,
+    account_id: u32,
+    position: &UserPosition,
+) -> Result<(), Error> {
+    let key = DataKey::UserPosition(user.clone(), account_id);
+
+    let old = env.storage().persistent().get::<_, UserPosition>(&key);
+    let old_stable = old.as_ref().map_or(0, |old| old.stable_borrowed);
+    let old_borrowed = old.as_ref().map_or(0, |old| old.borrowed);
+    update_total_stable_borrowed(env, position.stable_borrowed - old_stable);
+
+    // Count the change in debt against the isolated token backing the position
+    let isolated = isolated_token(env, &position.collateral)
+        .or_else(|| old.and_then(|old| isolated_token(env, &old.collateral)));
+    if let Some(token) = isolated {
+        update_isolated_debt(env, &token, position.borrowed - old_borrowed);
+    }
+
+    // A fixed loan, carried as the stable debt, ends once that is paid off, and
+    // otherwise keeps the principal repaid so far, which later interest must not
+    // count against
+    let terms = match &position.terms {
+        LoanTerms::Fixed(_) if position.stable_borrowed == 0 => Some(LoanTerms::Open),
+        LoanTerms::Fixed(loan) if position.stable_borrowed < loan.outstanding => {
+            Some(LoanTerms::Fixed(FixedLoan {
+                outstanding: position.stable_borrowed,
+                ..loan.clone()
+            }))
         }
+        _ => None,
+    };
+    match terms {
+        Some(terms) => {
+            let mut position = position.clone();
+            position.terms = terms;
+            env.storage().persistent().set(&key, &position);
+        }
+        None => env.storage().persistent().set(&key, position),
+    }
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POSITION_LIFETIME_THRESHOLD, POSITION_BUMP_AMOUNT);
+    record_snapshot(env, user, account_id, position);
 
-        // Get BENJI token
-        let benji_token: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::BenjiToken)
-            .ok_or(Error::NotInitialized)?;
+    warn_if_near_liquidation(env, config, user, account_id, position);
 
-        // Transfer BENJI from user to contract
-        let token_client = token::Client::new(&env, &benji_token);
-        token_client.transfer(&user, &env.current_contract_address(), &amount);
+    sync_debt_token(env, user, position.borrowed - old_borrowed);
+    update_reward_balance(
+        env,
+        RewardPool::Borrow,
+        user,
+        position.borrowed - old_borrowed,
+    )
+}
 
-        // Update user position
-        let mut position: UserPosition = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserPosition(user.clone()))
-            .unwrap_or(UserPosition {
-                collateral: 0,
-                borrowed: 0,
-                last_update: env.ledger().timestamp(),
-            });
+/// Remaining amount `delegatee` may borrow against one of `delegator`'s positions
+fn delegation(env: &Env, delegator: &Address, account_id: u32, delegatee: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Delegation(
+            delegator.clone(),
+            account_id,
+            delegatee.clone(),
+        ))
+        .unwrap_or(0)
+}
 
-        position.collateral += amount;
-        position.last_update = env.ledger().timestamp();
+fn write_delegation(
+    env: &Env,
+    delegator: &Address,
+    account_id: u32,
+    delegatee: &Address,
+    amount: i128,
+) {
+    let key = DataKey::Delegation(delegator.clone(), account_id, delegatee.clone());
+    if amount == 0 {
+        env.storage().persistent().remove(&key);
+        return;
+    }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::UserPosition(user), &position);
+    env.storage().persistent().set(&key, &amount);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POSITION_LIFETIME_THRESHOLD, POSITION_BUMP_AMOUNT);
+}
 
-        Ok(())
+fn load_protection(env: &Env, user: &Address, account_id: u32) -> Option<Protection> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Protection(user.clone(), account_id))
+}
+
+/// `Asset::Native` serialized as XDR, identifying native XLM to the asset deployer
+const NATIVE_ASSET_XDR: [u8; 4] = [0, 0, 0, 0];
+
+/// Address of the Stellar Asset Contract wrapping native XLM on this network
+fn native_token(env: &Env) -> Address {
+    env.deployer()
+        .with_stellar_asset(Bytes::from_array(env, &NATIVE_ASSET_XDR))
+        .deployed_address()
+}
+
+/// Check that `token` answers the token interface and record its decimals
+fn register_token(env: &Env, token: &Address) -> Result<u32, Error> {
+    let client = token::Client::new(env, token);
+
+    let decimals = match client.try_decimals() {
+        Ok(Ok(decimals)) if decimals <= 18 => decimals,
+        _ => return Err(Error::InvalidToken),
+    };
+    if !matches!(client.try_symbol(), Ok(Ok(_))) {
+        return Err(Error::InvalidToken);
     }
 
-    /// Borrow USDC against BENJI collateral
-    pub fn borrow(env: Env, user: Address, amount: i128) -> Result<(), Error> {
-        user.require_auth();
+    env.storage()
+        .instance()
+        .set(&DataKey::TokenDecimals(token.clone()), &decimals);
 
-        if amount <= 0 {
-            panic!("Amount must be positive");
-        }
+    Ok(decimals)
+}
 
-        // Get user position
-        let mut position: UserPosition = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserPosition(user.clone()))
-            .ok_or(Error::InsufficientCollateral)?;
+/// Check that `admin` is the stored admin and has authorized the call
+fn require_admin(env: &Env, admin: &Address) -> Result<(), Error> {
+    admin.require_auth();
 
-        // Calculate credit limit (70% of collateral value)
-        let ltv_ratio: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::LtvRatio)
-            .unwrap_or(7000);
+    let stored_admin: Address = env
+        .storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .ok_or(Error::NotInitialized)?;
 
-        let credit_limit = (position.collateral * ltv_ratio as i128) / 10000;
+    if *admin != stored_admin {
+        return Err(Error::Unauthorized);
+    }
 
-        // Check if borrow amount is within limit
-        if position.borrowed + amount > credit_limit {
-            return Err(Error::ExceedsCreditLimit);
-        }
+    Ok(())
+}
 
-        // Get USDC token
-        let usdc_token: Address = env
+/// Delay before a proposed admin can accept, counting a scheduled change once
+/// it is due
+fn admin_timelock(env: &Env) -> u64 {
+    let pending: Option<PendingTimelock> = env.storage().instance().get(&DataKey::PendingTimelock);
+    match pending {
+        Some(pending) if env.ledger().timestamp() >= pending.eta => pending.delay,
+        _ => env
             .storage()
             .instance()
-            .get(&DataKey::UsdcToken)
-            .ok_or(Error::NotInitialized)?;
+            .get(&DataKey::AdminTimelock)
+            .unwrap_or(0),
+    }
+}
 
-        // Transfer USDC to user
-        let token_client = token::Client::new(&env, &usdc_token);
-        token_client.transfer(&env.current_contract_address(), &user, &amount);
+/// Marks the contract as mid-operation until dropped, so a token or receiver
+/// calling back into the contract is rejected with `ReentrantCall`
+struct ReentrancyGuard<'a> {
+    env: &'a Env,
+}
 
-        // Update position
-        position.borrowed += amount;
-        position.last_update = env.ledger().timestamp();
+impl<'a> ReentrancyGuard<'a> {
+    fn acquire(env: &'a Env) -> Result<Self, Error> {
+        if env.storage().instance().has(&DataKey::Entered) {
+            return Err(Error::ReentrantCall);
+        }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::UserPosition(user), &position);
+        env.storage().instance().set(&DataKey::Entered, &true);
+        Ok(Self { env })
+    }
+}
 
-        Ok(())
+impl Drop for ReentrancyGuard<'_> {
+    fn drop(&mut self) {
+        self.env.storage().instance().remove(&DataKey::Entered);
     }
+}
 
-    /// Repay borrowed USDC
-    pub fn repay(env: Env, user: Address, amount: i128) -> Result<(), Error> {
-        user.require_auth();
+/// Fail if the admin has paused the market
+fn require_not_paused(env: &Env) -> Result<(), Error> {
+    if env
+        .storage()
+        .instance()
+        .get(&DataKey::Paused)
+        .unwrap_or(false)
+    {
+        return Err(Error::ContractPaused);
+    }
 
-        if amount <= 0 {
-            panic!("Amount must be positive");
-        }
+    Ok(())
+}
 
-        // Get user position
-        let mut position: UserPosition = env
+fn is_emergency_mode(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .get(&DataKey::EmergencyMode)
+        .unwrap_or(false)
+}
+
+/// When allowlist mode is on, check that `user` has been allowed by the admin
+fn require_allowlisted(env: &Env, user: &Address) -> Result<(), Error> {
+    let enabled = env
+        .storage()
+        .instance()
+        .get(&DataKey::AllowlistEnabled)
+        .unwrap_or(false);
+
+    if enabled
+        && !env
             .storage()
             .persistent()
-            .get(&DataKey::UserPosition(user.clone()))
-            .ok_or(Error::NotInitialized)?;
+            .has(&DataKey::Allowlisted(user.clone()))
+    {
+        return Err(Error::NotAllowlisted);
+    }
+
+    Ok(())
+}
 
-        if position.borrowed < amount {
-            panic!("Repay amount exceeds borrowed amount");
+/// Market configuration written at initialization
+fn load_config(env: &Env) -> Result<MarketConfig, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Config)
+        .ok_or(Error::NotInitialized)
+}
+
+/// Validate and store the market configuration, logging what `admin` changed
+fn store_config(env: &Env, admin: &Address, config: &MarketConfig) -> Result<(), Error> {
+    if config.interest_rate > MAX_RATE
+        || config.rate_slope > MAX_RATE
+        || config.stable_rate_premium > MAX_RATE
+        || config.late_penalty_rate > MAX_RATE
+        || config.liquidation_bonus > MAX_LIQUIDATION_BONUS
+        || config.reserve_factor > 10000
+        || config.flash_loan_fee > 10000
+        || config.max_price_deviation > 10000
+        || config.close_factor == 0
+        || config.close_factor > 10000
+        || config.debt_ceiling.is_some_and(|ceiling| ceiling < 0)
+        || config.min_borrow < 0
+        || config.min_collateral < 0
+    {
+        return Err(Error::InvalidParameter);
+    }
+
+    for (_, collateral) in config.collateral.iter() {
+        if collateral.ltv_ratio == 0
+            || collateral.ltv_ratio > MAX_LTV_RATIO
+            || collateral.liquidation_threshold < collateral.ltv_ratio
+            || collateral.liquidation_threshold > 10000
+        {
+            return Err(Error::InvalidParameter);
         }
+    }
 
-        // Get USDC token
-        let usdc_token: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::UsdcToken)
-            .ok_or(Error::NotInitialized)?;
+    // Log what changed, unless this is the first config written
+    if let Some(old) = env
+        .storage()
+        .instance()
+        .get::<_, MarketConfig>(&DataKey::Config)
+    {
+        record_config_changes(env, admin, &old, config);
+    }
 
-        // Transfer USDC from user to contract
-        let token_client = token::Client::new(&env, &usdc_token);
-        token_client.transfer(&user, &env.current_contract_address(), &amount);
+    env.storage().instance().set(&DataKey::Config, config);
 
-        // Update position
-        position.borrowed -= amount;
-        position.last_update = env.ledger().timestamp();
+    Ok(())
+}
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::UserPosition(user), &position);
+/// Fold the per-parameter config keys of versions 0 and 1 into a single
+/// `Config` entry
+fn migrate_legacy_config(env: &Env, admin: &Address) -> Result<(), Error> {
+    let storage = env.storage().instance();
+    let benji_token: Address = storage
+        .get(&LegacyKey::BenjiToken)
+        .ok_or(Error::NotInitialized)?;
 
-        Ok(())
+    let tokens: Vec<Address> = storage
+        .get(&LegacyKey::CollateralTokens)
+        .unwrap_or(Vec::new(env));
+    let mut collateral = Map::new(env);
+    for token in tokens.iter() {
+        let key = LegacyKey::CollateralConfig(token.clone());
+        if let Some(config) = storage.get::<_, CollateralConfig>(&key) {
+            collateral.set(token, config);
+        }
+        storage.remove(&key);
     }
 
-    /// Withdraw collateral (only if enough collateral remains)
-    pub fn withdraw_collateral(env: Env, user: Address, amount: i128) -> Result<(), Error> {
-        user.require_auth();
+    // Version 0 lent against BENJI alone at a single LTV, and never liquidated
+    if tokens.is_empty() {
+        let ltv_ratio: u32 = storage.get(&LegacyKey::LtvRatio).unwrap_or(7000);
+        collateral.set(
+            benji_token.clone(),
+            CollateralConfig {
+                ltv_ratio,
+                liquidation_threshold: (ltv_ratio + 1000).min(10000),
+            },
+        );
+    }
 
-        if amount <= 0 {
-            panic!("Amount must be positive");
-        }
+    let config = MarketConfig {
+        benji_token,
+        usdc_token: storage
+            .get(&LegacyKey::UsdcToken)
+            .ok_or(Error::NotInitialized)?,
+        collateral,
+        oracle: storage.get(&LegacyKey::Oracle),
+        treasury: storage.get(&LegacyKey::Treasury),
+        btoken: storage.get(&LegacyKey::BToken),
+        debt_token: storage.get(&LegacyKey::DebtToken),
+        staking: None,
+        interest_rate: storage.get(&LegacyKey::InterestRate).unwrap_or(0),
+        rate_slope: 0,
+        stable_rate_premium: 0,
+        liquidation_bonus: storage.get(&LegacyKey::LiquidationBonus).unwrap_or(0),
+        reserve_factor: storage.get(&LegacyKey::ReserveFactor).unwrap_or(0),
+        flash_loan_fee: storage.get(&LegacyKey::FlashLoanFee).unwrap_or(0),
+        debt_ceiling: storage.get(&LegacyKey::DebtCeiling),
+        min_borrow: 0,
+        min_collateral: 0,
+        grace_period: 0,
+        max_price_age: 0,
+        max_price_deviation: 0,
+        twap_window: 0,
+        interest_free_period: 0,
+        late_penalty_rate: 0,
+        close_factor: 10000,
+        compounding: Compounding::Linear,
+    };
+    store_config(env, admin, &config)?;
 
-        // Get user position
-        let mut position: UserPosition = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserPosition(user.clone()))
-            .ok_or(Error::NotInitialized)?;
+    for key in [
+        LegacyKey::LtvRatio,
+        LegacyKey::BenjiToken,
+        LegacyKey::UsdcToken,
+        LegacyKey::CollateralTokens,
+        LegacyKey::InterestRate,
+        LegacyKey::LiquidationBonus,
+        LegacyKey::Oracle,
+        LegacyKey::Treasury,
+        LegacyKey::ReserveFactor,
+        LegacyKey::DebtCeiling,
+        LegacyKey::FlashLoanFee,
+        LegacyKey::BToken,
+        LegacyKey::DebtToken,
+    ] {
+        storage.remove(&key);
+    }
 
-        if position.collateral < amount {
-            return Err(Error::InsufficientBalance);
-        }
+    Ok(())
+}
 
-        // Check if remaining collateral covers borrowed amount
-        let new_collateral = position.collateral - amount;
-        let ltv_ratio: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::LtvRatio)
-            .unwrap_or(7000);
+/// Risk parameters of an accepted collateral token
+fn collateral_config(env: &Env, token: &Address) -> Result<CollateralConfig, Error> {
+    load_config(env)?
+        .collateral
+        .get(token.clone())
+        .ok_or(Error::UnsupportedCollateral)
+}
 
-        let credit_limit = (new_collateral * ltv_ratio as i128) / 10000;
+/// Validate and store the risk parameters of a collateral token, logging what
+/// `admin` changed
+fn store_collateral_config(
+    env: &Env,
+    admin: &Address,
+    token: &Address,
+    config: &CollateralConfig,
+) -> Result<(), Error> {
+    let mut market = load_config(env)?;
 
-        if position.borrowed > credit_limit {
-            return Err(Error::InsufficientCollateral);
+    match market.collateral.get(token.clone()) {
+        // Check the token on first configuration
+        None => {
+            register_token(env, token)?;
         }
+        // Give positions a lower threshold would expose time to react, keeping
+        // the most lenient threshold if a grace period is already running
+        Some(previous) if config.liquidation_threshold < previous.liquidation_threshold => {
+            let threshold =
+                active_grace(env, token).map_or(previous.liquidation_threshold, |grace| {
+                    grace
+                        .liquidation_threshold
+                        .max(previous.liquidation_threshold)
+                });
+            env.storage().instance().set(
+                &DataKey::ThresholdGrace(token.clone()),
+                &ThresholdGrace {
+                    liquidation_threshold: threshold,
+                    deadline: env.ledger().timestamp() + market.grace_period,
+                },
+            );
+        }
+        Some(_) => {}
+    }
 
-        // Get BENJI token
-        let benji_token: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::BenjiToken)
-            .ok_or(Error::NotInitialized)?;
+    market.collateral.set(token.clone(), config.clone());
+    store_config(env, admin, &market)?;
 
-        // Transfer BENJI back to user
-        let token_client = token::Client::new(&env, &benji_token);
-        token_client.transfer(&env.current_contract_address(), &user, &amount);
+    CollateralConfigUpdated {
+        token: token.clone(),
+        ltv_ratio: config.ltv_ratio,
+        liquidation_threshold: config.liquidation_threshold,
+    }
+    .publish(env);
 
-        // Update position
-        position.collateral -= amount;
-        position.last_update = env.ledger().timestamp();
+    Ok(())
+}
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::UserPosition(user), &position);
+/// Collateral token price in USDC as `(price, scale)`, or 1:1 when no oracle is set
+///
+/// A price older than `max_price_age`, or further than `max_price_deviation` from
+/// the update before it, is refused with `OracleStale`. Anything that needs a
+/// price (borrows, withdrawals, liquidations) then fails until the feed
+/// recovers, while deposits and repays carry on.
+///
+/// With a `twap_window` set, the checked price is then smoothed over the window
+/// so a brief spike or dip does not swing credit limits or trigger liquidations.
+/// When the feed quotes another asset, USDC's own price in it goes through the
+/// same checks and smoothing.
+fn collateral_price(
+    env: &Env,
+    config: &MarketConfig,
+    token: &Address,
+) -> Result<(i128, i128), Error> {
+    let Some(oracle) = &config.oracle else {
+        return Ok((1, 1));
+    };
 
-        Ok(())
+    let client = PriceOracleClient::new(env, oracle);
+    let price = checked_price(env, config, &client, &Asset::Stellar(token.clone()))?;
+
+    let settings = oracle_settings(env);
+    let scale = 10_i128.pow(settings.decimals.unwrap_or_else(|| client.decimals()));
+    let price = oriented_price(&settings, price, scale)?;
+
+    // Divide out USDC's own price when the feed quotes in another asset
+    let QuoteAsset::Other(usdc_asset) = settings.quote.clone() else {
+        return Ok((price, scale));
+    };
+    let usdc_price = checked_price(env, config, &client, &usdc_asset)?;
+    let usdc_price = oriented_price(&settings, usdc_price, scale)?;
+
+    let price = mul_div(price, scale, usdc_price, Rounding::Down).ok_or(Error::MathOverflow)?;
+    Ok((price, scale))
+}
+
+/// An asset's feed price, checked for age and deviation and averaged over the
+/// TWAP window as `collateral_price` describes
+fn checked_price(
+    env: &Env,
+    config: &MarketConfig,
+    client: &PriceOracleClient,
+    asset: &Asset,
+) -> Result<i128, Error> {
+    let latest = client.lastprice(asset).ok_or(Error::PriceUnavailable)?;
+
+    if latest.price <= 0 {
+        return Err(Error::PriceUnavailable);
     }
 
-    /// Get user's position
-    pub fn get_position(env: Env, user: Address) -> UserPosition {
-        env.storage()
-            .persistent()
-            .get(&DataKey::UserPosition(user))
-            .unwrap_or(UserPosition {
-                collateral: 0,
-                borrowed: 0,
-                last_update: env.ledger().timestamp(),
-            })
+    let age = env.ledger().timestamp().saturating_sub(latest.timestamp);
+    if config.max_price_age > 0 && age > config.max_price_age {
+        return Err(Error::OracleStale);
     }
 
-    /// Calculate available credit for a user
-    pub fn get_available_credit(env: Env, user: Address) -> i128 {
-        let position = Self::get_position(env.clone(), user);
+    if config.max_price_deviation > 0 {
+        let previous = client.prices(asset, &2).and_then(|history| {
+            history
+                .iter()
+                .find(|record| record.timestamp < latest.timestamp)
+        });
+        if let Some(previous) = previous.filter(|previous| previous.price > 0) {
+            let deviation = mul_div(
+                (latest.price - previous.price).abs(),
+                BPS,
+                previous.price,
+                Rounding::Up,
+            )
+            .ok_or(Error::MathOverflow)?;
+            if deviation > config.max_price_deviation as i128 {
+                return Err(Error::OracleStale);
+            }
+        }
+    }
 
-        let ltv_ratio: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::LtvRatio)
-            .unwrap_or(7000);
+    if config.twap_window > 0 {
+        time_weighted_price(env, client, asset, &latest, config.twap_window)
+    } else {
+        Ok(latest.price)
+    }
+}
 
-        let credit_limit = (position.collateral * ltv_ratio as i128) / 10000;
-        let available = credit_limit - position.borrowed;
+/// Decimals recorded for a token, registering it on first use
+fn token_decimals(env: &Env, token: &Address) -> Result<u32, Error> {
+    match env
+        .storage()
+        .instance()
+        .get(&DataKey::TokenDecimals(token.clone()))
+    {
+        Some(decimals) => Ok(decimals),
+        None => register_token(env, token),
+    }
+}
 
-        if available < 0 {
-            0
-        } else {
-            available
-        }
+/// Scale a token amount up to the 18-decimal internal representation
+fn to_internal(env: &Env, token: &Address, amount: i128) -> Result<i128, Error> {
+    let factor = 10_i128.pow(INTERNAL_DECIMALS - token_decimals(env, token)?);
+    amount.checked_mul(factor).ok_or(Error::MathOverflow)
+}
+
+/// Scale an 18-decimal internal value back down to a token amount, rounding down
+fn from_internal(env: &Env, token: &Address, value: i128) -> Result<i128, Error> {
+    let factor = 10_i128.pow(INTERNAL_DECIMALS - token_decimals(env, token)?);
+    Ok(value / factor)
+}
+
+/// USDC debt in the 18-decimal internal representation
+fn debt_value(env: &Env, config: &MarketConfig, borrowed: i128) -> Result<i128, Error> {
+    to_internal(env, &config.usdc_token, borrowed)
+}
+
+/// USDC amount worth an 18-decimal internal value
+fn usdc_for_value(
+    env: &Env,
+    config: &MarketConfig,
+    value: i128,
+    rounding: Rounding,
+) -> Result<i128, Error> {
+    let factor = 10_i128.pow(INTERNAL_DECIMALS - token_decimals(env, &config.usdc_token)?);
+    mul_div(value, 1, factor, rounding).ok_or(Error::MathOverflow)
+}
+
+/// USDC value of an amount of a collateral token, in 18 decimals
+fn collateral_value(
+    env: &Env,
+    config: &MarketConfig,
+    token: &Address,
+    amount: i128,
+    rounding: Rounding,
+) -> Result<i128, Error> {
+    let (price, scale) = collateral_price(env, config, token)?;
+    let amount = to_internal(env, token, amount)?;
+    mul_div(amount, price, scale, rounding).ok_or(Error::MathOverflow)
+}
+
+/// Amount of a collateral token worth a given 18-decimal USDC value
+fn collateral_for_value(
+    env: &Env,
+    config: &MarketConfig,
+    token: &Address,
+    value: i128,
+) -> Result<i128, Error> {
+    let (price, scale) = collateral_price(env, config, token)?;
+    let amount = mul_div(value, scale, price, Rounding::Down).ok_or(Error::MathOverflow)?;
+    from_internal(env, token, amount)
+}
+
+/// Sum of collateral values, each weighted by a per-token ratio in basis points
+fn weighted_collateral_value(
+    env: &Env,
+    market: &MarketConfig,
+    collateral: &Map<Address, i128>,
+    ratio: impl Fn(&Address, &CollateralConfig) -> u32,
+) -> Result<i128, Error> {
+    let mut total = 0;
+    for (token, amount) in collateral.iter() {
+        let config = market
+            .collateral
+            .get(token.clone())
+            .ok_or(Error::UnsupportedCollateral)?;
+        let value = collateral_value(env, market, &token, amount, Rounding::Down)?;
+        let weighted =
+            bps_mul(value, ratio(&token, &config), Rounding::Down).ok_or(Error::MathOverflow)?;
+        total = weighted.checked_add(total).ok_or(Error::MathOverflow)?;
+    }
+
+    Ok(total)
+}
+
+/// Share of a collateral token's value held back from credit limits, in basis points
+fn collateral_haircut(env: &Env, token: &Address) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::CollateralHaircut(token.clone()))
+        .unwrap_or(0)
+}
+
+/// Maximum borrowable USDC for a user's collateral balances, in 18 decimals
+///
+/// Any staking boost raises each token's LTV, but never past its liquidation
+/// threshold. The token's haircut then comes off the result.
+fn credit_limit(
+    env: &Env,
+    market: &MarketConfig,
+    user: &Address,
+    collateral: &Map<Address, i128>,
+) -> Result<i128, Error> {
+    let boost = ltv_boost(env, market, user);
+    weighted_collateral_value(env, market, collateral, |token, config| {
+        let ltv = config
+            .ltv_ratio
+            .saturating_add(boost)
+            .min(config.liquidation_threshold);
+        ltv * (BPS as u32 - collateral_haircut(env, token)) / BPS as u32
+    })
+}
+
+/// Debt above which a set of collateral balances can be liquidated, in 18 decimals,
+/// honouring thresholds still in their grace period
+fn liquidation_limit(
+    env: &Env,
+    market: &MarketConfig,
+    collateral: &Map<Address, i128>,
+) -> Result<i128, Error> {
+    weighted_collateral_value(env, market, collateral, |token, config| {
+        active_grace(env, token).map_or(config.liquidation_threshold, |grace| {
+            grace
+                .liquidation_threshold
+                .max(config.liquidation_threshold)
+        })
+    })
+}
+
+/// Liquidation limit under the current thresholds, ignoring any grace period
+fn strict_liquidation_limit(
+    env: &Env,
+    market: &MarketConfig,
+    collateral: &Map<Address, i128>,
+) -> Result<i128, Error> {
+    weighted_collateral_value(env, market, collateral, |_, config| {
+        config.liquidation_threshold
+    })
+}
+
+/// Grace period of a collateral token whose threshold was recently lowered, if still running
+fn active_grace(env: &Env, token: &Address) -> Option<ThresholdGrace> {
+    env.storage()
+        .instance()
+        .get::<_, ThresholdGrace>(&DataKey::ThresholdGrace(token.clone()))
+        .filter(|grace| env.ledger().timestamp() < grace.deadline)
+}
+
+const MAX_LTV_RATIO: u32 = 9500;
+
+/// Highest liquidation bonus, in basis points
+const MAX_LIQUIDATION_BONUS: u32 = 2000;
+
+/// Highest APR any one rate parameter may contribute, in basis points
+const MAX_RATE: u32 = 10000;
+
+/// Decimals that collateral values and debt are compared in, whatever the token decimals
+const INTERNAL_DECIMALS: u32 = 18;
+
+/// Health factor of 1.0, in 7-decimal fixed point
+pub const HEALTH_FACTOR_ONE: i128 = 10_000_000;
+
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const SECONDS_PER_LEDGER: u64 = SECONDS_PER_DAY / DAY_IN_LEDGERS as u64;
+
+/// Most users returned by one page of `list_users` or `list_liquidatable`
+const MAX_PAGE_SIZE: u32 = 100;
+
+/// Most interest-free tranches a position keeps
+const MAX_FREE_TRANCHES: u32 = 8;
+
+/// Global borrow index as of the current ledger, RAY-scaled
+///
+/// Debt taken at index `i` is worth `debt * index / i` now. The index compounds
+/// the interest rate each time it is stored.
+fn borrow_index(env: &Env, config: &MarketConfig) -> Result<i128, Error> {
+    let index: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::BorrowIndex)
+        .unwrap_or(RAY);
+    let updated: u64 = env
+        .storage()
+        .instance()
+        .get(&DataKey::BorrowIndexUpdated)
+        .unwrap_or(env.ledger().timestamp());
+
+    // Interest stops once the market is in emergency mode
+    let elapsed = env.ledger().timestamp().saturating_sub(updated);
+    if elapsed == 0 || is_emergency_mode(env) {
+        return Ok(index);
+    }
+
+    let rate = variable_rate(env, config)?;
+    let growth = accrued_interest(config, index, rate, updated, env.ledger().timestamp())?;
+
+    index.checked_add(growth).ok_or(Error::MathOverflow)
+}
+
+/// Interest on `amount` at the annual `rate` from timestamp `from` to `to`,
+/// compounded as the market is configured to
+///
+/// Compounding periods are counted from the Unix epoch, so interest is
+/// capitalized at the same moments however often the debt is brought up to
+/// date. Within a period interest grows linearly; `amount` already carries the
+/// linear growth since the period containing `from` began, which is divided
+/// back out before compounding over the periods crossed since.
+fn accrued_interest(
+    config: &MarketConfig,
+    amount: i128,
+    rate: u32,
+    from: u64,
+    to: u64,
+) -> Result<i128, Error> {
+    let period = match config.compounding {
+        Compounding::Linear => {
+            return mul_div(
+                amount,
+                rate as i128 * to.saturating_sub(from) as i128,
+                BPS * SECONDS_PER_YEAR as i128,
+                Rounding::Up,
+            )
+            .ok_or(Error::MathOverflow);
+        }
+        Compounding::PerLedger => SECONDS_PER_LEDGER,
+        Compounding::Daily => SECONDS_PER_DAY,
+    };
+    if to <= from {
+        return Ok(0);
+    }
+
+    let period_rate = mul_div(
+        RAY,
+        rate as i128 * period as i128,
+        BPS * SECONDS_PER_YEAR as i128,
+        Rounding::Up,
+    )
+    .ok_or(Error::MathOverflow)?;
+    let partial = |time: u64| {
+        mul_div(
+            period_rate,
+            (time % period) as i128,
+            period as i128,
+            Rounding::Up,
+        )
+        .ok_or(Error::MathOverflow)
+    };
+
+    let periods = to / period - from / period;
+    let whole = compound(period_rate, periods, Rounding::Up).ok_or(Error::MathOverflow)?;
+    let grown = ray_mul(whole, RAY + partial(to)?, Rounding::Up).ok_or(Error::MathOverflow)?;
+    let factor = ray_div(grown, RAY + partial(from)?, Rounding::Up).ok_or(Error::MathOverflow)?;
+
+    mul_div(amount, factor - RAY, RAY, Rounding::Up).ok_or(Error::MathOverflow)
+}
+
+/// Share of pool assets lent out, in basis points
+fn utilization(env: &Env) -> Result<u32, Error> {
+    let total_borrowed: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalBorrowed)
+        .unwrap_or(0);
+    let total_supplied = pool_assets(env);
+
+    if total_supplied <= 0 {
+        return Ok(0);
+    }
+
+    Ok(mul_div(total_borrowed, BPS, total_supplied, Rounding::Down)
+        .ok_or(Error::MathOverflow)?
+        .clamp(0, BPS) as u32)
+}
+
+/// Annual variable borrow rate at the current utilization, in basis points
+fn variable_rate(env: &Env, config: &MarketConfig) -> Result<u32, Error> {
+    let slope = bps_mul(config.rate_slope as i128, utilization(env)?, Rounding::Down)
+        .ok_or(Error::MathOverflow)?;
+    Ok(config.interest_rate.saturating_add(slope as u32))
+}
+
+/// Annual rate a stable-mode borrow locks in now, in basis points
+fn stable_rate(env: &Env, config: &MarketConfig) -> Result<u32, Error> {
+    Ok(variable_rate(env, config)?.saturating_add(config.stable_rate_premium))
+}
+
+/// Annual rate a position's stable debt accrues at now, in basis points,
+/// including the late penalty on an overdue fixed loan
+fn stable_debt_rate(
+    env: &Env,
+    config: &MarketConfig,
+    position: &UserPosition,
+) -> Result<u32, Error> {
+    let mut rate = position.stable_rate;
+    if let LoanTerms::Fixed(loan) = &position.terms {
+        if is_overdue(env, loan, position.stable_borrowed)? {
+            rate = rate.saturating_add(config.late_penalty_rate);
+        }
+    }
+    Ok(rate)
+}
+
+/// Annual rate a position's debt accrues at now, in basis points
+///
+/// Stable and variable debt are weighted by their size. Without debt, this is
+/// the rate a borrow in the position's mode would take.
+fn position_rate(env: &Env, config: &MarketConfig, position: &UserPosition) -> Result<u32, Error> {
+    if position.borrowed <= 0 {
+        return match position.rate_mode {
+            RateMode::Variable => variable_rate(env, config),
+            RateMode::Stable => stable_rate(env, config),
+        };
+    }
+
+    let variable = (position.borrowed - position.stable_borrowed)
+        .checked_mul(variable_rate(env, config)? as i128)
+        .ok_or(Error::MathOverflow)?;
+    let stable = position
+        .stable_borrowed
+        .checked_mul(stable_debt_rate(env, config, position)? as i128)
+        .ok_or(Error::MathOverflow)?;
+    Ok(
+        mul_div(variable + stable, 1, position.borrowed, Rounding::Up).ok_or(Error::MathOverflow)?
+            as u32,
+    )
+}
+
+/// Move `amount` of a position's debt to the stable rate in force now,
+/// averaging it into the rate its stable debt already carries
+fn add_stable_debt(
+    env: &Env,
+    config: &MarketConfig,
+    position: &mut UserPosition,
+    amount: i128,
+) -> Result<(), Error> {
+    if amount <= 0 {
+        return Ok(());
+    }
+
+    let rate = stable_rate(env, config)?;
+    position.stable_rate = mul_div(
+        position.stable_borrowed * position.stable_rate as i128 + amount * rate as i128,
+        1,
+        position.stable_borrowed + amount,
+        Rounding::Up,
+    )
+    .ok_or(Error::MathOverflow)? as u32;
+    position.stable_borrowed += amount;
+    Ok(())
+}
+
+fn update_total_stable_borrowed(env: &Env, delta: i128) {
+    if delta == 0 {
+        return;
+    }
+
+    let total: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalStableBorrowed)
+        .unwrap_or(0);
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalStableBorrowed, &(total + delta).max(0));
+}
+
+/// Bring the stored borrow index up to date, recording the interest accrued on
+/// variable-rate debt since it was last updated
+fn update_borrow_index(env: &Env, config: &MarketConfig) -> Result<i128, Error> {
+    let old_index: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::BorrowIndex)
+        .unwrap_or(RAY);
+    let index = borrow_index(env, config)?;
+
+    if index != old_index {
+        let total_borrowed: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalBorrowed)
+            .unwrap_or(0);
+        let total_stable: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalStableBorrowed)
+            .unwrap_or(0);
+        let variable_borrowed = (total_borrowed - total_stable).max(0);
+        let interest = mul_div(
+            variable_borrowed,
+            index - old_index,
+            old_index,
+            Rounding::Down,
+        )
+        .ok_or(Error::MathOverflow)?;
+        record_interest(env, config, interest)?;
+    }
+
+    env.storage().instance().set(&DataKey::BorrowIndex, &index);
+    env.storage()
+        .instance()
+        .set(&DataKey::BorrowIndexUpdated, &env.ledger().timestamp());
+
+    Ok(index)
+}
+
+/// Bring a position's debt up to date, returning the interest added, the part
+/// of it added to the stable debt, and the variable interest waived on its
+/// interest-free tranches
+///
+/// Variable debt is scaled from its checkpoint to `index`; stable debt accrues its
+/// locked rate since `last_update`.
+fn apply_borrow_index(
+    env: &Env,
+    config: &MarketConfig,
+    position: &mut UserPosition,
+    index: i128,
+) -> Result<(i128, i128, i128), Error> {
+    let variable_borrowed = position.borrowed - position.stable_borrowed;
+    let mut variable_interest = 0;
+    if variable_borrowed > 0 && position.borrow_index > 0 && index != position.borrow_index {
+        let borrowed = mul_div(
+            variable_borrowed,
+            index,
+            position.borrow_index,
+            Rounding::Up,
+        )
+        .ok_or(Error::MathOverflow)?;
+        variable_interest = borrowed - variable_borrowed;
+    }
+
+    let now = env.ledger().timestamp();
+    let mut stable_interest = 0;
+    if position.stable_borrowed > 0 && now > position.last_update && !is_emergency_mode(env) {
+        let rate = stable_debt_rate(env, config, position)?;
+        stable_interest = accrued_interest(
+            config,
+            position.stable_borrowed,
+            rate,
+            position.last_update,
+            now,
+        )?;
+    }
+
+    // Waived interest comes off each kind of debt in proportion to its interest
+    let interest = variable_interest + stable_interest;
+    let waived = waived_interest(env, position, interest)?;
+    let stable_waived = match interest {
+        0 => 0,
+        _ => {
+            mul_div(waived, stable_interest, interest, Rounding::Down).ok_or(Error::MathOverflow)?
+        }
+    };
+    let stable_interest = stable_interest - stable_waived;
+    let interest = interest - waived;
+
+    position.borrowed = position
+        .borrowed
+        .checked_add(interest)
+        .ok_or(Error::MathOverflow)?;
+    position.stable_borrowed += stable_interest;
+    position.borrow_index = index;
+    position.last_update = now;
+    Ok((interest, stable_interest, waived - stable_waived))
+}
+
+/// Share of `interest`, accrued since the position's last update, that fell on
+/// tranches still inside their interest-free period, dropping expired tranches
+///
+/// Interest accrues near-evenly over the interval, so a tranche that expired
+/// part-way through is charged for the part after it expired.
+fn waived_interest(env: &Env, position: &mut UserPosition, interest: i128) -> Result<i128, Error> {
+    let now = env.ledger().timestamp();
+    let elapsed = now.saturating_sub(position.last_update);
+
+    let mut waived: i128 = 0;
+    let mut uncovered = position.borrowed;
+    let mut remaining = Vec::new(env);
+    for tranche in position.free_tranches.iter() {
+        let free_seconds = tranche
+            .free_until
+            .min(now)
+            .saturating_sub(position.last_update);
+        // No unit of debt is waived twice, however the tranches stack up
+        let covered = tranche.amount.min(uncovered);
+        uncovered -= covered;
+        if interest > 0 && covered > 0 && free_seconds > 0 {
+            let share = mul_div(interest, covered, position.borrowed, Rounding::Down)
+                .ok_or(Error::MathOverflow)?;
+            waived += mul_div(share, free_seconds as i128, elapsed as i128, Rounding::Down)
+                .ok_or(Error::MathOverflow)?;
+        }
+
+        if tranche.free_until > now {
+            remaining.push_back(tranche);
+        }
+    }
+
+    position.free_tranches = remaining;
+    Ok(waived.min(interest))
+}
+
+/// Update the global borrow index and bring a position's debt up to it,
+/// returning the interest added
+fn accrue_interest(
+    env: &Env,
+    config: &MarketConfig,
+    user: &Address,
+    position: &mut UserPosition,
+) -> Result<i128, Error> {
+    let index = update_borrow_index(env, config)?;
+    let (interest, stable_interest, variable_waived) =
+        apply_borrow_index(env, config, position, index)?;
+
+    // Variable interest is already counted by the index update, waived part and all
+    record_interest(env, config, stable_interest)?;
+    forgo_interest(env, config, variable_waived)?;
+    credit_referrer(env, config, user, interest)?;
+
+    Ok(interest)
+}
+
+/// A copy of a position with debt accrued to the current ledger, without writing state
+fn accrued_position(
+    env: &Env,
+    config: &MarketConfig,
+    mut position: UserPosition,
+) -> Result<UserPosition, Error> {
+    apply_borrow_index(env, config, &mut position, borrow_index(env, config)?)?;
+    Ok(position)
+}
+
+/// Debt with accrued interest on all of a user's accounts but `account_id`
+fn other_debt(
+    env: &Env,
+    config: &MarketConfig,
+    user: &Address,
+    account_id: u32,
+) -> Result<i128, Error> {
+    let index = borrow_index(env, config)?;
+    let mut debt = 0;
+    for id in accounts(env, user).iter().filter(|id| *id != account_id) {
+        if let Some(mut position) = load_position(env, user, id) {
+            apply_borrow_index(env, config, &mut position, index)?;
+            debt += position.borrowed;
+        }
+    }
+    Ok(debt)
+}
+
+/// Take back interest the index update counted but a borrower does not owe,
+/// along with the reserve share it diverted
+fn forgo_interest(env: &Env, config: &MarketConfig, interest: i128) -> Result<(), Error> {
+    if interest == 0 {
+        return Ok(());
+    }
+
+    update_total_borrowed(env, -interest);
+
+    let reserve_factor = config.reserve_factor;
+    let reserves: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalReserves)
+        .unwrap_or(0);
+
+    let reserve_share =
+        bps_mul(interest, reserve_factor, Rounding::Down).ok_or(Error::MathOverflow)?;
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalReserves, &(reserves - reserve_share).max(0));
+    update_total_supplied(env, -(interest - reserve_share));
+
+    Ok(())
+}
+
+/// Add a new borrow to an accrued position as `mode` debt, checking it against
+/// the credit limit and risk limits
+///
+/// Does not touch market totals or move tokens, so previews can run it too.
+fn add_debt(
+    env: &Env,
+    config: &MarketConfig,
+    user: &Address,
+    account_id: u32,
+    position: &mut UserPosition,
+    amount: i128,
+    mode: RateMode,
+) -> Result<(), Error> {
+    // Calculate credit limit (LTV-weighted collateral value)
+    let credit_limit = credit_limit(env, config, user, &position.collateral)?;
+
+    // Check if borrow amount is within limit
+    if debt_value(env, config, position.borrowed + amount)? > credit_limit {
+        return Err(Error::ExceedsCreditLimit);
+    }
+
+    // Check risk limits
+    let borrow_cap: Option<i128> = env
+        .storage()
+        .persistent()
+        .get(&DataKey::BorrowCap(user.clone()));
+    if let Some(cap) = borrow_cap {
+        if other_debt(env, config, user, account_id)? + position.borrowed + amount > cap {
+            return Err(Error::UserBorrowCapReached);
+        }
+    }
+
+    if let Some(ceiling) = config.debt_ceiling {
+        let total_borrowed: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalBorrowed)
+            .unwrap_or(0);
+        if total_borrowed + amount > ceiling {
+            return Err(Error::GlobalDebtCeilingReached);
+        }
+    }
+
+    if mode == RateMode::Stable {
+        add_stable_debt(env, config, position, amount)?;
+    }
+
+    // Open an interest-free tranche during a promotion. Past the most a position
+    // keeps, the borrow joins the newest tranche and ends with it
+    if config.interest_free_period > 0 {
+        let tranches = &mut position.free_tranches;
+        match tranches.last() {
+            Some(mut newest) if tranches.len() >= MAX_FREE_TRANCHES => {
+                newest.amount += amount;
+                tranches.set(tranches.len() - 1, newest);
+            }
+            _ => tranches.push_back(Tranche {
+                amount,
+                free_until: env.ledger().timestamp() + config.interest_free_period,
+            }),
+        }
+    }
+    position.borrowed += amount;
+
+    Ok(())
+}
+
+/// Take `amount` off a position's debt, using up its interest-free tranches
+/// oldest first so repaid debt cannot keep its free period for a later borrow
+///
+/// Stable debt, and with it any fixed loan, is paid before variable debt.
+pub(crate) fn reduce_debt(env: &Env, position: &mut UserPosition, amount: i128) {
+    position.borrowed -= amount;
+    position.stable_borrowed = (position.stable_borrowed - amount).max(0);
+    if position.stable_borrowed == 0 {
+        position.stable_rate = 0;
+    }
+
+    let mut left = amount;
+    let mut remaining = Vec::new(env);
+    for mut tranche in position.free_tranches.iter() {
+        if left >= tranche.amount {
+            left -= tranche.amount;
+            continue;
+        }
+        tranche.amount -= left;
+        left = 0;
+        remaining.push_back(tranche);
+    }
+    position.free_tranches = remaining;
+}
+
+/// Take collateral out of an accrued position, checking the rest still covers
+/// its debt, and return the token balance left
+fn remove_collateral(
+    env: &Env,
+    config: &MarketConfig,
+    user: &Address,
+    position: &mut UserPosition,
+    token: &Address,
+    amount: i128,
+) -> Result<i128, Error> {
+    let balance = position.collateral.get(token.clone()).unwrap_or(0);
+    if balance < amount {
+        return Err(Error::InsufficientBalance);
+    }
+
+    // Check if remaining collateral covers borrowed amount
+    let new_balance = balance - amount;
+    if new_balance == 0 {
+        position.collateral.remove(token.clone());
+    } else {
+        position.collateral.set(token.clone(), new_balance);
+    }
+
+    if debt_value(env, config, position.borrowed)?
+        > credit_limit(env, config, user, &position.collateral)?
+    {
+        return Err(Error::InsufficientCollateral);
+    }
+
+    Ok(new_balance)
+}
+
+/// Liquidation limit divided by debt, scaled by `HEALTH_FACTOR_ONE`
+fn health_factor(env: &Env, config: &MarketConfig, position: &UserPosition) -> Result<i128, Error> {
+    if position.borrowed == 0 {
+        return Ok(i128::MAX);
+    }
+
+    let limit = liquidation_limit(env, config, &position.collateral)?;
+    let debt = debt_value(env, config, position.borrowed)?;
+    mul_div(limit, HEALTH_FACTOR_ONE, debt, Rounding::Down).ok_or(Error::MathOverflow)
+}
+
+/// Health factor below which a position is warned about, if set
+fn health_warning(env: &Env) -> Option<i128> {
+    env.storage().instance().get(&DataKey::HealthWarning)
+}
+
+/// Publish a `HealthWarning` if a position with debt is below the warning
+/// threshold but not yet liquidatable
+///
+/// Positions that cannot be priced, such as while the oracle is stale, are
+/// skipped rather than failing the operation that saved them.
+fn warn_if_near_liquidation(
+    env: &Env,
+    config: &MarketConfig,
+    user: &Address,
+    account_id: u32,
+    position: &UserPosition,
+) {
+    let Some(threshold) = health_warning(env) else {
+        return;
+    };
+    if position.borrowed == 0 {
+        return;
+    }
+
+    let Ok(health_factor) = health_factor(env, config, position) else {
+        return;
+    };
+    if (HEALTH_FACTOR_ONE..threshold).contains(&health_factor) {
+        HealthWarning {
+            user: user.clone(),
+            account_id,
+            health_factor,
+            threshold,
+        }
+        .publish(env);
+    }
+}
+
+/// Whether a position taken as a fixed-term loan has missed an installment
+fn fixed_loan_overdue(env: &Env, position: &UserPosition) -> Result<bool, Error> {
+    match &position.terms {
+        LoanTerms::Fixed(loan) => is_overdue(env, loan, position.stable_borrowed),
+        LoanTerms::Open => Ok(false),
+    }
+}
+
+/// Adjust the market-wide outstanding debt
+fn update_total_borrowed(env: &Env, delta: i128) {
+    let total: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalBorrowed)
+        .unwrap_or(0);
+
+    // Per-position rounding can leave the sum of repayments a little above the total
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalBorrowed, &(total + delta).max(0));
+}
+
+/// USDC supplied plus the interest and fees credited to suppliers, less
+/// withdrawals and socialized bad debt
+///
+/// This never reads the contract's USDC balance, so tokens sent to the
+/// contract outside `supply` do not move it.
+fn total_supplied(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::TotalSupplied)
+        .unwrap_or(0)
+}
+
+fn update_total_supplied(env: &Env, delta: i128) {
+    env.storage().instance().set(
+        &DataKey::TotalSupplied,
+        &(total_supplied(env) + delta).max(0),
+    );
+}
+
+/// Share of BENJI's value withheld when it repays debt, in basis points
+fn repay_spread(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::RepaySpread)
+        .unwrap_or(0)
+}
+
+fn protocol_collateral(env: &Env) -> ProtocolCollateral {
+    env.storage()
+        .instance()
+        .get(&DataKey::ProtocolCollateral)
+        .unwrap_or(ProtocolCollateral { amount: 0, owed: 0 })
+}
+
+/// Balance of `token` the contract holds as protocol collateral, which is
+/// neither a deposit nor yield owed to depositors
+fn protocol_balance(env: &Env, token: &Address) -> i128 {
+    match env
+        .storage()
+        .instance()
+        .get::<_, MarketConfig>(&DataKey::Config)
+    {
+        Some(config) if config.benji_token == *token => protocol_collateral(env).amount,
+        _ => 0,
+    }
+}
+
+/// BENJI that, at its oracle price less the repay spread, covers `debt` USDC
+fn benji_for_debt(env: &Env, config: &MarketConfig, debt: i128) -> Result<i128, Error> {
+    let credited = BPS - repay_spread(env) as i128;
+    let value = mul_div(debt_value(env, config, debt)?, BPS, credited, Rounding::Up)
+        .ok_or(Error::MathOverflow)?;
+    // One unit more than the value converts to, covering rounding
+    Ok(collateral_for_value(env, config, &config.benji_token, value)? + 1)
+}
+
+/// Take BENJI into protocol collateral in exchange for `repaid` USDC of debt
+fn add_protocol_collateral(env: &Env, amount: i128, repaid: i128) {
+    let mut protocol = protocol_collateral(env);
+    protocol.amount += amount;
+    protocol.owed += repaid;
+    env.storage()
+        .instance()
+        .set(&DataKey::ProtocolCollateral, &protocol);
+}
+
+/// Log a change by `admin` to whether the market, and repayment with it, is paused
+fn record_pause_changes(env: &Env, admin: &Address, paused: bool, pause_repay: bool) {
+    let storage = env.storage().instance();
+    let was_paused: bool = storage.get(&DataKey::Paused).unwrap_or(false);
+    let was_repay_paused: bool = storage.get(&DataKey::RepayPaused).unwrap_or(false);
+    record_change(
+        env,
+        admin,
+        "paused",
+        None,
+        Some(was_paused.into()),
+        Some(paused.into()),
+    );
+    record_change(
+        env,
+        admin,
+        "repay_paused",
+        None,
+        Some(was_repay_paused.into()),
+        Some(pause_repay.into()),
+    );
+}
+
+/// Unrecoverable debt not yet covered from reserves or socialized
+fn bad_debt(env: &Env) -> i128 {
+    env.storage().instance().get(&DataKey::BadDebt).unwrap_or(0)
+}
+
+/// Move debt that can no longer be collected from outstanding debt to bad debt
+fn record_bad_debt(env: &Env, amount: i128) {
+    update_total_borrowed(env, -amount);
+    env.storage()
+        .instance()
+        .set(&DataKey::BadDebt, &(bad_debt(env) + amount));
+}
+
+/// Add accrued interest to outstanding debt, diverting the reserve share to the protocol
+fn record_interest(env: &Env, config: &MarketConfig, interest: i128) -> Result<(), Error> {
+    if interest == 0 {
+        return Ok(());
+    }
+
+    update_total_borrowed(env, interest);
+
+    let reserve_factor = config.reserve_factor;
+    let reserves: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalReserves)
+        .unwrap_or(0);
+
+    let reserve_share =
+        bps_mul(interest, reserve_factor, Rounding::Down).ok_or(Error::MathOverflow)?;
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalReserves, &(reserves + reserve_share));
+    update_total_supplied(env, interest - reserve_share);
+
+    Ok(())
+}
+
+/// USDC owned by suppliers: total supplied as recorded, less bad debt
+///
+/// Bad debt is left out from the moment it is recorded, so suppliers who
+/// withdraw before it is socialized do not leave the loss to those who stay.
+/// Covering it from reserves brings it back. USDC sent to the contract outside
+/// `supply` is not counted, so a donation cannot move the share price.
+fn pool_assets(env: &Env) -> i128 {
+    (total_supplied(env) - bad_debt(env)).max(0)
+}
+
+/// Fail if the collateral of `token` held across all positions is above its
+/// supply cap
+fn check_supply_cap(env: &Env, token: &Address) -> Result<(), Error> {
+    let supply_cap: Option<i128> = env
+        .storage()
+        .instance()
+        .get(&DataKey::SupplyCap(token.clone()));
+    if supply_cap.is_some_and(|cap| collateral_total(env, token) > cap) {
+        return Err(Error::SupplyCapExceeded);
+    }
+    Ok(())
+}
+
+/// Credit `amount` of `token` from `payer` to an account, pulling it with
+/// `transfer_from` against the payer's allowance to this contract when
+/// `from_allowance` is set, or with a transfer the payer authorizes otherwise
+fn deposit(
+    env: &Env,
+    payer: MuxedAddress,
+    account_id: u32,
+    token: Address,
+    amount: i128,
+    on_behalf_of: Option<Address>,
+    from_allowance: bool,
+) -> Result<(), Error> {
+    let _guard = ReentrancyGuard::acquire(env)?;
+    require_not_paused(env)?;
+
+    let muxed_id = payer.id();
+    let payer = payer.address();
+    let user = on_behalf_of.unwrap_or(payer.clone());
+    require_allowlisted(env, &user)?;
+
+    if amount <= 0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    let config = load_config(env)?;
+    if !config.collateral.contains_key(token.clone()) {
+        return Err(Error::UnsupportedCollateral);
+    }
+
+    // Get user position, indexing the account on its first deposit. Others may
+    // fund a user's existing accounts, but opening one takes the user's consent
+    let mut position: UserPosition = match load_position(env, &user, account_id) {
+        Some(position) => position,
+        None => {
+            if payer != user {
+                user.require_auth();
+            }
+            add_account(env, &user, account_id)?;
+            UserPosition {
+                collateral: Map::new(env),
+                borrowed: 0,
+                last_update: env.ledger().timestamp(),
+                borrow_index: RAY,
+                rate_mode: RateMode::Variable,
+                stable_borrowed: 0,
+                stable_rate: 0,
+                free_tranches: Vec::new(env),
+                terms: LoanTerms::Open,
+            }
+        }
+    };
+
+    // Settle collateral yield before the contract balance changes
+    let balance = position.collateral.get(token.clone()).unwrap_or(0);
+    settle_yield(env, &user, account_id, &token, balance)?;
+
+    // Update user position
+    accrue_interest(env, &config, &user, &mut position)?;
+    let balance = balance + amount;
+    position.collateral.set(token.clone(), balance);
+    check_collateral_mix(env, &position.collateral)?;
+    update_collateral_total(env, &token, amount);
+    check_supply_cap(env, &token)?;
+
+    // Only price the collateral when there is a minimum, so deposits go
+    // through while the oracle is stale
+    if config.min_collateral > 0 {
+        let collateral_value =
+            weighted_collateral_value(env, &config, &position.collateral, |_, _| 10000)?;
+        if collateral_value < to_internal(env, &config.usdc_token, config.min_collateral)? {
+            return Err(Error::BelowMinimum);
+        }
+    }
+
+    save_position(env, &config, &user, account_id, &position)?;
+
+    // Transfer collateral from payer to contract
+    let token_client = token::Client::new(env, &token);
+    let contract = env.current_contract_address();
+    if from_allowance {
+        if token_client.allowance(&payer, &contract) < amount {
+            return Err(Error::InsufficientAllowance);
+        }
+        token_client.transfer_from(&contract, &payer, &contract, &amount);
+    } else {
+        token_client.transfer(&payer, &contract, &amount);
+    }
+
+    Deposit {
+        user,
+        account_id,
+        token,
+        payer,
+        muxed_id,
+        amount,
+        collateral: balance,
+        borrowed: position.borrowed,
+    }
+    .publish(env);
+
+    invariant::check(env, &config)?;
+
+    Ok(())
+}
+
+/// Repay `amount` of an account's debt with USDC from `payer`, pulling it with
+/// `transfer_from` against the payer's allowance to this contract when
+/// `from_allowance` is set, or with a transfer the payer authorizes otherwise
+fn repay(
+    env: &Env,
+    payer: MuxedAddress,
+    account_id: u32,
+    amount: i128,
+    on_behalf_of: Option<Address>,
+    from_allowance: bool,
+) -> Result<(), Error> {
+    let muxed_id = payer.id();
+    let payer = payer.address();
+    let _guard = ReentrancyGuard::acquire(env)?;
+
+    if env
+        .storage()
+        .instance()
+        .get(&DataKey::RepayPaused)
+        .unwrap_or(false)
+    {
+        return Err(Error::ContractPaused);
+    }
+
+    if amount <= 0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    // Get user position
+    let user = on_behalf_of.unwrap_or(payer.clone());
+    let mut position: UserPosition =
+        load_position(env, &user, account_id).ok_or(Error::NotInitialized)?;
+
+    let config = load_config(env)?;
+    accrue_interest(env, &config, &user, &mut position)?;
+
+    // Anything past the debt accrued to this ledger is left with the payer
+    let amount = amount.min(position.borrowed);
+    if amount == 0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    // Check the allowance before the debt comes off the books
+    let token_client = token::Client::new(env, &config.usdc_token);
+    let contract = env.current_contract_address();
+    if from_allowance && token_client.allowance(&payer, &contract) < amount {
+        return Err(Error::InsufficientAllowance);
+    }
+
+    // Update position
+    reduce_debt(env, &mut position, amount);
+    update_total_borrowed(env, -amount);
+
+    save_position(env, &config, &user, account_id, &position)?;
+
+    // Transfer USDC from payer to contract
+    if from_allowance {
+        token_client.transfer_from(&contract, &payer, &contract, &amount);
+    } else {
+        token_client.transfer(&payer, &contract, &amount);
+    }
+
+    Repay {
+        user,
+        account_id,
+        payer,
+        muxed_id,
+        amount,
+        borrowed: position.borrowed,
+    }
+    .publish(env);
+
+    invariant::check(env, &config)?;
+
+    Ok(())
+}
+
+/// A liquidation applied to a position, for the caller to settle in tokens
+struct Seizure {
+    repaid: i128,       // debt repaid, all of it when closing out dust
+    paid: i128,         // collateral due to the liquidator
+    protocol_fee: i128, // collateral due to the treasury
+    collateral: i128,   // the position's balance of the seized token left
+    borrowed: i128,     // the position's debt left
+    shortfall: i128,    // debt written off as bad debt
+}
+
+/// Repay up to `repay_amount` of an underwater position's debt in exchange for
+/// its `token` collateral, updating the position and market totals; the caller
+/// holds the reentrancy guard and moves the tokens
+fn seize(
+    env: &Env,
+    config: &MarketConfig,
+    user: &Address,
+    account_id: u32,
+    token: &Address,
+    repay_amount: i128,
+) -> Result<Seizure, Error> {
+    if repay_amount <= 0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    // Get user position
+    let mut position: UserPosition =
+        load_position(env, user, account_id).ok_or(Error::NotInitialized)?;
+
+    accrue_interest(env, config, user, &mut position)?;
+
+    // Only positions above their liquidation limit, or behind on a fixed loan,
+    // can be liquidated
+    if debt_value(env, config, position.borrowed)?
+        <= liquidation_limit(env, config, &position.collateral)?
+        && !fixed_loan_overdue(env, &position)?
+    {
+        return Err(Error::PositionHealthy);
+    }
+
+    let repay_amount = repay_amount.min(position.borrowed);
+    let balance = position.collateral.get(token.clone()).unwrap_or(0);
+
+    // Large positions are unwound over several liquidations, down to a debt
+    // the close factor would leave as dust, which is closed out whole
+    let max_repay =
+        bps_mul(position.borrowed, config.close_factor, Rounding::Up).ok_or(Error::MathOverflow)?;
+    let close_out = position.borrowed - max_repay < config.min_borrow;
+    if repay_amount > max_repay && !(close_out && repay_amount == position.borrowed) {
+        return Err(Error::CloseFactorExceeded);
+    }
+
+    let remaining = position.borrowed - repay_amount;
+    if remaining > 0 && remaining < config.min_borrow {
+        return Err(Error::BelowMinimum);
+    }
+
+    // Seize collateral worth the repaid debt plus the liquidation bonus
+    let seized_value = debt_value(
+        env,
+        config,
+        mul_div(
+            repay_amount,
+            BPS + config.liquidation_bonus as i128,
+            BPS,
+            Rounding::Down,
+        )
+        .ok_or(Error::MathOverflow)?,
+    )?;
+    let seized = collateral_for_value(env, config, token, seized_value)?.min(balance);
+
+    // The treasury takes its share of the bonus: the collateral seized
+    // beyond the repaid debt's worth, up to the configured bonus
+    let repaid_collateral =
+        collateral_for_value(env, config, token, debt_value(env, config, repay_amount)?)?;
+    let bonus = (seized - repaid_collateral).min(
+        bps_mul(repaid_collateral, config.liquidation_bonus, Rounding::Down)
+            .ok_or(Error::MathOverflow)?,
+    );
+    let protocol_fee = take_liquidation_fee(env, token, bonus)?;
+
+    // Settle collateral yield before the contract balance changes
+    settle_yield(env, user, account_id, token, balance)?;
+    update_collateral_total(env, token, -seized);
+
+    // Update position
+    reduce_debt(env, &mut position, repay_amount);
+    update_total_borrowed(env, -repay_amount);
+    let new_balance = balance - seized;
+    if new_balance == 0 {
+        position.collateral.remove(token.clone());
+    } else {
+        position.collateral.set(token.clone(), new_balance);
+    }
+
+    // Debt left once the last collateral is gone can never be repaid by seizure
+    let shortfall = if position.collateral.is_empty() {
+        position.borrowed
+    } else {
+        0
+    };
+    if shortfall > 0 {
+        record_bad_debt(env, shortfall);
+        reduce_debt(env, &mut position, shortfall);
+    }
+
+    save_position(env, config, user, account_id, &position)?;
+
+    Ok(Seizure {
+        repaid: repay_amount,
+        paid: seized - protocol_fee,
+        protocol_fee,
+        collateral: new_balance,
+        borrowed: position.borrowed,
+        shortfall,
+    })
+}
+
+/// Send a liquidation's seized collateral to `to`, less the treasury's share
+fn pay_seized_collateral(
+    env: &Env,
+    config: &MarketConfig,
+    token: &Address,
+    to: &Address,
+    seizure: &Seizure,
+) -> Result<(), Error> {
+    let contract = env.current_contract_address();
+    let collateral_client = token::Client::new(env, token);
+    collateral_client.transfer(&contract, to, &seizure.paid);
+    if seizure.protocol_fee > 0 {
+        let treasury = config.treasury.clone().ok_or(Error::TreasuryNotSet)?;
+        collateral_client.transfer(&contract, &treasury, &seizure.protocol_fee);
+    }
+    Ok(())
+}
+
+/// Publish a liquidation and any bad debt it left
+fn publish_liquidation(
+    env: &Env,
+    user: Address,
+    account_id: u32,
+    liquidator: Address,
+    token: Address,
+    seizure: &Seizure,
+) {
+    Liquidate {
+        user: user.clone(),
+        account_id,
+        liquidator,
+        token,
+        amount: seizure.repaid,
+        seized: seizure.paid,
+        protocol_fee: seizure.protocol_fee,
+        collateral: seizure.collateral,
+        borrowed: seizure.borrowed,
+    }
+    .publish(env);
+
+    if seizure.shortfall > 0 {
+        BadDebtRecorded {
+            user,
+            account_id,
+            amount: seizure.shortfall,
+        }
+        .publish(env);
+    }
+}
+
+/// Lend `amount` against an account and pay it to `pay_to`, running every
+/// borrow check and returning the updated position; the caller holds the
+/// reentrancy guard and has `recipient`'s authorization
+///
+/// With `fixed_loan` set the debt is stable and repaid on that schedule;
+/// otherwise it takes the position's own rate mode.
+#[allow(clippy::too_many_arguments)]
+fn draw(
+    env: &Env,
+    config: &MarketConfig,
+    recipient: &Address,
+    account_id: u32,
+    amount: i128,
+    on_behalf_of: Option<Address>,
+    pay_to: MuxedAddress,
+    muxed_id: Option<u64>,
+    fixed_loan: Option<FixedLoan>,
+) -> Result<UserPosition, Error> {
+    require_not_paused(env)?;
+    require_allowlisted(env, recipient)?;
+
+    if is_emergency_mode(env) {
+        return Err(Error::EmergencyMode);
+    }
+
+    if amount <= 0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    if amount < config.min_borrow {
+        return Err(Error::BelowMinimum);
+    }
+
+    let user = on_behalf_of.unwrap_or(recipient.clone());
+    if user != *recipient {
+        require_allowlisted(env, &user)?;
+
+        let allowance = delegation(env, &user, account_id, recipient);
+        if allowance < amount {
+            return Err(Error::InsufficientDelegation);
+        }
+        write_delegation(env, &user, account_id, recipient, allowance - amount);
+    }
+
+    // Get user position
+    let mut position: UserPosition =
+        load_position(env, &user, account_id).ok_or(Error::InsufficientCollateral)?;
+
+    if position.terms != LoanTerms::Open {
+        return Err(Error::FixedLoanActive);
+    }
+
+    accrue_interest(env, config, &user, &mut position)?;
+
+    let mode = match fixed_loan {
+        Some(_) => RateMode::Stable,
+        None => position.rate_mode,
+    };
+    add_debt(env, config, &user, account_id, &mut position, amount, mode)?;
+    update_total_borrowed(env, amount);
+    if let Some(loan) = fixed_loan {
+        position.terms = LoanTerms::Fixed(loan);
+    }
+
+    save_position(env, config, &user, account_id, &position)?;
+    check_debt_ceiling(env, &position.collateral)?;
+
+    // Transfer USDC to recipient
+    let token_client = token::Client::new(env, &config.usdc_token);
+    token_client.transfer(&env.current_contract_address(), &pay_to, &amount);
+
+    Borrow {
+        user,
+        account_id,
+        recipient: recipient.clone(),
+        to: pay_to.address(),
+        muxed_id,
+        amount,
+        borrowed: position.borrowed,
+    }
+    .publish(env);
+
+    Ok(position)
+}
+
+#[contract]
+pub struct CreditLineContract;
+
+#[contractimpl]
+impl CreditLineContract {
+    /// Initialize the contract
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        benji_token: Address,
+        usdc_token: Address,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        // Refuse addresses that aren't tokens rather than brick the deployment
+        register_token(&env, &benji_token)?;
+        register_token(&env, &usdc_token)?;
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage()
+            .instance()
+            .set(&DataKey::Version, &CONTRACT_VERSION);
+        env.storage().instance().set(&DataKey::BorrowIndex, &RAY);
+        env.storage()
+            .instance()
+            .set(&DataKey::BorrowIndexUpdated, &env.ledger().timestamp());
+        store_config(
+            &env,
+            &admin,
+            &MarketConfig {
+                benji_token: benji_token.clone(),
+                usdc_token,
+                collateral: Map::new(&env),
+                oracle: None,
+                treasury: None,
+                btoken: None,
+                debt_token: None,
+                staking: None,
+                interest_rate: 500,       // 5%
+                rate_slope: 1500,         // up to 20% at full utilization
+                stable_rate_premium: 200, // 2%
+                liquidation_bonus: 500,   // 5%
+                reserve_factor: 1000,     // 10%
+                flash_loan_fee: 9,        // 0.09%
+                debt_ceiling: None,
+                min_borrow: 0,
+                min_collateral: 0,
+                grace_period: 3 * 24 * 60 * 60, // 3 days
+                max_price_age: 60 * 60,         // 1 hour
+                max_price_deviation: 2000,      // 20%
+                twap_window: 30 * 60,           // 30 minutes
+                interest_free_period: 0,
+                late_penalty_rate: 500, // 5%
+                close_factor: 5000,     // 50%
+                compounding: Compounding::Linear,
+            },
+        )?;
+
+        // BENJI is the initial collateral: borrow up to 70%, liquidatable above 80%
+        store_collateral_config(
+            &env,
+            &admin,
+            &benji_token,
+            &CollateralConfig {
+                ltv_ratio: 7000,
+                liquidation_threshold: 8000,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Propose a new admin, who can accept once the timelock has elapsed (admin only)
+    ///
+    /// The admin may be a contract, such as the governance contract, which then
+    /// makes admin calls by invoking them itself.
+    pub fn propose_admin(env: Env, admin: Address, new_admin: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        let eta = env.ledger().timestamp() + admin_timelock(&env);
+
+        let pending: Option<PendingAdmin> = env.storage().instance().get(&DataKey::PendingAdmin);
+        record_address_change(
+            &env,
+            &admin,
+            "pending_admin",
+            pending.map(|pending| pending.admin),
+            Some(new_admin.clone()),
+        );
+
+        env.storage().instance().set(
+            &DataKey::PendingAdmin,
+            &PendingAdmin {
+                admin: new_admin.clone(),
+                eta,
+            },
+        );
+
+        AdminProposed { new_admin, eta }.publish(&env);
+
+        Ok(())
+    }
+
+    /// Accept a pending admin proposal
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        new_admin.require_auth();
+
+        let pending: PendingAdmin = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .ok_or(Error::NoPendingAdmin)?;
+
+        if pending.admin != new_admin {
+            return Err(Error::Unauthorized);
+        }
+
+        if env.ledger().timestamp() < pending.eta {
+            return Err(Error::TimelockNotElapsed);
+        }
+
+        let old_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::NotInitialized)?;
+
+        record_address_change(
+            &env,
+            &new_admin,
+            "admin",
+            Some(old_admin.clone()),
+            Some(new_admin.clone()),
+        );
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+
+        AdminAccepted {
+            old_admin,
+            new_admin,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Set the delay in seconds before a proposed admin can accept (admin only)
+    ///
+    /// The new delay takes over once the current one has passed, so a
+    /// compromised admin cannot drop the timelock and rotate keys at once.
+    /// Setting it again replaces a change still waiting.
+    pub fn set_admin_timelock(env: Env, admin: Address, delay: u64) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        let old_delay = admin_timelock(&env);
+        record_change(
+            &env,
+            &admin,
+            "admin_timelock",
+            None,
+            Some(old_delay.into()),
+            Some(delay.into()),
+        );
+
+        env.storage()
+            .instance()
+            .set(&DataKey::AdminTimelock, &old_delay);
+        env.storage().instance().set(
+            &DataKey::PendingTimelock,
+            &PendingTimelock {
+                delay,
+                eta: env.ledger().timestamp() + old_delay,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Get the delay in seconds before a proposed admin can accept
+    pub fn get_admin_timelock(env: Env) -> u64 {
+        admin_timelock(&env)
+    }
+
+    /// Get logged admin parameter changes with sequence numbers from `offset`,
+    /// oldest first
+    ///
+    /// Returns at most `limit` changes, capped at `MAX_PARAM_CHANGES_PAGE`.
+    /// Changes older than `PARAM_LOG_WINDOW` have expired and are skipped.
+    pub fn get_param_changes(env: Env, offset: u32, limit: u32) -> Vec<ParamChange> {
+        param_changes(&env, offset, limit)
+    }
+
+    /// Get the number of parameter changes ever logged, expired ones included
+    pub fn get_param_change_count(env: Env) -> u32 {
+        param_change_count(&env)
+    }
+
+    /// Get the current admin
+    pub fn get_admin(env: Env) -> Result<Address, Error> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Get the pending admin proposal, if any
+    pub fn get_pending_admin(env: Env) -> Option<PendingAdmin> {
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
+
+    /// Replace the contract code, keeping all storage (admin only)
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+
+        Upgraded { new_wasm_hash }.publish(&env);
+
+        Ok(())
+    }
+
+    /// Bring storage written by an older version up to date after an upgrade (admin only)
+    pub fn migrate(env: Env, admin: Address) -> Result<u32, Error> {
+        require_admin(&env, &admin)?;
+
+        let version: u32 = env.storage().instance().get(&DataKey::Version).unwrap_or(0);
+
+        if version > CONTRACT_VERSION {
+            return Err(Error::InvalidParameter);
+        }
+
+        // Per-version migration steps go here, oldest first
+        if version < 2 {
+            migrate_legacy_config(&env, &admin)?;
+        }
+        if version < 3 {
+            // Start the supplied counter from what the pool holds for suppliers now
+            let config = load_config(&env)?;
+            update_borrow_index(&env, &config)?;
+            let cash = token::Client::new(&env, &config.usdc_token)
+                .balance(&env.current_contract_address());
+            let total_borrowed: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::TotalBorrowed)
+                .unwrap_or(0);
+            let reserves: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::TotalReserves)
+                .unwrap_or(0);
+            let supplied = cash + total_borrowed + protocol_collateral(&env).owed - reserves
+                + bad_debt(&env)
+                - unclaimed_referral_fees(&env);
+            env.storage()
+                .instance()
+                .set(&DataKey::TotalSupplied, &supplied);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Version, &CONTRACT_VERSION);
+
+        Ok(CONTRACT_VERSION)
+    }
+
+    /// Move positions stored by version 0, before sub-accounts, into account 0
+    /// of each of `users` (admin only), returning how many were moved
+    ///
+    /// Version 0 kept no index of its borrowers, so the admin names them, in
+    /// pages if need be, once `migrate` has run. A user who opened account 0
+    /// since the upgrade has the old position added to it; users without one
+    /// are skipped.
+    pub fn migrate_positions(env: Env, admin: Address, users: Vec<Address>) -> Result<u32, Error> {
+        require_admin(&env, &admin)?;
+
+        if Self::version(env.clone()) < CONTRACT_VERSION {
+            return Err(Error::NotInitialized);
+        }
+
+        let config = load_config(&env)?;
+        let benji = config.benji_token.clone();
+        let mut moved = 0;
+        for user in users.iter() {
+            let key = LegacyKey::UserPosition(user.clone());
+            let Some(legacy) = env.storage().persistent().get::<_, LegacyPosition>(&key) else {
+                continue;
+            };
+            env.storage().persistent().remove(&key);
+
+            let mut position = match load_position(&env, &user, 0) {
+                Some(position) => position,
+                None => {
+                    add_account(&env, &user, 0)?;
+                    UserPosition {
+                        collateral: Map::new(&env),
+                        borrowed: 0,
+                        last_update: legacy.last_update,
+                        borrow_index: RAY,
+                        rate_mode: RateMode::Variable,
+                        stable_borrowed: 0,
+                        stable_rate: 0,
+                        free_tranches: Vec::new(&env),
+                        terms: LoanTerms::Open,
+                    }
+                }
+            };
+
+            let balance = position.collateral.get(benji.clone()).unwrap_or(0);
+            settle_yield(&env, &user, 0, &benji, balance)?;
+            accrue_interest(&env, &config, &user, &mut position)?;
+            if legacy.collateral > 0 {
+                position
+                    .collateral
+                    .set(benji.clone(), balance + legacy.collateral);
+                update_collateral_total(&env, &benji, legacy.collateral);
+            }
+
+            // Version 0 debt was paid out of the pool, which counts it from now on
+            position.borrowed += legacy.borrowed;
+            update_total_borrowed(&env, legacy.borrowed);
+            update_total_supplied(&env, legacy.borrowed);
+            save_position(&env, &config, &user, 0, &position)?;
+            moved += 1;
+        }
+
+        Ok(moved)
+    }
+
+    /// Storage layout version of the deployed contract
+    pub fn version(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::Version).unwrap_or(0)
+    }
+
+    /// Halt the market, optionally still allowing repayments (admin only)
+    pub fn pause(env: Env, admin: Address, pause_repay: bool) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        record_pause_changes(&env, &admin, true, pause_repay);
+        env.storage().instance().set(&DataKey::Paused, &true);
+        env.storage()
+            .instance()
+            .set(&DataKey::RepayPaused, &pause_repay);
+
+        PauseUpdated {
+            paused: true,
+            pause_repay,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Resume the market (admin only)
+    pub fn unpause(env: Env, admin: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        record_pause_changes(&env, &admin, false, false);
+        env.storage().instance().set(&DataKey::Paused, &false);
+        env.storage().instance().set(&DataKey::RepayPaused, &false);
+
+        PauseUpdated {
+            paused: false,
+            pause_repay: false,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Check whether the market is paused
+    pub fn is_paused(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::Paused)
+            .unwrap_or(false)
+    }
+
+    /// Permanently freeze borrowing and open `emergency_withdraw` (admin only)
+    ///
+    /// For when collateral can no longer be valued or seized, e.g. a token frozen
+    /// by its issuer. Debt beyond the value of a user's collateral is moved to
+    /// bad debt as they exit.
+    pub fn enable_emergency_mode(env: Env, admin: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        if is_emergency_mode(&env) {
+            return Ok(());
+        }
+        record_change(&env, &admin, "emergency_mode", None, Some(0), Some(1));
+
+        // Settle interest up to now; the index is frozen from here on
+        update_borrow_index(&env, &load_config(&env)?)?;
+        env.storage().instance().set(&DataKey::EmergencyMode, &true);
+
+        EmergencyModeEnabled {
+            timestamp: env.ledger().timestamp(),
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Check whether the market is in emergency mode
+    pub fn is_emergency_mode(env: Env) -> bool {
+        is_emergency_mode(&env)
+    }
+
+    /// Restrict deposits and borrowing to allowlisted users (admin only)
+    pub fn set_allowlist_enabled(env: Env, admin: Address, enabled: bool) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::AllowlistEnabled(enabled))
+    }
+
+    /// Add a user to the allowlist (admin only)
+    pub fn allow(env: Env, admin: Address, user: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::Allowlisted(user, true))
+    }
+
+    /// Remove a user from the allowlist (admin only)
+    pub fn deny(env: Env, admin: Address, user: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::Allowlisted(user, false))
+    }
+
+    /// Check whether a user may deposit and borrow under the current allowlist mode
+    pub fn is_allowlisted(env: Env, user: Address) -> bool {
+        require_allowlisted(&env, &user).is_ok()
+    }
+
+    /// Change several market parameters at once (admin only)
+    pub fn update_config(
+        env: Env,
+        admin: Address,
+        update: MarketConfigUpdate,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        // Settle interest at the old rate before it changes
+        let mut config = load_config(&env)?;
+        update_borrow_index(&env, &config)?;
+
+        config.oracle = update.oracle.apply(config.oracle);
+        config.treasury = update.treasury.apply(config.treasury);
+        config.staking = update.staking.apply(config.staking);
+        config.debt_ceiling = update.debt_ceiling.apply(config.debt_ceiling);
+        config.interest_rate = update.interest_rate.unwrap_or(config.interest_rate);
+        config.rate_slope = update.rate_slope.unwrap_or(config.rate_slope);
+        config.stable_rate_premium = update
+            .stable_rate_premium
+            .unwrap_or(config.stable_rate_premium);
+        config.liquidation_bonus = update.liquidation_bonus.unwrap_or(config.liquidation_bonus);
+        config.reserve_factor = update.reserve_factor.unwrap_or(config.reserve_factor);
+        config.flash_loan_fee = update.flash_loan_fee.unwrap_or(config.flash_loan_fee);
+        config.min_borrow = update.min_borrow.unwrap_or(config.min_borrow);
+        config.min_collateral = update.min_collateral.unwrap_or(config.min_collateral);
+        config.grace_period = update.grace_period.unwrap_or(config.grace_period);
+        config.max_price_age = update.max_price_age.unwrap_or(config.max_price_age);
+        config.max_price_deviation = update
+            .max_price_deviation
+            .unwrap_or(config.max_price_deviation);
+        config.twap_window = update.twap_window.unwrap_or(config.twap_window);
+        config.interest_free_period = update
+            .interest_free_period
+            .unwrap_or(config.interest_free_period);
+        config.late_penalty_rate = update.late_penalty_rate.unwrap_or(config.late_penalty_rate);
+        config.close_factor = update.close_factor.unwrap_or(config.close_factor);
+
+        store_config(&env, &admin, &config)
+    }
+
+    /// Apply several risk parameter updates in one invocation (admin only)
+    ///
+    /// Updates are applied in order, each checked as its own setter would check
+    /// it, and any failure reverts the whole batch. To raise both the LTV and
+    /// the liquidation threshold of a token, put the threshold first.
+    pub fn multi_set(env: Env, admin: Address, params: Vec<ParamUpdate>) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        for update in params.iter() {
+            apply_update(&env, &admin, update)?;
+        }
+
+        Ok(())
+    }
+
+    /// Accept a collateral token or update its risk parameters (admin only)
+    pub fn set_collateral_config(
+        env: Env,
+        admin: Address,
+        token: Address,
+        config: CollateralConfig,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        store_collateral_config(&env, &admin, &token, &config)
+    }
+
+    /// Set the loan-to-value ratio of a collateral token in basis points (admin only)
+    pub fn set_ltv_ratio(
+        env: Env,
+        admin: Address,
+        token: Address,
+        new_ratio: u32,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::LtvRatio(token, new_ratio))
+    }
+
+    /// Set the liquidation threshold of a collateral token in basis points (admin only)
+    pub fn set_liquidation_threshold(
+        env: Env,
+        admin: Address,
+        token: Address,
+        threshold: u32,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(
+            &env,
+            &admin,
+            ParamUpdate::LiquidationThreshold(token, threshold),
+        )
+    }
+
+    /// Set the annual variable rate at zero utilization in basis points (admin only)
+    pub fn set_interest_rate(env: Env, admin: Address, rate_bps: u32) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::InterestRate(rate_bps))
+    }
+
+    /// Set how often interest compounds (admin only)
+    pub fn set_compounding(
+        env: Env,
+        admin: Address,
+        compounding: Compounding,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::Compounding(compounding))
+    }
+
+    /// Set the price oracle used to value BENJI collateral (admin only)
+    pub fn set_oracle(env: Env, admin: Address, oracle: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::Oracle(oracle))
+    }
+
+    /// Set how the oracle's prices are read (admin only)
+    ///
+    /// For feeds reporting at other decimals than they declare, quoting in
+    /// another asset than USDC, or quoting the other way round. With another
+    /// quote asset, every price is divided by USDC's price from the same feed,
+    /// so BENJI/USD and USDC/USD combine into BENJI/USDC.
+    pub fn set_oracle_settings(
+        env: Env,
+        admin: Address,
+        settings: OracleSettings,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        if settings
+            .decimals
+            .is_some_and(|decimals| decimals > INTERNAL_DECIMALS)
+        {
+            return Err(Error::InvalidParameter);
+        }
+
+        let old = oracle_settings(&env);
+        record_change(
+            &env,
+            &admin,
+            "oracle_decimals",
+            None,
+            old.decimals.map(Into::into),
+            settings.decimals.map(Into::into),
+        );
+        record_change(
+            &env,
+            &admin,
+            "oracle_invert",
+            None,
+            Some(old.invert.into()),
+            Some(settings.invert.into()),
+        );
+        record_change(
+            &env,
+            &admin,
+            "oracle_quote",
+            None,
+            quote_log_value(&env, &old.quote),
+            quote_log_value(&env, &settings.quote),
+        );
+
+        env.storage()
+            .instance()
+            .set(&DataKey::OracleSettings, &settings);
+        Ok(())
+    }
+
+    pub fn get_oracle_settings(env: Env) -> OracleSettings {
+        oracle_settings(&env)
+    }
+
+    /// Set the share of a collateral token's value held back from credit limits,
+    /// on top of its LTV, in basis points (admin only)
+    ///
+    /// Meant for assets priced off a lagging NAV; liquidation thresholds are
+    /// unaffected.
+    pub fn set_collateral_haircut(
+        env: Env,
+        admin: Address,
+        token: Address,
+        haircut_bps: u32,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        collateral_config(&env, &token)?;
+
+        if haircut_bps as i128 > BPS {
+            return Err(Error::InvalidParameter);
+        }
+
+        record_change(
+            &env,
+            &admin,
+            "collateral_haircut",
+            Some(token.clone()),
+            Some(collateral_haircut(&env, &token).into()),
+            Some(haircut_bps.into()),
+        );
+
+        if haircut_bps == 0 {
+            env.storage()
+                .instance()
+                .remove(&DataKey::CollateralHaircut(token));
+        } else {
+            env.storage()
+                .instance()
+                .set(&DataKey::CollateralHaircut(token), &haircut_bps);
+        }
+
+        Ok(())
+    }
+
+    /// Set the share of the reserve cut of referred users' interest paid to
+    /// their referrers, in basis points (admin only)
+    pub fn set_referral_share(env: Env, admin: Address, share_bps: u32) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        if share_bps as i128 > BPS {
+            return Err(Error::InvalidParameter);
+        }
+
+        record_change(
+            &env,
+            &admin,
+            "referral_share",
+            None,
+            Some(referral_share(&env).into()),
+            Some(share_bps.into()),
+        );
+        set_referral_share(&env, share_bps);
+        Ok(())
+    }
+
+    pub fn get_referral_share(env: Env) -> u32 {
+        referral_share(&env)
+    }
+
+    /// Get the referrer a user named with their first deposit
+    pub fn get_referrer(env: Env, user: Address) -> Option<Address> {
+        referrer(&env, &user)
+    }
+
+    /// Get the USDC a referrer has accrued and not yet claimed
+    pub fn get_referral_fees(env: Env, referrer: Address) -> i128 {
+        referral_fees(&env, &referrer)
+    }
+
+    /// Pay a referrer the USDC they have accrued, returning the amount
+    pub fn claim_referral_fees(env: Env, referrer: Address) -> Result<i128, Error> {
+        referrer.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        let config = load_config(&env)?;
+
+        let amount = take_referral_fees(&env, &referrer);
+        if amount > 0 {
+            token::Client::new(&env, &config.usdc_token).transfer(
+                &env.current_contract_address(),
+                &referrer,
+                &amount,
+            );
+
+            ReferralFeesClaimed { referrer, amount }.publish(&env);
+        }
+
+        invariant::check(&env, &config)?;
+
+        Ok(amount)
+    }
+
+    /// Set the share of BENJI's value withheld when it repays debt, in basis
+    /// points (admin only)
+    pub fn set_repay_spread(env: Env, admin: Address, spread_bps: u32) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        if spread_bps as i128 >= BPS {
+            return Err(Error::InvalidParameter);
+        }
+
+        record_change(
+            &env,
+            &admin,
+            "repay_spread",
+            None,
+            Some(repay_spread(&env).into()),
+            Some(spread_bps.into()),
+        );
+        env.storage()
+            .instance()
+            .set(&DataKey::RepaySpread, &spread_bps);
+        Ok(())
+    }
+
+    pub fn get_repay_spread(env: Env) -> u32 {
+        repay_spread(&env)
+    }
+
+    /// Set the share of the liquidation bonus sent to the treasury rather than
+    /// the liquidator, in basis points (admin only)
+    ///
+    /// A nonzero share needs a treasury to send it to.
+    pub fn set_liquidation_fee_share(
+        env: Env,
+        admin: Address,
+        share_bps: u32,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        if share_bps as i128 > BPS {
+            return Err(Error::InvalidParameter);
+        }
+        if share_bps > 0 && load_config(&env)?.treasury.is_none() {
+            return Err(Error::TreasuryNotSet);
+        }
+
+        record_change(
+            &env,
+            &admin,
+            "liquidation_fee_share",
+            None,
+            Some(liquidation_fee_share(&env).into()),
+            Some(share_bps.into()),
+        );
+        set_liquidation_fee_share(&env, share_bps);
+        Ok(())
+    }
+
+    pub fn get_liquidation_fee_share(env: Env) -> u32 {
+        liquidation_fee_share(&env)
+    }
+
+    /// Set the health factor below which any operation that leaves a position
+    /// under it, but not yet liquidatable, publishes a `HealthWarning`, scaled
+    /// by `HEALTH_FACTOR_ONE`; `None` turns warnings off (admin only)
+    pub fn set_health_warning(
+        env: Env,
+        admin: Address,
+        threshold: Option<i128>,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        if threshold.is_some_and(|threshold| threshold <= HEALTH_FACTOR_ONE) {
+            return Err(Error::InvalidParameter);
+        }
+
+        record_change(
+            &env,
+            &admin,
+            "health_warning",
+            None,
+            health_warning(&env),
+            threshold,
+        );
+        match threshold {
+            Some(threshold) => env
+                .storage()
+                .instance()
+                .set(&DataKey::HealthWarning, &threshold),
+            None => env.storage().instance().remove(&DataKey::HealthWarning),
+        }
+        Ok(())
+    }
+
+    pub fn get_health_warning(env: Env) -> Option<i128> {
+        health_warning(&env)
+    }
+
+    /// Get the collateral of a token liquidations have sent to the treasury
+    pub fn get_liquidation_fees(env: Env, token: Address) -> i128 {
+        liquidation_fees(&env, &token)
+    }
+
+    /// Get the share of a collateral token's value held back from credit limits
+    pub fn get_collateral_haircut(env: Env, token: Address) -> u32 {
+        collateral_haircut(&env, &token)
+    }
+
+    /// Set how many snapshots of each account to keep, up to
+    /// `MAX_HISTORY_LENGTH`; 0 stops recording (admin only)
+    ///
+    /// Snapshots already recorded stay readable, and a shorter length trims an
+    /// account's history on its next write.
+    pub fn set_history_length(env: Env, admin: Address, length: u32) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::HistoryLength(length))
+    }
+
+    /// Isolate a collateral token under a debt ceiling, or lift its isolation
+    /// with `None` (admin only)
+    ///
+    /// Positions holding an isolated token can hold no other collateral, and
+    /// together may borrow at most `debt_ceiling` USDC against it. A token can
+    /// only be isolated while no position holds it, so its debt is counted from
+    /// the first borrow.
+    pub fn set_isolation(
+        env: Env,
+        admin: Address,
+        token: Address,
+        debt_ceiling: Option<i128>,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        collateral_config(&env, &token)?;
+        let old_ceiling = isolation::debt_ceiling(&env, &token);
+
+        match debt_ceiling {
+            Some(ceiling) if ceiling < 0 => return Err(Error::InvalidParameter),
+            Some(_)
+                if isolation::debt_ceiling(&env, &token).is_none()
+                    && collateral_total(&env, &token) > 0 =>
+            {
+                return Err(Error::InvalidParameter)
+            }
+            Some(ceiling) => env
+                .storage()
+                .instance()
+                .set(&DataKey::IsolationCeiling(token.clone()), &ceiling),
+            None => {
+                env.storage()
+                    .instance()
+                    .remove(&DataKey::IsolationCeiling(token.clone()));
+                env.storage()
+                    .instance()
+                    .remove(&DataKey::IsolatedDebt(token.clone()));
+            }
+        }
+
+        record_change(
+            &env,
+            &admin,
+            "isolation_ceiling",
+            Some(token),
+            old_ceiling,
+            debt_ceiling,
+        );
+
+        Ok(())
+    }
+
+    /// Get the debt ceiling of an isolated collateral token and the debt
+    /// counted against it, or `None` if the token is not isolated
+    pub fn get_isolation(env: Env, token: Address) -> Option<IsolationUsage> {
+        debt_ceiling(&env, &token).map(|debt_ceiling| IsolationUsage {
+            debt_ceiling,
+            debt: isolated_debt(&env, &token),
+        })
+    }
+
+    /// Set the annual yield BENJI collateral earns in basis points (admin or oracle)
+    ///
+    /// Only informs `get_net_rate`; interest charged on debt is unchanged.
+    pub fn set_collateral_yield(env: Env, caller: Address, yield_bps: u32) -> Result<(), Error> {
+        caller.require_auth();
+
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::NotInitialized)?;
+        if caller != admin && load_config(&env)?.oracle.as_ref() != Some(&caller) {
+            return Err(Error::Unauthorized);
+        }
+
+        record_change(
+            &env,
+            &caller,
+            "collateral_yield",
+            None,
+            Some(Self::get_collateral_yield(env.clone()).into()),
+            Some(yield_bps.into()),
+        );
+        env.storage()
+            .instance()
+            .set(&DataKey::CollateralYield, &yield_bps);
+        Ok(())
+    }
+
+    /// Get the annual yield BENJI collateral earns in basis points
+    pub fn get_collateral_yield(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::CollateralYield)
+            .unwrap_or(0)
+    }
+
+    /// Set the liquidation bonus in basis points (admin only)
+    pub fn set_liquidation_bonus(env: Env, admin: Address, bonus_bps: u32) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::LiquidationBonus(bonus_bps))
+    }
+
+    /// Set or clear the market-wide debt ceiling (admin only)
+    pub fn set_debt_ceiling(env: Env, admin: Address, ceiling: Option<i128>) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::DebtCeiling(ceiling))
+    }
+
+    /// Set the smallest borrow and the smallest collateral value a deposit may leave, in USDC (admin only)
+    ///
+    /// A liquidation may not leave debt below `min_borrow`; once the close factor
+    /// would, the liquidator repays all of it in one call.
+    pub fn set_position_minimums(
+        env: Env,
+        admin: Address,
+        min_borrow: i128,
+        min_collateral: i128,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(
+            &env,
+            &admin,
+            ParamUpdate::PositionMinimums(min_borrow, min_collateral),
+        )
+    }
+
+    /// Set or clear a user's borrow cap (admin only)
+    pub fn set_borrow_cap(
+        env: Env,
+        admin: Address,
+        user: Address,
+        cap: Option<i128>,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::BorrowCap(user, cap))
+    }
+
+    /// Set or clear the most of a collateral token all positions may hold together (admin only)
+    pub fn set_supply_cap(
+        env: Env,
+        admin: Address,
+        token: Address,
+        cap: Option<i128>,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::SupplyCap(token, cap))
+    }
+
+    /// Set the flash loan fee in basis points (admin only)
+    pub fn set_flash_loan_fee(env: Env, admin: Address, fee_bps: u32) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::FlashLoanFee(fee_bps))
+    }
+
+    /// Set the address protocol reserves are paid to (admin only)
+    pub fn set_treasury(env: Env, admin: Address, treasury: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::Treasury(treasury))
+    }
+
+    /// Name the market positions may be migrated to, or stop migrations with
+    /// `None` (admin only)
+    pub fn set_successor(
+        env: Env,
+        admin: Address,
+        successor: Option<Address>,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        record_address_change(
+            &env,
+            &admin,
+            "successor",
+            migration::successor(&env),
+            successor.clone(),
+        );
+        set_successor(&env, successor);
+        Ok(())
+    }
+
+    pub fn get_successor(env: Env) -> Option<Address> {
+        successor(&env)
+    }
+
+    /// Name the market positions may be received from, or stop accepting
+    /// them with `None` (admin only)
+    pub fn set_predecessor(
+        env: Env,
+        admin: Address,
+        predecessor: Option<Address>,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        record_address_change(
+            &env,
+            &admin,
+            "predecessor",
+            migration::predecessor(&env),
+            predecessor.clone(),
+        );
+        set_predecessor(&env, predecessor);
+        Ok(())
+    }
+
+    pub fn get_predecessor(env: Env) -> Option<Address> {
+        predecessor(&env)
+    }
+
+    /// Issue supplier shares as a bToken minted and burned by this contract (admin only)
+    ///
+    /// Only allowed before any USDC has been supplied; the bToken's admin must
+    /// already be set to this contract.
+    pub fn set_btoken(env: Env, admin: Address, btoken: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::BToken(btoken))
+    }
+
+    /// Mirror borrower debt on a non-transferable token minted and burned by this contract (admin only)
+    ///
+    /// Only allowed while nothing is borrowed; the token's admin must already be
+    /// set to this contract.
+    pub fn set_debt_token(env: Env, admin: Address, debt_token: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::DebtToken(debt_token))
+    }
+
+    /// Set or remove the BENJI staking contract whose boosts raise LTV (admin only)
+    pub fn set_staking(env: Env, admin: Address, staking: Option<Address>) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::Staking(staking))
+    }
+
+    /// Set the token paid out as supplier and borrower rewards (admin only)
+    ///
+    /// Rewards are paid from this contract's balance of the token, which the
+    /// admin funds by transferring to it.
+    pub fn set_reward_token(env: Env, admin: Address, reward_token: Address) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        if reward_token == load_config(&env)?.usdc_token {
+            return Err(Error::InvalidToken);
+        }
+
+        record_address_change(
+            &env,
+            &admin,
+            "reward_token",
+            env.storage().instance().get(&DataKey::RewardToken),
+            Some(reward_token.clone()),
+        );
+        env.storage()
+            .instance()
+            .set(&DataKey::RewardToken, &reward_token);
+        Ok(())
+    }
+
+    /// Get the token paid out as rewards, if one is set
+    pub fn get_reward_token(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::RewardToken)
+    }
+
+    /// Set the reward tokens streamed to a pool each second, up to
+    /// `MAX_EMISSION_RATE` (admin only)
+    pub fn set_emission_rate(
+        env: Env,
+        admin: Address,
+        pool: RewardPool,
+        rate_per_second: i128,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+
+        if !(0..=MAX_EMISSION_RATE).contains(&rate_per_second) {
+            return Err(Error::InvalidParameter);
+        }
+
+        let old_rate = set_emission_rate(&env, pool, rate_per_second)?;
+        let param = match pool {
+            RewardPool::Supply => "supply_emission_rate",
+            RewardPool::Borrow => "borrow_emission_rate",
+        };
+        record_change(
+            &env,
+            &admin,
+            param,
+            None,
+            Some(old_rate),
+            Some(rate_per_second),
+        );
+
+        EmissionRateUpdated {
+            pool: pool as u32,
+            rate_per_second,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Set the share of interest kept as protocol reserves in basis points (admin only)
+    pub fn set_reserve_factor(env: Env, admin: Address, factor_bps: u32) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        apply_update(&env, &admin, ParamUpdate::ReserveFactor(factor_bps))
+    }
+
+    /// Send accumulated protocol reserves to the treasury (admin only)
+    pub fn withdraw_reserves(env: Env, admin: Address, amount: i128) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let config = load_config(&env)?;
+        let treasury = config.treasury.clone().ok_or(Error::TreasuryNotSet)?;
+        update_borrow_index(&env, &config)?;
+        let reserves: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalReserves)
+            .unwrap_or(0);
+
+        if amount > reserves {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Only idle USDC can be withdrawn
+        let token_client = token::Client::new(&env, &config.usdc_token);
+        if token_client.balance(&env.current_contract_address()) < amount {
+            return Err(Error::InsufficientLiquidity);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalReserves, &(reserves - amount));
+
+        // Transfer USDC to treasury
+        token_client.transfer(&env.current_contract_address(), &treasury, &amount);
+
+        ReservesWithdrawn { treasury, amount }.publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(())
+    }
+
+    /// Return tokens sent to the contract by mistake (admin only)
+    ///
+    /// Collateral tokens and the reward token are never moved, as they are held
+    /// for depositors and earned rewards. USDC is limited to what the contract
+    /// holds beyond its debts to suppliers, reserves and referrers.
+    pub fn rescue_token(
+        env: Env,
+        admin: Address,
+        token: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let config = load_config(&env)?;
+        let reward_token: Option<Address> = env.storage().instance().get(&DataKey::RewardToken);
+        if config.collateral.contains_key(token.clone()) || reward_token == Some(token.clone()) {
+            return Err(Error::ProtectedToken);
+        }
+        if token == config.usdc_token && amount > usdc_surplus(&env)? {
+            return Err(Error::InsufficientBalance);
+        }
+
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &to, &amount);
+
+        TokenRescued { token, to, amount }.publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(())
+    }
+
+    /// Get the USDC the contract holds beyond its debts to suppliers, reserves
+    /// and referrers, which `rescue_token` may return
+    pub fn get_usdc_surplus(env: Env) -> Result<i128, Error> {
+        usdc_surplus(&env)
+    }
+
+    /// Get the global borrow index as of the current ledger, RAY-scaled
+    pub fn get_borrow_index(env: Env) -> Result<i128, Error> {
+        borrow_index(&env, &load_config(&env)?)
+    }
+
+    /// Get accumulated protocol reserves
+    pub fn get_reserves(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TotalReserves)
+            .unwrap_or(0)
+    }
+
+    /// Get debt written off by liquidations and emergency exits that has not yet
+    /// been covered or socialized
+    pub fn get_bad_debt(env: Env) -> i128 {
+        bad_debt(&env)
+    }
+
+    /// Get the USDC debt outstanding across all positions as last accrued
+    pub fn get_total_borrowed(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TotalBorrowed)
+            .unwrap_or(0)
+    }
+
+    /// Get the USDC owed to suppliers as recorded by supplies, withdrawals,
+    /// credited interest and socialized bad debt
+    ///
+    /// Both this and `total_supplied` in the market summary are supplied USDC
+    /// less bad debt, and neither counts USDC transferred to the contract
+    /// outside `supply`. This takes off bad debt once socialized; the summary
+    /// also takes off bad debt still awaiting cover.
+    pub fn get_total_supplied(env: Env) -> i128 {
+        total_supplied(&env)
+    }
+
+    /// Get the amount of a collateral token held across all positions
+    pub fn get_total_collateral(env: Env, token: Address) -> i128 {
+        collateral_total(&env, &token)
+    }
+
+    /// Get the share of pool assets lent out, in basis points
+    pub fn get_utilization(env: Env) -> Result<u32, Error> {
+        load_config(&env)?;
+        utilization(&env)
+    }
+
+    /// Compare the pool's USDC balance and outstanding debt against total supplied
+    ///
+    /// A negative `gap` is the USDC suppliers are owed that the pool does not
+    /// hold or have lent out; beyond recorded bad debt it points to an
+    /// accounting bug.
+    pub fn check_solvency(env: Env) -> Result<Solvency, Error> {
+        solvency(&env)
+    }
+
+    /// Cover bad debt out of protocol reserves (admin only)
+    pub fn cover_bad_debt(env: Env, admin: Address, amount: i128) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        let config = load_config(&env)?;
+        update_borrow_index(&env, &config)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let bad_debt = bad_debt(&env);
+        if amount > bad_debt {
+            return Err(Error::ExceedsBadDebt);
+        }
+
+        let reserves = Self::get_reserves(env.clone());
+        if amount > reserves {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // The reserve USDC is already in the pool; it now belongs to suppliers
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalReserves, &(reserves - amount));
+        env.storage()
+            .instance()
+            .set(&DataKey::BadDebt, &(bad_debt - amount));
+
+        BadDebtCovered { amount }.publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(())
+    }
+
+    /// Write bad debt off against suppliers for good (admin only)
+    ///
+    /// The supply exchange rate left it out as soon as it was recorded; this
+    /// gives up covering it from reserves and takes it off total supplied.
+    pub fn socialize_bad_debt(env: Env, admin: Address, amount: i128) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        let config = load_config(&env)?;
+        update_borrow_index(&env, &config)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let bad_debt = bad_debt(&env);
+        if amount > bad_debt {
+            return Err(Error::ExceedsBadDebt);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::BadDebt, &(bad_debt - amount));
+        update_total_supplied(&env, -amount);
+
+        BadDebtSocialized { amount }.publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(())
+    }
+
+    /// Accrue pool interest and extend the TTL of the contract and its
+    /// instance storage, returning the borrow index (anyone may call)
+    ///
+    /// Meant for keepers to call on a schedule, so indices stay fresh and the
+    /// market's config is not archived while little else happens.
+    pub fn poke(env: Env) -> Result<i128, Error> {
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        let config = load_config(&env)?;
+        let borrow_index = update_borrow_index(&env, &config)?;
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        Heartbeat {
+            borrow_index,
+            total_borrowed: Self::get_total_borrowed(env.clone()),
+            total_supplied: total_supplied(&env),
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(borrow_index)
+    }
+
+    /// Accrue outstanding interest on the debt of one of a user's accounts
+    pub fn accrue(env: Env, user: Address, account_id: u32) -> Result<UserPosition, Error> {
+        let mut position: UserPosition =
+            load_position(&env, &user, account_id).ok_or(Error::NotInitialized)?;
+
+        let config = load_config(&env)?;
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        save_position(&env, &config, &user, account_id, &position)?;
+
+        Ok(position)
+    }
+
+    /// Deposit an accepted token as collateral
+    ///
+    /// `payer` sends the tokens; they are credited to account `account_id` of
+    /// `on_behalf_of`, or of the payer when it is `None`. The payer authorizes
+    /// the call, and so does `on_behalf_of` if the deposit opens a new account
+    /// for them. A user holds at most 16 accounts. A muxed payer is credited as
+    /// its base account, with the mux id recorded in the `Deposit` event.
+    ///
+    /// A `referrer` names who referred the user and is only recorded with the
+    /// user's first deposit, made by the user themselves; it is ignored
+    /// otherwise.
+    pub fn deposit_collateral(
+        env: Env,
+        payer: MuxedAddress,
+        account_id: u32,
+        token: Address,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+        referrer: Option<Address>,
+    ) -> Result<(), Error> {
+        payer.address().require_auth();
+        let user = on_behalf_of.clone().unwrap_or(payer.address());
+        name_referrer(&env, &payer.address(), &user, referrer)?;
+        deposit(&env, payer, account_id, token, amount, on_behalf_of, false)
+    }
+
+    /// Deposit collateral the payer has approved this contract to spend
+    ///
+    /// Works as `deposit_collateral`, but the contract pulls the tokens with
+    /// `transfer_from` against the payer's allowance, so the payer authorizes
+    /// only this call and not a token transfer nested inside it. Approve at
+    /// least `amount` first, or the call fails with `InsufficientAllowance`.
+    pub fn deposit_collateral_from(
+        env: Env,
+        payer: MuxedAddress,
+        account_id: u32,
+        token: Address,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+        referrer: Option<Address>,
+    ) -> Result<(), Error> {
+        payer.address().require_auth();
+        let user = on_behalf_of.clone().unwrap_or(payer.address());
+        name_referrer(&env, &payer.address(), &user, referrer)?;
+        deposit(&env, payer, account_id, token, amount, on_behalf_of, true)
+    }
+
+    /// Borrow USDC against BENJI collateral
+    ///
+    /// The USDC goes to `recipient` and the debt to the recipient's account
+    /// `account_id`. With `on_behalf_of` set, the debt is taken on that user's
+    /// account instead, spending the allowance they granted the recipient
+    /// through `approve_delegation`. A muxed recipient borrows as its base
+    /// account and is paid at the muxed address, so the mux id reaches the
+    /// USDC transfer as well as the `Borrow` event.
+    ///
+    /// With `to` set the USDC is paid there instead, such as another wallet or
+    /// a DEX contract, without a second transfer.
+    pub fn borrow(
+        env: Env,
+        recipient: MuxedAddress,
+        account_id: u32,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+        to: Option<Address>,
+    ) -> Result<(), Error> {
+        let (pay_to, muxed_id) = match to {
+            Some(to) => (to.into(), None),
+            None => (recipient.clone(), recipient.id()),
+        };
+        let recipient = recipient.address();
+        recipient.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        let config = load_config(&env)?;
+        draw(
+            &env,
+            &config,
+            &recipient,
+            account_id,
+            amount,
+            on_behalf_of,
+            pay_to,
+            muxed_id,
+            None,
+        )?;
+
+        invariant::check(&env, &config)?;
+
+        Ok(())
+    }
+
+    /// Borrow USDC as a fixed-term loan repaid in installments over `term_ledgers`
+    ///
+    /// The loan is the account's stable debt, so the account must have none
+    /// already; any variable debt stays as it is, outside the loan. The loan
+    /// locks in the current stable rate, falls due in monthly installments (a
+    /// single one for terms under a month), and can be repaid early at any time
+    /// with `repay`, which pays the loan before variable debt. Further borrows and
+    /// rate swaps on the account wait until the loan is paid off.
+    pub fn borrow_fixed(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        amount: i128,
+        term_ledgers: u32,
+    ) -> Result<(), Error> {
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        if term_ledgers == 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let config = load_config(&env)?;
+        let existing = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user.clone(), account_id),
+        )?;
+        if existing.terms != LoanTerms::Open {
+            return Err(Error::FixedLoanActive);
+        }
+        if existing.stable_borrowed > 0 {
+            return Err(Error::DebtOutstanding);
+        }
+
+        // Run every borrow check, putting the new stable debt on the schedule
+        let loan = new_fixed_loan(&env, amount, term_ledgers);
+        let position = draw(
+            &env,
+            &config,
+            &user,
+            account_id,
+            amount,
+            None,
+            user.clone().into(),
+            None,
+            Some(loan.clone()),
+        )?;
+
+        FixedLoanOpened {
+            user,
+            account_id,
+            amount,
+            rate: position.stable_rate,
+            installments: loan.installments,
+            maturity_ledger: loan.start_ledger + loan.installments * loan.interval,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(())
+    }
+
+    /// Pay the next installment of a fixed-term loan, returning the amount paid
+    ///
+    /// Pays whatever brings the balance down to its next scheduled level, so an
+    /// overdue installment is settled first. As with `repay`, `payer` funds the
+    /// payment and `on_behalf_of` names another user's loan.
+    pub fn repay_installment(
+        env: Env,
+        payer: Address,
+        account_id: u32,
+        on_behalf_of: Option<Address>,
+    ) -> Result<i128, Error> {
+        let user = on_behalf_of.clone().unwrap_or(payer.clone());
+        let (amount, _) = Self::get_installment_due(env.clone(), user, account_id)?;
+        if amount == 0 {
+            return Ok(0);
+        }
+
+        Self::repay(env, payer.into(), account_id, amount, on_behalf_of)?;
+        Ok(amount)
+    }
+
+    /// Get the amount of a fixed-term loan's next unpaid installment and the
+    /// ledger it is due by
+    pub fn get_installment_due(
+        env: Env,
+        user: Address,
+        account_id: u32,
+    ) -> Result<(i128, u32), Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user, account_id),
+        )?;
+        let LoanTerms::Fixed(loan) = position.terms else {
+            return Err(Error::NoFixedLoan);
+        };
+        installment_due(&env, &loan, position.stable_borrowed)
+    }
+
+    /// Switch a position between variable and stable rate
+    ///
+    /// Switching to stable locks in the current stable rate for the variable
+    /// debt; switching back moves all of the debt to the variable rate. Later
+    /// borrows on the account take the new mode.
+    pub fn swap_rate_mode(env: Env, user: Address, account_id: u32) -> Result<RateMode, Error> {
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+        let config = load_config(&env)?;
+
+        let mut position: UserPosition =
+            load_position(&env, &user, account_id).ok_or(Error::NotInitialized)?;
+        if position.terms != LoanTerms::Open {
+            return Err(Error::FixedLoanActive);
+        }
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        match position.rate_mode {
+            RateMode::Variable => {
+                let variable_borrowed = position.borrowed - position.stable_borrowed;
+                add_stable_debt(&env, &config, &mut position, variable_borrowed)?;
+                position.rate_mode = RateMode::Stable;
+            }
+            RateMode::Stable => {
+                position.stable_borrowed = 0;
+                position.stable_rate = 0;
+                position.rate_mode = RateMode::Variable;
+            }
+        }
+
+        save_position(&env, &config, &user, account_id, &position)?;
+
+        RateModeSwapped {
+            user,
+            account_id,
+            rate_mode: position.rate_mode as u32,
+            stable_rate: position.stable_rate,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(position.rate_mode)
+    }
+
+    /// Let `delegatee` borrow up to `amount` USDC against one of the delegator's accounts
+    ///
+    /// Replaces any previous allowance; the debt stays on the delegator's account.
+    pub fn approve_delegation(
+        env: Env,
+        delegator: Address,
+        account_id: u32,
+        delegatee: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        delegator.require_auth();
+
+        if amount < 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        write_delegation(&env, &delegator, account_id, &delegatee, amount);
+
+        DelegationApproved {
+            delegator,
+            delegatee,
+            account_id,
+            amount,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Get the amount `delegatee` may still borrow against one of `delegator`'s accounts
+    pub fn get_delegation(
+        env: Env,
+        delegator: Address,
+        account_id: u32,
+        delegatee: Address,
+    ) -> i128 {
+        delegation(&env, &delegator, account_id, &delegatee)
+    }
+
+    /// Register a protector contract that repays debt on one of a user's accounts
+    /// when its health factor falls below `trigger_health_factor`
+    ///
+    /// Once triggered, anyone can call `protect` to draw up to `max_repay` USDC
+    /// from the protector and repay it, keeping the position out of liquidation.
+    /// Replaces any previous protection on the account.
+    pub fn set_protection(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        protector: Address,
+        trigger_health_factor: i128,
+        max_repay: i128,
+    ) -> Result<(), Error> {
+        user.require_auth();
+
+        // Triggering at or below 1.0 would leave the position liquidatable first
+        if trigger_health_factor <= HEALTH_FACTOR_ONE || max_repay <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let key = DataKey::Protection(user.clone(), account_id);
+        env.storage().persistent().set(
+            &key,
+            &Protection {
+                protector: protector.clone(),
+                trigger_health_factor,
+                max_repay,
+            },
+        );
+        env.storage().persistent().extend_ttl(
+            &key,
+            POSITION_LIFETIME_THRESHOLD,
+            POSITION_BUMP_AMOUNT,
+        );
+
+        ProtectionSet {
+            user,
+            account_id,
+            protector,
+            trigger_health_factor,
+            max_repay,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Stop a protector from repaying debt on one of a user's accounts
+    pub fn remove_protection(env: Env, user: Address, account_id: u32) -> Result<(), Error> {
+        user.require_auth();
+
+        let key = DataKey::Protection(user.clone(), account_id);
+        if !env.storage().persistent().has(&key) {
+            return Err(Error::NoProtection);
+        }
+        env.storage().persistent().remove(&key);
+
+        ProtectionRemoved { user, account_id }.publish(&env);
+
+        Ok(())
+    }
+
+    pub fn get_protection(env: Env, user: Address, account_id: u32) -> Result<Protection, Error> {
+        load_protection(&env, &user, account_id).ok_or(Error::NoProtection)
+    }
+
+    /// Have a position's protector repay enough debt to lift its health factor
+    /// back to the trigger, up to the protection's `max_repay`
+    ///
+    /// Anyone may call this once the health factor is below the trigger.
+    /// Returns the amount repaid.
+    pub fn protect(env: Env, user: Address, account_id: u32) -> Result<i128, Error> {
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        if env
+            .storage()
+            .instance()
+            .get(&DataKey::RepayPaused)
+            .unwrap_or(false)
+        {
+            return Err(Error::ContractPaused);
+        }
+
+        let protection = load_protection(&env, &user, account_id).ok_or(Error::NoProtection)?;
+        let mut position: UserPosition =
+            load_position(&env, &user, account_id).ok_or(Error::NotInitialized)?;
+        let config = load_config(&env)?;
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        if health_factor(&env, &config, &position)? >= protection.trigger_health_factor {
+            return Err(Error::PositionHealthy);
+        }
+
+        // Debt at which the health factor is back at the trigger
+        let target = mul_div(
+            liquidation_limit(&env, &config, &position.collateral)?,
+            HEALTH_FACTOR_ONE,
+            protection.trigger_health_factor,
+            Rounding::Down,
+        )
+        .ok_or(Error::MathOverflow)?;
+        let usdc_token = config.usdc_token.clone();
+        let excess = debt_value(&env, &config, position.borrowed)? - target;
+        let amount = (from_internal(&env, &usdc_token, excess)? + 1)
+            .min(position.borrowed)
+            .min(protection.max_repay);
+
+        // Hand control to the protector, which must pay in the USDC
+        let token_client = token::Client::new(&env, &usdc_token);
+        let balance_before = token_client.balance(&env.current_contract_address());
+        ProtectorClient::new(&env, &protection.protector).on_protect(
+            &user,
+            &account_id,
+            &usdc_token,
+            &amount,
+        );
+        if token_client.balance(&env.current_contract_address()) < balance_before + amount {
+            return Err(Error::ProtectorNotPaid);
+        }
+
+        reduce_debt(&env, &mut position, amount);
+        update_total_borrowed(&env, -amount);
+        save_position(&env, &config, &user, account_id, &position)?;
+
+        Protected {
+            user,
+            account_id,
+            protector: protection.protector,
+            amount,
+            borrowed: position.borrowed,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(amount)
+    }
+
+    /// Repay borrowed USDC
+    ///
+    /// `payer` sends the USDC; it repays the debt of account `account_id` of
+    /// `on_behalf_of`, or of the payer when it is `None`. Only the payer
+    /// authorizes the call. A muxed payer pays from its base account, with the
+    /// mux id recorded in the `Repay` event. An `amount` above the debt is
+    /// capped at it, so only the debt is transferred and the account is cleared.
+    pub fn repay(
+        env: Env,
+        payer: MuxedAddress,
+        account_id: u32,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+    ) -> Result<(), Error> {
+        payer.address().require_auth();
+        repay(&env, payer, account_id, amount, on_behalf_of, false)
+    }
+
+    /// Repay borrowed USDC the payer has approved this contract to spend
+    ///
+    /// Works as `repay`, but the contract pulls the USDC with `transfer_from`
+    /// against the payer's allowance, so the payer authorizes only this call
+    /// and not a token transfer nested inside it. Approve at least `amount`
+    /// first, or the call fails with `InsufficientAllowance`.
+    pub fn repay_from(
+        env: Env,
+        payer: MuxedAddress,
+        account_id: u32,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+    ) -> Result<(), Error> {
+        payer.address().require_auth();
+        repay(&env, payer, account_id, amount, on_behalf_of, true)
+    }
+
+    /// Repay debt with `amount` BENJI from the user's wallet, valued at the
+    /// oracle price less the repay spread, returning the USDC debt repaid
+    ///
+    /// The BENJI becomes protocol-owned collateral, and the pool counts the
+    /// debt it repaid as owed to it until the BENJI is sold. Only as much BENJI
+    /// as clears the debt is taken.
+    pub fn repay_with_collateral(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        amount: i128,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        if env
+            .storage()
+            .instance()
+            .get(&DataKey::RepayPaused)
+            .unwrap_or(false)
+        {
+            return Err(Error::ContractPaused);
+        }
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut position: UserPosition =
+            load_position(&env, &user, account_id).ok_or(Error::NotInitialized)?;
+        let config = load_config(&env)?;
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        let benji_token = &config.benji_token;
+        let credited = BPS - repay_spread(&env) as i128;
+        let value = mul_div(
+            collateral_value(&env, &config, benji_token, amount, Rounding::Down)?,
+            credited,
+            BPS,
+            Rounding::Down,
+        )
+        .ok_or(Error::MathOverflow)?;
+        let mut amount = amount;
+        let mut repaid = usdc_for_value(&env, &config, value, Rounding::Down)?;
+        if repaid > position.borrowed {
+            amount = amount.min(benji_for_debt(&env, &config, position.borrowed)?);
+            repaid = position.borrowed;
+        }
+        if repaid <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        reduce_debt(&env, &mut position, repaid);
+        update_total_borrowed(&env, -repaid);
+
+        save_position(&env, &config, &user, account_id, &position)?;
+
+        token::Client::new(&env, benji_token).transfer(
+            &user,
+            env.current_contract_address(),
+            &amount,
+        );
+
+        add_protocol_collateral(&env, amount, repaid);
+
+        RepayWithCollateral {
+            user,
+            account_id,
+            amount,
+            repaid,
+            borrowed: position.borrowed,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(repaid)
+    }
+
+    /// Repay up to `debt_amount` of debt by surrendering BENJI collateral from
+    /// the position, returning the BENJI surrendered
+    ///
+    /// Lets a borrower with no USDC pay down debt before being liquidated. The
+    /// BENJI is valued as in `repay_with_collateral` and becomes protocol
+    /// collateral the same way. The position must come out healthy, so an
+    /// underwater borrower cannot use it to dodge the liquidation bonus.
+    pub fn repay_from_collateral(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        debt_amount: i128,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        if env
+            .storage()
+            .instance()
+            .get(&DataKey::RepayPaused)
+            .unwrap_or(false)
+        {
+            return Err(Error::ContractPaused);
+        }
+
+        if debt_amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut position: UserPosition =
+            load_position(&env, &user, account_id).ok_or(Error::NotInitialized)?;
+        let config = load_config(&env)?;
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        let repaid = debt_amount.min(position.borrowed);
+        if repaid <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let benji_token = config.benji_token.clone();
+        let balance = position.collateral.get(benji_token.clone()).unwrap_or(0);
+        let amount = benji_for_debt(&env, &config, repaid)?;
+        if amount > balance {
+            return Err(Error::InsufficientCollateral);
+        }
+
+        // Settle collateral yield before the position's balance changes
+        settle_yield(&env, &user, account_id, &benji_token, balance)?;
+        update_collateral_total(&env, &benji_token, -amount);
+
+        if amount == balance {
+            position.collateral.remove(benji_token);
+        } else {
+            position.collateral.set(benji_token, balance - amount);
+        }
+        reduce_debt(&env, &mut position, repaid);
+        if health_factor(&env, &config, &position)? < HEALTH_FACTOR_ONE {
+            return Err(Error::InsufficientCollateral);
+        }
+        update_total_borrowed(&env, -repaid);
+
+        save_position(&env, &config, &user, account_id, &position)?;
+
+        // The BENJI never leaves the contract, it just changes owner. Booked as
+        // protocol collateral, it stays out of the depositors' yield index
+        add_protocol_collateral(&env, amount, repaid);
+
+        RepayFromCollateral {
+            user,
+            account_id,
+            amount,
+            repaid,
+            borrowed: position.borrowed,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(amount)
+    }
+
+    /// Buy `amount` of the protocol's BENJI at the oracle price, paying at
+    /// most `max_cost` USDC, and return the USDC paid
+    ///
+    /// The USDC settles what the pool is owed for the BENJI, any excess going
+    /// to reserves. Whatever is still owed once the last of it is sold becomes
+    /// bad debt.
+    pub fn buy_protocol_collateral(
+        env: Env,
+        buyer: Address,
+        amount: i128,
+        max_cost: i128,
+    ) -> Result<i128, Error> {
+        buyer.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+
+        let mut protocol = protocol_collateral(&env);
+        if amount <= 0 || amount > protocol.amount {
+            return Err(Error::InvalidParameter);
+        }
+
+        let config = load_config(&env)?;
+        let value = collateral_value(&env, &config, &config.benji_token, amount, Rounding::Up)?;
+        let cost = usdc_for_value(&env, &config, value, Rounding::Up)?;
+        if cost > max_cost {
+            return Err(Error::MaxAmountExceeded);
+        }
+
+        let settled = cost.min(protocol.owed);
+        protocol.amount -= amount;
+        protocol.owed -= settled;
+        if cost > settled {
+            let reserves: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::TotalReserves)
+                .unwrap_or(0);
+            env.storage()
+                .instance()
+                .set(&DataKey::TotalReserves, &(reserves + cost - settled));
+        }
+        if protocol.amount == 0 && protocol.owed > 0 {
+            env.storage()
+                .instance()
+                .set(&DataKey::BadDebt, &(bad_debt(&env) + protocol.owed));
+            protocol.owed = 0;
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::ProtocolCollateral, &protocol);
+
+        // Take the signed maximum and refund the rest, so the transfer the buyer
+        // authorizes does not depend on the price at execution
+        let contract = env.current_contract_address();
+        let usdc_client = token::Client::new(&env, &config.usdc_token);
+        usdc_client.transfer(&buyer, &contract, &max_cost);
+        if max_cost > cost {
+            usdc_client.transfer(&contract, &buyer, &(max_cost - cost));
+        }
+        token::Client::new(&env, &config.benji_token).transfer(&contract, &buyer, &amount);
+
+        ProtocolCollateralSold {
+            buyer,
+            amount,
+            cost,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(cost)
+    }
+
+    /// Get the BENJI taken in repayment of debt and the USDC still owed for it
+    pub fn get_protocol_collateral(env: Env) -> ProtocolCollateral {
+        protocol_collateral(&env)
+    }
+
+    /// Repay all outstanding debt and withdraw all collateral in one call,
+    /// returning the debt repaid
+    ///
+    /// Fails if the debt has grown past `max_repay`. The user always sends
+    /// `max_repay` USDC and is refunded what the debt did not need, so the
+    /// transfer they authorize is known when they sign.
+    pub fn close_position(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        max_repay: i128,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+        let config = load_config(&env)?;
+
+        if max_repay < 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        // Get user position with interest accrued to this ledger
+        let mut position: UserPosition =
+            load_position(&env, &user, account_id).ok_or(Error::NotInitialized)?;
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        let repaid = position.borrowed;
+        if repaid > max_repay {
+            return Err(Error::MaxAmountExceeded);
+        }
+        let collateral = position.collateral.clone();
+
+        // Settle collateral yield before the contract balances change
+        for (token, amount) in collateral.iter() {
+            settle_yield(&env, &user, account_id, &token, amount)?;
+            update_collateral_total(&env, &token, -amount);
+        }
+
+        // Clear the position before moving any tokens
+        reduce_debt(&env, &mut position, repaid);
+        position.collateral = Map::new(&env);
+        update_total_borrowed(&env, -repaid);
+        save_position(&env, &config, &user, account_id, &position)?;
+
+        if max_repay > 0 {
+            let contract = env.current_contract_address();
+            let token_client = token::Client::new(&env, &config.usdc_token);
+            token_client.transfer(&user, &contract, &max_repay);
+            if max_repay > repaid {
+                token_client.transfer(&contract, &user, &(max_repay - repaid));
+            }
+        }
+
+        if repaid > 0 {
+            Repay {
+                user: user.clone(),
+                account_id,
+                payer: user.clone(),
+                muxed_id: None,
+                amount: repaid,
+                borrowed: 0,
+            }
+            .publish(&env);
+        }
+
+        // Return every collateral balance
+        for (token, amount) in collateral.iter() {
+            let token_client = token::Client::new(&env, &token);
+            token_client.transfer(&env.current_contract_address(), &user, &amount);
+
+            Withdraw {
+                user: user.clone(),
+                account_id,
+                token,
+                to: user.clone(),
+                muxed_id: None,
+                amount,
+                collateral: 0,
+                borrowed: 0,
+            }
+            .publish(&env);
+        }
+
+        invariant::check(&env, &config)?;
+
+        Ok(repaid)
+    }
+
+    /// Move one account's collateral and debt to the successor market named
+    /// by the admin, returning the debt moved
+    ///
+    /// The successor takes the position on through `receive_migration` and
+    /// pays this pool the debt in USDC, so the borrower keeps their loan
+    /// without repaying. Accounts with a fixed-term loan cannot migrate.
+    pub fn migrate_position(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        new_contract: Address,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+        let config = load_config(&env)?;
+
+        if successor(&env) != Some(new_contract.clone()) {
+            return Err(Error::Unauthorized);
+        }
+
+        let mut position: UserPosition =
+            load_position(&env, &user, account_id).ok_or(Error::NotInitialized)?;
+        if position.terms != LoanTerms::Open {
+            return Err(Error::FixedLoanActive);
+        }
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        let borrowed = position.borrowed;
+        let collateral = position.collateral.clone();
+
+        // Settle collateral yield before the contract balances change
+        for (token, amount) in collateral.iter() {
+            settle_yield(&env, &user, account_id, &token, amount)?;
+            update_collateral_total(&env, &token, -amount);
+        }
+
+        reduce_debt(&env, &mut position, borrowed);
+        position.collateral = Map::new(&env);
+        update_total_borrowed(&env, -borrowed);
+        save_position(&env, &config, &user, account_id, &position)?;
+
+        // The successor credits the position before its collateral arrives,
+        // and must pay for the debt before returning
+        let contract = env.current_contract_address();
+        let usdc_client = token::Client::new(&env, &config.usdc_token);
+        let balance_before = usdc_client.balance(&contract);
+        SuccessorClient::new(&env, &new_contract).receive_migration(
+            &contract,
+            &user,
+            &account_id,
+            &collateral,
+            &borrowed,
+        );
+        if usdc_client.balance(&contract) < balance_before + borrowed {
+            return Err(Error::MigrationNotPaid);
+        }
+
+        for (token, amount) in collateral.iter() {
+            token::Client::new(&env, &token).transfer(&contract, &new_contract, &amount);
+        }
+
+        PositionMigrated {
+            user,
+            account_id,
+            successor: new_contract,
+            borrowed,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(borrowed)
+    }
+
+    /// Take over a position migrated from the predecessor market named by the
+    /// admin
+    ///
+    /// Credits `collateral` and `borrowed` to the user's account, subject to
+    /// the same supply caps as a deposit and the same credit limit and risk
+    /// limits as a borrow, and pays the predecessor `borrowed` USDC. The debt
+    /// continues at the variable rate. Only the predecessor may call this, from
+    /// its `migrate_position`, which sends the collateral once this returns.
+    pub fn receive_migration(
+        env: Env,
+        predecessor: Address,
+        user: Address,
+        account_id: u32,
+        collateral: Map<Address, i128>,
+        borrowed: i128,
+    ) -> Result<(), Error> {
+        predecessor.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+        require_allowlisted(&env, &user)?;
+
+        if self::predecessor(&env) != Some(predecessor.clone()) {
+            return Err(Error::Unauthorized);
+        }
+        if borrowed < 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut position: UserPosition = match load_position(&env, &user, account_id) {
+            Some(position) => position,
+            None => {
+                add_account(&env, &user, account_id)?;
+                UserPosition {
+                    collateral: Map::new(&env),
+                    borrowed: 0,
+                    last_update: env.ledger().timestamp(),
+                    borrow_index: RAY,
+                    rate_mode: RateMode::Variable,
+                    stable_borrowed: 0,
+                    stable_rate: 0,
+                    free_tranches: Vec::new(&env),
+                    terms: LoanTerms::Open,
+                }
+            }
+        };
+        if position.terms != LoanTerms::Open {
+            return Err(Error::FixedLoanActive);
+        }
+        let config = load_config(&env)?;
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        for (token, amount) in collateral.iter() {
+            if !config.collateral.contains_key(token.clone()) {
+                return Err(Error::UnsupportedCollateral);
+            }
+            if amount <= 0 {
+                return Err(Error::InvalidParameter);
+            }
+
+            let balance = position.collateral.get(token.clone()).unwrap_or(0);
+            settle_yield(&env, &user, account_id, &token, balance)?;
+            position.collateral.set(token.clone(), balance + amount);
+            update_collateral_total(&env, &token, amount);
+            check_supply_cap(&env, &token)?;
+        }
+        check_collateral_mix(&env, &position.collateral)?;
+
+        let usdc_client = token::Client::new(&env, &config.usdc_token);
+        if borrowed > 0 {
+            if usdc_client.balance(&env.current_contract_address()) < borrowed {
+                return Err(Error::InsufficientLiquidity);
+            }
+            add_debt(
+                &env,
+                &config,
+                &user,
+                account_id,
+                &mut position,
+                borrowed,
+                RateMode::Variable,
+            )?;
+            update_total_borrowed(&env, borrowed);
+        }
+
+        save_position(&env, &config, &user, account_id, &position)?;
+        check_debt_ceiling(&env, &position.collateral)?;
+
+        if borrowed > 0 {
+            usdc_client.transfer(&env.current_contract_address(), &predecessor, &borrowed);
+        }
+
+        PositionReceived {
+            user,
+            account_id,
+            predecessor,
+            borrowed: position.borrowed,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(())
+    }
+
+    /// Withdraw collateral (only if enough collateral remains)
+    ///
+    /// A muxed user withdraws from its base account's position and is paid at
+    /// the muxed address, unless `to` names another account to pay.
+    pub fn withdraw_collateral(
+        env: Env,
+        user: MuxedAddress,
+        account_id: u32,
+        token: Address,
+        amount: i128,
+        to: Option<Address>,
+    ) -> Result<(), Error> {
+        let (pay_to, muxed_id) = match to {
+            Some(to) => (to.into(), None),
+            None => (user.clone(), user.id()),
+        };
+        let user = user.address();
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        // Get user position
+        let mut position: UserPosition =
+            load_position(&env, &user, account_id).ok_or(Error::NotInitialized)?;
+
+        let config = load_config(&env)?;
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        let balance = position.collateral.get(token.clone()).unwrap_or(0);
+        let new_balance = remove_collateral(&env, &config, &user, &mut position, &token, amount)?;
+
+        // Settle collateral yield before the contract balance changes
+        settle_yield(&env, &user, account_id, &token, balance)?;
+        update_collateral_total(&env, &token, -amount);
+
+        save_position(&env, &config, &user, account_id, &position)?;
+
+        // Transfer collateral back to user
+        let token_client = token::Client::new(&env, &token);
+        token_client.transfer(&env.current_contract_address(), &pay_to, &amount);
+
+        Withdraw {
+            user,
+            account_id,
+            token,
+            to: pay_to.address(),
+            muxed_id,
+            amount,
+            collateral: new_balance,
+            borrowed: position.borrowed,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(())
+    }
+
+    /// Withdraw an account's balance of one collateral token in emergency mode
+    ///
+    /// Collateral worth the debt the account's other collateral does not cover,
+    /// each at its full value, stays on the account; only the excess is sent.
+    /// When the account's collateral is worth less than its debt, all of this
+    /// token stays and the difference is written off as bad debt. An account
+    /// with debt cannot withdraw while its collateral cannot be priced. If the
+    /// contract holds less of the token than was deposited, every depositor
+    /// receives the same pro-rata share. Returns the amount sent.
+    pub fn emergency_withdraw(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        token: Address,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        if !is_emergency_mode(&env) {
+            return Err(Error::NotEmergencyMode);
+        }
+
+        let mut position: UserPosition =
+            load_position(&env, &user, account_id).ok_or(Error::NotInitialized)?;
+        let config = load_config(&env)?;
+        accrue_interest(&env, &config, &user, &mut position)?;
+
+        let balance = position.collateral.get(token.clone()).unwrap_or(0);
+        if balance == 0 {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Deposits held for everyone, less any shortfall in the contract's balance
+        settle_yield(&env, &user, account_id, &token, balance)?;
+        let total = collateral_total(&env, &token);
+        let reserved: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::YieldReserved(token.clone()))
+            .unwrap_or(0);
+        let token_client = token::Client::new(&env, &token);
+        let held = (token_client.balance(&env.current_contract_address())
+            - reserved
+            - protocol_balance(&env, &token))
+        .min(total);
+
+        // Keep back collateral worth the debt the rest of the account leaves
+        // uncovered, writing off whatever this token cannot cover either
+        position.collateral.remove(token.clone());
+        let mut kept = 0;
+        let mut written_off = 0;
+        if position.borrowed > 0 {
+            let covered = if position.collateral.is_empty() {
+                0
+            } else {
+                weighted_collateral_value(&env, &config, &position.collateral, |_, _| 10000)?
+            };
+            let uncovered = (debt_value(&env, &config, position.borrowed)? - covered).max(0);
+            let value = collateral_value(&env, &config, &token, balance, Rounding::Down)?;
+            if value <= uncovered {
+                kept = balance;
+                written_off = usdc_for_value(&env, &config, uncovered - value, Rounding::Down)?
+                    .min(position.borrowed);
+            } else {
+                kept =
+                    mul_div(balance, uncovered, value, Rounding::Up).ok_or(Error::MathOverflow)?;
+            }
+        }
+        let released = balance - kept;
+        let amount =
+            mul_div(released, held.max(0), total, Rounding::Down).ok_or(Error::MathOverflow)?;
+
+        if kept > 0 {
+            position.collateral.set(token.clone(), kept);
+        }
+        update_collateral_total(&env, &token, -released);
+        record_bad_debt(&env, written_off);
+        reduce_debt(&env, &mut position, written_off);
+
+        save_position(&env, &config, &user, account_id, &position)?;
+
+        if amount > 0 {
+            token_client.transfer(&env.current_contract_address(), &user, &amount);
+        }
+
+        EmergencyWithdraw {
+            user,
+            account_id,
+            token,
+            amount,
+            written_off,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(amount)
+    }
+
+    /// Claim yield earned by one account's collateral while held by the contract
+    pub fn claim_collateral_yield(
+        env: Env,
+        user: Address,
+        account_id: u32,
+    ) -> Result<Map<Address, i128>, Error> {
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        let position = Self::get_position(env.clone(), user.clone(), account_id);
+        let mut claimed = Map::new(&env);
+
+        for token in Self::get_collateral_tokens(env.clone())?.iter() {
+            let balance = position.collateral.get(token.clone()).unwrap_or(0);
+            let amount = claim_yield(&env, &user, account_id, &token, balance)?;
+
+            if amount > 0 {
+                claimed.set(token.clone(), amount);
+
+                CollateralYieldClaimed {
+                    user: user.clone(),
+                    account_id,
+                    token,
+                    amount,
+                }
+                .publish(&env);
+            }
+        }
+
+        invariant::check(&env, &load_config(&env)?)?;
+
+        Ok(claimed)
+    }
+
+    /// Claim reward tokens earned as a supplier and borrower, returning the amount sent
+    pub fn claim_rewards(env: Env, user: Address) -> Result<i128, Error> {
+        user.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+
+        let reward_token: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::RewardToken)
+            .ok_or(Error::NotInitialized)?;
+        let amount = claim_rewards(&env, &reward_token, &user)?;
+
+        if amount > 0 {
+            RewardsClaimed { user, amount }.publish(&env);
+        }
+
+        invariant::check(&env, &load_config(&env)?)?;
+
+        Ok(amount)
+    }
+
+    /// Set a bToken holder's supply reward balance to the shares they now hold
+    /// (bToken only)
+    ///
+    /// The bToken calls this after every transfer and burn, so rewards stop for
+    /// shares that leave a holder and start for the one receiving them.
+    pub fn sync_supply_rewards(env: Env, holder: Address, shares: i128) -> Result<(), Error> {
+        let btoken = load_config(&env)?.btoken.ok_or(Error::NotInitialized)?;
+        btoken.require_auth();
+
+        set_reward_balance(&env, RewardPool::Supply, &holder, shares)
+    }
+
+    /// Get reward tokens a user has earned and not yet claimed
+    pub fn get_claimable_rewards(env: Env, user: Address) -> Result<i128, Error> {
+        claimable_rewards(&env, &user)
+    }
+
+    /// Supply USDC liquidity to the pool in exchange for shares
+    ///
+    /// A muxed lender's shares go to its base account.
+    pub fn supply(env: Env, lender: MuxedAddress, amount: i128) -> Result<i128, Error> {
+        let muxed_id = lender.id();
+        let lender = lender.address();
+        lender.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        // Price shares against pool assets before the deposit lands
+        let config = load_config(&env)?;
+        update_borrow_index(&env, &config)?;
+        let total_assets = pool_assets(&env);
+        let total_shares = total_supply_shares(&env, &config);
+
+        let shares = if total_shares == 0 || total_assets == 0 {
+            amount
+        } else {
+            mul_div(amount, total_shares, total_assets, Rounding::Down)
+                .ok_or(Error::MathOverflow)?
+        };
+
+        if shares <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        // Mint shares
+        mint_supply_shares(&env, &config, &lender, shares);
+        let lender_shares = supply_shares(&env, &config, &lender);
+        set_reward_balance(&env, RewardPool::Supply, &lender, lender_shares)?;
+        update_total_supplied(&env, amount);
+
+        // Transfer USDC from lender to contract
+        let token_client = token::Client::new(&env, &config.usdc_token);
+        token_client.transfer(&lender, env.current_contract_address(), &amount);
+
+        Supply {
+            lender,
+            muxed_id,
+            amount,
+            shares,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(shares)
+    }
+
+    /// Withdraw supplied USDC (plus earned interest) by burning shares
+    ///
+    /// A muxed lender burns its base account's shares and is paid at the
+    /// muxed address.
+    pub fn withdraw_supply(env: Env, lender: MuxedAddress, amount: i128) -> Result<i128, Error> {
+        let pay_to = lender.clone();
+        let muxed_id = lender.id();
+        let lender = lender.address();
+        lender.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let config = load_config(&env)?;
+        update_borrow_index(&env, &config)?;
+        let total_assets = pool_assets(&env);
+        let total_shares = total_supply_shares(&env, &config);
+        let lender_shares = supply_shares(&env, &config, &lender);
+
+        if total_assets == 0 || total_shares == 0 {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Burn shares rounded up so the pool never pays out more than owed
+        let shares =
+            mul_div(amount, total_shares, total_assets, Rounding::Up).ok_or(Error::MathOverflow)?;
+        if shares > lender_shares {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Only idle USDC can be withdrawn
+        let token_client = token::Client::new(&env, &config.usdc_token);
+        if token_client.balance(&env.current_contract_address()) < amount {
+            return Err(Error::InsufficientLiquidity);
+        }
+
+        // Burn shares
+        burn_supply_shares(&env, &config, &lender, shares);
+        let lender_shares = supply_shares(&env, &config, &lender);
+        set_reward_balance(&env, RewardPool::Supply, &lender, lender_shares)?;
+        update_total_supplied(&env, -amount);
+
+        // Transfer USDC to lender
+        token_client.transfer(&env.current_contract_address(), &pay_to, &amount);
+
+        WithdrawSupply {
+            lender,
+            muxed_id,
+            amount,
+            shares,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(shares)
+    }
+
+    /// Lend pool USDC to a receiver contract that must repay it plus a fee within `exec_op`
+    ///
+    /// `initiator` authorizes the loan and `receiver` only gets the callback,
+    /// so a contract can start a loan for a receiver it deployed. Soroban
+    /// rejects any call back into a contract already on the call stack, so
+    /// `exec_op` cannot use this market, or the initiator, while it holds the
+    /// loan. It can trade the USDC, or repay and liquidate on other markets;
+    /// `flash_liquidate` liquidates on this one. A receiver that fails fails
+    /// the loan with its own error.
+    pub fn flash_loan(
+        env: Env,
+        initiator: Address,
+        receiver: Address,
+        amount: i128,
+    ) -> Result<i128, Error> {
+        initiator.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let config = load_config(&env)?;
+        let fee =
+            bps_mul(amount, config.flash_loan_fee, Rounding::Up).ok_or(Error::MathOverflow)?;
+
+        let token_client = token::Client::new(&env, &config.usdc_token);
+        let balance_before = token_client.balance(&env.current_contract_address());
+        if balance_before < amount {
+            return Err(Error::InsufficientLiquidity);
+        }
+
+        // Send the loan and hand control to the receiver
+        token_client.transfer(&env.current_contract_address(), &receiver, &amount);
+        FlashLoanReceiverClient::new(&env, &receiver).exec_op(&config.usdc_token, &amount, &fee);
+
+        // The fee stays in the pool and accrues to suppliers
+        if token_client.balance(&env.current_contract_address()) < balance_before + fee {
+            return Err(Error::FlashLoanNotRepaid);
+        }
+        update_total_supplied(&env, fee);
+
+        FlashLoan {
+            initiator,
+            receiver,
+            amount,
+            fee,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(fee)
+    }
+
+    /// Repay part of an underwater position's debt in exchange for its
+    /// collateral, returning the collateral paid to the liquidator
+    ///
+    /// The treasury keeps its share of the liquidation bonus, as set by
+    /// `set_liquidation_fee_share`.
+    ///
+    /// The liquidator never pays more than `repay_amount`: an amount above the
+    /// debt repays just the debt and the rest is refunded, so a position can be
+    /// closed out without knowing its interest to the second. A liquidation may
+    /// not leave debt below the minimum borrow. Once the close factor would, the
+    /// whole debt may be repaid in one call instead.
+    pub fn liquidate(
+        env: Env,
+        liquidator: Address,
+        user: Address,
+        account_id: u32,
+        token: Address,
+        repay_amount: i128,
+    ) -> Result<i128, Error> {
+        liquidator.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+
+        let config = load_config(&env)?;
+        let seizure = seize(&env, &config, &user, account_id, &token, repay_amount)?;
+
+        // Take the signed amount and refund what the debt did not need, so the
+        // transfer the liquidator authorizes does not depend on accrued interest
+        let contract = env.current_contract_address();
+        let usdc_client = token::Client::new(&env, &config.usdc_token);
+        usdc_client.transfer(&liquidator, &contract, &repay_amount);
+        if repay_amount > seizure.repaid {
+            usdc_client.transfer(&contract, &liquidator, &(repay_amount - seizure.repaid));
+        }
+
+        pay_seized_collateral(&env, &config, &token, &liquidator, &seizure)?;
+        publish_liquidation(&env, user, account_id, liquidator, token, &seizure);
+
+        invariant::check(&env, &config)?;
+
+        Ok(seizure.paid)
+    }
+
+    /// Liquidate a position with pool USDC, returning the collateral sent to
+    /// `receiver`, which must pay back the debt repaid plus the flash loan fee
+    /// within `exec_liquidation`
+    ///
+    /// Lets a liquidator holding no USDC liquidate on this market, which
+    /// `flash_loan` cannot do: the receiver can sell the collateral for the
+    /// USDC it owes and keep what the bonus leaves over. The close factor,
+    /// minimum borrow and treasury share are as in `liquidate`, and the fee
+    /// accrues to suppliers as in `flash_loan`.
+    pub fn flash_liquidate(
+        env: Env,
+        liquidator: Address,
+        receiver: Address,
+        user: Address,
+        account_id: u32,
+        token: Address,
+        repay_amount: i128,
+    ) -> Result<i128, Error> {
+        liquidator.require_auth();
+        let _guard = ReentrancyGuard::acquire(&env)?;
+        require_not_paused(&env)?;
+
+        let config = load_config(&env)?;
+        let usdc_client = token::Client::new(&env, &config.usdc_token);
+        let balance_before = usdc_client.balance(&env.current_contract_address());
+
+        let seizure = seize(&env, &config, &user, account_id, &token, repay_amount)?;
+        let fee = bps_mul(seizure.repaid, config.flash_loan_fee, Rounding::Up)
+            .ok_or(Error::MathOverflow)?;
+        let owed = seizure.repaid + fee;
+
+        // Hand the collateral to the receiver, which owes the pool the debt it
+        // repaid on the liquidator's behalf
+        pay_seized_collateral(&env, &config, &token, &receiver, &seizure)?;
+        FlashLiquidationReceiverClient::new(&env, &receiver).exec_liquidation(
+            &token,
+            &seizure.paid,
+            &owed,
+        );
+
+        // The fee stays in the pool and accrues to suppliers
+        if usdc_client.balance(&env.current_contract_address()) < balance_before + owed {
+            return Err(Error::FlashLoanNotRepaid);
+        }
+        update_total_supplied(&env, fee);
+
+        publish_liquidation(&env, user, account_id, liquidator.clone(), token, &seizure);
+        FlashLoan {
+            initiator: liquidator,
+            receiver,
+            amount: seizure.repaid,
+            fee,
+        }
+        .publish(&env);
+
+        invariant::check(&env, &config)?;
+
+        Ok(seizure.paid)
+    }
+
+    /// Extend the TTL of one of a user's positions so it is not archived
+    pub fn bump_position(env: Env, user: Address, account_id: u32) -> Result<(), Error> {
+        let key = DataKey::UserPosition(user, account_id);
+        if !env.storage().persistent().has(&key) {
+            return Err(Error::NotInitialized);
+        }
+
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, POSITION_BUMP_AMOUNT, POSITION_BUMP_AMOUNT);
+
+        Ok(())
+    }
+
+    /// Get the position held in one of a user's accounts
+    pub fn get_position(env: Env, user: Address, account_id: u32) -> UserPosition {
+        load_position(&env, &user, account_id).unwrap_or(UserPosition {
+            collateral: Map::new(&env),
+            borrowed: 0,
+            last_update: env.ledger().timestamp(),
+            borrow_index: RAY,
+            rate_mode: RateMode::Variable,
+            stable_borrowed: 0,
+            stable_rate: 0,
+            free_tranches: Vec::new(&env),
+            terms: LoanTerms::Open,
+        })
+    }
+
+    /// Get an account's debt with interest accrued to the current ledger
+    ///
+    /// This is the exact amount `repay` would need to clear the debt in this
+    /// ledger; `get_position` shows the debt as of the account's last update.
+    pub fn get_current_debt(env: Env, user: Address, account_id: u32) -> Result<i128, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user, account_id),
+        )?;
+        Ok(position.borrowed)
+    }
+
+    /// Get several `(user, account id)` positions in one call, in the order given
+    pub fn get_positions(env: Env, accounts: Vec<(Address, u32)>) -> Vec<UserPosition> {
+        let mut positions = Vec::new(&env);
+        for (user, account_id) in accounts.iter() {
+            positions.push_back(Self::get_position(env.clone(), user, account_id));
+        }
+        positions
+    }
+
+    /// Get the recorded snapshots of one of a user's accounts, oldest first
+    pub fn get_history(env: Env, user: Address, account_id: u32) -> Vec<Snapshot> {
+        history(&env, &user, account_id)
+    }
+
+    /// List the sub-account ids a user has opened
+    pub fn get_accounts(env: Env, user: Address) -> Vec<u32> {
+        accounts(&env, &user)
+    }
+
+    /// Number of users that have opened a position
+    pub fn get_user_count(env: Env) -> u32 {
+        user_count(&env)
+    }
+
+    /// List users with positions, in the order they first deposited
+    pub fn list_users(env: Env, offset: u32, limit: u32) -> Vec<Address> {
+        users(&env, offset, limit.min(MAX_PAGE_SIZE))
+    }
+
+    /// List the liquidatable `(user, account id)` positions among one page of `list_users`
+    pub fn list_liquidatable(
+        env: Env,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<(Address, u32)>, Error> {
+        let mut liquidatable = Vec::new(&env);
+        for user in users(&env, offset, limit.min(MAX_PAGE_SIZE)).iter() {
+            for account_id in accounts(&env, &user).iter() {
+                if Self::is_liquidatable(env.clone(), user.clone(), account_id)? {
+                    liquidatable.push_back((user.clone(), account_id));
+                }
+            }
+        }
+        Ok(liquidatable)
+    }
+
+    /// Calculate available credit for one of a user's accounts
+    pub fn get_available_credit(env: Env, user: Address, account_id: u32) -> Result<i128, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user.clone(), account_id),
+        )?;
+
+        let available = credit_limit(&env, &config, &user, &position.collateral)?
+            - debt_value(&env, &config, position.borrowed)?;
+
+        if available < 0 {
+            return Ok(0);
+        }
+
+        from_internal(&env, &config.usdc_token, available)
+    }
+
+    /// Largest amount `borrow` would currently accept on one of a user's accounts
+    ///
+    /// Available credit, further held to the user's borrow cap, the debt ceiling,
+    /// the isolation ceiling of its collateral and the pool's idle USDC. A
+    /// threshold cut still in its grace period already lowers the available
+    /// credit, as it does for `borrow`. Zero while borrowing is blocked or when
+    /// the most that fits is under the minimum borrow.
+    pub fn get_max_borrowable(env: Env, user: Address, account_id: u32) -> Result<i128, Error> {
+        let config = load_config(&env)?;
+        let saved = Self::get_position(env.clone(), user.clone(), account_id);
+        let saved_borrowed = saved.borrowed;
+        let position = accrued_position(&env, &config, saved)?;
+        if require_not_paused(&env).is_err()
+            || require_allowlisted(&env, &user).is_err()
+            || is_emergency_mode(&env)
+            || position.terms != LoanTerms::Open
+        {
+            return Ok(0);
+        }
+
+        let mut max = Self::get_available_credit(env.clone(), user.clone(), account_id)?;
+
+        let borrow_cap: Option<i128> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::BorrowCap(user.clone()));
+        if let Some(cap) = borrow_cap {
+            max = max.min(cap - other_debt(&env, &config, &user, account_id)? - position.borrowed);
+        }
+
+        if let Some(ceiling) = config.debt_ceiling {
+            let total_borrowed: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::TotalBorrowed)
+                .unwrap_or(0);
+            max = max.min(ceiling - total_borrowed);
+        }
+
+        // Saving the position counts its interest since the last save against
+        // the isolation ceiling, ahead of the new debt
+        if let Some(headroom) = isolation_headroom(&env, &position.collateral) {
+            max = max.min(headroom - (position.borrowed - saved_borrowed));
+        }
+
+        let cash =
+            token::Client::new(&env, &config.usdc_token).balance(&env.current_contract_address());
+        max = max.min(cash);
+
+        if max < config.min_borrow {
+            return Ok(0);
+        }
+        Ok(max.max(0))
+    }
+
+    /// Largest amount of `token` `withdraw_collateral` would currently release
+    /// from one of a user's accounts
+    pub fn get_max_withdrawable(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        token: Address,
+    ) -> Result<i128, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user.clone(), account_id),
+        )?;
+        if require_not_paused(&env).is_err() {
+            return Ok(0);
+        }
+
+        let balance = position.collateral.get(token.clone()).unwrap_or(0);
+        if balance == 0 || position.borrowed == 0 {
+            return Ok(balance);
+        }
+
+        let debt = debt_value(&env, &config, position.borrowed)?;
+        let limit = credit_limit(&env, &config, &user, &position.collateral)?;
+        if limit <= debt {
+            return Ok(0);
+        }
+
+        let mut rest = position.collateral.clone();
+        rest.remove(token.clone());
+        let limit_without = credit_limit(&env, &config, &user, &rest)?;
+        if limit_without >= debt {
+            return Ok(balance);
+        }
+
+        // The token's share of the credit limit is linear in its balance
+        let mut amount = mul_div(balance, limit - debt, limit - limit_without, Rounding::Down)
+            .ok_or(Error::MathOverflow)?
+            .min(balance);
+
+        // Per-token rounding can leave the estimate a unit over
+        if remove_collateral(&env, &config, &user, &mut position.clone(), &token, amount).is_err() {
+            amount = (amount - 1).max(0);
+        }
+
+        Ok(amount)
+    }
+
+    /// Liquidation limit divided by debt (including accrued interest), scaled by `HEALTH_FACTOR_ONE`
+    pub fn get_health_factor(env: Env, user: Address, account_id: u32) -> Result<i128, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user, account_id),
+        )?;
+        health_factor(&env, &config, &position)
+    }
+
+    /// Annual borrow rate of one of a user's accounts less the yield its BENJI
+    /// collateral earns, as a share of its debt in basis points
+    ///
+    /// Negative when the collateral yields more than the debt costs. A position
+    /// without debt has nothing to offset and gets its plain borrow rate.
+    pub fn get_net_rate(env: Env, user: Address, account_id: u32) -> Result<i128, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user, account_id),
+        )?;
+        let rate = position_rate(&env, &config, &position)? as i128;
+
+        let debt = debt_value(&env, &config, position.borrowed)?;
+        let benji = position
+            .collateral
+            .get(config.benji_token.clone())
+            .unwrap_or(0);
+        if debt <= 0 || benji <= 0 {
+            return Ok(rate);
+        }
+
+        let earned = bps_mul(
+            collateral_value(&env, &config, &config.benji_token, benji, Rounding::Down)?,
+            Self::get_collateral_yield(env.clone()),
+            Rounding::Down,
+        )
+        .ok_or(Error::MathOverflow)?;
+        let offset = mul_div(earned, BPS, debt, Rounding::Down).ok_or(Error::MathOverflow)?;
+        Ok(rate - offset)
+    }
+
+    /// Preview `borrow` of `amount` on one of a user's accounts without submitting it
+    pub fn preview_borrow(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        amount: i128,
+    ) -> Result<Preview, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user.clone(), account_id),
+        )?;
+        let outcome = preview_borrow(&env, &user, account_id, position.clone(), amount);
+        preview(&env, position, outcome)
+    }
+
+    /// Preview `withdraw_collateral` of `amount` of `token` without submitting it
+    pub fn preview_withdraw(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        token: Address,
+        amount: i128,
+    ) -> Result<Preview, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user.clone(), account_id),
+        )?;
+        let outcome = preview_withdraw(&env, &user, account_id, position.clone(), &token, amount);
+        preview(&env, position, outcome)
+    }
+
+    /// Preview `repay` of `amount` on one of a user's accounts without submitting it
+    pub fn preview_repay(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        amount: i128,
+    ) -> Result<Preview, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user, account_id),
+        )?;
+        let outcome = preview_repay(&env, position.clone(), amount);
+        preview(&env, position, outcome)
+    }
+
+    /// End of the grace period shielding a position from liquidation after a threshold cut
+    ///
+    /// Returns `None` unless the position is liquidatable under the current
+    /// thresholds but not under the ones it is still being held to.
+    pub fn get_grace_deadline(
+        env: Env,
+        user: Address,
+        account_id: u32,
+    ) -> Result<Option<u64>, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user, account_id),
+        )?;
+
+        let debt = debt_value(&env, &config, position.borrowed)?;
+        if debt <= strict_liquidation_limit(&env, &config, &position.collateral)?
+            || debt > liquidation_limit(&env, &config, &position.collateral)?
+        {
+            return Ok(None);
+        }
+
+        let mut deadline = None;
+        for token in position.collateral.keys().iter() {
+            if let Some(grace) = active_grace(&env, &token) {
+                deadline = deadline.max(Some(grace.deadline));
+            }
+        }
+        Ok(deadline)
+    }
+
+    /// Check whether a position can currently be liquidated
+    pub fn is_liquidatable(env: Env, user: Address, account_id: u32) -> Result<bool, Error> {
+        let config = load_config(&env)?;
+        let position = accrued_position(
+            &env,
+            &config,
+            Self::get_position(env.clone(), user.clone(), account_id),
+        )?;
+        if fixed_loan_overdue(&env, &position)? {
+            return Ok(true);
+        }
+
+        Ok(Self::get_health_factor(env, user, account_id)? < HEALTH_FACTOR_ONE)
+    }
+
+    /// Get the risk parameters of a collateral token
+    pub fn get_collateral_config(env: Env, token: Address) -> Result<CollateralConfig, Error> {
+        collateral_config(&env, &token)
+    }
+
+    /// Get the Stellar Asset Contract address for native XLM
+    ///
+    /// Pass it to `set_collateral_config` to accept XLM as collateral. Like every
+    /// Stellar asset it has 7 decimals, and oracles price it under this address.
+    pub fn get_native_token(env: Env) -> Address {
+        native_token(&env)
+    }
+
+    /// List accepted collateral tokens
+    pub fn get_collateral_tokens(env: Env) -> Result<Vec<Address>, Error> {
+        Ok(load_config(&env)?.collateral.keys())
+    }
+
+    /// Get a lender's pool shares
+    pub fn get_supply_shares(env: Env, lender: Address) -> i128 {
+        load_config(&env).map_or(0, |config| supply_shares(&env, &config, &lender))
+    }
+
+    /// Get the USDC value of a lender's pool shares
+    pub fn get_supply_balance(env: Env, lender: Address) -> Result<i128, Error> {
+        let config = load_config(&env)?;
+        let total_shares = total_supply_shares(&env, &config);
+
+        if total_shares == 0 {
+            return Ok(0);
+        }
+
+        let shares = supply_shares(&env, &config, &lender);
+        mul_div(shares, pool_assets(&env), total_shares, Rounding::Down).ok_or(Error::MathOverflow)
+    }
+
+    /// Get USDC redeemable per pool share (bToken), scaled by 1e18
+    pub fn get_exchange_rate(env: Env) -> Result<i128, Error> {
+        let config = load_config(&env)?;
+        let total_shares = total_supply_shares(&env, &config);
+        if total_shares == 0 {
+            return Ok(WAD);
+        }
+
+        let total_assets = pool_assets(&env);
+        mul_div(total_assets, WAD, total_shares, Rounding::Down).ok_or(Error::MathOverflow)
+    }
+
+    /// Get the bToken issued to suppliers, if one is configured
+    pub fn get_btoken(env: Env) -> Result<Option<Address>, Error> {
+        Ok(load_config(&env)?.btoken)
+    }
+
+    /// Get the debt token mirroring borrower debt, if one is configured
+    pub fn get_debt_token(env: Env) -> Result<Option<Address>, Error> {
+        Ok(load_config(&env)?.debt_token)
+    }
+
+    /// Get the market configuration
+    pub fn get_config(env: Env) -> Result<MarketConfig, Error> {
+        load_config(&env)
+    }
+
+    /// Get the collateral held of each accepted token, against its supply cap
+    pub fn get_collateral_totals(env: Env) -> Result<Map<Address, CollateralUsage>, Error> {
+        let mut totals = Map::new(&env);
+        for token in load_config(&env)?.collateral.keys().iter() {
+            let usage = CollateralUsage {
+                total: collateral_total(&env, &token),
+                supply_cap: env
+                    .storage()
+                    .instance()
+                    .get(&DataKey::SupplyCap(token.clone())),
+            };
+            totals.set(token, usage);
+        }
+        Ok(totals)
+    }
+
+    /// Get market-wide totals, utilization and rates
+    pub fn get_market_summary(env: Env) -> Result<MarketSummary, Error> {
+        let config = load_config(&env)?;
+
+        let mut total_collateral = Map::new(&env);
+        for token in config.collateral.keys().iter() {
+            let total = collateral_total(&env, &token);
+            total_collateral.set(token, total);
+        }
+
+        let total_borrowed: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalBorrowed)
+            .unwrap_or(0);
+        let total_reserves = Self::get_reserves(env.clone());
+        let total_supplied = pool_assets(&env);
+        let available_liquidity =
+            token::Client::new(&env, &config.usdc_token).balance(&env.current_contract_address());
+
+        let utilization = utilization(&env)?;
+        let borrow_rate = variable_rate(&env, &config)?;
+
+        // Suppliers earn the borrow rate on the lent-out share, less reserves
+        let supply_rate = mul_div(
+            borrow_rate as i128 * utilization as i128,
+            BPS - config.reserve_factor as i128,
+            BPS * BPS,
+            Rounding::Down,
+        )
+        .ok_or(Error::MathOverflow)? as u32;
+
+        Ok(MarketSummary {
+            total_collateral,
+            total_borrowed,
+            total_supplied,
+            total_reserves,
+            available_liquidity,
+            utilization,
+            borrow_rate,
+            stable_borrow_rate: stable_rate(&env, &config)?,
+            supply_rate,
+        })
     }
 }
@@ -0,0 +1,55 @@
+//! Protocol share of the liquidation bonus.
+//!
+//! A liquidation seizes collateral worth the repaid debt plus the bonus. A
+//! configurable share of the bonus goes to the treasury instead of the
+//! liquidator, and the collateral sent there is totalled per token.
+
+use soroban_sdk::{contracttype, Address, Env};
+
+use crate::math::{bps_mul, Rounding};
+use crate::Error;
+
+/// Storage keys for the liquidation fee
+#[contracttype]
+enum LiquidationFeeKey {
+    Share,              // 2000 = the treasury takes 20% of the liquidation bonus
+    Collected(Address), // collateral token -> amount sent to the treasury so far
+}
+
+/// Share of the liquidation bonus taken by the treasury, in basis points
+pub(crate) fn liquidation_fee_share(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&LiquidationFeeKey::Share)
+        .unwrap_or(0)
+}
+
+pub(crate) fn set_liquidation_fee_share(env: &Env, share_bps: u32) {
+    env.storage()
+        .instance()
+        .set(&LiquidationFeeKey::Share, &share_bps);
+}
+
+/// Collateral of a token sent to the treasury by liquidations so far
+pub(crate) fn liquidation_fees(env: &Env, token: &Address) -> i128 {
+    env.storage()
+        .instance()
+        .get(&LiquidationFeeKey::Collected(token.clone()))
+        .unwrap_or(0)
+}
+
+/// The treasury's cut of `bonus` collateral seized above the repaid debt's
+/// worth, counted as collected
+pub(crate) fn take_liquidation_fee(env: &Env, token: &Address, bonus: i128) -> Result<i128, Error> {
+    let share = liquidation_fee_share(env);
+    if bonus <= 0 || share == 0 {
+        return Ok(0);
+    }
+
+    let fee = bps_mul(bonus, share, Rounding::Up).ok_or(Error::MathOverflow)?;
+    env.storage().instance().set(
+        &LiquidationFeeKey::Collected(token.clone()),
+        &(liquidation_fees(env, token) + fee),
+    );
+    Ok(fee)
+}
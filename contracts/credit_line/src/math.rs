@@ -0,0 +1,158 @@
+//! Overflow-safe fixed-point arithmetic for credit line calculations.
+//!
+//! All helpers return `None` instead of overflowing. Intermediate products are
+//! computed in 256 bits, so `mul_div` only fails when the final result does not
+//! fit in an `i128` or the denominator is zero.
+//!
+//! # Rounding policy
+//!
+//! Every inexact result rounds against the user it concerns, so no sequence of
+//! calls can accumulate rounding dust into a profit paid out of the pool:
+//!
+//! - What users owe rounds up: the borrow index and accrued interest, the cost
+//!   of BENJI bought from the protocol, flash loan fees, shares burned on a
+//!   supply withdrawal.
+//! - What users are credited rounds down: collateral values, credit and
+//!   liquidation limits, health factors, the most collateral a user may
+//!   withdraw, shares minted on supply and the USDC they redeem for, debt
+//!   repaid by BENJI, waived interest.
+//! - Liquidations round for the protocol: collateral seized for the liquidator
+//!   rounds down, and the treasury's cut of the bonus rounds up.
+
+/// 1.0 with 18 decimals
+pub const WAD: i128 = 1_000_000_000_000_000_000;
+
+/// 1.0 with 27 decimals
+pub const RAY: i128 = 1_000_000_000_000_000_000_000_000_000;
+
+/// 100% in basis points
+pub const BPS: i128 = 10_000;
+
+/// Direction to round the magnitude of an inexact result
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    /// Toward zero
+    Down,
+    /// Away from zero
+    Up,
+}
+
+/// Full 256-bit product of two `u128` values as `(high, low)`
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    let cross = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let low = (cross << 64) | (lo_lo & MASK);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64);
+
+    (high, low)
+}
+
+/// Divide a 256-bit value by a `u128`, returning `(quotient, remainder)`,
+/// or `None` if the quotient does not fit in 128 bits
+fn wide_div(high: u128, low: u128, divisor: u128) -> Option<(u128, u128)> {
+    if high == 0 {
+        return Some((low / divisor, low % divisor));
+    }
+
+    if high >= divisor {
+        return None;
+    }
+
+    // Shift-subtract long division over the low word
+    let mut remainder = high;
+    let mut quotient = 0_u128;
+    for i in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> i) & 1);
+        if carry == 1 || remainder >= divisor {
+            remainder = remainder.wrapping_sub(divisor);
+            quotient |= 1 << i;
+        }
+    }
+
+    Some((quotient, remainder))
+}
+
+/// Compute `a * b / denominator` with the given rounding
+pub fn mul_div(a: i128, b: i128, denominator: i128, rounding: Rounding) -> Option<i128> {
+    if denominator == 0 {
+        return None;
+    }
+
+    let negative = (a < 0) ^ (b < 0) ^ (denominator < 0);
+
+    let (high, low) = wide_mul(a.unsigned_abs(), b.unsigned_abs());
+    let (mut quotient, remainder) = wide_div(high, low, denominator.unsigned_abs())?;
+
+    if rounding == Rounding::Up && remainder != 0 {
+        quotient = quotient.checked_add(1)?;
+    }
+
+    if negative {
+        if quotient > i128::MIN.unsigned_abs() {
+            return None;
+        }
+        Some((quotient as i128).wrapping_neg())
+    } else {
+        i128::try_from(quotient).ok()
+    }
+}
+
+/// Multiply two WAD-scaled values
+pub fn wad_mul(a: i128, b: i128, rounding: Rounding) -> Option<i128> {
+    mul_div(a, b, WAD, rounding)
+}
+
+/// Divide two WAD-scaled values
+pub fn wad_div(a: i128, b: i128, rounding: Rounding) -> Option<i128> {
+    mul_div(a, WAD, b, rounding)
+}
+
+/// Multiply two RAY-scaled values
+pub fn ray_mul(a: i128, b: i128, rounding: Rounding) -> Option<i128> {
+    mul_div(a, b, RAY, rounding)
+}
+
+/// Divide two RAY-scaled values
+pub fn ray_div(a: i128, b: i128, rounding: Rounding) -> Option<i128> {
+    mul_div(a, RAY, b, rounding)
+}
+
+/// Apply a basis-point ratio to an amount
+pub fn bps_mul(amount: i128, bps: u32, rounding: Rounding) -> Option<i128> {
+    mul_div(amount, bps as i128, BPS, rounding)
+}
+
+/// `(1 + rate)^periods` for a RAY-scaled per-period `rate`, by exponentiation
+/// by squaring
+///
+/// Takes at most `2 * log2(periods)` RAY multiplications, each off by under one
+/// unit of `RAY` scale in the `rounding` direction. Squaring doubles the
+/// relative error carried into it, so the result stays within
+/// `2 * periods / RAY` of the exact power, relatively: under 2e-20 for a year
+/// of per-ledger periods. `None` once the power no longer fits in an `i128`.
+pub fn compound(rate: i128, periods: u64, rounding: Rounding) -> Option<i128> {
+    let mut base = RAY.checked_add(rate)?;
+    let mut result = RAY;
+    let mut remaining = periods;
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            result = ray_mul(result, base, rounding)?;
+        }
+        remaining >>= 1;
+        if remaining > 0 {
+            base = ray_mul(base, base, rounding)?;
+        }
+    }
+
+    Some(result)
+}
@@ -0,0 +1,63 @@
+//! Moving positions to a successor market.
+//!
+//! The admin of the old market names a successor and the admin of the new one
+//! names its predecessor. A user then calls `migrate_position` on the old
+//! market, which hands the position's collateral and debt to the successor's
+//! `receive_migration`. The successor takes on the debt and pays the old pool
+//! the same amount of USDC, so the old pool's suppliers are made whole without
+//! the borrower repaying.
+
+use soroban_sdk::{contractclient, contracttype, Address, Env, Map};
+
+/// Storage keys for migration
+#[contracttype]
+enum MigrationKey {
+    Successor,   // market positions may be migrated to
+    Predecessor, // market positions may be received from
+}
+
+/// Entry point a successor market exposes to take over a position
+///
+/// `receive_migration` is invoked by the predecessor's `migrate_position`. It
+/// must credit `collateral` and `borrowed` to the user's account, which the
+/// predecessor transfers right after it returns, and send `borrowed` USDC to
+/// the predecessor before returning.
+#[contractclient(name = "SuccessorClient")]
+pub trait Successor {
+    fn receive_migration(
+        env: Env,
+        predecessor: Address,
+        user: Address,
+        account_id: u32,
+        collateral: Map<Address, i128>,
+        borrowed: i128,
+    );
+}
+
+pub(crate) fn successor(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&MigrationKey::Successor)
+}
+
+pub(crate) fn predecessor(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&MigrationKey::Predecessor)
+}
+
+pub(crate) fn set_successor(env: &Env, successor: Option<Address>) {
+    match successor {
+        Some(successor) => env
+            .storage()
+            .instance()
+            .set(&MigrationKey::Successor, &successor),
+        None => env.storage().instance().remove(&MigrationKey::Successor),
+    }
+}
+
+pub(crate) fn set_predecessor(env: &Env, predecessor: Option<Address>) {
+    match predecessor {
+        Some(predecessor) => env
+            .storage()
+            .instance()
+            .set(&MigrationKey::Predecessor, &predecessor),
+        None => env.storage().instance().remove(&MigrationKey::Predecessor),
+    }
+}
@@ -0,0 +1,140 @@
+use soroban_sdk::{contractclient, contracttype, xdr::ToXdr, Address, Env, Symbol, Vec};
+
+use crate::math::{mul_div, Rounding};
+use crate::{DataKey, Error};
+
+/// Asset identifier used by SEP-40 price feeds
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
+
+/// Price reported by a SEP-40 price feed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+/// Asset an oracle quotes token prices in
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum QuoteAsset {
+    Usdc,
+    Other(Asset), // another asset, such as USD, with USDC priced in it under this entry
+}
+
+/// How the credit line reads the oracle's prices
+///
+/// The defaults suit a feed quoting tokens in USDC at its reported decimals.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OracleSettings {
+    pub decimals: Option<u32>, // price decimals, overriding the feed's `decimals`
+    pub quote: QuoteAsset,
+    pub invert: bool, // the feed reports the quote asset per token
+}
+
+pub(crate) fn oracle_settings(env: &Env) -> OracleSettings {
+    env.storage()
+        .instance()
+        .get(&DataKey::OracleSettings)
+        .unwrap_or(OracleSettings {
+            decimals: None,
+            quote: QuoteAsset::Usdc,
+            invert: false,
+        })
+}
+
+/// A quote asset as a parameter log value: `None` for USDC, otherwise the first
+/// 16 bytes of the SHA-256 of its XDR, so a change of quote asset shows up
+pub(crate) fn quote_log_value(env: &Env, quote: &QuoteAsset) -> Option<i128> {
+    let QuoteAsset::Other(asset) = quote else {
+        return None;
+    };
+
+    let hash = env.crypto().sha256(&asset.clone().to_xdr(env)).to_array();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    Some(i128::from_be_bytes(bytes))
+}
+
+/// A feed price turned into the price of a token in the quote asset, at `scale`
+pub(crate) fn oriented_price(
+    settings: &OracleSettings,
+    price: i128,
+    scale: i128,
+) -> Result<i128, Error> {
+    if !settings.invert {
+        return Ok(price);
+    }
+
+    let price = mul_div(scale, scale, price, Rounding::Down).ok_or(Error::MathOverflow)?;
+    if price <= 0 {
+        return Err(Error::PriceUnavailable);
+    }
+    Ok(price)
+}
+
+/// Subset of the SEP-40 oracle interface used by the credit line
+#[contractclient(name = "PriceOracleClient")]
+pub trait PriceOracle {
+    fn decimals(env: Env) -> u32;
+    fn lastprice(env: Env, asset: Asset) -> Option<PriceData>;
+    fn prices(env: Env, asset: Asset, records: u32) -> Option<Vec<PriceData>>;
+}
+
+/// Most price records read from the feed to average over a window
+const MAX_TWAP_RECORDS: u32 = 32;
+
+/// Time-weighted average of an asset's price over the last `window` seconds
+///
+/// Each record counts for the time until the next one replaced it, so a spike
+/// that lasts a single update moves the average only in proportion to how long
+/// it was the feed's price. Falls back to `latest` when the feed has no history
+/// inside the window.
+pub(crate) fn time_weighted_price(
+    env: &Env,
+    client: &PriceOracleClient,
+    asset: &Asset,
+    latest: &PriceData,
+    window: u64,
+) -> Result<i128, Error> {
+    let now = env.ledger().timestamp();
+    let start = now.saturating_sub(window);
+
+    let Some(history) = client.prices(asset, &MAX_TWAP_RECORDS) else {
+        return Ok(latest.price);
+    };
+
+    // Records come newest first; walk back until the window is covered
+    let mut end = now;
+    let mut weighted: i128 = 0;
+    let mut covered: i128 = 0;
+    for record in history.iter() {
+        let from = record.timestamp.max(start);
+        if record.price > 0 && end > from {
+            let duration = (end - from) as i128;
+            weighted = record
+                .price
+                .checked_mul(duration)
+                .and_then(|value| value.checked_add(weighted))
+                .ok_or(Error::MathOverflow)?;
+            covered += duration;
+        }
+
+        end = end.min(record.timestamp);
+        if record.timestamp <= start {
+            break;
+        }
+    }
+
+    if covered == 0 {
+        return Ok(latest.price);
+    }
+
+    Ok(weighted / covered)
+}
@@ -0,0 +1,270 @@
+//! Append-only on-chain log of admin parameter changes.
+//!
+//! Every change to a risk or fee parameter is recorded with its old and new
+//! value, who made it and when, so integrators can audit the market from
+//! contract storage alone, and published as a `ParamChanged` event. Entries
+//! live in temporary storage and expire after `PARAM_LOG_WINDOW`; their
+//! sequence numbers keep counting regardless.
+
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+use crate::events::ParamChanged;
+use crate::{MarketConfig, DAY_IN_LEDGERS};
+
+/// Ledgers a logged change stays readable for
+pub const PARAM_LOG_WINDOW: u32 = 90 * DAY_IN_LEDGERS;
+
+/// Most changes one `get_param_changes` call returns
+pub const MAX_PARAM_CHANGES_PAGE: u32 = 50;
+
+/// Storage keys for the log
+#[contracttype]
+enum ParamLogKey {
+    Count,       // changes ever recorded, and the sequence number of the next one
+    Change(u32), // sequence number -> change, until it expires
+}
+
+/// One admin parameter change
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParamChange {
+    pub param: Symbol,
+    pub subject: Option<Address>, // collateral token or user the parameter belongs to, if any
+    pub old_value: Option<i128>,  // `None` while unset, e.g. no debt ceiling
+    pub new_value: Option<i128>,
+    pub old_address: Option<Address>, // for parameters naming a contract, e.g. the oracle
+    pub new_address: Option<Address>,
+    pub admin: Address,
+    pub timestamp: u64,
+}
+
+/// Changes recorded so far, expired ones included
+pub(crate) fn param_change_count(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&ParamLogKey::Count)
+        .unwrap_or(0)
+}
+
+/// Append a change to the log and publish it
+fn append(env: &Env, change: ParamChange) {
+    let sequence = param_change_count(env);
+    let key = ParamLogKey::Change(sequence);
+    env.storage().temporary().set(&key, &change);
+    env.storage()
+        .temporary()
+        .extend_ttl(&key, PARAM_LOG_WINDOW, PARAM_LOG_WINDOW);
+    env.storage()
+        .instance()
+        .set(&ParamLogKey::Count, &(sequence + 1));
+
+    ParamChanged {
+        param: change.param,
+        admin: change.admin,
+        sequence,
+        subject: change.subject,
+        old_value: change.old_value,
+        new_value: change.new_value,
+        old_address: change.old_address,
+        new_address: change.new_address,
+    }
+    .publish(env);
+}
+
+/// Log a parameter change by `admin`, unless the value did not change
+pub(crate) fn record_change(
+    env: &Env,
+    admin: &Address,
+    param: &str,
+    subject: Option<Address>,
+    old_value: Option<i128>,
+    new_value: Option<i128>,
+) {
+    if old_value == new_value {
+        return;
+    }
+
+    append(
+        env,
+        ParamChange {
+            param: Symbol::new(env, param),
+            subject,
+            old_value,
+            new_value,
+            old_address: None,
+            new_address: None,
+            admin: admin.clone(),
+            timestamp: env.ledger().timestamp(),
+        },
+    );
+}
+
+/// Log a change by `admin` to a parameter naming a contract, unless it did
+/// not change
+pub(crate) fn record_address_change(
+    env: &Env,
+    admin: &Address,
+    param: &str,
+    old_address: Option<Address>,
+    new_address: Option<Address>,
+) {
+    if old_address == new_address {
+        return;
+    }
+
+    append(
+        env,
+        ParamChange {
+            param: Symbol::new(env, param),
+            subject: None,
+            old_value: None,
+            new_value: None,
+            old_address,
+            new_address,
+            admin: admin.clone(),
+            timestamp: env.ledger().timestamp(),
+        },
+    );
+}
+
+/// Log every market parameter that differs between two configs, as changed by
+/// `admin`
+pub(crate) fn record_config_changes(
+    env: &Env,
+    admin: &Address,
+    old: &MarketConfig,
+    new: &MarketConfig,
+) {
+    let record = |param: &str, old_value: i128, new_value: i128| {
+        record_change(env, admin, param, None, Some(old_value), Some(new_value));
+    };
+
+    record(
+        "interest_rate",
+        old.interest_rate.into(),
+        new.interest_rate.into(),
+    );
+    record("rate_slope", old.rate_slope.into(), new.rate_slope.into());
+    record(
+        "stable_rate_premium",
+        old.stable_rate_premium.into(),
+        new.stable_rate_premium.into(),
+    );
+    record(
+        "liquidation_bonus",
+        old.liquidation_bonus.into(),
+        new.liquidation_bonus.into(),
+    );
+    record(
+        "reserve_factor",
+        old.reserve_factor.into(),
+        new.reserve_factor.into(),
+    );
+    record(
+        "flash_loan_fee",
+        old.flash_loan_fee.into(),
+        new.flash_loan_fee.into(),
+    );
+    record("min_borrow", old.min_borrow, new.min_borrow);
+    record("min_collateral", old.min_collateral, new.min_collateral);
+    record(
+        "grace_period",
+        old.grace_period.into(),
+        new.grace_period.into(),
+    );
+    record(
+        "max_price_age",
+        old.max_price_age.into(),
+        new.max_price_age.into(),
+    );
+    record(
+        "max_price_deviation",
+        old.max_price_deviation.into(),
+        new.max_price_deviation.into(),
+    );
+    record(
+        "twap_window",
+        old.twap_window.into(),
+        new.twap_window.into(),
+    );
+    record(
+        "interest_free_period",
+        old.interest_free_period.into(),
+        new.interest_free_period.into(),
+    );
+    record(
+        "late_penalty_rate",
+        old.late_penalty_rate.into(),
+        new.late_penalty_rate.into(),
+    );
+    record(
+        "close_factor",
+        old.close_factor.into(),
+        new.close_factor.into(),
+    );
+    record(
+        "compounding",
+        (old.compounding as u32).into(),
+        (new.compounding as u32).into(),
+    );
+    record_change(
+        env,
+        admin,
+        "debt_ceiling",
+        None,
+        old.debt_ceiling,
+        new.debt_ceiling,
+    );
+
+    let record_address =
+        |param: &str, old_address: &Option<Address>, new_address: &Option<Address>| {
+            record_address_change(env, admin, param, old_address.clone(), new_address.clone());
+        };
+    record_address("oracle", &old.oracle, &new.oracle);
+    record_address("treasury", &old.treasury, &new.treasury);
+    record_address("btoken", &old.btoken, &new.btoken);
+    record_address("debt_token", &old.debt_token, &new.debt_token);
+    record_address("staking", &old.staking, &new.staking);
+
+    for (token, config) in new.collateral.iter() {
+        let previous = old.collateral.get(token.clone());
+        record_change(
+            env,
+            admin,
+            "ltv_ratio",
+            Some(token.clone()),
+            previous.as_ref().map(|previous| previous.ltv_ratio.into()),
+            Some(config.ltv_ratio.into()),
+        );
+        record_change(
+            env,
+            admin,
+            "liquidation_threshold",
+            Some(token),
+            previous.map(|previous| previous.liquidation_threshold.into()),
+            Some(config.liquidation_threshold.into()),
+        );
+    }
+}
+
+/// Logged changes with sequence numbers from `offset`, up to `limit` of them,
+/// oldest first
+///
+/// Expired changes are left out, so a page can hold fewer than `limit`.
+pub(crate) fn param_changes(env: &Env, offset: u32, limit: u32) -> Vec<ParamChange> {
+    let end = offset
+        .saturating_add(limit.min(MAX_PARAM_CHANGES_PAGE))
+        .min(param_change_count(env));
+
+    let mut changes = Vec::new(env);
+    for sequence in offset..end {
+        if let Some(change) = env
+            .storage()
+            .temporary()
+            .get(&ParamLogKey::Change(sequence))
+        {
+            changes.push_back(change);
+        }
+    }
+    changes
+}
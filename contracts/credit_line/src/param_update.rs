@@ -0,0 +1,227 @@
+//! Admin parameter updates, singly or in batches.
+//!
+//! Each admin setter for a market parameter applies one `ParamUpdate`, and
+//! `multi_set` applies several in one invocation. A batch either lands whole or
+//! not at all, so a market never runs between two transactions with, say, a
+//! raised LTV but the old liquidation threshold.
+
+use soroban_sdk::{contracttype, Address, Env};
+
+use crate::events::LtvUpdated;
+use crate::history::history_length;
+use crate::param_log::record_change;
+use crate::{
+    collateral_config, load_config, store_collateral_config, store_config, total_supply_shares,
+    update_borrow_index, Compounding, DataKey, Error, MAX_HISTORY_LENGTH,
+};
+
+/// One risk parameter to set, as its admin setter would
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParamUpdate {
+    LtvRatio(Address, u32),             // collateral token, basis points
+    LiquidationThreshold(Address, u32), // collateral token, basis points
+    SupplyCap(Address, Option<i128>),   // collateral token, cap across positions
+    BorrowCap(Address, Option<i128>),   // user, cap on their debt
+    DebtCeiling(Option<i128>),          // market-wide debt
+    InterestRate(u32),                  // annual rate at zero utilization, basis points
+    RateSlope(u32),                     // added rate at full utilization, basis points
+    ReserveFactor(u32),                 // share of interest kept, basis points
+    Compounding(Compounding),           // how often interest compounds
+    LiquidationBonus(u32),              // basis points
+    FlashLoanFee(u32),                  // basis points of each flash loan
+    PositionMinimums(i128, i128),       // smallest borrow and collateral value, USDC
+    Oracle(Address),                    // price oracle valuing collateral
+    Treasury(Address),                  // where protocol reserves are paid
+    Staking(Option<Address>),           // BENJI staking contract whose boosts raise LTV
+    BToken(Address),                    // supplier share token, before any supply
+    DebtToken(Address),                 // debt mirror token, while nothing is borrowed
+    AllowlistEnabled(bool),             // restrict deposits and borrowing to the allowlist
+    Allowlisted(Address, bool),         // user, on the allowlist or not
+    HistoryLength(u32),                 // snapshots kept per account
+}
+
+/// Apply one update, after the caller has checked the admin
+pub(crate) fn apply_update(env: &Env, admin: &Address, update: ParamUpdate) -> Result<(), Error> {
+    match update {
+        ParamUpdate::LtvRatio(token, new_ratio) => {
+            let mut config = collateral_config(env, &token)?;
+            let old_ratio = config.ltv_ratio;
+            config.ltv_ratio = new_ratio;
+            store_collateral_config(env, admin, &token, &config)?;
+
+            LtvUpdated {
+                token,
+                old_ratio,
+                new_ratio,
+            }
+            .publish(env);
+        }
+        ParamUpdate::LiquidationThreshold(token, threshold) => {
+            let mut config = collateral_config(env, &token)?;
+            config.liquidation_threshold = threshold;
+            store_collateral_config(env, admin, &token, &config)?;
+        }
+        ParamUpdate::SupplyCap(token, cap) => {
+            let key = DataKey::SupplyCap(token.clone());
+            let old_cap: Option<i128> = env.storage().instance().get(&key);
+            match cap {
+                Some(cap) if cap < 0 => return Err(Error::InvalidParameter),
+                Some(cap) => env.storage().instance().set(&key, &cap),
+                None => env.storage().instance().remove(&key),
+            }
+
+            record_change(env, admin, "supply_cap", Some(token), old_cap, cap);
+        }
+        ParamUpdate::BorrowCap(user, cap) => {
+            let key = DataKey::BorrowCap(user.clone());
+            let old_cap: Option<i128> = env.storage().persistent().get(&key);
+            match cap {
+                Some(cap) if cap < 0 => return Err(Error::InvalidParameter),
+                Some(cap) => env.storage().persistent().set(&key, &cap),
+                None => env.storage().persistent().remove(&key),
+            }
+
+            record_change(env, admin, "borrow_cap", Some(user), old_cap, cap);
+        }
+        ParamUpdate::DebtCeiling(ceiling) => {
+            let mut config = load_config(env)?;
+            config.debt_ceiling = ceiling;
+            store_config(env, admin, &config)?;
+        }
+        // Settle interest at the old rates before they change
+        ParamUpdate::InterestRate(rate_bps) => {
+            let mut config = load_config(env)?;
+            update_borrow_index(env, &config)?;
+            config.interest_rate = rate_bps;
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::RateSlope(slope_bps) => {
+            let mut config = load_config(env)?;
+            update_borrow_index(env, &config)?;
+            config.rate_slope = slope_bps;
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::ReserveFactor(factor_bps) => {
+            let mut config = load_config(env)?;
+            update_borrow_index(env, &config)?;
+            config.reserve_factor = factor_bps;
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::Compounding(compounding) => {
+            let mut config = load_config(env)?;
+            update_borrow_index(env, &config)?;
+            config.compounding = compounding;
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::LiquidationBonus(bonus_bps) => {
+            let mut config = load_config(env)?;
+            config.liquidation_bonus = bonus_bps;
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::FlashLoanFee(fee_bps) => {
+            let mut config = load_config(env)?;
+            config.flash_loan_fee = fee_bps;
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::PositionMinimums(min_borrow, min_collateral) => {
+            let mut config = load_config(env)?;
+            config.min_borrow = min_borrow;
+            config.min_collateral = min_collateral;
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::Oracle(oracle) => {
+            let mut config = load_config(env)?;
+            config.oracle = Some(oracle);
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::Treasury(treasury) => {
+            let mut config = load_config(env)?;
+            config.treasury = Some(treasury);
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::Staking(staking) => {
+            let mut config = load_config(env)?;
+            config.staking = staking;
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::BToken(btoken) => {
+            let mut config = load_config(env)?;
+            if total_supply_shares(env, &config) != 0 {
+                return Err(Error::InvalidParameter);
+            }
+            config.btoken = Some(btoken);
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::DebtToken(debt_token) => {
+            let total_borrowed: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::TotalBorrowed)
+                .unwrap_or(0);
+            if total_borrowed != 0 {
+                return Err(Error::InvalidParameter);
+            }
+
+            let mut config = load_config(env)?;
+            config.debt_token = Some(debt_token);
+            store_config(env, admin, &config)?;
+        }
+        ParamUpdate::AllowlistEnabled(enabled) => {
+            let old: bool = env
+                .storage()
+                .instance()
+                .get(&DataKey::AllowlistEnabled)
+                .unwrap_or(false);
+            env.storage()
+                .instance()
+                .set(&DataKey::AllowlistEnabled, &enabled);
+
+            record_change(
+                env,
+                admin,
+                "allowlist_enabled",
+                None,
+                Some(old.into()),
+                Some(enabled.into()),
+            );
+        }
+        ParamUpdate::Allowlisted(user, allowed) => {
+            let key = DataKey::Allowlisted(user.clone());
+            let old = env.storage().persistent().has(&key);
+            if allowed {
+                env.storage().persistent().set(&key, &true);
+            } else {
+                env.storage().persistent().remove(&key);
+            }
+
+            record_change(
+                env,
+                admin,
+                "allowlisted",
+                Some(user),
+                Some(old.into()),
+                Some(allowed.into()),
+            );
+        }
+        ParamUpdate::HistoryLength(length) => {
+            if length > MAX_HISTORY_LENGTH {
+                return Err(Error::InvalidParameter);
+            }
+
+            record_change(
+                env,
+                admin,
+                "history_length",
+                None,
+                Some(history_length(env).into()),
+                Some(length.into()),
+            );
+            env.storage()
+                .instance()
+                .set(&DataKey::HistoryLength, &length);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! Dry runs of position changes, for frontends to warn before submitting.
+//!
+//! Each preview puts a copy of the accrued position through the same checks as
+//! the call it mirrors, without writing state or moving tokens, and reports the
+//! error the call would fail with rather than failing itself.
+
+use soroban_sdk::{contracttype, token, Address, Env};
+
+use crate::fixed_loan::LoanTerms;
+use crate::{
+    add_debt, health_factor, is_emergency_mode, load_config, reduce_debt, remove_collateral,
+    require_allowlisted, require_not_paused, DataKey, Error, UserPosition,
+};
+
+/// Outcome of a previewed call
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Preview {
+    pub position: UserPosition, // after the call, or as it stands if the call would fail
+    pub health_factor: i128,    // of `position`, scaled by `HEALTH_FACTOR_ONE`
+    pub error: u32,             // `Error` code the call would fail with, 0 if it would succeed
+}
+
+/// Report a previewed call's outcome against the position it started from
+///
+/// A non-positive amount, which the real call rejects by panicking, is
+/// reported as `InvalidParameter`.
+pub(crate) fn preview(
+    env: &Env,
+    current: UserPosition,
+    outcome: Result<UserPosition, Error>,
+) -> Result<Preview, Error> {
+    let (position, error) = match outcome {
+        Ok(position) => (position, None),
+        Err(error) => (current, Some(error)),
+    };
+
+    // A position that cannot be priced reports why rather than failing the view
+    let (health_factor, error) =
+        match load_config(env).and_then(|config| health_factor(env, &config, &position)) {
+            Ok(health_factor) => (health_factor, error),
+            Err(price_error) => (0, error.or(Some(price_error))),
+        };
+
+    Ok(Preview {
+        position,
+        health_factor,
+        error: error.map_or(0, |error| error as u32),
+    })
+}
+
+pub(crate) fn preview_borrow(
+    env: &Env,
+    user: &Address,
+    account_id: u32,
+    mut position: UserPosition,
+    amount: i128,
+) -> Result<UserPosition, Error> {
+    require_not_paused(env)?;
+    require_allowlisted(env, user)?;
+
+    if is_emergency_mode(env) {
+        return Err(Error::EmergencyMode);
+    }
+
+    let config = load_config(env)?;
+    if amount <= 0 {
+        return Err(Error::InvalidParameter);
+    }
+    if amount < config.min_borrow {
+        return Err(Error::BelowMinimum);
+    }
+
+    if !env
+        .storage()
+        .persistent()
+        .has(&DataKey::UserPosition(user.clone(), account_id))
+    {
+        return Err(Error::InsufficientCollateral);
+    }
+    if position.terms != LoanTerms::Open {
+        return Err(Error::FixedLoanActive);
+    }
+
+    let mode = position.rate_mode;
+    add_debt(env, &config, user, account_id, &mut position, amount, mode)?;
+
+    // The pool must hold enough idle USDC to pay out the loan
+    let cash = token::Client::new(env, &config.usdc_token).balance(&env.current_contract_address());
+    if cash < amount {
+        return Err(Error::InsufficientLiquidity);
+    }
+
+    Ok(position)
+}
+
+pub(crate) fn preview_withdraw(
+    env: &Env,
+    user: &Address,
+    account_id: u32,
+    mut position: UserPosition,
+    token: &Address,
+    amount: i128,
+) -> Result<UserPosition, Error> {
+    require_not_paused(env)?;
+
+    if amount <= 0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    if !env
+        .storage()
+        .persistent()
+        .has(&DataKey::UserPosition(user.clone(), account_id))
+    {
+        return Err(Error::NotInitialized);
+    }
+
+    remove_collateral(env, &load_config(env)?, user, &mut position, token, amount)?;
+    Ok(position)
+}
+
+pub(crate) fn preview_repay(
+    env: &Env,
+    mut position: UserPosition,
+    amount: i128,
+) -> Result<UserPosition, Error> {
+    if env
+        .storage()
+        .instance()
+        .get(&DataKey::RepayPaused)
+        .unwrap_or(false)
+    {
+        return Err(Error::ContractPaused);
+    }
+
+    let amount = amount.min(position.borrowed);
+    if amount <= 0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    reduce_debt(env, &mut position, amount);
+    Ok(position)
+}
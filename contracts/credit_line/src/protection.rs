@@ -0,0 +1,20 @@
+use soroban_sdk::{contractclient, contracttype, Address, Env};
+
+/// Standing instruction to repay part of a position before it can be liquidated
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Protection {
+    pub protector: Address,
+    pub trigger_health_factor: i128, // `protect` runs below this, scaled by `HEALTH_FACTOR_ONE`
+    pub max_repay: i128,             // most USDC one `protect` call draws from the protector
+}
+
+/// Callback implemented by contracts that guard positions from liquidation
+///
+/// `on_protect` is invoked by `protect` and must send `amount` of `token` to
+/// the credit line before it returns; the credit line then repays that much
+/// of the position's debt.
+#[contractclient(name = "ProtectorClient")]
+pub trait Protector {
+    fn on_protect(env: Env, user: Address, account_id: u32, token: Address, amount: i128);
+}
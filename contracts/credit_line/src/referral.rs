@@ -0,0 +1,169 @@
+//! Referral fee sharing for distribution partners.
+//!
+//! A user may name a referrer with their first deposit. From then on a share
+//! of the reserve cut of the interest the user pays is set aside for the
+//! referrer, who claims it in USDC.
+
+use soroban_sdk::{contracttype, Address, Env};
+
+use crate::events::Referred;
+use crate::math::{bps_mul, Rounding};
+use crate::user_index::accounts;
+use crate::{DataKey, Error, MarketConfig, POSITION_BUMP_AMOUNT, POSITION_LIFETIME_THRESHOLD};
+
+/// Storage keys for referrals
+#[contracttype]
+enum ReferralKey {
+    Share,             // 2000 = referrers get 20% of the reserve cut of their users' interest
+    Referrer(Address), // user -> referrer
+    Fees(Address),     // referrer -> USDC accrued and not yet claimed
+    Unclaimed,         // USDC accrued and not yet claimed across all referrers
+}
+
+/// Share of the reserve cut of a referred user's interest paid to the referrer,
+/// in basis points
+pub(crate) fn referral_share(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&ReferralKey::Share)
+        .unwrap_or(0)
+}
+
+pub(crate) fn set_referral_share(env: &Env, share_bps: u32) {
+    env.storage()
+        .instance()
+        .set(&ReferralKey::Share, &share_bps);
+}
+
+pub(crate) fn referrer(env: &Env, user: &Address) -> Option<Address> {
+    let key = ReferralKey::Referrer(user.clone());
+    let referrer = env.storage().persistent().get(&key);
+
+    if referrer.is_some() {
+        env.storage().persistent().extend_ttl(
+            &key,
+            POSITION_LIFETIME_THRESHOLD,
+            POSITION_BUMP_AMOUNT,
+        );
+    }
+
+    referrer
+}
+
+fn set_referrer(env: &Env, user: &Address, referrer: &Address) {
+    let key = ReferralKey::Referrer(user.clone());
+    env.storage().persistent().set(&key, referrer);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POSITION_LIFETIME_THRESHOLD, POSITION_BUMP_AMOUNT);
+}
+
+/// Record the referrer a user names with their first deposit
+///
+/// Ignored once the user has an account, so a referrer can never be replaced,
+/// and when `payer` deposits for someone else, so nobody names one for them.
+pub(crate) fn name_referrer(
+    env: &Env,
+    payer: &Address,
+    user: &Address,
+    referrer: Option<Address>,
+) -> Result<(), Error> {
+    let Some(referrer) = referrer.filter(|_| payer == user && accounts(env, user).is_empty())
+    else {
+        return Ok(());
+    };
+    if referrer == *user {
+        return Err(Error::InvalidParameter);
+    }
+
+    set_referrer(env, user, &referrer);
+    Referred {
+        user: user.clone(),
+        referrer,
+    }
+    .publish(env);
+
+    Ok(())
+}
+
+/// USDC accrued to a referrer and not yet claimed
+pub(crate) fn referral_fees(env: &Env, referrer: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&ReferralKey::Fees(referrer.clone()))
+        .unwrap_or(0)
+}
+
+/// USDC accrued and not yet claimed across all referrers
+pub(crate) fn unclaimed_referral_fees(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&ReferralKey::Unclaimed)
+        .unwrap_or(0)
+}
+
+fn update_unclaimed_referral_fees(env: &Env, delta: i128) {
+    env.storage().instance().set(
+        &ReferralKey::Unclaimed,
+        &(unclaimed_referral_fees(env) + delta).max(0),
+    );
+}
+
+fn set_referral_fees(env: &Env, referrer: &Address, amount: i128) {
+    let key = ReferralKey::Fees(referrer.clone());
+    env.storage().persistent().set(&key, &amount);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POSITION_LIFETIME_THRESHOLD, POSITION_BUMP_AMOUNT);
+}
+
+/// Move the referrer's share of the reserve cut of `interest` paid by `user`
+/// out of reserves and into the referrer's fees
+pub(crate) fn credit_referrer(
+    env: &Env,
+    config: &MarketConfig,
+    user: &Address,
+    interest: i128,
+) -> Result<(), Error> {
+    let share = referral_share(env);
+    if interest <= 0 || share == 0 {
+        return Ok(());
+    }
+    let Some(referrer) = referrer(env, user) else {
+        return Ok(());
+    };
+
+    let reserve_share =
+        bps_mul(interest, config.reserve_factor, Rounding::Down).ok_or(Error::MathOverflow)?;
+    let reserves: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalReserves)
+        .unwrap_or(0);
+    let fee = bps_mul(reserve_share, share, Rounding::Down)
+        .ok_or(Error::MathOverflow)?
+        .min(reserves);
+    if fee <= 0 {
+        return Ok(());
+    }
+
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalReserves, &(reserves - fee));
+    set_referral_fees(env, &referrer, referral_fees(env, &referrer) + fee);
+    update_unclaimed_referral_fees(env, fee);
+
+    Ok(())
+}
+
+/// Clear a referrer's accrued fees, returning the amount to pay them
+pub(crate) fn take_referral_fees(env: &Env, referrer: &Address) -> i128 {
+    let fees = referral_fees(env, referrer);
+    if fees > 0 {
+        env.storage()
+            .persistent()
+            .remove(&ReferralKey::Fees(referrer.clone()));
+        update_unclaimed_referral_fees(env, -fees);
+    }
+    fees
+}
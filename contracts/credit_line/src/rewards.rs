@@ -0,0 +1,218 @@
+//! Incentive emissions for suppliers and borrowers.
+//!
+//! Each pool streams `emission_rate` reward tokens per second, split between
+//! participants in proportion to their balance: pool shares for suppliers and
+//! outstanding debt for borrowers. An accumulator index per pool records the
+//! reward earned per unit of balance, and each participant's checkpoint
+//! records the index they were last settled at.
+//!
+//! Supplier balances are set to the pool shares a holder has after each supply
+//! and withdrawal, and the bToken reports its holders' balances after every
+//! transfer and burn, so rewards follow the shares wherever they move.
+
+use soroban_sdk::{contracttype, token, Address, Env};
+
+use crate::math::{mul_div, Rounding, WAD};
+use crate::{DataKey, Error};
+
+/// Most reward tokens, in base units, a pool may stream each second
+pub const MAX_EMISSION_RATE: i128 = 1_000_000_000_000_000;
+
+/// Balances that earn rewards
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum RewardPool {
+    Supply = 0,
+    Borrow = 1,
+}
+
+/// Emission and accumulated reward per unit of balance for one pool
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RewardState {
+    pub emission_rate: i128, // reward tokens per second across the pool
+    pub index: i128,         // WAD-scaled reward per unit of balance
+    pub total: i128,         // sum of participant balances
+    pub last_update: u64,
+}
+
+/// A participant's balance in one pool and the rewards settled on it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RewardCheckpoint {
+    pub index: i128,
+    pub balance: i128,
+    pub accrued: i128,
+}
+
+/// A pool's state brought up to the current ledger, without writing it
+fn current_state(env: &Env, pool: RewardPool) -> Result<RewardState, Error> {
+    let now = env.ledger().timestamp();
+    let mut state = env
+        .storage()
+        .instance()
+        .get(&DataKey::RewardState(pool))
+        .unwrap_or(RewardState {
+            emission_rate: 0,
+            index: 0,
+            total: 0,
+            last_update: now,
+        });
+
+    let elapsed = now.saturating_sub(state.last_update);
+    if elapsed > 0 && state.total > 0 && state.emission_rate > 0 {
+        let emitted = state
+            .emission_rate
+            .checked_mul(elapsed.into())
+            .ok_or(Error::MathOverflow)?;
+        let growth =
+            mul_div(emitted, WAD, state.total, Rounding::Down).ok_or(Error::MathOverflow)?;
+        state.index = state.index.checked_add(growth).ok_or(Error::MathOverflow)?;
+    }
+
+    state.last_update = now;
+    Ok(state)
+}
+
+/// A participant's checkpoint with rewards settled up to `state`, without writing it
+fn current_checkpoint(
+    env: &Env,
+    pool: RewardPool,
+    user: &Address,
+    state: &RewardState,
+) -> Result<RewardCheckpoint, Error> {
+    let mut checkpoint = env
+        .storage()
+        .persistent()
+        .get(&DataKey::RewardCheckpoint(pool, user.clone()))
+        .unwrap_or(RewardCheckpoint {
+            index: state.index,
+            balance: 0,
+            accrued: 0,
+        });
+
+    let earned = mul_div(
+        checkpoint.balance,
+        state.index - checkpoint.index,
+        WAD,
+        Rounding::Down,
+    )
+    .ok_or(Error::MathOverflow)?;
+    checkpoint.accrued = checkpoint
+        .accrued
+        .checked_add(earned)
+        .ok_or(Error::MathOverflow)?;
+    checkpoint.index = state.index;
+
+    Ok(checkpoint)
+}
+
+fn save_checkpoint(env: &Env, pool: RewardPool, user: &Address, checkpoint: &RewardCheckpoint) {
+    let key = DataKey::RewardCheckpoint(pool, user.clone());
+    env.storage().persistent().set(&key, checkpoint);
+    env.storage().persistent().extend_ttl(
+        &key,
+        crate::POSITION_LIFETIME_THRESHOLD,
+        crate::POSITION_BUMP_AMOUNT,
+    );
+}
+
+/// Settle a participant's rewards, then adjust their balance in the pool by `delta`
+pub(crate) fn update_reward_balance(
+    env: &Env,
+    pool: RewardPool,
+    user: &Address,
+    delta: i128,
+) -> Result<(), Error> {
+    rebalance(env, pool, user, |balance| balance + delta)
+}
+
+/// Settle a participant's rewards, then set their balance in the pool
+pub(crate) fn set_reward_balance(
+    env: &Env,
+    pool: RewardPool,
+    user: &Address,
+    balance: i128,
+) -> Result<(), Error> {
+    rebalance(env, pool, user, |_| balance)
+}
+
+fn rebalance(
+    env: &Env,
+    pool: RewardPool,
+    user: &Address,
+    new_balance: impl FnOnce(i128) -> i128,
+) -> Result<(), Error> {
+    let mut state = current_state(env, pool)?;
+    let mut checkpoint = current_checkpoint(env, pool, user, &state)?;
+
+    let balance = new_balance(checkpoint.balance).max(0);
+    state.total = (state.total + balance - checkpoint.balance).max(0);
+    checkpoint.balance = balance;
+
+    save_checkpoint(env, pool, user, &checkpoint);
+    env.storage()
+        .instance()
+        .set(&DataKey::RewardState(pool), &state);
+
+    Ok(())
+}
+
+/// Change a pool's emission rate, settling emissions at the old rate first,
+/// and return the old rate
+pub(crate) fn set_emission_rate(env: &Env, pool: RewardPool, rate: i128) -> Result<i128, Error> {
+    let mut state = current_state(env, pool)?;
+    let old_rate = state.emission_rate;
+    state.emission_rate = rate;
+    env.storage()
+        .instance()
+        .set(&DataKey::RewardState(pool), &state);
+
+    Ok(old_rate)
+}
+
+/// Rewards a participant has earned across both pools and not yet claimed
+pub(crate) fn claimable_rewards(env: &Env, user: &Address) -> Result<i128, Error> {
+    let mut total: i128 = 0;
+    for pool in [RewardPool::Supply, RewardPool::Borrow] {
+        let state = current_state(env, pool)?;
+        let checkpoint = current_checkpoint(env, pool, user, &state)?;
+        total = total
+            .checked_add(checkpoint.accrued)
+            .ok_or(Error::MathOverflow)?;
+    }
+    Ok(total)
+}
+
+/// Pay out a participant's rewards across both pools, returning the amount sent
+pub(crate) fn claim_rewards(
+    env: &Env,
+    reward_token: &Address,
+    user: &Address,
+) -> Result<i128, Error> {
+    let mut amount: i128 = 0;
+    for pool in [RewardPool::Supply, RewardPool::Borrow] {
+        let state = current_state(env, pool)?;
+        let mut checkpoint = current_checkpoint(env, pool, user, &state)?;
+        amount = amount
+            .checked_add(checkpoint.accrued)
+            .ok_or(Error::MathOverflow)?;
+        checkpoint.accrued = 0;
+
+        save_checkpoint(env, pool, user, &checkpoint);
+        env.storage()
+            .instance()
+            .set(&DataKey::RewardState(pool), &state);
+    }
+
+    if amount > 0 {
+        token::Client::new(env, reward_token).transfer(
+            &env.current_contract_address(),
+            user,
+            &amount,
+        );
+    }
+
+    Ok(amount)
+}
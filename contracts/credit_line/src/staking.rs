@@ -0,0 +1,17 @@
+use soroban_sdk::{contractclient, Address, Env};
+
+use crate::MarketConfig;
+
+/// BENJI lock-up contract whose boost raises a borrower's LTV
+#[contractclient(name = "StakingClient")]
+pub trait Staking {
+    fn get_boost(env: Env, user: Address) -> u32;
+}
+
+/// LTV boost a user has earned by locking BENJI, in basis points
+pub(crate) fn ltv_boost(env: &Env, config: &MarketConfig, user: &Address) -> u32 {
+    match &config.staking {
+        Some(staking) => StakingClient::new(env, staking).get_boost(user),
+        None => 0,
+    }
+}
@@ -0,0 +1,103 @@
+use soroban_sdk::{Address, Env, Vec};
+
+use crate::{DataKey, Error, POSITION_BUMP_AMOUNT, POSITION_LIFETIME_THRESHOLD};
+
+/// Users stored per `DataKey::UserList` chunk
+const CHUNK_SIZE: u32 = 64;
+
+/// Most sub-accounts one user may open
+pub(crate) const MAX_ACCOUNTS: u32 = 16;
+
+/// Number of users that have ever opened a position
+pub(crate) fn user_count(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::UserCount)
+        .unwrap_or(0)
+}
+
+fn load_chunk(env: &Env, chunk: u32) -> Vec<Address> {
+    let key = DataKey::UserList(chunk);
+    let users = env.storage().persistent().get(&key);
+
+    if users.is_some() {
+        env.storage().persistent().extend_ttl(
+            &key,
+            POSITION_LIFETIME_THRESHOLD,
+            POSITION_BUMP_AMOUNT,
+        );
+    }
+
+    users.unwrap_or(Vec::new(env))
+}
+
+/// Append a user opening their first position to the index
+fn add_user(env: &Env, user: &Address) {
+    let count = user_count(env);
+    let chunk = count / CHUNK_SIZE;
+
+    let mut users = load_chunk(env, chunk);
+    users.push_back(user.clone());
+
+    let key = DataKey::UserList(chunk);
+    env.storage().persistent().set(&key, &users);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POSITION_LIFETIME_THRESHOLD, POSITION_BUMP_AMOUNT);
+    env.storage()
+        .instance()
+        .set(&DataKey::UserCount, &(count + 1));
+}
+
+/// Up to `limit` indexed users starting at position `offset`
+pub(crate) fn users(env: &Env, offset: u32, limit: u32) -> Vec<Address> {
+    let end = offset.saturating_add(limit).min(user_count(env));
+    let mut page = Vec::new(env);
+
+    let mut index = offset;
+    while index < end {
+        let chunk = load_chunk(env, index / CHUNK_SIZE);
+        let start = index % CHUNK_SIZE;
+        let stop = (end - index + start).min(chunk.len());
+        if stop <= start {
+            break;
+        }
+
+        page.append(&chunk.slice(start..stop));
+        index += stop - start;
+    }
+
+    page
+}
+
+/// Sub-account ids a user has opened, in the order they were opened
+pub(crate) fn accounts(env: &Env, user: &Address) -> Vec<u32> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Accounts(user.clone()))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Record a user opening a sub-account, indexing the user on their first one
+pub(crate) fn add_account(env: &Env, user: &Address, account_id: u32) -> Result<(), Error> {
+    let mut ids = accounts(env, user);
+    if ids.contains(account_id) {
+        return Ok(());
+    }
+    if ids.len() >= MAX_ACCOUNTS {
+        return Err(Error::TooManyAccounts);
+    }
+
+    if ids.is_empty() {
+        add_user(env, user);
+    }
+    ids.push_back(account_id);
+
+    let key = DataKey::Accounts(user.clone());
+    env.storage().persistent().set(&key, &ids);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POSITION_LIFETIME_THRESHOLD, POSITION_BUMP_AMOUNT);
+
+    Ok(())
+}
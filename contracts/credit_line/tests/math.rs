@@ -0,0 +1,115 @@
+use credit_line::math::{
+    bps_mul, mul_div, ray_div, ray_mul, wad_div, wad_mul, Rounding, BPS, RAY, WAD,
+};
+
+#[test]
+fn mul_div_exact() {
+    assert_eq!(mul_div(6, 7, 3, Rounding::Down), Some(14));
+    assert_eq!(mul_div(6, 7, 3, Rounding::Up), Some(14));
+    assert_eq!(mul_div(0, 7, 3, Rounding::Up), Some(0));
+}
+
+#[test]
+fn mul_div_rounding() {
+    assert_eq!(mul_div(10, 1, 3, Rounding::Down), Some(3));
+    assert_eq!(mul_div(10, 1, 3, Rounding::Up), Some(4));
+    assert_eq!(mul_div(1, 1, 2, Rounding::Down), Some(0));
+    assert_eq!(mul_div(1, 1, 2, Rounding::Up), Some(1));
+}
+
+#[test]
+fn mul_div_negative_rounds_magnitude() {
+    assert_eq!(mul_div(-10, 1, 3, Rounding::Down), Some(-3));
+    assert_eq!(mul_div(-10, 1, 3, Rounding::Up), Some(-4));
+    assert_eq!(mul_div(10, -1, 3, Rounding::Up), Some(-4));
+    assert_eq!(mul_div(10, 1, -3, Rounding::Down), Some(-3));
+    assert_eq!(mul_div(-10, -1, 3, Rounding::Down), Some(3));
+}
+
+#[test]
+fn mul_div_zero_denominator() {
+    assert_eq!(mul_div(1, 1, 0, Rounding::Down), None);
+}
+
+#[test]
+fn mul_div_wide_intermediate() {
+    // a * b overflows i128 but the quotient fits
+    let a = i128::MAX / 2;
+    assert_eq!(mul_div(a, 4, 4, Rounding::Down), Some(a));
+    assert_eq!(mul_div(a, WAD, WAD, Rounding::Up), Some(a));
+    assert_eq!(
+        mul_div(i128::MAX, i128::MAX, i128::MAX, Rounding::Down),
+        Some(i128::MAX)
+    );
+    assert_eq!(mul_div(i128::MIN, 3, 3, Rounding::Down), Some(i128::MIN));
+}
+
+#[test]
+fn mul_div_wide_matches_reference() {
+    // (2^100 + 7) * (2^90 + 3) / (2^95 + 1), computed with arbitrary precision
+    let a = (1_i128 << 100) + 7;
+    let b = (1_i128 << 90) + 3;
+    let d = (1_i128 << 95) + 1;
+
+    assert_eq!(
+        mul_div(a, b, d, Rounding::Down),
+        Some(39_614_081_257_132_168_796_771_975_263)
+    );
+    assert_eq!(
+        mul_div(a, b, d, Rounding::Up),
+        Some(39_614_081_257_132_168_796_771_975_264)
+    );
+}
+
+#[test]
+fn mul_div_result_overflow() {
+    assert_eq!(mul_div(i128::MAX, 2, 1, Rounding::Down), None);
+    assert_eq!(mul_div(i128::MIN, -1, 1, Rounding::Down), None);
+    assert_eq!(mul_div(i128::MAX, i128::MAX, 1, Rounding::Down), None);
+    assert_eq!(mul_div(i128::MAX, 1, 1, Rounding::Up), Some(i128::MAX));
+}
+
+#[test]
+fn wad_operations() {
+    let half = WAD / 2;
+    assert_eq!(wad_mul(3 * WAD, half, Rounding::Down), Some(3 * WAD / 2));
+    assert_eq!(wad_div(3 * WAD, half, Rounding::Down), Some(6 * WAD));
+    assert_eq!(wad_mul(1, 1, Rounding::Down), Some(0));
+    assert_eq!(wad_mul(1, 1, Rounding::Up), Some(1));
+    assert_eq!(wad_div(1, 3 * WAD, Rounding::Down), Some(0));
+    assert_eq!(wad_div(1, 3 * WAD, Rounding::Up), Some(1));
+    assert_eq!(wad_div(WAD, 0, Rounding::Down), None);
+}
+
+#[test]
+fn ray_operations() {
+    assert_eq!(ray_mul(2 * RAY, 3 * RAY, Rounding::Down), Some(6 * RAY));
+    assert_eq!(ray_div(RAY, 4 * RAY, Rounding::Down), Some(RAY / 4));
+    assert_eq!(ray_div(RAY, 3 * RAY, Rounding::Down), Some(RAY / 3));
+    assert_eq!(ray_div(RAY, 3 * RAY, Rounding::Up), Some(RAY / 3 + 1));
+    // Large collateral amounts do not overflow through a RAY multiplier
+    let amount = 1_000_000_000_000 * 10_000_000_i128;
+    assert_eq!(ray_mul(amount, RAY, Rounding::Down), Some(amount));
+}
+
+#[test]
+fn bps_operations() {
+    assert_eq!(bps_mul(1_000, 7_000, Rounding::Down), Some(700));
+    assert_eq!(bps_mul(1, 7_000, Rounding::Down), Some(0));
+    assert_eq!(bps_mul(1, 7_000, Rounding::Up), Some(1));
+    assert_eq!(bps_mul(12_345, BPS as u32, Rounding::Down), Some(12_345));
+    assert_eq!(bps_mul(i128::MAX, 10_500, Rounding::Down), None);
+}
+
+#[test]
+fn rounding_up_never_below_down() {
+    for a in [1_i128, 7, 999, 1_000_003, i64::MAX as i128] {
+        for b in [1_i128, 3, 10_000, WAD] {
+            for d in [1_i128, 2, 7, 10_000, RAY] {
+                let down = mul_div(a, b, d, Rounding::Down).unwrap();
+                let up = mul_div(a, b, d, Rounding::Up).unwrap();
+                assert!(up == down || up == down + 1);
+            }
+        }
+    }
+}
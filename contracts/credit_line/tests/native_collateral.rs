@@ -0,0 +1,168 @@
+use std::rc::Rc;
+
+use credit_line::{CollateralConfig, CreditLineContract, CreditLineContractClient};
+use soroban_sdk::{
+    testutils::Address as _,
+    token::{StellarAssetClient, TokenClient},
+    xdr, Address, Bytes, Env, TryFromVal,
+};
+
+/// One XLM in stroops
+const XLM: i128 = 10_000_000;
+
+/// A classic account holding `balance` stroops of native XLM
+fn funded_account(env: &Env, seed: u8, balance: i64) -> Address {
+    let account_id = xdr::AccountId(xdr::PublicKey::PublicKeyTypeEd25519(xdr::Uint256(
+        [seed; 32],
+    )));
+    let key = Rc::new(xdr::LedgerKey::Account(xdr::LedgerKeyAccount {
+        account_id: account_id.clone(),
+    }));
+    let entry = Rc::new(xdr::LedgerEntry {
+        data: xdr::LedgerEntryData::Account(xdr::AccountEntry {
+            account_id: account_id.clone(),
+            balance,
+            flags: 0,
+            home_domain: Default::default(),
+            inflation_dest: None,
+            num_sub_entries: 0,
+            seq_num: xdr::SequenceNumber(0),
+            thresholds: xdr::Thresholds([1; 4]),
+            signers: xdr::VecM::default(),
+            ext: xdr::AccountEntryExt::V0,
+        }),
+        last_modified_ledger_seq: 0,
+        ext: xdr::LedgerEntryExt::V0,
+    });
+    env.host().add_ledger_entry(&key, &entry, None).unwrap();
+
+    Address::try_from_val(env, &xdr::ScAddress::Account(account_id)).unwrap()
+}
+
+struct Market<'a> {
+    env: Env,
+    credit_line: CreditLineContractClient<'a>,
+    xlm: TokenClient<'a>,
+    usdc: TokenClient<'a>,
+}
+
+/// A credit line accepting native XLM at 50% LTV, with USDC liquidity supplied
+fn setup<'a>() -> Market<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let benji = env.register_stellar_asset_contract_v2(admin.clone());
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+
+    let credit_line = CreditLineContractClient::new(&env, &env.register(CreditLineContract, ()));
+    credit_line.initialize(&admin, &benji.address(), &usdc.address());
+
+    let xlm = env
+        .deployer()
+        .with_stellar_asset(Bytes::from_array(&env, &[0, 0, 0, 0]))
+        .deploy();
+    assert_eq!(credit_line.get_native_token(), xlm);
+    credit_line.set_collateral_config(
+        &admin,
+        &xlm,
+        &CollateralConfig {
+            ltv_ratio: 5000,
+            liquidation_threshold: 7000,
+        },
+    );
+
+    let lender = Address::generate(&env);
+    StellarAssetClient::new(&env, &usdc.address()).mint(&lender, &(1_000 * XLM));
+    credit_line.supply(&lender, &(1_000 * XLM));
+
+    Market {
+        xlm: TokenClient::new(&env, &xlm),
+        usdc: TokenClient::new(&env, &usdc.address()),
+        env,
+        credit_line,
+    }
+}
+
+#[test]
+fn native_token_is_accepted_collateral() {
+    let market = setup();
+
+    assert_eq!(market.xlm.decimals(), 7);
+    assert!(market
+        .credit_line
+        .get_collateral_tokens()
+        .contains(market.xlm.address.clone()));
+}
+
+#[test]
+fn deposit_and_withdraw_native() {
+    let market = setup();
+    let user = funded_account(&market.env, 1, 500 * XLM as i64);
+
+    market.credit_line.deposit_collateral(
+        &user,
+        &0,
+        &market.xlm.address,
+        &(200 * XLM),
+        &None,
+        &None,
+    );
+    assert_eq!(market.xlm.balance(&user), 300 * XLM);
+    assert_eq!(market.xlm.balance(&market.credit_line.address), 200 * XLM);
+    assert_eq!(
+        market
+            .credit_line
+            .get_position(&user, &0)
+            .collateral
+            .get(market.xlm.address.clone()),
+        Some(200 * XLM)
+    );
+
+    market
+        .credit_line
+        .withdraw_collateral(&user, &0, &market.xlm.address, &(200 * XLM), &None);
+    assert_eq!(market.xlm.balance(&user), 500 * XLM);
+    assert_eq!(market.xlm.balance(&market.credit_line.address), 0);
+}
+
+#[test]
+fn borrow_against_native() {
+    let market = setup();
+    let user = funded_account(&market.env, 2, 500 * XLM as i64);
+
+    market.credit_line.deposit_collateral(
+        &user,
+        &0,
+        &market.xlm.address,
+        &(200 * XLM),
+        &None,
+        &None,
+    );
+
+    // Both tokens have 7 decimals, so with no oracle 200 XLM backs 100 USDC
+    assert_eq!(
+        market.credit_line.get_available_credit(&user, &0),
+        100 * XLM
+    );
+
+    // The account holds no USDC trustline, so a delegated contract draws the loan
+    let spender = Address::generate(&market.env);
+    market
+        .credit_line
+        .approve_delegation(&user, &0, &spender, &(100 * XLM));
+    market
+        .credit_line
+        .borrow(&spender, &0, &(100 * XLM), &Some(user.clone()), &None);
+    assert_eq!(market.usdc.balance(&spender), 100 * XLM);
+    assert_eq!(
+        market.credit_line.get_position(&user, &0).borrowed,
+        100 * XLM
+    );
+
+    // The collateral is locked while the debt is open
+    assert!(market
+        .credit_line
+        .try_withdraw_collateral(&user, &0, &market.xlm.address, &XLM, &None)
+        .is_err());
+}
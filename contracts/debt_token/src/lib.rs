@@ -0,0 +1,167 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractimpl, contracttype, token::TokenInterface, Address, Env, MuxedAddress, String,
+};
+use soroban_token_sdk::metadata::TokenMetadata;
+
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    Metadata,
+    Balance(Address),
+    TotalSupply,
+}
+
+fn require_admin(env: &Env) -> Address {
+    let admin: Address = env
+        .storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .expect("Not initialized");
+    admin.require_auth();
+    admin
+}
+
+fn update_total_supply(env: &Env, delta: i128) {
+    let total: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalSupply)
+        .unwrap_or(0);
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalSupply, &(total + delta));
+}
+
+fn read_metadata(env: &Env) -> TokenMetadata {
+    env.storage()
+        .instance()
+        .get(&DataKey::Metadata)
+        .expect("Not initialized")
+}
+
+/// Non-transferable record of USDC owed to the credit line.
+///
+/// The credit line mints and burns it so each borrower's balance tracks their
+/// outstanding debt, including interest accrued as of their last interaction.
+#[contract]
+pub struct DebtToken;
+
+#[contractimpl]
+impl DebtToken {
+    /// Initialize the debt token; `admin` is the credit line that mints and burns it
+    pub fn initialize(env: Env, admin: Address, decimal: u32, name: String, symbol: String) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+
+        if decimal > 18 {
+            panic!("Decimal must not be greater than 18");
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(
+            &DataKey::Metadata,
+            &TokenMetadata {
+                decimal,
+                name,
+                symbol,
+            },
+        );
+        env.storage().instance().set(&DataKey::TotalSupply, &0_i128);
+    }
+
+    /// Record new debt for a borrower (admin only)
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        require_admin(&env);
+
+        if amount < 0 {
+            panic!("Amount must be non-negative");
+        }
+
+        let balance = Self::balance(env.clone(), to.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::Balance(to), &(balance + amount));
+        update_total_supply(&env, amount);
+    }
+
+    /// Clear repaid or liquidated debt for a borrower (admin only)
+    pub fn admin_burn(env: Env, from: Address, amount: i128) {
+        require_admin(&env);
+
+        if amount < 0 {
+            panic!("Amount must be non-negative");
+        }
+
+        let balance = Self::balance(env.clone(), from.clone());
+        if balance < amount {
+            panic!("Insufficient balance");
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Balance(from), &(balance - amount));
+        update_total_supply(&env, -amount);
+    }
+
+    pub fn total_supply(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TotalSupply)
+            .unwrap_or(0)
+    }
+}
+
+#[contractimpl]
+impl TokenInterface for DebtToken {
+    fn allowance(_env: Env, _from: Address, _spender: Address) -> i128 {
+        0
+    }
+
+    fn approve(
+        _env: Env,
+        _from: Address,
+        _spender: Address,
+        _amount: i128,
+        _expiration_ledger: u32,
+    ) {
+        panic!("Debt token is non-transferable");
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Balance(id))
+            .unwrap_or(0)
+    }
+
+    fn transfer(_env: Env, _from: Address, _to_muxed: MuxedAddress, _amount: i128) {
+        panic!("Debt token is non-transferable");
+    }
+
+    fn transfer_from(_env: Env, _spender: Address, _from: Address, _to: Address, _amount: i128) {
+        panic!("Debt token is non-transferable");
+    }
+
+    fn burn(_env: Env, _from: Address, _amount: i128) {
+        panic!("Debt token is non-transferable");
+    }
+
+    fn burn_from(_env: Env, _spender: Address, _from: Address, _amount: i128) {
+        panic!("Debt token is non-transferable");
+    }
+
+    fn decimals(env: Env) -> u32 {
+        read_metadata(&env).decimal
+    }
+
+    fn name(env: Env) -> String {
+        read_metadata(&env).name
+    }
+
+    fn symbol(env: Env) -> String {
+        read_metadata(&env).symbol
+    }
+}
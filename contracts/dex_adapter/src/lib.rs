@@ -0,0 +1,35 @@
+//! Cross-contract interface to the DEX adapters BondBridge swaps through.
+//!
+//! An adapter wraps one venue behind `swap_exact_in` and `quote`, so contracts
+//! that need to turn collateral into USDC or back, like the leverage router,
+//! can switch venues by pointing at another adapter. This crate holds only the
+//! interface and its generated `DexAdapterClient`; adapters implement it as
+//! contracts of their own.
+
+#![no_std]
+
+use soroban_sdk::{contractclient, Address, Env};
+
+/// Swap venue behind a common interface
+#[contractclient(name = "DexAdapterClient")]
+pub trait DexAdapter {
+    /// Swap exactly `amount_in` of `token_in` for `token_out`, paying `to`
+    ///
+    /// The `amount_in` has already been paid to the adapter when this is
+    /// called. The adapter must send at least `min_out` of `token_out` to `to`,
+    /// or fail, and returns the amount sent.
+    fn swap_exact_in(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+        to: Address,
+    ) -> i128;
+
+    /// Amount of `token_out` that swapping `amount_in` of `token_in` would pay now
+    ///
+    /// Reads the venue's current state, so it moves with anything else in the
+    /// same transaction; do not use it as a price source.
+    fn quote(env: Env, token_in: Address, token_out: Address, amount_in: i128) -> i128;
+}
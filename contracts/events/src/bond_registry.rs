@@ -0,0 +1,55 @@
+//! Events emitted by the bond registry contract.
+
+contract_events! {
+    /// An event emitted by the bond registry
+    pub enum BondRegistryEvent {
+        /// New bond series registered by its issuer
+        SeriesRegistered("series_registered") {
+            #[topic] series_id: u32 = to_u32,
+            #[topic] issuer: String = to_address,
+            face_value: i128 = to_i128,
+            coupon_rate: u32 = to_u32,
+            maturity: u64 = to_u64,
+        },
+        /// Bonds of a series issued to an investor
+        BondsMinted("bonds_minted") {
+            #[topic] series_id: u32 = to_u32,
+            #[topic] to: String = to_address,
+            amount: i128 = to_i128,
+            supply: i128 = to_i128,
+        },
+        /// Bonds of a series moved between holders
+        BondsTransferred("bonds_transferred") {
+            #[topic] series_id: u32 = to_u32,
+            #[topic] from: String = to_address,
+            #[topic] to: String = to_address,
+            amount: i128 = to_i128,
+        },
+        /// Coupon paid in by the issuer for holders on record
+        CouponPaid("coupon_paid") {
+            #[topic] series_id: u32 = to_u32,
+            coupon: u32 = to_u32,
+            record_ledger: u32 = to_u32,
+            amount: i128 = to_i128,
+        },
+        /// Coupons claimed by a holder
+        CouponClaimed("coupon_claimed") {
+            #[topic] series_id: u32 = to_u32,
+            #[topic] holder: String = to_address,
+            amount: i128 = to_i128,
+        },
+        /// Principal deposited by the issuer into a series' redemption escrow
+        RedemptionFunded("redemption_funded") {
+            #[topic] series_id: u32 = to_u32,
+            amount: i128 = to_i128,
+            escrow: i128 = to_i128,
+        },
+        /// Matured bonds burned and their face value paid out of escrow
+        BondsRedeemed("bonds_redeemed") {
+            #[topic] series_id: u32 = to_u32,
+            #[topic] holder: String = to_address,
+            amount: i128 = to_i128,
+            paid: i128 = to_i128,
+        },
+    }
+}
@@ -0,0 +1,39 @@
+//! Events emitted by the bridge contract.
+
+contract_events! {
+    /// An event emitted by the bridge
+    pub enum BridgeEvent {
+        /// Tokens locked on Stellar to be minted on another chain
+        Locked("locked") {
+            #[topic] token: String = to_address,
+            #[topic] from: String = to_address,
+            nonce: u64 = to_u64,
+            amount: i128 = to_i128,
+            dest_chain: u32 = to_u32,
+            dest_address: Vec<u8> = to_bytes,
+        },
+        /// Locked tokens released for a transfer proven on another chain
+        Released("released") {
+            #[topic] token: String = to_address,
+            #[topic] to: String = to_address,
+            source_chain: u32 = to_u32,
+            nonce: u64 = to_u64,
+            amount: i128 = to_i128,
+        },
+        /// Relayer approval recorded for a release request
+        Attested("attested") {
+            #[topic] digest: [u8; 32] = to_bytes32,
+            #[topic] relayer: String = to_address,
+        },
+        /// Relayer set or approval threshold changed
+        RelayerSetUpdated("relayer_set_updated") {
+            relayers: Vec<String> = to_addresses,
+            threshold: u32 = to_u32,
+        },
+        /// Token allowed or disallowed for locking
+        TokenAllowed("token_allowed") {
+            #[topic] token: String = to_address,
+            allowed: bool = to_bool,
+        },
+    }
+}
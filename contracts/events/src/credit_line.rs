@@ -0,0 +1,281 @@
+//! Events emitted by the credit line contract.
+
+contract_events! {
+    /// An event emitted by the credit line
+    pub enum CreditLineEvent {
+        /// Collateral deposited
+        Deposit("deposit") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            #[topic] token: String = to_address,
+            payer: String = to_address,
+            muxed_id: Option<u64> = to_optional_u64,
+            amount: i128 = to_i128,
+            collateral: i128 = to_i128,
+            borrowed: i128 = to_i128,
+        },
+        /// USDC borrowed against collateral
+        Borrow("borrow") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            recipient: String = to_address,
+            to: String = to_address,
+            muxed_id: Option<u64> = to_optional_u64,
+            amount: i128 = to_i128,
+            borrowed: i128 = to_i128,
+        },
+        /// Borrowing allowance granted against a user's position
+        DelegationApproved("delegation_approved") {
+            #[topic] delegator: String = to_address,
+            #[topic] delegatee: String = to_address,
+            account_id: u32 = to_u32,
+            amount: i128 = to_i128,
+        },
+        /// USDC debt repaid
+        Repay("repay") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            payer: String = to_address,
+            muxed_id: Option<u64> = to_optional_u64,
+            amount: i128 = to_i128,
+            borrowed: i128 = to_i128,
+        },
+        /// Referrer named with a user's first deposit
+        Referred("referred") {
+            #[topic] user: String = to_address,
+            #[topic] referrer: String = to_address,
+        },
+        /// Referral fees paid out to a referrer
+        ReferralFeesClaimed("referral_fees_claimed") {
+            #[topic] referrer: String = to_address,
+            amount: i128 = to_i128,
+        },
+        /// USDC debt repaid in BENJI, which the protocol keeps
+        RepayWithCollateral("repay_with_collateral") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            amount: i128 = to_i128,
+            repaid: i128 = to_i128,
+            borrowed: i128 = to_i128,
+        },
+        /// USDC debt repaid by surrendering BENJI collateral to the protocol
+        RepayFromCollateral("repay_from_collateral") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            amount: i128 = to_i128,
+            repaid: i128 = to_i128,
+            borrowed: i128 = to_i128,
+        },
+        /// BENJI taken in repayment of debt sold for USDC
+        ProtocolCollateralSold("protocol_collateral_sold") {
+            #[topic] buyer: String = to_address,
+            amount: i128 = to_i128,
+            cost: i128 = to_i128,
+        },
+        /// Collateral withdrawn
+        Withdraw("withdraw") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            #[topic] token: String = to_address,
+            to: String = to_address,
+            muxed_id: Option<u64> = to_optional_u64,
+            amount: i128 = to_i128,
+            collateral: i128 = to_i128,
+            borrowed: i128 = to_i128,
+        },
+        /// Underwater position partially repaid by a liquidator
+        Liquidate("liquidate") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            #[topic] liquidator: String = to_address,
+            token: String = to_address,
+            amount: i128 = to_i128,
+            seized: i128 = to_i128,
+            protocol_fee: i128 = to_i128,
+            collateral: i128 = to_i128,
+            borrowed: i128 = to_i128,
+        },
+        /// Loan-to-value ratio of a collateral token changed by the admin
+        LtvUpdated("ltv_updated") {
+            #[topic] token: String = to_address,
+            old_ratio: u32 = to_u32,
+            new_ratio: u32 = to_u32,
+        },
+        /// Market paused or resumed by the admin
+        PauseUpdated("pause_updated") {
+            paused: bool = to_bool,
+            pause_repay: bool = to_bool,
+        },
+        /// Collateral token accepted or its risk parameters changed
+        CollateralConfigUpdated("collateral_config_updated") {
+            #[topic] token: String = to_address,
+            ltv_ratio: u32 = to_u32,
+            liquidation_threshold: u32 = to_u32,
+        },
+        /// USDC supplied to the pool
+        Supply("supply") {
+            #[topic] lender: String = to_address,
+            muxed_id: Option<u64> = to_optional_u64,
+            amount: i128 = to_i128,
+            shares: i128 = to_i128,
+        },
+        /// USDC withdrawn from the pool
+        WithdrawSupply("withdraw_supply") {
+            #[topic] lender: String = to_address,
+            muxed_id: Option<u64> = to_optional_u64,
+            amount: i128 = to_i128,
+            shares: i128 = to_i128,
+        },
+        /// Protocol reserves sent to the treasury
+        ReservesWithdrawn("reserves_withdrawn") {
+            #[topic] treasury: String = to_address,
+            amount: i128 = to_i128,
+        },
+        /// Tokens sent to the contract by mistake returned by the admin
+        TokenRescued("token_rescued") {
+            #[topic] token: String = to_address,
+            #[topic] to: String = to_address,
+            amount: i128 = to_i128,
+        },
+        /// Yield earned by held collateral paid out to its depositor
+        CollateralYieldClaimed("collateral_yield_claimed") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            #[topic] token: String = to_address,
+            amount: i128 = to_i128,
+        },
+        /// Contract code replaced by the admin
+        Upgraded("upgraded") {
+            new_wasm_hash: [u8; 32] = to_bytes32,
+        },
+        /// New admin proposed by the current admin
+        AdminProposed("admin_proposed") {
+            #[topic] new_admin: String = to_address,
+            eta: u64 = to_u64,
+        },
+        /// Admin role handed over to a previously proposed admin
+        AdminAccepted("admin_accepted") {
+            #[topic] old_admin: String = to_address,
+            #[topic] new_admin: String = to_address,
+        },
+        /// Pool USDC lent and repaid within a single invocation
+        FlashLoan("flash_loan") {
+            #[topic] initiator: String = to_address,
+            #[topic] receiver: String = to_address,
+            amount: i128 = to_i128,
+            fee: i128 = to_i128,
+        },
+        /// Position left below the health warning threshold by an operation,
+        /// but not yet liquidatable
+        HealthWarning("health_warning") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            health_factor: i128 = to_i128,
+            threshold: i128 = to_i128,
+        },
+        /// Position handed to the successor market
+        PositionMigrated("position_migrated") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            #[topic] successor: String = to_address,
+            borrowed: i128 = to_i128,
+        },
+        /// Position taken over from the predecessor market
+        PositionReceived("position_received") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            #[topic] predecessor: String = to_address,
+            borrowed: i128 = to_i128,
+        },
+        /// Pool interest accrued and storage kept alive by a `poke`
+        Heartbeat("heartbeat") {
+            borrow_index: i128 = to_i128,
+            total_borrowed: i128 = to_i128,
+            total_supplied: i128 = to_i128,
+        },
+        /// Borrowing frozen and emergency withdrawals opened by the admin
+        EmergencyModeEnabled("emergency_mode_enabled") {
+            timestamp: u64 = to_u64,
+        },
+        /// Collateral withdrawn in emergency mode with the user's debt written off
+        EmergencyWithdraw("emergency_withdraw") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            #[topic] token: String = to_address,
+            amount: i128 = to_i128,
+            written_off: i128 = to_i128,
+        },
+        /// Debt left on a position with no collateral moved to bad debt
+        BadDebtRecorded("bad_debt_recorded") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            amount: i128 = to_i128,
+        },
+        /// Bad debt paid off out of protocol reserves
+        BadDebtCovered("bad_debt_covered") {
+            amount: i128 = to_i128,
+        },
+        /// Bad debt written off against suppliers
+        BadDebtSocialized("bad_debt_socialized") {
+            amount: i128 = to_i128,
+        },
+        /// Position switched between variable and stable rate
+        RateModeSwapped("rate_mode_swapped") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            rate_mode: u32 = to_u32,
+            stable_rate: u32 = to_u32,
+        },
+        /// Reward tokens paid out to a supplier or borrower
+        RewardsClaimed("rewards_claimed") {
+            #[topic] user: String = to_address,
+            amount: i128 = to_i128,
+        },
+        /// Reward emission rate of a pool changed
+        EmissionRateUpdated("emission_rate_updated") {
+            #[topic] pool: u32 = to_u32,
+            rate_per_second: i128 = to_i128,
+        },
+        /// Fixed-term loan taken on an account
+        FixedLoanOpened("fixed_loan_opened") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            amount: i128 = to_i128,
+            rate: u32 = to_u32,
+            installments: u32 = to_u32,
+            maturity_ledger: u32 = to_u32,
+        },
+        /// Protector registered to repay a position's debt before liquidation
+        ProtectionSet("protection_set") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            protector: String = to_address,
+            trigger_health_factor: i128 = to_i128,
+            max_repay: i128 = to_i128,
+        },
+        /// Protector unregistered from a position
+        ProtectionRemoved("protection_removed") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+        },
+        /// Debt repaid by a position's protector
+        Protected("protected") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            protector: String = to_address,
+            amount: i128 = to_i128,
+            borrowed: i128 = to_i128,
+        },
+        /// Admin parameter changed, as recorded in the parameter log under `sequence`
+        ParamChanged("param_changed") {
+            #[topic] param: String = to_symbol,
+            #[topic] admin: String = to_address,
+            sequence: u32 = to_u32,
+            subject: Option<String> = to_optional_address,
+            old_value: Option<i128> = to_optional_i128,
+            new_value: Option<i128> = to_optional_i128,
+            old_address: Option<String> = to_optional_address,
+            new_address: Option<String> = to_optional_address,
+        },
+    }
+}
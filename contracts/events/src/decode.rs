@@ -0,0 +1,113 @@
+//! Decoders from event topics and data fields to plain Rust types.
+//!
+//! Addresses decode to their strkey (`G...` or `C...`), `Bytes` to a byte
+//! vector and `BytesN<32>` to a byte array.
+
+use stellar_xdr::curr::ScVal;
+
+use crate::DecodeError;
+
+pub fn to_address(value: &ScVal) -> Result<String, DecodeError> {
+    match value {
+        ScVal::Address(address) => Ok(address.to_string()),
+        _ => Err(unexpected("address", value)),
+    }
+}
+
+/// `None` for a void value, as an unset `Option<Address>` field is published
+pub fn to_optional_address(value: &ScVal) -> Result<Option<String>, DecodeError> {
+    match value {
+        ScVal::Void => Ok(None),
+        value => to_address(value).map(Some),
+    }
+}
+
+pub fn to_addresses(value: &ScVal) -> Result<Vec<String>, DecodeError> {
+    match value {
+        ScVal::Vec(Some(items)) => items.iter().map(to_address).collect(),
+        _ => Err(unexpected("vec of addresses", value)),
+    }
+}
+
+pub fn to_i128(value: &ScVal) -> Result<i128, DecodeError> {
+    match value {
+        ScVal::I128(parts) => Ok(parts.into()),
+        _ => Err(unexpected("i128", value)),
+    }
+}
+
+/// `None` for a void value, as an unset `Option<i128>` field is published
+pub fn to_optional_i128(value: &ScVal) -> Result<Option<i128>, DecodeError> {
+    match value {
+        ScVal::Void => Ok(None),
+        value => to_i128(value).map(Some),
+    }
+}
+
+pub fn to_u32(value: &ScVal) -> Result<u32, DecodeError> {
+    match value {
+        ScVal::U32(value) => Ok(*value),
+        _ => Err(unexpected("u32", value)),
+    }
+}
+
+pub fn to_u64(value: &ScVal) -> Result<u64, DecodeError> {
+    match value {
+        ScVal::U64(value) => Ok(*value),
+        _ => Err(unexpected("u64", value)),
+    }
+}
+
+/// `None` for a void value, as an unset `Option<u64>` field is published
+pub fn to_optional_u64(value: &ScVal) -> Result<Option<u64>, DecodeError> {
+    match value {
+        ScVal::Void => Ok(None),
+        value => to_u64(value).map(Some),
+    }
+}
+
+pub fn to_bool(value: &ScVal) -> Result<bool, DecodeError> {
+    match value {
+        ScVal::Bool(value) => Ok(*value),
+        _ => Err(unexpected("bool", value)),
+    }
+}
+
+pub fn to_symbol(value: &ScVal) -> Result<String, DecodeError> {
+    match value {
+        ScVal::Symbol(symbol) => Ok(symbol.to_utf8_string_lossy()),
+        _ => Err(unexpected("symbol", value)),
+    }
+}
+
+pub fn to_bytes(value: &ScVal) -> Result<Vec<u8>, DecodeError> {
+    match value {
+        ScVal::Bytes(bytes) => Ok(bytes.to_vec()),
+        _ => Err(unexpected("bytes", value)),
+    }
+}
+
+pub fn to_bytes32(value: &ScVal) -> Result<[u8; 32], DecodeError> {
+    match value {
+        ScVal::Bytes(bytes) => bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| unexpected("32 bytes", value)),
+        _ => Err(unexpected("32 bytes", value)),
+    }
+}
+
+/// A named field of an event's data map
+pub fn field<'a>(data: &'a ScVal, name: &str) -> Result<&'a ScVal, DecodeError> {
+    let ScVal::Map(Some(map)) = data else {
+        return Err(unexpected("map", data));
+    };
+    map.iter()
+        .find(|entry| matches!(&entry.key, ScVal::Symbol(key) if key.0.as_vec() == name.as_bytes()))
+        .map(|entry| &entry.val)
+        .ok_or_else(|| DecodeError::Malformed(format!("missing field `{name}`")))
+}
+
+fn unexpected(expected: &str, value: &ScVal) -> DecodeError {
+    DecodeError::Malformed(format!("expected {expected}, got {value:?}"))
+}
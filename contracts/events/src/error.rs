@@ -0,0 +1,30 @@
+use std::fmt;
+
+/// Why an event could not be decoded
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Base64 or XDR that failed to parse
+    Xdr(stellar_xdr::curr::Error),
+    /// The event's name is not one the contract emits
+    UnknownEvent(String),
+    /// A topic or data field was missing or of the wrong type
+    Malformed(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Xdr(error) => write!(f, "xdr error: {error}"),
+            DecodeError::UnknownEvent(name) => write!(f, "unknown event `{name}`"),
+            DecodeError::Malformed(message) => write!(f, "malformed event: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<stellar_xdr::curr::Error> for DecodeError {
+    fn from(error: stellar_xdr::curr::Error) -> Self {
+        DecodeError::Xdr(error)
+    }
+}
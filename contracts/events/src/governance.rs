@@ -0,0 +1,29 @@
+//! Events emitted by the governance contract.
+
+contract_events! {
+    /// An event emitted by governance
+    pub enum GovernanceEvent {
+        /// Call on a target contract proposed by a member
+        Proposed("proposed") {
+            #[topic] proposal_id: u32 = to_u32,
+            #[topic] proposer: String = to_address,
+            target: String = to_address,
+            function: String = to_symbol,
+            voting_ends: u64 = to_u64,
+        },
+        /// Member vote in favour of a proposal recorded
+        Voted("voted") {
+            #[topic] proposal_id: u32 = to_u32,
+            #[topic] voter: String = to_address,
+            votes: u32 = to_u32,
+        },
+        /// Approved proposal's call made on its target
+        Executed("executed") {
+            #[topic] proposal_id: u32 = to_u32,
+        },
+        /// Proposal withdrawn by its proposer before execution
+        Cancelled("cancelled") {
+            #[topic] proposal_id: u32 = to_u32,
+        },
+    }
+}
@@ -0,0 +1,70 @@
+//! Typed events of the BondBridge contracts, for indexers, bots and tools.
+//!
+//! Each contract's events are a struct per event, in a module named after the
+//! contract, and an enum over them: [`CreditLineEvent`], [`BondRegistryEvent`],
+//! [`BridgeEvent`], [`GovernanceEvent`], [`PositionNftEvent`], [`RouterEvent`],
+//! [`SavingsVaultEvent`] and [`StakingEvent`]. Event names are only unique
+//! within a contract, so decode with the enum of the contract that emitted the
+//! event.
+//!
+//! ```no_run
+//! use bondbridge_events::{CreditLineEvent, DecodeEvent};
+//!
+//! // Topics and value of one event, as `getEvents` returns them
+//! let topics = ["AAAADwAAAAZib3Jyb3cAAA==", "AAAAEgAAAAAAAAAA..."];
+//! let value = "AAAAEQAAAAEAAAAE...";
+//!
+//! if let CreditLineEvent::Borrow(borrow) = CreditLineEvent::from_xdr_base64(&topics, value)? {
+//!     println!("{} borrowed {}", borrow.user, borrow.amount);
+//! }
+//! # Ok::<(), bondbridge_events::DecodeError>(())
+//! ```
+
+#[macro_use]
+mod macros;
+
+pub mod bond_registry;
+pub mod bridge;
+pub mod credit_line;
+pub mod decode;
+mod error;
+pub mod governance;
+pub mod position_nft;
+pub mod router;
+pub mod savings_vault;
+pub mod staking;
+
+use stellar_xdr::curr::{ContractEvent, ContractEventBody, Limits, ReadXdr, ScVal};
+
+pub use bond_registry::BondRegistryEvent;
+pub use bridge::BridgeEvent;
+pub use credit_line::CreditLineEvent;
+pub use error::DecodeError;
+pub use governance::GovernanceEvent;
+pub use position_nft::PositionNftEvent;
+pub use router::RouterEvent;
+pub use savings_vault::SavingsVaultEvent;
+pub use staking::StakingEvent;
+pub use stellar_xdr::curr as xdr;
+
+/// The events of one contract, decodable from their topics and data
+pub trait DecodeEvent: Sized {
+    /// Decode an event from all its topics, its name first, and its data
+    fn decode(topics: &[ScVal], data: &ScVal) -> Result<Self, DecodeError>;
+
+    /// Decode an event from base64 XDR topics and value, as Soroban RPC `getEvents` returns them
+    fn from_xdr_base64(topics: &[impl AsRef<str>], value: &str) -> Result<Self, DecodeError> {
+        let topics = topics
+            .iter()
+            .map(|topic| ScVal::from_xdr_base64(topic.as_ref(), Limits::none()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let data = ScVal::from_xdr_base64(value, Limits::none())?;
+        Self::decode(&topics, &data)
+    }
+
+    /// Decode an event from transaction meta
+    fn from_contract_event(event: &ContractEvent) -> Result<Self, DecodeError> {
+        let ContractEventBody::V0(body) = &event.body;
+        Self::decode(&body.topics, &body.data)
+    }
+}
@@ -0,0 +1,74 @@
+/// Declare one contract's events: a struct per event and an enum over them
+/// that decodes from topics and data.
+///
+/// Each event names the symbol the contract publishes it under, its first
+/// topic. Fields marked `#[topic]` are read from the remaining topics in order,
+/// the rest from the data map by name, each with the named `decode` function.
+macro_rules! contract_events {
+    (
+        $(#[$enum_meta:meta])*
+        pub enum $enum:ident {
+            $(
+                $(#[doc = $doc:literal])*
+                $event:ident($name:literal) {
+                    $( $(#[$kind:ident])? $field:ident: $ty:ty = $decode:ident ),* $(,)?
+                }
+            ),* $(,)?
+        }
+    ) => {
+        $(
+            $(#[doc = $doc])*
+            #[derive(Clone, Debug, Eq, PartialEq)]
+            pub struct $event {
+                $( pub $field: $ty, )*
+            }
+        )*
+
+        $(#[$enum_meta])*
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        pub enum $enum {
+            $( $event($event), )*
+        }
+
+        impl $enum {
+            /// The symbol the event is published under
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( $enum::$event(_) => $name, )*
+                }
+            }
+        }
+
+        impl $crate::DecodeEvent for $enum {
+            fn decode(
+                topics: &[$crate::xdr::ScVal],
+                data: &$crate::xdr::ScVal,
+            ) -> Result<Self, $crate::DecodeError> {
+                let (name, topics) = topics.split_first().ok_or_else(|| {
+                    $crate::DecodeError::Malformed("event without topics".to_string())
+                })?;
+                let name = $crate::decode::to_symbol(name)?;
+                let mut topics = topics.iter();
+
+                match name.as_str() {
+                    $(
+                        $name => Ok($enum::$event($event {
+                            $( $field: contract_events!(@field [$($kind)?] topics data $field $decode), )*
+                        })),
+                    )*
+                    _ => Err($crate::DecodeError::UnknownEvent(name)),
+                }
+            }
+        }
+    };
+
+    (@field [topic] $topics:ident $data:ident $field:ident $decode:ident) => {
+        $crate::decode::$decode($topics.next().ok_or_else(|| {
+            $crate::DecodeError::Malformed(format!("missing topic `{}`", stringify!($field)))
+        })?)?
+    };
+
+    (@field [] $topics:ident $data:ident $field:ident $decode:ident) => {
+        $crate::decode::$decode($crate::decode::field($data, stringify!($field))?)?
+    };
+}
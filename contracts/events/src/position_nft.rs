@@ -0,0 +1,19 @@
+//! Events emitted by the position NFT contract.
+
+contract_events! {
+    /// An event emitted by the position NFT
+    pub enum PositionNftEvent {
+        /// Position token minted with a fresh vault
+        Minted("minted") {
+            #[topic] token_id: u64 = to_u64,
+            #[topic] owner: String = to_address,
+            vault: String = to_address,
+        },
+        /// Position token, and control of its vault, handed to a new holder
+        Transferred("transferred") {
+            #[topic] token_id: u64 = to_u64,
+            #[topic] from: String = to_address,
+            #[topic] to: String = to_address,
+        },
+    }
+}
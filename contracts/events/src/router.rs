@@ -0,0 +1,24 @@
+//! Events emitted by the leverage router contract.
+
+contract_events! {
+    /// An event emitted by the router
+    pub enum RouterEvent {
+        /// Position levered up by looping borrowed USDC back into BENJI collateral
+        Leveraged("leveraged") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            loops: u32 = to_u32,
+            collateral: i128 = to_i128,
+            borrowed: i128 = to_i128,
+        },
+        /// Position unwound by selling collateral to repay its debt
+        Deleveraged("deleveraged") {
+            #[topic] user: String = to_address,
+            account_id: u32 = to_u32,
+            loops: u32 = to_u32,
+            withdrawn: i128 = to_i128,
+            repaid: i128 = to_i128,
+            remaining_debt: i128 = to_i128,
+        },
+    }
+}
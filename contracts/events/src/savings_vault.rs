@@ -0,0 +1,24 @@
+//! Events emitted by the savings vault contract.
+
+contract_events! {
+    /// An event emitted by the savings vault
+    pub enum SavingsVaultEvent {
+        /// USDC deposited for vault shares
+        Deposit("deposit") {
+            #[topic] owner: String = to_address,
+            assets: i128 = to_i128,
+            shares: i128 = to_i128,
+        },
+        /// Vault shares burned for USDC
+        Withdraw("withdraw") {
+            #[topic] owner: String = to_address,
+            assets: i128 = to_i128,
+            shares: i128 = to_i128,
+        },
+        /// Reward tokens claimed, swapped to USDC and supplied back to the pool
+        Harvest("harvest") {
+            rewards: i128 = to_i128,
+            compounded: i128 = to_i128,
+        },
+    }
+}
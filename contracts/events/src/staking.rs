@@ -0,0 +1,18 @@
+//! Events emitted by the staking contract.
+
+contract_events! {
+    /// An event emitted by staking
+    pub enum StakingEvent {
+        /// BENJI locked until `unlock_at`
+        Locked("locked") {
+            #[topic] user: String = to_address,
+            amount: i128 = to_i128,
+            unlock_at: u64 = to_u64,
+        },
+        /// Expired locks paid back to their owner
+        Unlocked("unlocked") {
+            #[topic] user: String = to_address,
+            amount: i128 = to_i128,
+        },
+    }
+}
@@ -0,0 +1,41 @@
+use soroban_sdk::{contractevent, Address, Symbol};
+
+/// Call on a target contract proposed by a member
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposed {
+    #[topic]
+    pub proposal_id: u32,
+    #[topic]
+    pub proposer: Address,
+    pub target: Address,
+    pub function: Symbol,
+    pub voting_ends: u64,
+}
+
+/// Member vote in favour of a proposal recorded
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Voted {
+    #[topic]
+    pub proposal_id: u32,
+    #[topic]
+    pub voter: Address,
+    pub votes: u32,
+}
+
+/// Approved proposal's call made on its target
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Executed {
+    #[topic]
+    pub proposal_id: u32,
+}
+
+/// Proposal withdrawn by its proposer before execution
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cancelled {
+    #[topic]
+    pub proposal_id: u32,
+}
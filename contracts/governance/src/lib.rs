@@ -0,0 +1,401 @@
+#![no_std]
+
+mod events;
+
+use events::{Cancelled, Executed, Proposed, Voted};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, Address, Env, Symbol, TryFromVal, Val, Vec,
+};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    NotMember = 3,
+    ProposalNotFound = 4,
+    VotingClosed = 5,
+    AlreadyVoted = 6,
+    QuorumNotReached = 7,
+    TimelockNotElapsed = 8,
+    AlreadyExecuted = 9,
+    InvalidParameter = 10,
+    ProposalCancelled = 11,
+    NotProposer = 12,
+}
+
+/// A call on `target` that members vote on and anyone can execute once approved
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposal {
+    pub proposer: Address,
+    pub target: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+    pub voting_ends: u64,
+    pub voters: Vec<Address>, // members who voted for it, in order
+    pub executed: bool,
+    pub cancelled: bool,
+}
+
+#[contracttype]
+pub enum DataKey {
+    Members,
+    Quorum,       // votes a proposal needs to pass
+    VotingPeriod, // seconds a proposal is open for votes
+    QueueDelay,   // seconds between the end of voting and execution
+    ProposalCount,
+    Proposal(u32),
+}
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const PROPOSAL_BUMP_AMOUNT: u32 = 90 * DAY_IN_LEDGERS;
+const PROPOSAL_LIFETIME_THRESHOLD: u32 = PROPOSAL_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Check that `member` is a voting member and has authorized the call
+fn require_member(env: &Env, member: &Address) -> Result<(), Error> {
+    member.require_auth();
+
+    if !load_members(env)?.contains(member) {
+        return Err(Error::NotMember);
+    }
+
+    Ok(())
+}
+
+fn load_proposal(env: &Env, proposal_id: u32) -> Result<Proposal, Error> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Proposal(proposal_id))
+        .ok_or(Error::ProposalNotFound)
+}
+
+fn save_proposal(env: &Env, proposal_id: u32, proposal: &Proposal) {
+    let key = DataKey::Proposal(proposal_id);
+    env.storage().persistent().set(&key, proposal);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, PROPOSAL_LIFETIME_THRESHOLD, PROPOSAL_BUMP_AMOUNT);
+}
+
+fn load_members(env: &Env) -> Result<Vec<Address>, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Members)
+        .ok_or(Error::NotInitialized)
+}
+
+/// Check that `quorum` votes can be reached by `members`
+fn check_quorum(members: &Vec<Address>, quorum: u32) -> Result<(), Error> {
+    if quorum == 0 || quorum > members.len() {
+        return Err(Error::InvalidParameter);
+    }
+    Ok(())
+}
+
+fn arg<T: TryFromVal<Env, Val>>(env: &Env, args: &Vec<Val>, index: u32) -> Result<T, Error> {
+    let val = args.get(index).ok_or(Error::InvalidParameter)?;
+    T::try_from_val(env, &val).map_err(|_| Error::InvalidParameter)
+}
+
+/// Apply a proposal targeting this contract
+///
+/// Soroban does not let a contract call itself, so `execute` makes these
+/// changes directly rather than invoking the contract.
+fn reconfigure(env: &Env, function: &Symbol, args: &Vec<Val>) -> Result<(), Error> {
+    let mut members = load_members(env)?;
+    let mut quorum: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::Quorum)
+        .ok_or(Error::NotInitialized)?;
+
+    if *function == Symbol::new(env, "add_member") {
+        let member: Address = arg(env, args, 0)?;
+        if members.contains(&member) {
+            return Err(Error::InvalidParameter);
+        }
+        members.push_back(member);
+    } else if *function == Symbol::new(env, "remove_member") {
+        let member: Address = arg(env, args, 0)?;
+        let index = members.first_index_of(&member).ok_or(Error::NotMember)?;
+        members.remove(index);
+    } else if *function == Symbol::new(env, "set_quorum") {
+        quorum = arg(env, args, 0)?;
+    } else if *function == Symbol::new(env, "set_voting_period") {
+        let voting_period: u64 = arg(env, args, 0)?;
+        if voting_period == 0 {
+            return Err(Error::InvalidParameter);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::VotingPeriod, &voting_period);
+    } else if *function == Symbol::new(env, "set_queue_delay") {
+        let queue_delay: u64 = arg(env, args, 0)?;
+        env.storage()
+            .instance()
+            .set(&DataKey::QueueDelay, &queue_delay);
+    } else {
+        return Err(Error::InvalidParameter);
+    }
+
+    check_quorum(&members, quorum)?;
+    env.storage().instance().set(&DataKey::Members, &members);
+    env.storage().instance().set(&DataKey::Quorum, &quorum);
+
+    Ok(())
+}
+
+/// Record `voter`'s vote for a proposal still open for voting
+fn cast_vote(env: &Env, proposal: &mut Proposal, voter: &Address) -> Result<(), Error> {
+    if proposal.cancelled {
+        return Err(Error::ProposalCancelled);
+    }
+    if env.ledger().timestamp() >= proposal.voting_ends {
+        return Err(Error::VotingClosed);
+    }
+    if proposal.voters.contains(voter) {
+        return Err(Error::AlreadyVoted);
+    }
+
+    proposal.voters.push_back(voter.clone());
+
+    Ok(())
+}
+
+/// Votes for a proposal from those who are still members
+fn member_votes(members: &Vec<Address>, proposal: &Proposal) -> u32 {
+    proposal
+        .voters
+        .iter()
+        .filter(|voter| members.contains(voter))
+        .count() as u32
+}
+
+/// Time-locked governor for protocol admin roles.
+///
+/// Members propose calls on other contracts and vote on them. A proposal that
+/// reaches quorum can be executed by anyone once voting has ended and the queue
+/// delay has passed, so every parameter change is public before it lands.
+///
+/// To govern the credit line, propose it as admin there and pass a proposal
+/// calling `accept_admin` with this contract's address; later proposals can
+/// then call any admin function with this contract as `admin`.
+///
+/// Proposals targeting this contract change its own rules, each taking one
+/// argument: `add_member` and `remove_member` a member, `set_quorum` the votes
+/// needed, and `set_voting_period` and `set_queue_delay` in seconds. The quorum
+/// must stay reachable by the members left.
+#[contract]
+pub struct Governance;
+
+#[contractimpl]
+impl Governance {
+    /// Initialize the member set and voting rules (every member must authorize)
+    pub fn initialize(
+        env: Env,
+        members: Vec<Address>,
+        quorum: u32,
+        voting_period: u64,
+        queue_delay: u64,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Members) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        for (index, member) in members.iter().enumerate() {
+            if members.first_index_of(&member) != Some(index as u32) {
+                return Err(Error::InvalidParameter);
+            }
+            member.require_auth();
+        }
+        check_quorum(&members, quorum)?;
+        if voting_period == 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        env.storage().instance().set(&DataKey::Members, &members);
+        env.storage().instance().set(&DataKey::Quorum, &quorum);
+        env.storage()
+            .instance()
+            .set(&DataKey::VotingPeriod, &voting_period);
+        env.storage()
+            .instance()
+            .set(&DataKey::QueueDelay, &queue_delay);
+
+        Ok(())
+    }
+
+    /// Propose calling `function` on `target` with `args`, voting for it (members only)
+    pub fn propose(
+        env: Env,
+        proposer: Address,
+        target: Address,
+        function: Symbol,
+        args: Vec<Val>,
+    ) -> Result<u32, Error> {
+        require_member(&env, &proposer)?;
+
+        let voting_period: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::VotingPeriod)
+            .unwrap_or(0);
+        let proposal_id: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::ProposalCount)
+            .unwrap_or(0);
+
+        let mut proposal = Proposal {
+            proposer: proposer.clone(),
+            target: target.clone(),
+            function: function.clone(),
+            args,
+            voting_ends: env.ledger().timestamp() + voting_period,
+            voters: Vec::new(&env),
+            executed: false,
+            cancelled: false,
+        };
+        cast_vote(&env, &mut proposal, &proposer)?;
+
+        save_proposal(&env, proposal_id, &proposal);
+        env.storage()
+            .instance()
+            .set(&DataKey::ProposalCount, &(proposal_id + 1));
+
+        Proposed {
+            proposal_id,
+            proposer,
+            target,
+            function,
+            voting_ends: proposal.voting_ends,
+        }
+        .publish(&env);
+
+        Ok(proposal_id)
+    }
+
+    /// Vote in favour of a proposal while voting is open (members only)
+    pub fn vote(env: Env, voter: Address, proposal_id: u32) -> Result<u32, Error> {
+        require_member(&env, &voter)?;
+
+        let mut proposal = load_proposal(&env, proposal_id)?;
+        cast_vote(&env, &mut proposal, &voter)?;
+        save_proposal(&env, proposal_id, &proposal);
+
+        Voted {
+            proposal_id,
+            voter,
+            votes: proposal.voters.len(),
+        }
+        .publish(&env);
+
+        Ok(proposal.voters.len())
+    }
+
+    /// Withdraw a proposal that has not been executed (proposer only)
+    pub fn cancel(env: Env, proposer: Address, proposal_id: u32) -> Result<(), Error> {
+        proposer.require_auth();
+
+        let mut proposal = load_proposal(&env, proposal_id)?;
+        if proposal.proposer != proposer {
+            return Err(Error::NotProposer);
+        }
+        if proposal.executed {
+            return Err(Error::AlreadyExecuted);
+        }
+        if proposal.cancelled {
+            return Err(Error::ProposalCancelled);
+        }
+
+        proposal.cancelled = true;
+        save_proposal(&env, proposal_id, &proposal);
+
+        Cancelled { proposal_id }.publish(&env);
+
+        Ok(())
+    }
+
+    /// Make a passed proposal's call once its queue delay has elapsed
+    ///
+    /// Only votes from current members count towards the quorum, so removing
+    /// a member withdraws their votes from proposals not yet executed.
+    pub fn execute(env: Env, proposal_id: u32) -> Result<Val, Error> {
+        let mut proposal = load_proposal(&env, proposal_id)?;
+
+        if proposal.executed {
+            return Err(Error::AlreadyExecuted);
+        }
+        if proposal.cancelled {
+            return Err(Error::ProposalCancelled);
+        }
+
+        let quorum: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Quorum)
+            .ok_or(Error::NotInitialized)?;
+        if member_votes(&load_members(&env)?, &proposal) < quorum {
+            return Err(Error::QuorumNotReached);
+        }
+
+        let queue_delay: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::QueueDelay)
+            .unwrap_or(0);
+        if env.ledger().timestamp() < proposal.voting_ends + queue_delay {
+            return Err(Error::TimelockNotElapsed);
+        }
+
+        // Mark executed before calling out so the call cannot be replayed
+        proposal.executed = true;
+        save_proposal(&env, proposal_id, &proposal);
+
+        let result: Val = if proposal.target == env.current_contract_address() {
+            reconfigure(&env, &proposal.function, &proposal.args)?;
+            Val::VOID.into()
+        } else {
+            env.invoke_contract(&proposal.target, &proposal.function, proposal.args)
+        };
+
+        Executed { proposal_id }.publish(&env);
+
+        Ok(result)
+    }
+
+    /// Get a proposal, including who has voted for it
+    pub fn get_proposal(env: Env, proposal_id: u32) -> Result<Proposal, Error> {
+        load_proposal(&env, proposal_id)
+    }
+
+    /// Number of proposals made so far, which is also the next proposal's id
+    pub fn proposal_count(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::ProposalCount)
+            .unwrap_or(0)
+    }
+
+    /// Get the voting members
+    pub fn get_members(env: Env) -> Result<Vec<Address>, Error> {
+        load_members(&env)
+    }
+
+    /// Get the votes from current members a proposal needs to pass
+    pub fn get_quorum(env: Env) -> Result<u32, Error> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Quorum)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Check whether `voter` has voted for a proposal
+    pub fn has_voted(env: Env, proposal_id: u32, voter: Address) -> bool {
+        load_proposal(&env, proposal_id)
+            .map(|proposal| proposal.voters.contains(&voter))
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+
+/// Length of a fee budget period
+const PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Caps the fees the keeper spends in each day
+pub struct FeeBudget {
+    limit: u64,
+    spent: u64,
+    period_start: Instant,
+}
+
+impl FeeBudget {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            spent: 0,
+            period_start: Instant::now(),
+        }
+    }
+
+    /// Stroops left to spend in the current period
+    pub fn remaining(&mut self) -> u64 {
+        if self.period_start.elapsed() >= PERIOD {
+            self.spent = 0;
+            self.period_start = Instant::now();
+        }
+        self.limit.saturating_sub(self.spent)
+    }
+
+    pub fn spend(&mut self, fee: u32) {
+        self.spent = self.spent.saturating_add(fee.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_refills_each_period() {
+        let mut budget = FeeBudget::new(1_000);
+        budget.spend(400);
+        budget.spend(700);
+        assert_eq!(budget.remaining(), 0);
+
+        // A day later the spending starts over
+        budget.period_start = Instant::now()
+            .checked_sub(PERIOD)
+            .expect("clock too close to its start");
+        assert_eq!(budget.remaining(), 1_000);
+        budget.spend(u32::MAX);
+        assert_eq!(budget.remaining(), 0);
+    }
+}
@@ -0,0 +1,53 @@
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Keeper settings, read from the environment
+pub struct Config {
+    pub rpc_url: String,
+    pub credit_line: String,
+    pub secret_key: String,
+    pub poll_interval: Duration,
+    pub poke_interval: Duration, // time between `poke` calls keeping the market's indices fresh
+    pub min_profit: i128, // least USDC, in base units, a liquidation must earn over its repayment
+    pub max_fee: u32,     // most stroops to offer for one liquidation
+    pub fee_budget: u64,  // most stroops to spend on fees in a day
+}
+
+impl Config {
+    /// Read settings from the environment:
+    ///
+    /// - `BONDBRIDGE_RPC_URL`: Soroban RPC endpoint
+    /// - `BONDBRIDGE_CREDIT_LINE`: credit line contract id
+    /// - `KEEPER_SECRET_KEY`: secret seed of the account that liquidates and pays fees
+    /// - `KEEPER_POLL_SECS`: seconds between rounds, 30 by default
+    /// - `KEEPER_POKE_SECS`: seconds between pokes, an hour by default
+    /// - `KEEPER_MIN_PROFIT`: USDC base units, 0 by default
+    /// - `KEEPER_MAX_FEE`: stroops per transaction, 0.1 XLM by default
+    /// - `KEEPER_FEE_BUDGET`: stroops per day, 10 XLM by default
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Config {
+            rpc_url: required("BONDBRIDGE_RPC_URL")?,
+            credit_line: required("BONDBRIDGE_CREDIT_LINE")?,
+            secret_key: required("KEEPER_SECRET_KEY")?,
+            poll_interval: Duration::from_secs(optional("KEEPER_POLL_SECS", 30)?),
+            poke_interval: Duration::from_secs(optional("KEEPER_POKE_SECS", 3600)?),
+            min_profit: optional("KEEPER_MIN_PROFIT", 0)?,
+            max_fee: optional("KEEPER_MAX_FEE", 1_000_000)?,
+            fee_budget: optional("KEEPER_FEE_BUDGET", 100_000_000)?,
+        })
+    }
+}
+
+fn required(name: &str) -> Result<String, String> {
+    env::var(name).map_err(|_| format!("{name} is not set"))
+}
+
+fn optional<T: FromStr>(name: &str, default: T) -> Result<T, String> {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| format!("{name} is not a valid number: {value}")),
+        Err(_) => Ok(default),
+    }
+}
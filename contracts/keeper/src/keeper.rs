@@ -0,0 +1,391 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use bondbridge_client::{
+    Asset, CreditLine, Error, MarketConfig, Oracle, OracleSettings, QuoteAsset, Signer, Token,
+};
+
+use crate::budget::FeeBudget;
+use crate::config::Config;
+
+/// Users scanned per `list_liquidatable` call, the contract's page size
+const PAGE_SIZE: u32 = 50;
+
+/// Finds underwater positions and liquidates the ones worth liquidating
+pub struct Keeper {
+    credit_line: CreditLine,
+    signer: Signer,
+    min_profit: i128,
+    max_fee: u32,
+    budget: FeeBudget,
+}
+
+/// Why a liquidatable position was passed over
+#[derive(Debug, PartialEq, Eq)]
+enum Skip {
+    NoPricedCollateral,
+    CannotCloseOut,
+    NoUsdc,
+    BelowMinProfit(i128),
+    FeeOverMax(u32),
+    BudgetSpent,
+}
+
+/// A position left alone, by choice or because a call failed
+enum Failure {
+    Skipped(Skip),
+    Client(Error),
+}
+
+impl From<Skip> for Failure {
+    fn from(skip: Skip) -> Self {
+        Failure::Skipped(skip)
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Self {
+        Failure::Client(error)
+    }
+}
+
+impl fmt::Display for Skip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Skip::NoPricedCollateral => write!(f, "no priced collateral"),
+            Skip::CannotCloseOut => write!(f, "not enough USDC to close out"),
+            Skip::NoUsdc => write!(f, "no USDC to repay with"),
+            Skip::BelowMinProfit(profit) => write!(f, "profit {profit} below minimum"),
+            Skip::FeeOverMax(fee) => write!(f, "fee {fee} over maximum"),
+            Skip::BudgetSpent => write!(f, "fee budget spent"),
+        }
+    }
+}
+
+/// Prices for one round, so each token is looked up once
+struct Prices {
+    oracle: Option<(Oracle, u32)>, // and its decimals; without one, tokens trade 1:1
+    settings: OracleSettings,
+    usdc_decimals: u32,
+    tokens: BTreeMap<String, Option<(i128, u32)>>, // token -> price and token decimals
+}
+
+impl Keeper {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        Ok(Self {
+            credit_line: CreditLine::connect(&config.rpc_url, &config.credit_line)?,
+            signer: Signer::from_secret(&config.secret_key)?,
+            min_profit: config.min_profit,
+            max_fee: config.max_fee,
+            budget: FeeBudget::new(config.fee_budget),
+        })
+    }
+
+    pub fn address(&self) -> String {
+        self.signer.address()
+    }
+
+    /// Accrue pool interest and keep the market's storage alive
+    pub fn poke(&self) -> Result<(), Error> {
+        self.credit_line.poke(&self.signer)
+    }
+
+    /// Scan every user once, returning the number of positions liquidated
+    pub fn run_round(&mut self) -> Result<u32, Error> {
+        let config = self.credit_line.config()?;
+        let contract = self.credit_line.contract();
+        let usdc = Token::new(contract.at(&config.usdc_token)?);
+        let settings = self.credit_line.oracle_settings()?;
+        let oracle = match &config.oracle {
+            Some(oracle) => {
+                let oracle = Oracle::new(contract.at(oracle)?);
+                let decimals = match settings.decimals {
+                    Some(decimals) => decimals,
+                    None => oracle.decimals()?,
+                };
+                Some((oracle, decimals))
+            }
+            None => None,
+        };
+        let mut prices = Prices {
+            oracle,
+            settings,
+            usdc_decimals: usdc.decimals()?,
+            tokens: BTreeMap::new(),
+        };
+
+        let mut liquidated = 0;
+        let user_count = self.credit_line.user_count()?;
+        for offset in (0..user_count).step_by(PAGE_SIZE as usize) {
+            for (user, account_id) in self.credit_line.list_liquidatable(offset, PAGE_SIZE)? {
+                let balance = usdc.balance(&self.signer.address())?;
+                match self.liquidate(&config, &mut prices, &user, account_id, balance) {
+                    Ok(()) => liquidated += 1,
+                    Err(Failure::Skipped(skip)) => {
+                        println!("{user}/{account_id}: {skip}, skipping")
+                    }
+                    Err(Failure::Client(error)) => eprintln!("{user}/{account_id}: {error}"),
+                }
+            }
+        }
+
+        Ok(liquidated)
+    }
+
+    /// Liquidate one position if it pays
+    fn liquidate(
+        &mut self,
+        config: &MarketConfig,
+        prices: &mut Prices,
+        user: &str,
+        account_id: u32,
+        balance: i128,
+    ) -> Result<(), Failure> {
+        let position = self.credit_line.position(user, account_id)?;
+        let debt = self.credit_line.current_debt(user, account_id)?;
+
+        // Seize the collateral worth the most, repaying as much as one call may
+        let mut best = None;
+        for (token, amount) in &position.collateral {
+            let Some(value) = prices.value(&self.credit_line, token, *amount)? else {
+                continue;
+            };
+            if best
+                .as_ref()
+                .is_none_or(|(_, best_value)| value > *best_value)
+            {
+                best = Some((token.clone(), value));
+            }
+        }
+        let (token, _) = best.ok_or(Skip::NoPricedCollateral)?;
+        let repay_amount = repay_amount(debt, config.close_factor, config.min_borrow, balance)?;
+
+        let prepared = self.credit_line.prepare_liquidate(
+            &self.signer,
+            user,
+            account_id,
+            &token,
+            repay_amount,
+        )?;
+
+        let seized = bondbridge_client::scval::to_i128(&prepared.result)?;
+        let seized_value = prices
+            .value(&self.credit_line, &token, seized)?
+            .ok_or(Skip::NoPricedCollateral)?;
+        let profit = seized_value - repay_amount;
+        approve(
+            profit,
+            prepared.fee,
+            self.min_profit,
+            self.max_fee,
+            &mut self.budget,
+        )?;
+
+        self.credit_line.contract().submit(&self.signer, prepared)?;
+        println!(
+            "{user}/{account_id}: repaid {repay_amount}, seized {seized} of {token}, profit {profit}"
+        );
+
+        Ok(())
+    }
+}
+
+/// USDC to repay on `debt`: what the close factor allows, or the whole debt
+/// when the rest would be dust, since a liquidation may not leave dust. Only
+/// as much as `balance` covers is repaid, and a close-out needs all of it.
+fn repay_amount(
+    debt: i128,
+    close_factor: u32,
+    min_borrow: i128,
+    balance: i128,
+) -> Result<i128, Skip> {
+    let mut repay_amount = debt * close_factor as i128 / 10_000;
+    if debt - repay_amount < min_borrow {
+        if balance < debt {
+            return Err(Skip::CannotCloseOut);
+        }
+        repay_amount = debt;
+    }
+
+    let repay_amount = repay_amount.min(balance);
+    if repay_amount <= 0 {
+        return Err(Skip::NoUsdc);
+    }
+    Ok(repay_amount)
+}
+
+/// Take a liquidation earning `profit` for `fee` if it clears the minimum
+/// profit and fits both fee limits, charging the fee to the budget
+fn approve(
+    profit: i128,
+    fee: u32,
+    min_profit: i128,
+    max_fee: u32,
+    budget: &mut FeeBudget,
+) -> Result<(), Skip> {
+    if profit < min_profit {
+        return Err(Skip::BelowMinProfit(profit));
+    }
+    if fee > max_fee {
+        return Err(Skip::FeeOverMax(fee));
+    }
+    if u64::from(fee) > budget.remaining() {
+        return Err(Skip::BudgetSpent);
+    }
+
+    budget.spend(fee);
+    Ok(())
+}
+
+/// USDC value, in USDC base units, of `amount` of a token at `price`
+fn usdc_value(
+    amount: i128,
+    price: i128,
+    price_decimals: u32,
+    token_decimals: u32,
+    usdc_decimals: u32,
+) -> Option<i128> {
+    // Scale in one step, so an 18-decimal token on a 14-decimal feed does not overflow
+    let value = amount.checked_mul(price)?;
+    let decimals = price_decimals + token_decimals;
+    if decimals >= usdc_decimals {
+        Some(value / 10_i128.pow(decimals - usdc_decimals))
+    } else {
+        value.checked_mul(10_i128.pow(usdc_decimals - decimals))
+    }
+}
+
+impl Prices {
+    /// USDC value of `amount` of `token`, in USDC base units, if it can be priced
+    fn value(
+        &mut self,
+        credit_line: &CreditLine,
+        token: &str,
+        amount: i128,
+    ) -> Result<Option<i128>, Error> {
+        if !self.tokens.contains_key(token) {
+            let price = self.lookup(credit_line, token)?;
+            self.tokens.insert(token.to_string(), price);
+        }
+        let Some((price, token_decimals)) = self.tokens[token] else {
+            return Ok(None);
+        };
+
+        let price_decimals = self.oracle.as_ref().map_or(0, |(_, decimals)| *decimals);
+        Ok(usdc_value(
+            amount,
+            price,
+            price_decimals,
+            token_decimals,
+            self.usdc_decimals,
+        ))
+    }
+
+    /// A token's price in USDC as the credit line reads it, honouring its
+    /// oracle settings
+    fn lookup(&self, credit_line: &CreditLine, token: &str) -> Result<Option<(i128, u32)>, Error> {
+        let decimals = Token::new(credit_line.contract().at(token)?).decimals()?;
+        let Some((oracle, price_decimals)) = &self.oracle else {
+            return Ok(Some((1, decimals)));
+        };
+        let scale = 10_i128.pow(*price_decimals);
+
+        let Some(price) = self.feed_price(oracle, &Asset::Stellar(token.to_string()), scale)?
+        else {
+            return Ok(None);
+        };
+        let QuoteAsset::Other(usdc_asset) = &self.settings.quote else {
+            return Ok(Some((price, decimals)));
+        };
+        let Some(usdc_price) = self.feed_price(oracle, usdc_asset, scale)? else {
+            return Ok(None);
+        };
+        let price = price
+            .checked_mul(scale)
+            .map(|price| price / usdc_price)
+            .filter(|price| *price > 0);
+        Ok(price.map(|price| (price, decimals)))
+    }
+
+    /// An asset's latest feed price, inverted when the feed reports it the
+    /// other way round
+    fn feed_price(
+        &self,
+        oracle: &Oracle,
+        asset: &Asset,
+        scale: i128,
+    ) -> Result<Option<i128>, Error> {
+        let price = match oracle.lastprice(asset)? {
+            Some(record) if record.price > 0 => record.price,
+            _ => return Ok(None),
+        };
+        if !self.settings.invert {
+            return Ok(Some(price));
+        }
+        let price = scale
+            .checked_mul(scale)
+            .map(|square| square / price)
+            .filter(|price| *price > 0);
+        Ok(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repays_up_to_the_close_factor_and_balance() {
+        // Half of a 1,000 debt, or what the keeper holds if less
+        assert_eq!(repay_amount(1_000, 5_000, 0, 2_000), Ok(500));
+        assert_eq!(repay_amount(1_000, 5_000, 0, 300), Ok(300));
+        assert_eq!(repay_amount(1_000, 5_000, 0, 0), Err(Skip::NoUsdc));
+
+        // When half would be left as dust the debt is closed out, which the
+        // balance must cover in full
+        assert_eq!(repay_amount(1_000, 5_000, 600, 1_000), Ok(1_000));
+        assert_eq!(
+            repay_amount(1_000, 5_000, 600, 999),
+            Err(Skip::CannotCloseOut)
+        );
+    }
+
+    #[test]
+    fn approves_only_profitable_liquidations_within_the_fee_limits() {
+        let mut budget = FeeBudget::new(250);
+
+        assert_eq!(
+            approve(9, 100, 10, 200, &mut budget),
+            Err(Skip::BelowMinProfit(9))
+        );
+        assert_eq!(
+            approve(10, 201, 10, 200, &mut budget),
+            Err(Skip::FeeOverMax(201))
+        );
+        assert_eq!(budget.remaining(), 250);
+
+        // Approved fees come out of the budget until it runs short
+        assert_eq!(approve(10, 200, 10, 200, &mut budget), Ok(()));
+        assert_eq!(budget.remaining(), 50);
+        assert_eq!(
+            approve(1_000, 51, 10, 200, &mut budget),
+            Err(Skip::BudgetSpent)
+        );
+        assert_eq!(approve(1_000, 50, 10, 200, &mut budget), Ok(()));
+        assert_eq!(budget.remaining(), 0);
+    }
+
+    #[test]
+    fn values_collateral_in_usdc_base_units() {
+        // 100 of an 18-decimal token at 1.5 on a 14-decimal feed, in 7-decimal USDC
+        let amount = 100 * 10_i128.pow(18);
+        assert_eq!(
+            usdc_value(amount, 15 * 10_i128.pow(13), 14, 18, 7),
+            Some(150 * 10_i128.pow(7))
+        );
+
+        // Without an oracle tokens trade 1:1
+        assert_eq!(usdc_value(5_000_000, 1, 0, 6, 7), Some(50_000_000));
+        assert_eq!(usdc_value(i128::MAX, 2, 0, 7, 7), None);
+    }
+}
@@ -0,0 +1,63 @@
+//! Reference liquidation keeper for the BondBridge credit line.
+//!
+//! Every round the keeper pages through `list_liquidatable`, prices each
+//! position's collateral from the market's oracle and simulates liquidating the
+//! most valuable token, repaying as much of the debt as the close factor and
+//! its USDC balance allow. It submits only liquidations whose seized collateral
+//! is worth at least `KEEPER_MIN_PROFIT` more than the repayment and whose fee
+//! fits both the per-transaction maximum and the daily fee budget. Between
+//! rounds it pokes the market every `KEEPER_POKE_SECS`, so interest accrues and
+//! storage stays alive while the market is quiet.
+//!
+//! Configuration comes from the environment; see `Config::from_env`.
+
+mod budget;
+mod config;
+mod keeper;
+
+use std::process::ExitCode;
+use std::thread;
+use std::time::Instant;
+
+use config::Config;
+use keeper::Keeper;
+
+fn main() -> ExitCode {
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut keeper = match Keeper::new(&config) {
+        Ok(keeper) => keeper,
+        Err(error) => {
+            eprintln!("failed to start: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "keeper {} watching {}",
+        keeper.address(),
+        config.credit_line
+    );
+
+    let mut last_poke: Option<Instant> = None;
+    loop {
+        if last_poke.is_none_or(|at| at.elapsed() >= config.poke_interval) {
+            match keeper.poke() {
+                Ok(()) => last_poke = Some(Instant::now()),
+                Err(error) => eprintln!("poke failed: {error}"),
+            }
+        }
+
+        match keeper.run_round() {
+            Ok(0) => {}
+            Ok(liquidated) => println!("liquidated {liquidated} positions"),
+            Err(error) => eprintln!("round failed: {error}"),
+        }
+        thread::sleep(config.poll_interval);
+    }
+}
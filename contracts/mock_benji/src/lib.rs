@@ -4,12 +4,127 @@ use soroban_sdk::{
 };
 use soroban_token_sdk::metadata::TokenMetadata;
 
+#[contracttype]
+pub struct AllowanceDataKey {
+    pub from: Address,
+    pub spender: Address,
+}
+
+#[contracttype]
+pub struct AllowanceValue {
+    pub amount: i128, // in tokens, not shares
+    pub expiration_ledger: u32,
+}
+
 #[contracttype]
 pub enum DataKey {
     Admin,
     Metadata,
-    Balance(Address),
-    TotalSupply,
+    Balance(Address), // shares, scaled to tokens by YieldIndex
+    TotalSupply,      // total shares
+    YieldIndex,
+    Allowance(AllowanceDataKey),
+}
+
+/// Yield index of 1.0: one share is worth one token
+const INDEX_ONE: i128 = 1_000_000_000;
+
+fn yield_index(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::YieldIndex)
+        .unwrap_or(INDEX_ONE)
+}
+
+fn shares_of(env: &Env, id: Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Balance(id))
+        .unwrap_or(0)
+}
+
+fn read_allowance(env: &Env, from: Address, spender: Address) -> AllowanceValue {
+    let key = DataKey::Allowance(AllowanceDataKey { from, spender });
+    match env.storage().temporary().get::<_, AllowanceValue>(&key) {
+        Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => allowance,
+        Some(allowance) => AllowanceValue {
+            amount: 0,
+            expiration_ledger: allowance.expiration_ledger,
+        },
+        None => AllowanceValue {
+            amount: 0,
+            expiration_ledger: 0,
+        },
+    }
+}
+
+fn write_allowance(
+    env: &Env,
+    from: Address,
+    spender: Address,
+    amount: i128,
+    expiration_ledger: u32,
+) {
+    if amount > 0 && expiration_ledger < env.ledger().sequence() {
+        panic!("Expiration ledger is in the past");
+    }
+
+    let key = DataKey::Allowance(AllowanceDataKey { from, spender });
+    env.storage().temporary().set(
+        &key,
+        &AllowanceValue {
+            amount,
+            expiration_ledger,
+        },
+    );
+
+    if amount > 0 {
+        let live_for = expiration_ledger - env.ledger().sequence();
+        env.storage()
+            .temporary()
+            .extend_ttl(&key, live_for, live_for);
+    }
+}
+
+fn spend_allowance(env: &Env, from: Address, spender: Address, amount: i128) {
+    let allowance = read_allowance(env, from.clone(), spender.clone());
+    if allowance.amount < amount {
+        panic!("Insufficient allowance");
+    }
+
+    if amount > 0 {
+        write_allowance(
+            env,
+            from,
+            spender,
+            allowance.amount - amount,
+            allowance.expiration_ledger,
+        );
+    }
+}
+
+fn move_balance(env: &Env, from: Address, to: Address, amount: i128) {
+    if amount < 0 {
+        panic!("Amount must be non-negative");
+    }
+
+    // Convert to shares, rounding up so the sender covers the full amount
+    let index = yield_index(env);
+    let shares = (amount * INDEX_ONE + index - 1) / index;
+
+    let from_shares = shares_of(env, from.clone());
+    if from_shares < shares {
+        panic!("Insufficient balance");
+    }
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(from.clone()), &(from_shares - shares));
+
+    let to_shares = shares_of(env, to.clone());
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(to.clone()), &(to_shares + shares));
 }
 
 #[contract]
@@ -53,10 +168,11 @@ impl BenjiToken {
         }
 
         // Update balance
-        let balance = Self::balance(env.clone(), to.clone());
+        let shares = (amount * INDEX_ONE) / yield_index(&env);
+        let balance = shares_of(&env, to.clone());
         env.storage()
             .persistent()
-            .set(&DataKey::Balance(to.clone()), &(balance + amount));
+            .set(&DataKey::Balance(to.clone()), &(balance + shares));
 
         // Update total supply
         let total: i128 = env
@@ -66,59 +182,72 @@ impl BenjiToken {
             .unwrap_or(0);
         env.storage()
             .instance()
-            .set(&DataKey::TotalSupply, &(total + amount));
+            .set(&DataKey::TotalSupply, &(total + shares));
+    }
+
+    /// Distribute fund yield to all holders by rebasing balances (admin only)
+    pub fn distribute_yield(env: Env, rate_bps: u32) {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("Not initialized");
+        admin.require_auth();
+
+        let index = yield_index(&env) * (10000 + rate_bps as i128) / 10000;
+        env.storage().instance().set(&DataKey::YieldIndex, &index);
+    }
+
+    /// Current token value of one share, scaled by 1e9
+    pub fn yield_index(env: Env) -> i128 {
+        yield_index(&env)
+    }
+
+    /// Total token supply including distributed yield
+    pub fn total_supply(env: Env) -> i128 {
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalSupply)
+            .unwrap_or(0);
+        (total * yield_index(&env)) / INDEX_ONE
     }
 }
 
 #[contractimpl]
 impl TokenInterface for BenjiToken {
-    fn allowance(_env: Env, _from: Address, _spender: Address) -> i128 {
-        0 // Simplified: no allowance for hackathon
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        read_allowance(&env, from, spender).amount
     }
 
-    fn approve(
-        _env: Env,
-        _from: Address,
-        _spender: Address,
-        _amount: i128,
-        _expiration_ledger: u32,
-    ) {
-        panic!("Not implemented for hackathon");
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+
+        if amount < 0 {
+            panic!("Amount must be non-negative");
+        }
+
+        write_allowance(&env, from, spender, amount, expiration_ledger);
     }
 
     fn balance(env: Env, id: Address) -> i128 {
-        env.storage()
-            .persistent()
-            .get(&DataKey::Balance(id))
-            .unwrap_or(0)
+        (shares_of(&env, id) * yield_index(&env)) / INDEX_ONE
     }
 
     fn transfer(env: Env, from: Address, to_muxed: soroban_sdk::MuxedAddress, amount: i128) {
         from.require_auth();
+        move_balance(&env, from, to_muxed.address(), amount);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
 
         if amount < 0 {
             panic!("Amount must be non-negative");
         }
 
-        let to = to_muxed.address();
-
-        let from_balance = Self::balance(env.clone(), from.clone());
-        let to_balance = Self::balance(env.clone(), to.clone());
-
-        if from_balance < amount {
-            panic!("Insufficient balance");
-        }
-
-        env.storage()
-            .persistent()
-            .set(&DataKey::Balance(from.clone()), &(from_balance - amount));
-        env.storage()
-            .persistent()
-            .set(&DataKey::Balance(to.clone()), &(to_balance + amount));
-    }
-
-    fn transfer_from(_env: Env, _spender: Address, _from: Address, _to: Address, _amount: i128) {
-        panic!("Not implemented for hackathon");
+        spend_allowance(&env, from.clone(), spender, amount);
+        move_balance(&env, from, to, amount);
     }
 
     fn burn(_env: Env, _from: Address, _amount: i128) {
@@ -0,0 +1,137 @@
+#![no_std]
+use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, Symbol, Vec};
+
+/// Asset identifier per SEP-40
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
+
+/// Price record per SEP-40
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    Base,
+    Decimals,
+    Resolution,
+    Assets,
+    Prices(Asset),
+}
+
+#[contract]
+pub struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    /// Initialize the oracle
+    pub fn initialize(env: Env, admin: Address, base: Asset, decimals: u32, resolution: u32) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Base, &base);
+        env.storage().instance().set(&DataKey::Decimals, &decimals);
+        env.storage()
+            .instance()
+            .set(&DataKey::Resolution, &resolution);
+        env.storage()
+            .instance()
+            .set(&DataKey::Assets, &Vec::<Asset>::new(&env));
+    }
+
+    /// Record a price for an asset at a given timestamp (admin only)
+    pub fn set_price(env: Env, asset: Asset, price: i128, timestamp: u64) {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("Not initialized");
+        admin.require_auth();
+
+        // Register the asset on its first price
+        let mut assets: Vec<Asset> = env
+            .storage()
+            .instance()
+            .get(&DataKey::Assets)
+            .unwrap_or(Vec::new(&env));
+        if !assets.contains(&asset) {
+            assets.push_back(asset.clone());
+            env.storage().instance().set(&DataKey::Assets, &assets);
+        }
+
+        // Keep records ordered newest first
+        let mut prices: Vec<PriceData> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Prices(asset.clone()))
+            .unwrap_or(Vec::new(&env));
+        prices.push_front(PriceData { price, timestamp });
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Prices(asset), &prices);
+    }
+
+    /// Base asset prices are quoted in
+    pub fn base(env: Env) -> Asset {
+        env.storage()
+            .instance()
+            .get(&DataKey::Base)
+            .expect("Not initialized")
+    }
+
+    /// Assets with at least one recorded price
+    pub fn assets(env: Env) -> Vec<Asset> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Assets)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Number of decimals prices are reported with
+    pub fn decimals(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Decimals)
+            .expect("Not initialized")
+    }
+
+    /// Expected seconds between price updates
+    pub fn resolution(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Resolution)
+            .expect("Not initialized")
+    }
+
+    /// Price recorded for an asset at an exact timestamp
+    pub fn price(env: Env, asset: Asset, timestamp: u64) -> Option<PriceData> {
+        let prices: Vec<PriceData> = env.storage().persistent().get(&DataKey::Prices(asset))?;
+
+        prices.iter().find(|p| p.timestamp == timestamp)
+    }
+
+    /// Up to `records` most recent prices for an asset, newest first
+    pub fn prices(env: Env, asset: Asset, records: u32) -> Option<Vec<PriceData>> {
+        let prices: Vec<PriceData> = env.storage().persistent().get(&DataKey::Prices(asset))?;
+
+        Some(prices.slice(0..records.min(prices.len())))
+    }
+
+    /// Most recent price for an asset
+    pub fn lastprice(env: Env, asset: Asset) -> Option<PriceData> {
+        let prices: Vec<PriceData> = env.storage().persistent().get(&DataKey::Prices(asset))?;
+
+        prices.first()
+    }
+}
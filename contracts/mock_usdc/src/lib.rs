@@ -1,16 +1,235 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token::TokenInterface, Address, Env, String,
+    contract, contractevent, contractimpl, contracttype, token::TokenInterface, Address, Env,
+    String,
+};
+use soroban_token_sdk::events::{
+    Approve, Burn, Clawback, MintWithAmountOnly, Transfer, TransferWithAmountOnly,
 };
 use soroban_token_sdk::metadata::TokenMetadata;
 
+/// Admin role handed over, as the Stellar Asset Contract reports it
+#[contractevent(topics = ["set_admin"], data_format = "single-value")]
+pub struct SetAdmin {
+    #[topic]
+    pub admin: Address,
+    pub new_admin: Address,
+}
+
+/// Account frozen or unfrozen, as the Stellar Asset Contract reports a change
+/// of authorization
+#[contractevent(topics = ["set_authorized"], data_format = "single-value")]
+pub struct SetAuthorized {
+    #[topic]
+    pub id: Address,
+    pub authorize: bool,
+}
+
+/// Account added to or removed from the blocklist
+#[contractevent(topics = ["blocklist"], data_format = "single-value")]
+pub struct Blocklist {
+    #[topic]
+    pub id: Address,
+    pub blocked: bool,
+}
+
+#[contracttype]
+pub struct AllowanceDataKey {
+    pub from: Address,
+    pub spender: Address,
+}
+
+#[contracttype]
+pub struct AllowanceValue {
+    pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+/// Tokens an address has taken from the faucet on one day
+#[contracttype]
+pub struct FaucetClaim {
+    pub day: u64,
+    pub amount: i128,
+}
+
 #[contracttype]
 pub enum DataKey {
     Admin,
     Metadata,
     Balance(Address),
     TotalSupply,
+    Allowance(AllowanceDataKey),
+    Frozen(Address),
+    FaucetLimit,
+    FaucetClaim(Address),
+    MaxSupply,
+    Blocked(Address),
+}
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const DAY_IN_LEDGERS: u32 = 17280;
+
+/// Whole tokens each address may take from the faucet per day until the admin
+/// changes it
+const DEFAULT_FAUCET_LIMIT: i128 = 1_000;
+
+fn require_admin(env: &Env) -> Address {
+    let admin: Address = env
+        .storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .expect("Not initialized");
+    admin.require_auth();
+    admin
+}
+
+fn check_not_frozen(env: &Env, id: &Address) {
+    if env
+        .storage()
+        .persistent()
+        .get(&DataKey::Frozen(id.clone()))
+        .unwrap_or(false)
+    {
+        panic!("Account is frozen");
+    }
+}
+
+/// Refuse any use of the token by a blocked account, as Circle's blacklist does
+fn check_not_blocked(env: &Env, id: &Address) {
+    if env
+        .storage()
+        .persistent()
+        .has(&DataKey::Blocked(id.clone()))
+    {
+        panic!("Account is blocked");
+    }
+}
+
+fn read_allowance(env: &Env, from: Address, spender: Address) -> AllowanceValue {
+    let key = DataKey::Allowance(AllowanceDataKey { from, spender });
+    match env.storage().temporary().get::<_, AllowanceValue>(&key) {
+        Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => allowance,
+        Some(allowance) => AllowanceValue {
+            amount: 0,
+            expiration_ledger: allowance.expiration_ledger,
+        },
+        None => AllowanceValue {
+            amount: 0,
+            expiration_ledger: 0,
+        },
+    }
+}
+
+fn write_allowance(
+    env: &Env,
+    from: Address,
+    spender: Address,
+    amount: i128,
+    expiration_ledger: u32,
+) {
+    if amount > 0 && expiration_ledger < env.ledger().sequence() {
+        panic!("Expiration ledger is in the past");
+    }
+
+    let key = DataKey::Allowance(AllowanceDataKey { from, spender });
+    env.storage().temporary().set(
+        &key,
+        &AllowanceValue {
+            amount,
+            expiration_ledger,
+        },
+    );
+
+    if amount > 0 {
+        let live_for = expiration_ledger - env.ledger().sequence();
+        env.storage()
+            .temporary()
+            .extend_ttl(&key, live_for, live_for);
+    }
+}
+
+fn spend_allowance(env: &Env, from: Address, spender: Address, amount: i128) {
+    let allowance = read_allowance(env, from.clone(), spender.clone());
+    if allowance.amount < amount {
+        panic!("Insufficient allowance");
+    }
+
+    if amount > 0 {
+        write_allowance(
+            env,
+            from,
+            spender,
+            allowance.amount - amount,
+            allowance.expiration_ledger,
+        );
+    }
+}
+
+fn mint_balance(env: &Env, to: Address, amount: i128) {
+    if amount < 0 {
+        panic!("Amount must be non-negative");
+    }
+
+    check_not_blocked(env, &to);
+
+    let total = UsdcToken::total_supply(env.clone());
+    let total = total.checked_add(amount).expect("Total supply overflow");
+    if UsdcToken::max_supply(env.clone()).is_some_and(|max_supply| total > max_supply) {
+        panic!("Max supply exceeded");
+    }
+    env.storage().instance().set(&DataKey::TotalSupply, &total);
+
+    let balance = UsdcToken::balance(env.clone(), to.clone());
+    let balance = balance.checked_add(amount).expect("Balance overflow");
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(to), &balance);
+}
+
+fn burn_balance(env: &Env, from: Address, amount: i128) {
+    if amount < 0 {
+        panic!("Amount must be non-negative");
+    }
+
+    let balance = UsdcToken::balance(env.clone(), from.clone());
+    if balance < amount {
+        panic!("Insufficient balance");
+    }
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(from), &(balance - amount));
+
+    let total = UsdcToken::total_supply(env.clone());
+    let total = total.checked_sub(amount).expect("Total supply underflow");
+    env.storage().instance().set(&DataKey::TotalSupply, &total);
+}
+
+fn move_balance(env: &Env, from: Address, to: Address, amount: i128) {
+    if amount < 0 {
+        panic!("Amount must be non-negative");
+    }
+
+    check_not_frozen(env, &from);
+    check_not_frozen(env, &to);
+    check_not_blocked(env, &from);
+    check_not_blocked(env, &to);
+
+    let from_balance = UsdcToken::balance(env.clone(), from.clone());
+    if from_balance < amount {
+        panic!("Insufficient balance");
+    }
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(from), &(from_balance - amount));
+
+    let to_balance = UsdcToken::balance(env.clone(), to.clone());
+    let to_balance = to_balance.checked_add(amount).expect("Balance overflow");
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(to), &to_balance);
 }
 
 #[contract]
@@ -37,50 +256,210 @@ impl UsdcToken {
             },
         );
         env.storage().instance().set(&DataKey::TotalSupply, &0_i128);
+        env.storage().instance().set(
+            &DataKey::FaucetLimit,
+            &(DEFAULT_FAUCET_LIMIT * 10_i128.pow(decimal)),
+        );
     }
 
     pub fn mint(env: Env, to: Address, amount: i128) {
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Admin)
-            .expect("Not initialized");
-        admin.require_auth();
+        require_admin(&env);
+        mint_balance(&env, to.clone(), amount);
+        MintWithAmountOnly { to, amount }.publish(&env);
+    }
+
+    /// Mint tokens to `to` without the admin, up to the faucet limit per
+    /// address per day
+    pub fn faucet(env: Env, to: Address, amount: i128) {
+        to.require_auth();
+        check_not_frozen(&env, &to);
+
+        let limit = Self::faucet_limit(env.clone());
+        if limit == 0 {
+            panic!("Faucet is disabled");
+        }
+
+        let day = env.ledger().timestamp() / SECONDS_PER_DAY;
+        let key = DataKey::FaucetClaim(to.clone());
+        let claimed = match env.storage().temporary().get::<_, FaucetClaim>(&key) {
+            Some(claim) if claim.day == day => claim.amount,
+            _ => 0,
+        };
+        if amount > limit - claimed {
+            panic!("Faucet limit exceeded");
+        }
+
+        mint_balance(&env, to.clone(), amount);
+        MintWithAmountOnly { to, amount }.publish(&env);
+        env.storage().temporary().set(
+            &key,
+            &FaucetClaim {
+                day,
+                amount: claimed + amount,
+            },
+        );
+        env.storage()
+            .temporary()
+            .extend_ttl(&key, DAY_IN_LEDGERS, DAY_IN_LEDGERS);
+    }
+
+    /// Set how much each address may take from the faucet per day; 0 turns the
+    /// faucet off (admin only)
+    pub fn set_faucet_limit(env: Env, admin: Address, amount: i128) {
+        if require_admin(&env) != admin {
+            panic!("Not the admin");
+        }
 
         if amount < 0 {
             panic!("Amount must be non-negative");
         }
 
-        let balance = Self::balance(env.clone(), to.clone());
-        env.storage()
-            .persistent()
-            .set(&DataKey::Balance(to.clone()), &(balance + amount));
+        env.storage().instance().set(&DataKey::FaucetLimit, &amount);
+    }
 
-        let total: i128 = env
-            .storage()
+    /// Cap total supply, refusing mints beyond it; `None` lifts the cap
+    /// (admin only)
+    pub fn set_max_supply(env: Env, admin: Address, max_supply: Option<i128>) {
+        if require_admin(&env) != admin {
+            panic!("Not the admin");
+        }
+
+        match max_supply {
+            Some(max_supply) if max_supply < 0 => panic!("Max supply must be non-negative"),
+            Some(max_supply) => env
+                .storage()
+                .instance()
+                .set(&DataKey::MaxSupply, &max_supply),
+            None => env.storage().instance().remove(&DataKey::MaxSupply),
+        }
+    }
+
+    /// Cap on total supply, if any
+    pub fn max_supply(env: Env) -> Option<i128> {
+        env.storage().instance().get(&DataKey::MaxSupply)
+    }
+
+    /// Tokens in circulation
+    pub fn total_supply(env: Env) -> i128 {
+        env.storage()
             .instance()
             .get(&DataKey::TotalSupply)
-            .unwrap_or(0);
+            .unwrap_or(0)
+    }
+
+    /// Tokens each address may take from the faucet per day; 0 if it is off
+    pub fn faucet_limit(env: Env) -> i128 {
         env.storage()
             .instance()
-            .set(&DataKey::TotalSupply, &(total + amount));
+            .get(&DataKey::FaucetLimit)
+            .unwrap_or(0)
+    }
+
+    /// Hand over the admin role (admin only)
+    pub fn set_admin(env: Env, new_admin: Address) {
+        let admin = require_admin(&env);
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        SetAdmin { admin, new_admin }.publish(&env);
+    }
+
+    /// Remove tokens from an account, reducing total supply (admin only)
+    pub fn clawback(env: Env, from: Address, amount: i128) {
+        require_admin(&env);
+        burn_balance(&env, from.clone(), amount);
+        Clawback { from, amount }.publish(&env);
+    }
+
+    /// Block an account from sending or receiving tokens (admin only)
+    pub fn freeze(env: Env, id: Address) {
+        require_admin(&env);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Frozen(id.clone()), &true);
+        SetAuthorized {
+            id,
+            authorize: false,
+        }
+        .publish(&env);
+    }
+
+    /// Lift a freeze on an account (admin only)
+    pub fn unfreeze(env: Env, id: Address) {
+        require_admin(&env);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Frozen(id.clone()));
+        SetAuthorized {
+            id,
+            authorize: true,
+        }
+        .publish(&env);
+    }
+
+    /// Whether an account is frozen
+    pub fn is_frozen(env: Env, id: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Frozen(id))
+            .unwrap_or(false)
+    }
+
+    /// Blocklist an account, as Circle does (admin only)
+    ///
+    /// Unlike a freeze, which only stops transfers, a blocked account cannot
+    /// send, receive, be minted to, burn, approve or spend an allowance. The
+    /// admin can still claw its balance back.
+    pub fn block(env: Env, id: Address) {
+        require_admin(&env);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Blocked(id.clone()), &true);
+        Blocklist { id, blocked: true }.publish(&env);
+    }
+
+    /// Remove an account from the blocklist (admin only)
+    pub fn unblock(env: Env, id: Address) {
+        require_admin(&env);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Blocked(id.clone()));
+        Blocklist { id, blocked: false }.publish(&env);
+    }
+
+    /// Whether an account is on the blocklist
+    pub fn is_blocked(env: Env, id: Address) -> bool {
+        env.storage().persistent().has(&DataKey::Blocked(id))
     }
 }
 
 #[contractimpl]
 impl TokenInterface for UsdcToken {
-    fn allowance(_env: Env, _from: Address, _spender: Address) -> i128 {
-        0
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        read_allowance(&env, from, spender).amount
     }
 
-    fn approve(
-        _env: Env,
-        _from: Address,
-        _spender: Address,
-        _amount: i128,
-        _expiration_ledger: u32,
-    ) {
-        panic!("Not implemented");
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        check_not_blocked(&env, &from);
+        check_not_blocked(&env, &spender);
+
+        if amount < 0 {
+            panic!("Amount must be non-negative");
+        }
+
+        write_allowance(
+            &env,
+            from.clone(),
+            spender.clone(),
+            amount,
+            expiration_ledger,
+        );
+        Approve {
+            from,
+            spender,
+            amount,
+            expiration_ledger,
+        }
+        .publish(&env);
     }
 
     fn balance(env: Env, id: Address) -> i128 {
@@ -92,38 +471,48 @@ impl TokenInterface for UsdcToken {
 
     fn transfer(env: Env, from: Address, to_muxed: soroban_sdk::MuxedAddress, amount: i128) {
         from.require_auth();
-
-        if amount < 0 {
-            panic!("Amount must be non-negative");
+        move_balance(&env, from.clone(), to_muxed.address(), amount);
+        Transfer {
+            from,
+            to: to_muxed.address(),
+            to_muxed_id: to_muxed.id(),
+            amount,
         }
+        .publish(&env);
+    }
 
-        let to = to_muxed.address();
-
-        let from_balance = Self::balance(env.clone(), from.clone());
-        let to_balance = Self::balance(env.clone(), to.clone());
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        check_not_blocked(&env, &spender);
 
-        if from_balance < amount {
-            panic!("Insufficient balance");
+        if amount < 0 {
+            panic!("Amount must be non-negative");
         }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Balance(from), &(from_balance - amount));
-        env.storage()
-            .persistent()
-            .set(&DataKey::Balance(to), &(to_balance + amount));
+        spend_allowance(&env, from.clone(), spender, amount);
+        move_balance(&env, from.clone(), to.clone(), amount);
+        TransferWithAmountOnly { from, to, amount }.publish(&env);
     }
 
-    fn transfer_from(_env: Env, _spender: Address, _from: Address, _to: Address, _amount: i128) {
-        panic!("Not implemented");
+    fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+        check_not_blocked(&env, &from);
+        burn_balance(&env, from.clone(), amount);
+        Burn { from, amount }.publish(&env);
     }
 
-    fn burn(_env: Env, _from: Address, _amount: i128) {
-        panic!("Not implemented");
-    }
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        check_not_blocked(&env, &spender);
+        check_not_blocked(&env, &from);
+
+        if amount < 0 {
+            panic!("Amount must be non-negative");
+        }
 
-    fn burn_from(_env: Env, _spender: Address, _from: Address, _amount: i128) {
-        panic!("Not implemented");
+        spend_allowance(&env, from.clone(), spender, amount);
+        burn_balance(&env, from.clone(), amount);
+        Burn { from, amount }.publish(&env);
     }
 
     fn decimals(env: Env) -> u32 {
@@ -0,0 +1,24 @@
+use soroban_sdk::{contractevent, Address};
+
+/// Position token minted with a fresh vault
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Minted {
+    #[topic]
+    pub token_id: u64,
+    #[topic]
+    pub owner: Address,
+    pub vault: Address,
+}
+
+/// Position token, and control of its vault, handed to a new holder
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Transferred {
+    #[topic]
+    pub token_id: u64,
+    #[topic]
+    pub from: Address,
+    #[topic]
+    pub to: Address,
+}
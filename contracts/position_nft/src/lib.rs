@@ -0,0 +1,321 @@
+#![no_std]
+
+mod events;
+
+use events::{Minted, Transferred};
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, Address, BytesN, Env, Map,
+};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    NotOwner = 3,
+    TokenNotFound = 4,
+}
+
+/// Credit line entry points any payer may call for a vault
+#[contractclient(name = "CreditLineClient")]
+pub trait CreditLine {
+    fn deposit_collateral(
+        env: Env,
+        payer: Address,
+        account_id: u32,
+        token: Address,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+        referrer: Option<Address>,
+    );
+    fn repay(
+        env: Env,
+        payer: Address,
+        account_id: u32,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+    );
+}
+
+/// Vault entry points reserved for this contract
+#[contractclient(name = "PositionVaultClient")]
+pub trait PositionVault {
+    fn deposit(env: Env, payer: Address, token: Address, amount: i128);
+    fn borrow(env: Env, amount: i128, to: Address);
+    fn withdraw(env: Env, token: Address, amount: i128, to: Address);
+    fn claim_collateral_yield(env: Env, to: Address) -> Map<Address, i128>;
+    fn claim_rewards(env: Env, to: Address) -> i128;
+}
+
+/// Credit line account holding each vault's position
+const VAULT_ACCOUNT: u32 = 0;
+
+#[contracttype]
+pub enum DataKey {
+    CreditLine,
+    Usdc,
+    VaultWasm,
+    NextId,
+    Owner(u64),
+    Vault(u64),
+    Balance(Address), // number of position tokens held
+}
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const TOKEN_BUMP_AMOUNT: u32 = 365 * DAY_IN_LEDGERS;
+const TOKEN_LIFETIME_THRESHOLD: u32 = TOKEN_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+fn credit_line(env: &Env) -> Result<CreditLineClient<'_>, Error> {
+    let credit_line: Address = env
+        .storage()
+        .instance()
+        .get(&DataKey::CreditLine)
+        .ok_or(Error::NotInitialized)?;
+    Ok(CreditLineClient::new(env, &credit_line))
+}
+
+fn vault(env: &Env, token_id: u64) -> Result<Address, Error> {
+    let key = DataKey::Vault(token_id);
+    let vault = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .ok_or(Error::TokenNotFound)?;
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    Ok(vault)
+}
+
+fn write_owner(env: &Env, token_id: u64, owner: &Address) {
+    let key = DataKey::Owner(token_id);
+    env.storage().persistent().set(&key, owner);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+}
+
+fn update_balance(env: &Env, owner: &Address, delta: i64) {
+    let key = DataKey::Balance(owner.clone());
+    let balance: u64 = env.storage().persistent().get(&key).unwrap_or(0);
+    env.storage()
+        .persistent()
+        .set(&key, &balance.saturating_add_signed(delta));
+}
+
+/// Check that `owner` holds `token_id` and has authorized the call, returning its vault
+fn require_owner(env: &Env, owner: &Address, token_id: u64) -> Result<Address, Error> {
+    owner.require_auth();
+
+    let vault = vault(env, token_id)?;
+    if PositionNft::owner_of(env.clone(), token_id)? != *owner {
+        return Err(Error::NotOwner);
+    }
+
+    Ok(vault)
+}
+
+/// Transferable tokens that each own a credit line position.
+///
+/// Every token controls its own vault contract, which holds the position. The
+/// holder of the token can borrow and withdraw collateral from it, and claims
+/// its collateral yield and rewards; anyone can deposit collateral into it or
+/// repay its debt. Transferring the token hands over the whole position,
+/// collateral and debt alike.
+#[contract]
+pub struct PositionNft;
+
+#[contractimpl]
+impl PositionNft {
+    /// Initialize with the credit line and USDC token, and the wasm hash vaults are
+    /// deployed from
+    pub fn initialize(
+        env: Env,
+        credit_line: Address,
+        usdc: Address,
+        vault_wasm_hash: BytesN<32>,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::CreditLine) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::CreditLine, &credit_line);
+        env.storage().instance().set(&DataKey::Usdc, &usdc);
+        env.storage()
+            .instance()
+            .set(&DataKey::VaultWasm, &vault_wasm_hash);
+
+        Ok(())
+    }
+
+    /// Open a new vault with an initial collateral deposit, minting its token to `owner`
+    pub fn mint(env: Env, owner: Address, token: Address, amount: i128) -> Result<u64, Error> {
+        owner.require_auth();
+
+        let credit_line = credit_line(&env)?;
+        let usdc: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Usdc)
+            .ok_or(Error::NotInitialized)?;
+        let wasm_hash: BytesN<32> = env
+            .storage()
+            .instance()
+            .get(&DataKey::VaultWasm)
+            .ok_or(Error::NotInitialized)?;
+
+        let token_id: u64 = env.storage().instance().get(&DataKey::NextId).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::NextId, &(token_id + 1));
+
+        // The token id fixes the vault's address
+        let mut salt = [0u8; 32];
+        salt[24..].copy_from_slice(&token_id.to_be_bytes());
+        let vault = env
+            .deployer()
+            .with_current_contract(BytesN::from_array(&env, &salt))
+            .deploy_v2(
+                wasm_hash,
+                (
+                    env.current_contract_address(),
+                    credit_line.address.clone(),
+                    usdc,
+                ),
+            );
+
+        let key = DataKey::Vault(token_id);
+        env.storage().persistent().set(&key, &vault);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+        write_owner(&env, token_id, &owner);
+        update_balance(&env, &owner, 1);
+
+        PositionVaultClient::new(&env, &vault).deposit(&owner, &token, &amount);
+
+        Minted {
+            token_id,
+            owner,
+            vault,
+        }
+        .publish(&env);
+
+        Ok(token_id)
+    }
+
+    /// Add collateral to a token's vault, paid by `payer`
+    pub fn deposit(
+        env: Env,
+        payer: Address,
+        token_id: u64,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        payer.require_auth();
+
+        let vault = vault(&env, token_id)?;
+        credit_line(&env)?.deposit_collateral(
+            &payer,
+            &VAULT_ACCOUNT,
+            &token,
+            &amount,
+            &Some(vault),
+            &None,
+        );
+
+        Ok(())
+    }
+
+    /// Repay debt of a token's vault, paid by `payer`
+    pub fn repay(env: Env, payer: Address, token_id: u64, amount: i128) -> Result<(), Error> {
+        payer.require_auth();
+
+        let vault = vault(&env, token_id)?;
+        credit_line(&env)?.repay(&payer, &VAULT_ACCOUNT, &amount, &Some(vault));
+
+        Ok(())
+    }
+
+    /// Borrow USDC against a token's vault, sent to its holder
+    pub fn borrow(env: Env, owner: Address, token_id: u64, amount: i128) -> Result<(), Error> {
+        let vault = require_owner(&env, &owner, token_id)?;
+        PositionVaultClient::new(&env, &vault).borrow(&amount, &owner);
+
+        Ok(())
+    }
+
+    /// Withdraw collateral from a token's vault, sent to its holder
+    pub fn withdraw(
+        env: Env,
+        owner: Address,
+        token_id: u64,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        let vault = require_owner(&env, &owner, token_id)?;
+        PositionVaultClient::new(&env, &vault).withdraw(&token, &amount, &owner);
+
+        Ok(())
+    }
+
+    /// Claim the yield earned by a token's vault collateral, sent to its
+    /// holder, returning the amount of each token sent
+    pub fn claim_collateral_yield(
+        env: Env,
+        owner: Address,
+        token_id: u64,
+    ) -> Result<Map<Address, i128>, Error> {
+        let vault = require_owner(&env, &owner, token_id)?;
+        Ok(PositionVaultClient::new(&env, &vault).claim_collateral_yield(&owner))
+    }
+
+    /// Claim the borrower rewards earned by a token's vault, sent to its
+    /// holder, returning the amount sent
+    pub fn claim_rewards(env: Env, owner: Address, token_id: u64) -> Result<i128, Error> {
+        let vault = require_owner(&env, &owner, token_id)?;
+        Ok(PositionVaultClient::new(&env, &vault).claim_rewards(&owner))
+    }
+
+    /// Hand a token, and with it the vault's collateral and debt, to `to`
+    pub fn transfer(env: Env, from: Address, to: Address, token_id: u64) -> Result<(), Error> {
+        require_owner(&env, &from, token_id)?;
+
+        write_owner(&env, token_id, &to);
+        update_balance(&env, &from, -1);
+        update_balance(&env, &to, 1);
+
+        Transferred { token_id, from, to }.publish(&env);
+
+        Ok(())
+    }
+
+    /// Get the holder of a token, who controls its vault's position
+    pub fn owner_of(env: Env, token_id: u64) -> Result<Address, Error> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Owner(token_id))
+            .ok_or(Error::TokenNotFound)
+    }
+
+    /// Get the vault whose credit line position a token controls
+    pub fn vault_of(env: Env, token_id: u64) -> Result<Address, Error> {
+        vault(&env, token_id)
+    }
+
+    /// Get the number of position tokens held by `owner`
+    pub fn balance(env: Env, owner: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Balance(owner))
+            .unwrap_or(0)
+    }
+
+    /// Get the number of position tokens ever minted
+    pub fn total_supply(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::NextId).unwrap_or(0)
+    }
+}
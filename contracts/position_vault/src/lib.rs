@@ -0,0 +1,137 @@
+#![no_std]
+
+use soroban_sdk::{contract, contractclient, contractimpl, contracttype, token, Address, Env, Map};
+
+/// Credit line entry points a vault calls as the owner of its position
+#[contractclient(name = "CreditLineClient")]
+pub trait CreditLine {
+    fn deposit_collateral(
+        env: Env,
+        payer: Address,
+        account_id: u32,
+        token: Address,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+        referrer: Option<Address>,
+    );
+    fn borrow(
+        env: Env,
+        recipient: Address,
+        account_id: u32,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+        to: Option<Address>,
+    );
+    fn withdraw_collateral(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        token: Address,
+        amount: i128,
+        to: Option<Address>,
+    );
+    fn claim_collateral_yield(env: Env, user: Address, account_id: u32) -> Map<Address, i128>;
+    fn claim_rewards(env: Env, user: Address) -> i128;
+    fn get_reward_token(env: Env) -> Option<Address>;
+}
+
+/// Credit line account holding the vault's position
+const VAULT_ACCOUNT: u32 = 0;
+
+#[contracttype]
+pub enum DataKey {
+    Nft,
+    CreditLine,
+    Usdc,
+}
+
+fn require_nft(env: &Env) {
+    let nft: Address = env.storage().instance().get(&DataKey::Nft).unwrap();
+    nft.require_auth();
+}
+
+fn credit_line(env: &Env) -> CreditLineClient<'_> {
+    let credit_line: Address = env.storage().instance().get(&DataKey::CreditLine).unwrap();
+    CreditLineClient::new(env, &credit_line)
+}
+
+/// Holds a single credit line position on behalf of a position NFT.
+///
+/// Each NFT gets its own vault so its position has an address of its own. Only
+/// the NFT contract can move funds out, paying whoever holds the token.
+#[contract]
+pub struct PositionVault;
+
+#[contractimpl]
+impl PositionVault {
+    pub fn __constructor(env: Env, nft: Address, credit_line: Address, usdc: Address) {
+        env.storage().instance().set(&DataKey::Nft, &nft);
+        env.storage()
+            .instance()
+            .set(&DataKey::CreditLine, &credit_line);
+        env.storage().instance().set(&DataKey::Usdc, &usdc);
+    }
+
+    /// Credit collateral paid by `payer` to the vault's position, opening it on
+    /// the first deposit (NFT contract only)
+    ///
+    /// The credit line only opens an account for someone else with the
+    /// owner's consent, which the vault gives by making the call itself.
+    pub fn deposit(env: Env, payer: Address, token: Address, amount: i128) {
+        require_nft(&env);
+
+        let vault = env.current_contract_address();
+        credit_line(&env).deposit_collateral(
+            &payer,
+            &VAULT_ACCOUNT,
+            &token,
+            &amount,
+            &Some(vault),
+            &None,
+        );
+    }
+
+    /// Borrow USDC against the vault's position and send it to `to` (NFT contract only)
+    pub fn borrow(env: Env, amount: i128, to: Address) {
+        require_nft(&env);
+
+        let vault = env.current_contract_address();
+        credit_line(&env).borrow(&vault, &VAULT_ACCOUNT, &amount, &None, &Some(to));
+    }
+
+    /// Withdraw collateral from the vault's position and send it to `to` (NFT contract only)
+    pub fn withdraw(env: Env, token: Address, amount: i128, to: Address) {
+        require_nft(&env);
+
+        let vault = env.current_contract_address();
+        credit_line(&env).withdraw_collateral(&vault, &VAULT_ACCOUNT, &token, &amount, &Some(to));
+    }
+
+    /// Claim the yield earned by the position's collateral and send it to
+    /// `to`, returning the amount of each token sent (NFT contract only)
+    pub fn claim_collateral_yield(env: Env, to: Address) -> Map<Address, i128> {
+        require_nft(&env);
+
+        let vault = env.current_contract_address();
+        let claimed = credit_line(&env).claim_collateral_yield(&vault, &VAULT_ACCOUNT);
+        for (token, amount) in claimed.iter() {
+            token::Client::new(&env, &token).transfer(&vault, &to, &amount);
+        }
+        claimed
+    }
+
+    /// Claim the position's borrower rewards and send them to `to`, returning
+    /// the amount sent (NFT contract only)
+    pub fn claim_rewards(env: Env, to: Address) -> i128 {
+        require_nft(&env);
+
+        let vault = env.current_contract_address();
+        let credit_line = credit_line(&env);
+        let amount = credit_line.claim_rewards(&vault);
+        if amount > 0 {
+            let reward_token = credit_line.get_reward_token().unwrap();
+            token::Client::new(&env, &reward_token).transfer(&vault, &to, &amount);
+        }
+        amount
+    }
+}
@@ -0,0 +1,26 @@
+use soroban_sdk::{contractevent, Address};
+
+/// Position levered up by looping borrowed USDC back into BENJI collateral
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Leveraged {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub loops: u32,       // loops run, fewer than asked once credit ran out
+    pub collateral: i128, // BENJI deposited, the initial collateral included
+    pub borrowed: i128,   // USDC borrowed and swapped
+}
+
+/// Position unwound by selling collateral to repay its debt
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Deleveraged {
+    #[topic]
+    pub user: Address,
+    pub account_id: u32,
+    pub loops: u32,
+    pub withdrawn: i128, // BENJI withdrawn and swapped
+    pub repaid: i128,    // USDC repaid out of the swaps
+    pub remaining_debt: i128,
+}
@@ -0,0 +1,295 @@
+#![no_std]
+
+mod events;
+
+use dex_adapter::DexAdapterClient;
+use events::{Deleveraged, Leveraged};
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, Address, Env,
+};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    Unauthorized = 3,
+    InvalidParameter = 4,
+    HealthFactorTooLow = 5,
+    MathOverflow = 6,
+}
+
+/// Credit line entry points the router calls for the user
+#[contractclient(name = "CreditLineClient")]
+pub trait CreditLine {
+    fn deposit_collateral(
+        env: Env,
+        payer: Address,
+        account_id: u32,
+        token: Address,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+        referrer: Option<Address>,
+    );
+    fn borrow(
+        env: Env,
+        recipient: Address,
+        account_id: u32,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+        to: Option<Address>,
+    );
+    fn repay(
+        env: Env,
+        payer: Address,
+        account_id: u32,
+        amount: i128,
+        on_behalf_of: Option<Address>,
+    );
+    fn withdraw_collateral(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        token: Address,
+        amount: i128,
+        to: Option<Address>,
+    );
+    fn get_available_credit(env: Env, user: Address, account_id: u32) -> i128;
+    fn get_max_withdrawable(env: Env, user: Address, account_id: u32, token: Address) -> i128;
+    fn get_current_debt(env: Env, user: Address, account_id: u32) -> i128;
+    fn get_health_factor(env: Env, user: Address, account_id: u32) -> i128;
+}
+
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    CreditLine,
+    Benji,
+    Usdc,
+    Adapter,
+}
+
+/// Most loops one call may run, bounding its cost
+pub const MAX_LOOPS: u32 = 10;
+
+/// Share of the available credit borrowed on each loop, in basis points
+///
+/// The rest is headroom for price moves and swap costs, and lets `deleverage`
+/// withdraw collateral from a fully levered position.
+pub const LOOP_BORROW_SHARE: i128 = 9_500;
+
+const BPS: i128 = 10_000;
+
+/// Price of 1.0 in the 7-decimal fixed point `deleverage` takes its minimum
+/// price in
+pub const PRICE_ONE: i128 = 10_000_000;
+
+fn read(env: &Env, key: DataKey) -> Result<Address, Error> {
+    env.storage()
+        .instance()
+        .get(&key)
+        .ok_or(Error::NotInitialized)
+}
+
+fn credit_line(env: &Env) -> Result<CreditLineClient<'_>, Error> {
+    Ok(CreditLineClient::new(env, &read(env, DataKey::CreditLine)?))
+}
+
+/// Opens and unwinds leveraged BENJI positions on the credit line in one call.
+///
+/// `leverage` deposits BENJI, borrows USDC against it, swaps the USDC back to
+/// BENJI through the configured DEX adapter and deposits that too, looping as
+/// asked. `deleverage` runs the loop in reverse. Everything happens in a single
+/// atomic invocation; the router never holds funds between calls, and the
+/// user authorizes the credit line calls made in their name.
+#[contract]
+pub struct Router;
+
+#[contractimpl]
+impl Router {
+    /// Initialize with the credit line, its BENJI and USDC tokens and the DEX adapter
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        credit_line: Address,
+        benji: Address,
+        usdc: Address,
+        adapter: Address,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        let storage = env.storage().instance();
+        storage.set(&DataKey::Admin, &admin);
+        storage.set(&DataKey::CreditLine, &credit_line);
+        storage.set(&DataKey::Benji, &benji);
+        storage.set(&DataKey::Usdc, &usdc);
+        storage.set(&DataKey::Adapter, &adapter);
+
+        Ok(())
+    }
+
+    /// Swap through a different DEX adapter (admin only)
+    pub fn set_adapter(env: Env, admin: Address, adapter: Address) -> Result<(), Error> {
+        admin.require_auth();
+        if admin != read(&env, DataKey::Admin)? {
+            return Err(Error::Unauthorized);
+        }
+
+        env.storage().instance().set(&DataKey::Adapter, &adapter);
+        Ok(())
+    }
+
+    pub fn get_adapter(env: Env) -> Result<Address, Error> {
+        read(&env, DataKey::Adapter)
+    }
+
+    /// Deposit `initial_collateral` BENJI, then up to `loops` times borrow
+    /// USDC against the account, swap it to BENJI and deposit that
+    ///
+    /// Each loop borrows `LOOP_BORROW_SHARE` of the available credit, stopping
+    /// early once there is none. An `initial_collateral` of 0 levers up the
+    /// collateral already in the account. Swaps take whatever the adapter
+    /// returns; the call fails with `HealthFactorTooLow` unless the account
+    /// ends at or above `min_health_factor`, which bounds what a bad fill can
+    /// cost. Returns the USDC borrowed.
+    pub fn leverage(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        initial_collateral: i128,
+        loops: u32,
+        min_health_factor: i128,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+
+        if initial_collateral < 0 || loops > MAX_LOOPS {
+            return Err(Error::InvalidParameter);
+        }
+
+        let credit_line = credit_line(&env)?;
+        let benji = read(&env, DataKey::Benji)?;
+        let usdc = read(&env, DataKey::Usdc)?;
+        let adapter = read(&env, DataKey::Adapter)?;
+
+        if initial_collateral > 0 {
+            credit_line.deposit_collateral(
+                &user,
+                &account_id,
+                &benji,
+                &initial_collateral,
+                &None,
+                &None,
+            );
+        }
+
+        let mut collateral = initial_collateral;
+        let mut borrowed = 0;
+        let mut looped = 0;
+        while looped < loops {
+            let amount =
+                credit_line.get_available_credit(&user, &account_id) * LOOP_BORROW_SHARE / BPS;
+            if amount <= 0 {
+                break;
+            }
+
+            // The USDC goes straight to the adapter and the BENJI straight to the user
+            credit_line.borrow(&user, &account_id, &amount, &None, &Some(adapter.clone()));
+            let bought = DexAdapterClient::new(&env, &adapter)
+                .swap_exact_in(&usdc, &benji, &amount, &0, &user);
+            if bought > 0 {
+                credit_line.deposit_collateral(&user, &account_id, &benji, &bought, &None, &None);
+            }
+
+            collateral += bought;
+            borrowed += amount;
+            looped += 1;
+        }
+
+        if credit_line.get_health_factor(&user, &account_id) < min_health_factor {
+            return Err(Error::HealthFactorTooLow);
+        }
+
+        Leveraged {
+            user,
+            account_id,
+            loops: looped,
+            collateral,
+            borrowed,
+        }
+        .publish(&env);
+
+        Ok(borrowed)
+    }
+
+    /// Up to `loops` times withdraw as much BENJI as the account allows, swap
+    /// it to USDC and repay the debt with it
+    ///
+    /// Stops early once the debt is repaid. USDC left over from the last swap
+    /// stays with the user, as does any collateral not needed. Each swap must
+    /// pay at least `min_price` USDC per BENJI sold, in units of `PRICE_ONE`,
+    /// or the adapter fails the call. Returns the debt still outstanding.
+    pub fn deleverage(
+        env: Env,
+        user: Address,
+        account_id: u32,
+        loops: u32,
+        min_price: i128,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+
+        if loops > MAX_LOOPS || min_price < 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let credit_line = credit_line(&env)?;
+        let benji = read(&env, DataKey::Benji)?;
+        let usdc = read(&env, DataKey::Usdc)?;
+        let adapter = read(&env, DataKey::Adapter)?;
+
+        let mut withdrawn = 0;
+        let mut repaid = 0;
+        let mut looped = 0;
+        let mut debt = credit_line.get_current_debt(&user, &account_id);
+        while looped < loops && debt > 0 {
+            let amount = credit_line.get_max_withdrawable(&user, &account_id, &benji);
+            if amount <= 0 {
+                break;
+            }
+
+            credit_line.withdraw_collateral(
+                &user,
+                &account_id,
+                &benji,
+                &amount,
+                &Some(adapter.clone()),
+            );
+            let min_out = amount.checked_mul(min_price).ok_or(Error::MathOverflow)? / PRICE_ONE;
+            let sold = DexAdapterClient::new(&env, &adapter)
+                .swap_exact_in(&benji, &usdc, &amount, &min_out, &user);
+            let repay = sold.min(debt);
+            if repay > 0 {
+                credit_line.repay(&user, &account_id, &repay, &None);
+            }
+
+            withdrawn += amount;
+            repaid += repay;
+            looped += 1;
+            debt = credit_line.get_current_debt(&user, &account_id);
+        }
+
+        Deleveraged {
+            user,
+            account_id,
+            loops: looped,
+            withdrawn,
+            repaid,
+            remaining_debt: debt,
+        }
+        .publish(&env);
+
+        Ok(debt)
+    }
+}
@@ -0,0 +1,29 @@
+use soroban_sdk::{contractevent, Address};
+
+/// USDC deposited for vault shares
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Deposit {
+    #[topic]
+    pub owner: Address,
+    pub assets: i128,
+    pub shares: i128,
+}
+
+/// Vault shares burned for USDC
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Withdraw {
+    #[topic]
+    pub owner: Address,
+    pub assets: i128,
+    pub shares: i128,
+}
+
+/// Reward tokens claimed, swapped to USDC and supplied back to the pool
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Harvest {
+    pub rewards: i128,
+    pub compounded: i128, // USDC supplied, the swapped rewards included
+}
@@ -0,0 +1,360 @@
+#![no_std]
+
+mod events;
+
+use dex_adapter::DexAdapterClient;
+use events::{Deposit, Harvest, Withdraw};
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractclient, contracterror, contractimpl, contracttype, token, vec, Address, Env,
+    IntoVal, Symbol,
+};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    Unauthorized = 3,
+    InvalidAmount = 4,
+    InsufficientShares = 5,
+}
+
+/// Credit line entry points the vault calls as a lender
+#[contractclient(name = "CreditLineClient")]
+pub trait CreditLine {
+    fn supply(env: Env, lender: Address, amount: i128) -> i128;
+    fn withdraw_supply(env: Env, lender: Address, amount: i128) -> i128;
+    fn claim_rewards(env: Env, user: Address) -> i128;
+    fn get_supply_balance(env: Env, lender: Address) -> i128;
+}
+
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    CreditLine,
+    Usdc,
+    RewardToken,
+    Adapter,
+    Keeper,
+    TotalShares,
+    Shares(Address),
+}
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const SHARES_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const SHARES_LIFETIME_THRESHOLD: u32 = SHARES_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+fn read(env: &Env, key: DataKey) -> Result<Address, Error> {
+    env.storage()
+        .instance()
+        .get(&key)
+        .ok_or(Error::NotInitialized)
+}
+
+fn credit_line(env: &Env) -> Result<CreditLineClient<'_>, Error> {
+    Ok(CreditLineClient::new(env, &read(env, DataKey::CreditLine)?))
+}
+
+fn total_shares(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::TotalShares)
+        .unwrap_or(0)
+}
+
+fn shares(env: &Env, owner: &Address) -> i128 {
+    let key = DataKey::Shares(owner.clone());
+    let shares = env.storage().persistent().get(&key);
+
+    if shares.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, SHARES_LIFETIME_THRESHOLD, SHARES_BUMP_AMOUNT);
+    }
+
+    shares.unwrap_or(0)
+}
+
+fn update_shares(env: &Env, owner: &Address, delta: i128) {
+    let key = DataKey::Shares(owner.clone());
+    env.storage()
+        .persistent()
+        .set(&key, &(shares(env, owner) + delta));
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, SHARES_LIFETIME_THRESHOLD, SHARES_BUMP_AMOUNT);
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalShares, &(total_shares(env) + delta));
+}
+
+/// USDC the vault holds, idle or supplied to the pool
+fn total_assets(env: &Env) -> Result<i128, Error> {
+    let vault = env.current_contract_address();
+    let idle = token::Client::new(env, &read(env, DataKey::Usdc)?).balance(&vault);
+    Ok(idle + credit_line(env)?.get_supply_balance(&vault))
+}
+
+/// Shares `assets` USDC are worth
+///
+/// Both conversions count one virtual share and asset, so the first deposit
+/// cannot set a price that rounds later deposits down to nothing.
+fn to_shares(env: &Env, assets: i128, round_up: bool) -> Result<i128, Error> {
+    let numerator = assets * (total_shares(env) + 1);
+    let denominator = total_assets(env)? + 1;
+    Ok(if round_up {
+        (numerator + denominator - 1) / denominator
+    } else {
+        numerator / denominator
+    })
+}
+
+fn to_assets(env: &Env, shares: i128) -> Result<i128, Error> {
+    Ok(shares * (total_assets(env)? + 1) / (total_shares(env) + 1))
+}
+
+/// Supply every idle USDC the vault holds to the credit line, returning the amount
+fn supply_idle(env: &Env) -> Result<i128, Error> {
+    let vault = env.current_contract_address();
+    let usdc = read(env, DataKey::Usdc)?;
+    let amount = token::Client::new(env, &usdc).balance(&vault);
+    if amount <= 0 {
+        return Ok(0);
+    }
+
+    // The credit line pulls the USDC from the vault one call down
+    let credit_line = credit_line(env)?;
+    env.authorize_as_current_contract(vec![
+        env,
+        InvokerContractAuthEntry::Contract(SubContractInvocation {
+            context: ContractContext {
+                contract: usdc,
+                fn_name: Symbol::new(env, "transfer"),
+                args: (vault.clone(), credit_line.address.clone(), amount).into_val(env),
+            },
+            sub_invocations: vec![env],
+        }),
+    ]);
+    credit_line.supply(&vault, &amount);
+
+    Ok(amount)
+}
+
+/// Burn `shares` of `owner` and pay them `assets`, withdrawing from the pool
+/// whatever idle USDC does not cover
+fn pay_out(env: &Env, owner: &Address, assets: i128, shares: i128) -> Result<(), Error> {
+    if shares > self::shares(env, owner) {
+        return Err(Error::InsufficientShares);
+    }
+    update_shares(env, owner, -shares);
+
+    let vault = env.current_contract_address();
+    let usdc = token::Client::new(env, &read(env, DataKey::Usdc)?);
+    let idle = usdc.balance(&vault);
+    if idle < assets {
+        credit_line(env)?.withdraw_supply(&vault, &(assets - idle));
+    }
+    usdc.transfer(&vault, owner, &assets);
+
+    Withdraw {
+        owner: owner.clone(),
+        assets,
+        shares,
+    }
+    .publish(env);
+
+    Ok(())
+}
+
+/// Pools lenders' USDC in the credit line behind a single supply position.
+///
+/// Shares follow ERC-4626: they price in the vault's USDC, which grows as pool
+/// interest accrues to its supply position. `harvest` adds the pool's reward
+/// emissions on top by swapping them to USDC through a DEX adapter and
+/// supplying that too, so holders earn both without claiming anything.
+#[contract]
+pub struct SavingsVault;
+
+#[contractimpl]
+impl SavingsVault {
+    /// Initialize with the credit line, its USDC and reward tokens, and the DEX
+    /// adapter that swaps rewards to USDC
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        credit_line: Address,
+        usdc: Address,
+        reward_token: Address,
+        adapter: Address,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        let storage = env.storage().instance();
+        storage.set(&DataKey::Admin, &admin);
+        storage.set(&DataKey::CreditLine, &credit_line);
+        storage.set(&DataKey::Usdc, &usdc);
+        storage.set(&DataKey::RewardToken, &reward_token);
+        storage.set(&DataKey::Adapter, &adapter);
+
+        Ok(())
+    }
+
+    /// Swap rewards through a different DEX adapter (admin only)
+    pub fn set_adapter(env: Env, admin: Address, adapter: Address) -> Result<(), Error> {
+        admin.require_auth();
+        if admin != read(&env, DataKey::Admin)? {
+            return Err(Error::Unauthorized);
+        }
+
+        env.storage().instance().set(&DataKey::Adapter, &adapter);
+        Ok(())
+    }
+
+    /// Let `keeper` harvest alongside the admin, or only the admin if `None`
+    /// (admin only)
+    pub fn set_keeper(env: Env, admin: Address, keeper: Option<Address>) -> Result<(), Error> {
+        admin.require_auth();
+        if admin != read(&env, DataKey::Admin)? {
+            return Err(Error::Unauthorized);
+        }
+
+        match keeper {
+            Some(keeper) => env.storage().instance().set(&DataKey::Keeper, &keeper),
+            None => env.storage().instance().remove(&DataKey::Keeper),
+        }
+        Ok(())
+    }
+
+    pub fn get_keeper(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Keeper)
+    }
+
+    /// Deposit `assets` USDC from `owner`, supplying it to the pool, and return
+    /// the shares minted to them
+    pub fn deposit(env: Env, owner: Address, assets: i128) -> Result<i128, Error> {
+        owner.require_auth();
+
+        if assets <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let shares = to_shares(&env, assets, false)?;
+        if shares == 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let usdc = read(&env, DataKey::Usdc)?;
+        token::Client::new(&env, &usdc).transfer(&owner, env.current_contract_address(), &assets);
+        update_shares(&env, &owner, shares);
+        supply_idle(&env)?;
+
+        Deposit {
+            owner,
+            assets,
+            shares,
+        }
+        .publish(&env);
+
+        Ok(shares)
+    }
+
+    /// Withdraw exactly `assets` USDC to `owner`, returning the shares burned
+    ///
+    /// Fails like `withdraw_supply` if the pool does not hold that much idle USDC.
+    pub fn withdraw(env: Env, owner: Address, assets: i128) -> Result<i128, Error> {
+        owner.require_auth();
+
+        if assets <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Burn shares rounded up so the vault never pays out more than owed
+        let shares = to_shares(&env, assets, true)?;
+        pay_out(&env, &owner, assets, shares)?;
+
+        Ok(shares)
+    }
+
+    /// Burn `shares` of `owner` for the USDC they are worth, returning the amount paid
+    pub fn redeem(env: Env, owner: Address, shares: i128) -> Result<i128, Error> {
+        owner.require_auth();
+
+        if shares <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let assets = to_assets(&env, shares)?;
+        pay_out(&env, &owner, assets, shares)?;
+
+        Ok(assets)
+    }
+
+    /// Claim the vault's pool rewards, swap them to USDC and supply it, returning
+    /// the USDC supplied
+    ///
+    /// Only the admin or the keeper may call this, as they set `min_out`, below
+    /// which the swap fails. Pool interest needs no harvesting, as it already
+    /// raises the value of the vault's supply position.
+    pub fn harvest(env: Env, caller: Address, min_out: i128) -> Result<i128, Error> {
+        caller.require_auth();
+        let keeper: Option<Address> = env.storage().instance().get(&DataKey::Keeper);
+        if caller != read(&env, DataKey::Admin)? && Some(&caller) != keeper.as_ref() {
+            return Err(Error::Unauthorized);
+        }
+
+        let vault = env.current_contract_address();
+        let rewards = credit_line(&env)?.claim_rewards(&vault);
+
+        if rewards > 0 {
+            let reward_token = read(&env, DataKey::RewardToken)?;
+            let usdc = read(&env, DataKey::Usdc)?;
+            let adapter = read(&env, DataKey::Adapter)?;
+
+            token::Client::new(&env, &reward_token).transfer(&vault, &adapter, &rewards);
+            DexAdapterClient::new(&env, &adapter).swap_exact_in(
+                &reward_token,
+                &usdc,
+                &rewards,
+                &min_out,
+                &vault,
+            );
+        }
+
+        let compounded = supply_idle(&env)?;
+        Harvest {
+            rewards,
+            compounded,
+        }
+        .publish(&env);
+
+        Ok(compounded)
+    }
+
+    /// Get the USDC the vault holds for its shareholders
+    pub fn total_assets(env: Env) -> Result<i128, Error> {
+        total_assets(&env)
+    }
+
+    pub fn total_shares(env: Env) -> i128 {
+        total_shares(&env)
+    }
+
+    /// Get the shares held by `owner`
+    pub fn balance(env: Env, owner: Address) -> i128 {
+        shares(&env, &owner)
+    }
+
+    /// Get the shares a deposit of `assets` USDC would mint now
+    pub fn convert_to_shares(env: Env, assets: i128) -> Result<i128, Error> {
+        to_shares(&env, assets, false)
+    }
+
+    /// Get the USDC `shares` would redeem for now
+    pub fn convert_to_assets(env: Env, shares: i128) -> Result<i128, Error> {
+        to_assets(&env, shares)
+    }
+}
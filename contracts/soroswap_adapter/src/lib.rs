@@ -0,0 +1,102 @@
+#![no_std]
+
+use dex_adapter::DexAdapter;
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractclient, contractimpl, contracttype, token, vec, Address, Env, IntoVal,
+    Symbol, Vec,
+};
+
+/// Soroswap router entry points the adapter swaps through
+#[contractclient(name = "SoroswapRouterClient")]
+pub trait SoroswapRouter {
+    fn router_pair_for(env: Env, token_a: Address, token_b: Address) -> Address;
+    fn router_get_amounts_out(env: Env, amount_in: i128, path: Vec<Address>) -> Vec<i128>;
+    fn swap_exact_tokens_for_tokens(
+        env: Env,
+        amount_in: i128,
+        amount_out_min: i128,
+        path: Vec<Address>,
+        to: Address,
+        deadline: u64,
+    ) -> Vec<i128>;
+}
+
+#[contracttype]
+pub enum DataKey {
+    SoroswapRouter,
+}
+
+fn soroswap_router(env: &Env) -> SoroswapRouterClient<'_> {
+    let router: Address = env
+        .storage()
+        .instance()
+        .get(&DataKey::SoroswapRouter)
+        .unwrap();
+    SoroswapRouterClient::new(env, &router)
+}
+
+/// `DexAdapter` over the Soroswap AMM, swapping through the direct pool of
+/// each token pair.
+///
+/// Soroswap pulls the input from the adapter into the pool, so the adapter
+/// authorizes that one transfer before each swap and then forwards the output
+/// to the recipient.
+#[contract]
+pub struct SoroswapAdapter;
+
+#[contractimpl]
+impl SoroswapAdapter {
+    pub fn __constructor(env: Env, soroswap_router: Address) {
+        env.storage()
+            .instance()
+            .set(&DataKey::SoroswapRouter, &soroswap_router);
+    }
+}
+
+#[contractimpl]
+impl DexAdapter for SoroswapAdapter {
+    fn swap_exact_in(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+        to: Address,
+    ) -> i128 {
+        let router = soroswap_router(&env);
+        let adapter = env.current_contract_address();
+        let pair = router.router_pair_for(&token_in, &token_out);
+
+        env.authorize_as_current_contract(vec![
+            &env,
+            InvokerContractAuthEntry::Contract(SubContractInvocation {
+                context: ContractContext {
+                    contract: token_in.clone(),
+                    fn_name: Symbol::new(&env, "transfer"),
+                    args: (adapter.clone(), pair, amount_in).into_val(&env),
+                },
+                sub_invocations: vec![&env],
+            }),
+        ]);
+
+        let amounts = router.swap_exact_tokens_for_tokens(
+            &amount_in,
+            &min_out,
+            &vec![&env, token_in, token_out.clone()],
+            &adapter,
+            &env.ledger().timestamp(),
+        );
+        let out = amounts.last().unwrap();
+
+        token::Client::new(&env, &token_out).transfer(&adapter, &to, &out);
+        out
+    }
+
+    fn quote(env: Env, token_in: Address, token_out: Address, amount_in: i128) -> i128 {
+        soroswap_router(&env)
+            .router_get_amounts_out(&amount_in, &vec![&env, token_in, token_out])
+            .last()
+            .unwrap()
+    }
+}
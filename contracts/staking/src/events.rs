@@ -0,0 +1,20 @@
+use soroban_sdk::{contractevent, Address};
+
+/// BENJI locked until `unlock_at`
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Locked {
+    #[topic]
+    pub user: Address,
+    pub amount: i128,
+    pub unlock_at: u64,
+}
+
+/// Expired locks paid back to their owner
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unlocked {
+    #[topic]
+    pub user: Address,
+    pub amount: i128,
+}
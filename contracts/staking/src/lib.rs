@@ -0,0 +1,258 @@
+#![no_std]
+
+mod events;
+
+use events::{Locked, Unlocked};
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, token, Address, Env, Vec};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    Unauthorized = 3,
+    InvalidParameter = 4,
+    NothingToUnlock = 5,
+    TooManyLocks = 6,
+}
+
+/// BENJI locked by a user until `unlock_at`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Lock {
+    pub amount: i128,
+    pub unlock_at: u64,
+}
+
+/// How locked BENJI translates into an LTV boost
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BoostParams {
+    pub max_boost: u32,          // 500 = LTV raised by up to 5 percentage points
+    pub full_boost_amount: i128, // BENJI locked for `max_lock` that earns the full boost
+    pub max_lock: u64,           // longest lock duration, in seconds
+}
+
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    Token,
+    Params,
+    Locks(Address),
+}
+
+/// Most locks a user may hold at once, so `get_boost` stays cheap to call
+pub const MAX_LOCKS: u32 = 16;
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const LOCK_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const LOCK_LIFETIME_THRESHOLD: u32 = LOCK_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Check that `admin` is the stored admin and has authorized the call
+fn require_admin(env: &Env, admin: &Address) -> Result<(), Error> {
+    admin.require_auth();
+
+    let stored: Address = env
+        .storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .ok_or(Error::NotInitialized)?;
+    if *admin != stored {
+        return Err(Error::Unauthorized);
+    }
+
+    Ok(())
+}
+
+fn load_params(env: &Env) -> Result<BoostParams, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Params)
+        .ok_or(Error::NotInitialized)
+}
+
+fn store_params(env: &Env, params: &BoostParams) -> Result<(), Error> {
+    if params.max_boost > 10000 || params.full_boost_amount <= 0 || params.max_lock == 0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    env.storage().instance().set(&DataKey::Params, params);
+    Ok(())
+}
+
+fn load_locks(env: &Env, user: &Address) -> Vec<Lock> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Locks(user.clone()))
+        .unwrap_or(Vec::new(env))
+}
+
+fn save_locks(env: &Env, user: &Address, locks: &Vec<Lock>) {
+    let key = DataKey::Locks(user.clone());
+    if locks.is_empty() {
+        env.storage().persistent().remove(&key);
+        return;
+    }
+
+    env.storage().persistent().set(&key, locks);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LOCK_LIFETIME_THRESHOLD, LOCK_BUMP_AMOUNT);
+}
+
+/// Time-locked BENJI staking that boosts a borrower's LTV on the credit line.
+///
+/// Each lock earns boost in proportion to its amount and the time left until it
+/// unlocks, so the boost decays as locks approach expiry. A user's boost is
+/// capped at `max_boost`, reached by locking `full_boost_amount` for `max_lock`.
+#[contract]
+pub struct Staking;
+
+#[contractimpl]
+impl Staking {
+    /// Initialize with the BENJI token and boost parameters
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        token: Address,
+        params: BoostParams,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        store_params(&env, &params)?;
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Token, &token);
+
+        Ok(())
+    }
+
+    /// Change the boost parameters (admin only)
+    pub fn set_params(env: Env, admin: Address, params: BoostParams) -> Result<(), Error> {
+        require_admin(&env, &admin)?;
+        store_params(&env, &params)
+    }
+
+    /// Lock BENJI for `duration` seconds, at most `max_lock`. A user holds at
+    /// most `MAX_LOCKS` locks, expired ones included until unlocked; a lock
+    /// ending with an existing one is added to it instead.
+    pub fn lock(env: Env, user: Address, amount: i128, duration: u64) -> Result<u64, Error> {
+        user.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let params = load_params(&env)?;
+        if duration == 0 || duration > params.max_lock {
+            return Err(Error::InvalidParameter);
+        }
+
+        let unlock_at = env.ledger().timestamp() + duration;
+        let mut locks = load_locks(&env, &user);
+        match locks.iter().position(|lock| lock.unlock_at == unlock_at) {
+            Some(index) => {
+                let mut lock = locks.get_unchecked(index as u32);
+                lock.amount = lock
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(Error::InvalidParameter)?;
+                locks.set(index as u32, lock);
+            }
+            None if locks.len() >= MAX_LOCKS => return Err(Error::TooManyLocks),
+            None => locks.push_back(Lock { amount, unlock_at }),
+        }
+        save_locks(&env, &user, &locks);
+
+        let token: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Token)
+            .ok_or(Error::NotInitialized)?;
+        token::Client::new(&env, &token).transfer(&user, env.current_contract_address(), &amount);
+
+        Locked {
+            user,
+            amount,
+            unlock_at,
+        }
+        .publish(&env);
+
+        Ok(unlock_at)
+    }
+
+    /// Withdraw every expired lock, returning the amount sent
+    pub fn unlock(env: Env, user: Address) -> Result<i128, Error> {
+        user.require_auth();
+
+        let now = env.ledger().timestamp();
+        let mut remaining = Vec::new(&env);
+        let mut amount = 0;
+        for lock in load_locks(&env, &user).iter() {
+            if lock.unlock_at <= now {
+                amount += lock.amount;
+            } else {
+                remaining.push_back(lock);
+            }
+        }
+
+        if amount == 0 {
+            return Err(Error::NothingToUnlock);
+        }
+
+        save_locks(&env, &user, &remaining);
+
+        let token: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Token)
+            .ok_or(Error::NotInitialized)?;
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &user, &amount);
+
+        Unlocked { user, amount }.publish(&env);
+
+        Ok(amount)
+    }
+
+    /// Get a user's locks, expired or not, in the order they were made
+    pub fn get_locks(env: Env, user: Address) -> Vec<Lock> {
+        load_locks(&env, &user)
+    }
+
+    /// Get the LTV boost a user's active locks earn, in basis points
+    pub fn get_boost(env: Env, user: Address) -> u32 {
+        let Ok(params) = load_params(&env) else {
+            return 0;
+        };
+        if params.max_boost == 0 {
+            return 0;
+        }
+
+        let now = env.ledger().timestamp();
+        let mut weight: i128 = 0;
+        for lock in load_locks(&env, &user).iter() {
+            let remaining = lock.unlock_at.saturating_sub(now) as i128;
+            weight = weight.saturating_add(lock.amount.saturating_mul(remaining));
+        }
+
+        let full_weight = params
+            .full_boost_amount
+            .saturating_mul(params.max_lock as i128);
+        if weight >= full_weight {
+            return params.max_boost;
+        }
+
+        // Below full weight, so the result is under max_boost
+        let boost = weight.checked_mul(params.max_boost as i128).map_or(
+            weight / (full_weight / params.max_boost as i128),
+            |scaled| scaled / full_weight,
+        );
+        boost as u32
+    }
+
+    pub fn get_params(env: Env) -> Result<BoostParams, Error> {
+        load_params(&env)
+    }
+}
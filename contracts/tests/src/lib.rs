@@ -0,0 +1,333 @@
+//! Fixtures for end-to-end tests of the credit line against the mock tokens and
+//! oracle, all running in one test `Env`.
+//!
+//! `Fixture::new` deploys a market with BENJI collateral priced by the oracle
+//! and USDC liquidity ready to borrow; scenario tests live under `tests/`.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use credit_line::{CollateralConfig, CreditLineContract, CreditLineContractClient};
+use dex_adapter::DexAdapter;
+use mock_benji_token::{BenjiToken, BenjiTokenClient};
+use mock_oracle::{Asset, MockOracle, MockOracleClient};
+use mock_usdc_token::{UsdcToken, UsdcTokenClient};
+use soroban_sdk::{
+    auth::{Context, CustomAccountInterface},
+    contract, contracterror, contractimpl,
+    crypto::Hash,
+    testutils::{Address as _, Ledger},
+    token::{self, TokenClient},
+    xdr::{
+        InvokeContractArgs, ScVal, SorobanAddressCredentials, SorobanAuthorizationEntry,
+        SorobanAuthorizedFunction, SorobanAuthorizedInvocation, SorobanCredentials,
+    },
+    Address, Env, String, Symbol, TryFromVal, Val, Vec,
+};
+
+/// One whole token, at the 7 decimals both mock tokens use
+pub const TOKEN: i128 = 10_000_000;
+
+/// Oracle price of 1.0, at the 7 decimals the mock oracle reports
+pub const PRICE_ONE: i128 = 10_000_000;
+
+/// Seconds in a day
+pub const DAY: u64 = 24 * 60 * 60;
+
+/// Seconds in a 365-day year
+pub const YEAR: u64 = 365 * DAY;
+
+/// Ledgers closed in a 365-day year, at five seconds each
+pub const LEDGERS_PER_YEAR: u32 = 365 * 17_280;
+
+/// USDC the fixture's lender supplies to the pool
+pub const LIQUIDITY: i128 = 100_000 * TOKEN;
+
+/// A deployed market and the accounts that run it
+pub struct Fixture<'a> {
+    pub env: Env,
+    pub admin: Address,
+    pub lender: Address,
+    pub usdc: TokenClient<'a>,
+    pub benji: TokenClient<'a>,
+    pub oracle: MockOracleClient<'a>,
+    pub credit_line: CreditLineContractClient<'a>,
+}
+
+impl<'a> Fixture<'a> {
+    /// Deploy the mock tokens, oracle and credit line, with BENJI priced at 1.0
+    /// and `LIQUIDITY` USDC supplied to the pool
+    pub fn new() -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.cost_estimate().budget().reset_unlimited();
+        env.ledger().set_timestamp(1_700_000_000);
+        // Keep entries alive for a year of ledgers, so tests can move through
+        // loan schedules without their state being archived
+        env.ledger().set_min_persistent_entry_ttl(LEDGERS_PER_YEAR);
+        env.ledger().set_min_temp_entry_ttl(LEDGERS_PER_YEAR);
+        env.ledger().set_max_entry_ttl(2 * LEDGERS_PER_YEAR);
+
+        let admin = Address::generate(&env);
+
+        let usdc = env.register(UsdcToken, ());
+        UsdcTokenClient::new(&env, &usdc).initialize(
+            &admin,
+            &7,
+            &String::from_str(&env, "USD Coin"),
+            &String::from_str(&env, "USDC"),
+        );
+
+        let benji = env.register(BenjiToken, ());
+        BenjiTokenClient::new(&env, &benji).initialize(
+            &admin,
+            &7,
+            &String::from_str(&env, "Franklin OnChain U.S. Government Money Fund"),
+            &String::from_str(&env, "BENJI"),
+        );
+
+        let oracle = MockOracleClient::new(&env, &env.register(MockOracle, ()));
+        oracle.initialize(&admin, &Asset::Stellar(usdc.clone()), &7, &300);
+
+        let credit_line =
+            CreditLineContractClient::new(&env, &env.register(CreditLineContract, ()));
+        credit_line.initialize(&admin, &benji, &usdc);
+        credit_line.set_oracle(&admin, &oracle.address);
+
+        let fixture = Fixture {
+            lender: Address::generate(&env),
+            usdc: TokenClient::new(&env, &usdc),
+            benji: TokenClient::new(&env, &benji),
+            oracle,
+            credit_line,
+            admin,
+            env,
+        };
+        fixture.set_benji_price(PRICE_ONE);
+
+        fixture.mint_usdc(&fixture.lender, LIQUIDITY);
+        fixture.credit_line.supply(&fixture.lender, &LIQUIDITY);
+
+        fixture
+    }
+
+    /// A fresh account holding `benji` BENJI and `usdc` USDC
+    pub fn fund(&self, benji: i128, usdc: i128) -> Address {
+        let user = Address::generate(&self.env);
+        self.mint_benji(&user, benji);
+        self.mint_usdc(&user, usdc);
+        user
+    }
+
+    pub fn mint_usdc(&self, to: &Address, amount: i128) {
+        UsdcTokenClient::new(&self.env, &self.usdc.address).mint(to, &amount);
+    }
+
+    pub fn mint_benji(&self, to: &Address, amount: i128) {
+        BenjiTokenClient::new(&self.env, &self.benji.address).mint(to, &amount);
+    }
+
+    /// Record a BENJI price at the current ledger time
+    pub fn set_benji_price(&self, price: i128) {
+        self.set_price(&self.benji.address, price);
+    }
+
+    /// Record a token's price at the current ledger time
+    pub fn set_price(&self, token: &Address, price: i128) {
+        self.oracle.set_price(
+            &Asset::Stellar(token.clone()),
+            &price,
+            &self.env.ledger().timestamp(),
+        );
+    }
+
+    /// A new mintable 7-decimal token, administered by the fixture's admin
+    pub fn add_token(&self, name: &str, symbol: &str) -> UsdcTokenClient<'a> {
+        let token = UsdcTokenClient::new(&self.env, &self.env.register(UsdcToken, ()));
+        token.initialize(
+            &self.admin,
+            &7,
+            &String::from_str(&self.env, name),
+            &String::from_str(&self.env, symbol),
+        );
+        token
+    }
+
+    /// A new token accepted as collateral at the given risk parameters, priced at 1.0
+    pub fn add_collateral_token(
+        &self,
+        name: &str,
+        symbol: &str,
+        ltv_ratio: u32,
+        liquidation_threshold: u32,
+    ) -> UsdcTokenClient<'a> {
+        let token = self.add_token(name, symbol);
+        self.set_price(&token.address, PRICE_ONE);
+        self.credit_line.set_collateral_config(
+            &self.admin,
+            &token.address,
+            &CollateralConfig {
+                ltv_ratio,
+                liquidation_threshold,
+            },
+        );
+        token
+    }
+
+    /// A `MockDex` swapping at `rate_bps`, holding 10,000 of both tokens to pay out
+    pub fn mock_dex(&self, rate_bps: i128) -> Address {
+        let dex = self.env.register(MockDex, (rate_bps,));
+        self.mint_benji(&dex, 10_000 * TOKEN);
+        self.mint_usdc(&dex, 10_000 * TOKEN);
+        dex
+    }
+
+    /// Move the ledger clock forward
+    pub fn advance(&self, seconds: u64) {
+        self.env
+            .ledger()
+            .set_timestamp(self.env.ledger().timestamp() + seconds);
+    }
+
+    /// Close `ledgers` ledgers, moving the clock forward five seconds for each
+    pub fn advance_ledgers(&self, ledgers: u32) {
+        self.env
+            .ledger()
+            .set_sequence_number(self.env.ledger().sequence() + ledgers);
+        self.advance(ledgers as u64 * 5);
+    }
+}
+
+impl Default for Fixture<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// DEX adapter paying out of its own balances at a fixed rate, in basis points
+#[contract]
+pub struct MockDex;
+
+#[contractimpl]
+impl MockDex {
+    pub fn __constructor(env: Env, rate_bps: i128) {
+        env.storage().instance().set(&0u32, &rate_bps);
+    }
+}
+
+#[contractimpl]
+impl DexAdapter for MockDex {
+    fn swap_exact_in(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+        to: Address,
+    ) -> i128 {
+        let out = Self::quote(env.clone(), token_in, token_out.clone(), amount_in);
+        assert!(out >= min_out);
+
+        token::Client::new(&env, &token_out).transfer(&env.current_contract_address(), &to, &out);
+        out
+    }
+
+    fn quote(env: Env, _token_in: Address, _token_out: Address, amount_in: i128) -> i128 {
+        let rate: i128 = env.storage().instance().get(&0u32).unwrap();
+        amount_in * rate / 10_000
+    }
+}
+
+/// Smart wallet whose `__check_auth` signs anything except a token transfer
+/// out of the wallet above its spending limit, as a policy wallet would
+#[contract]
+pub struct PolicyWallet;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum WalletError {
+    OverLimit = 1,
+}
+
+#[contractimpl]
+impl PolicyWallet {
+    pub fn __constructor(env: Env, limit: i128) {
+        env.storage().instance().set(&0u32, &limit);
+    }
+}
+
+#[contractimpl]
+impl CustomAccountInterface for PolicyWallet {
+    type Signature = ();
+    type Error = WalletError;
+
+    fn __check_auth(
+        env: Env,
+        _signature_payload: Hash<32>,
+        _signatures: (),
+        auth_contexts: Vec<Context>,
+    ) -> Result<(), WalletError> {
+        let limit: i128 = env.storage().instance().get(&0u32).unwrap();
+        let wallet = env.current_contract_address();
+
+        for context in auth_contexts.iter() {
+            let Context::Contract(call) = context else {
+                continue;
+            };
+            if call.fn_name != Symbol::new(&env, "transfer") {
+                continue;
+            }
+            let from = Address::try_from_val(&env, &call.args.get(0).unwrap()).unwrap();
+            let amount = i128::try_from_val(&env, &call.args.get(2).unwrap()).unwrap();
+            if from == wallet && amount > limit {
+                return Err(WalletError::OverLimit);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A call for a wallet to authorize, along with the calls it makes that need
+/// the same wallet's authorization
+pub fn invocation(
+    env: &Env,
+    contract: &Address,
+    fn_name: &str,
+    args: Vec<Val>,
+    sub_invocations: std::vec::Vec<SorobanAuthorizedInvocation>,
+) -> SorobanAuthorizedInvocation {
+    let ScVal::Vec(Some(args)) = ScVal::try_from_val(env, &args.to_val()).unwrap() else {
+        unreachable!("a Vec converts to an ScVal::Vec");
+    };
+
+    SorobanAuthorizedInvocation {
+        function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+            contract_address: contract.into(),
+            function_name: fn_name.try_into().unwrap(),
+            args: args.0,
+        }),
+        sub_invocations: sub_invocations.try_into().unwrap(),
+    }
+}
+
+/// `wallet`'s authorization of `root_invocation`, checked by its
+/// `__check_auth` rather than mocked
+pub fn wallet_auth(
+    env: &Env,
+    wallet: &Address,
+    root_invocation: SorobanAuthorizedInvocation,
+) -> SorobanAuthorizationEntry {
+    // Every entry needs a nonce the wallet has not used
+    static NONCE: AtomicI64 = AtomicI64::new(0);
+
+    SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address: wallet.into(),
+            nonce: NONCE.fetch_add(1, Ordering::Relaxed),
+            signature_expiration_ledger: env.ledger().sequence() + 100,
+            signature: ScVal::Void,
+        }),
+        root_invocation,
+    }
+}
@@ -0,0 +1,64 @@
+use credit_line::Error;
+use integration_tests::{Fixture, DAY};
+use soroban_sdk::{testutils::Address as _, Address};
+
+#[test]
+fn admin_transfer_waits_out_the_timelock() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let admin = &fixture.admin;
+    let new_admin = Address::generate(&fixture.env);
+
+    // With no timelock the first delay applies at once
+    credit_line.set_admin_timelock(admin, &(2 * DAY));
+    assert_eq!(credit_line.get_admin_timelock(), 2 * DAY);
+
+    credit_line.propose_admin(admin, &new_admin);
+    fixture.advance(DAY);
+    assert_eq!(
+        credit_line.try_accept_admin(&new_admin),
+        Err(Ok(Error::TimelockNotElapsed))
+    );
+    fixture.advance(DAY);
+    credit_line.accept_admin(&new_admin);
+    assert_eq!(credit_line.get_admin(), new_admin);
+    assert_eq!(
+        credit_line.try_propose_admin(admin, admin),
+        Err(Ok(Error::Unauthorized))
+    );
+}
+
+#[test]
+fn timelock_changes_wait_out_the_current_delay() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let admin = &fixture.admin;
+    let new_admin = Address::generate(&fixture.env);
+    credit_line.set_admin_timelock(admin, &(2 * DAY));
+
+    // Dropping the delay does not let a new admin in any sooner
+    credit_line.set_admin_timelock(admin, &0);
+    assert_eq!(credit_line.get_admin_timelock(), 2 * DAY);
+    credit_line.propose_admin(admin, &new_admin);
+    assert_eq!(
+        credit_line.try_accept_admin(&new_admin),
+        Err(Ok(Error::TimelockNotElapsed))
+    );
+
+    fixture.advance(2 * DAY);
+    assert_eq!(credit_line.get_admin_timelock(), 0);
+
+    // From no delay a new one applies at once, but raising it waits too
+    credit_line.set_admin_timelock(admin, &DAY);
+    assert_eq!(credit_line.get_admin_timelock(), DAY);
+    credit_line.set_admin_timelock(admin, &(5 * DAY));
+    fixture.advance(DAY - 1);
+    assert_eq!(credit_line.get_admin_timelock(), DAY);
+
+    // A later change replaces one still waiting
+    credit_line.set_admin_timelock(admin, &(3 * DAY));
+    fixture.advance(1);
+    assert_eq!(credit_line.get_admin_timelock(), DAY);
+    fixture.advance(DAY);
+    assert_eq!(credit_line.get_admin_timelock(), 3 * DAY);
+}
@@ -0,0 +1,195 @@
+use bond_registry::{BondRegistry, BondRegistryClient, Error};
+use integration_tests::{Fixture, TOKEN};
+use soroban_sdk::{testutils::Address as _, Address};
+
+/// Seconds between coupon dates
+const MONTH: u64 = 30 * 24 * 60 * 60;
+
+#[test]
+fn series_are_issued_until_maturity() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let registry = BondRegistryClient::new(env, &env.register(BondRegistry, ()));
+    let issuer = Address::generate(env);
+    let holder = Address::generate(env);
+    let other = Address::generate(env);
+
+    // Coupons must fall due before maturity
+    let issue_date = env.ledger().timestamp();
+    let maturity = issue_date + 12 * MONTH;
+    let usdc = &fixture.usdc.address;
+    assert_eq!(
+        registry.try_register_series(
+            &issuer,
+            usdc,
+            &(100 * TOKEN),
+            &500,
+            &maturity,
+            &(13 * MONTH)
+        ),
+        Err(Ok(Error::InvalidParameter))
+    );
+    let series = registry.register_series(&issuer, usdc, &(100 * TOKEN), &500, &maturity, &MONTH);
+    assert_eq!(registry.series_count(), 1);
+    assert_eq!(registry.next_coupon_date(&series), Some(issue_date + MONTH));
+
+    registry.mint(&series, &holder, &600);
+    assert_eq!(
+        registry.try_mint(&series, &holder, &0),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(registry.get_series(&series).supply, 600);
+    assert_eq!(registry.outstanding_principal(&series), 600 * 100 * TOKEN);
+
+    registry.transfer(&series, &holder, &other, &200);
+    assert_eq!(
+        registry.try_transfer(&series, &holder, &other, &401),
+        Err(Ok(Error::InsufficientBalance))
+    );
+    assert_eq!(registry.balance(&series, &holder), 400);
+    assert_eq!(registry.balance(&series, &other), 200);
+
+    // No more can be issued once the series matures
+    fixture.advance(12 * MONTH);
+    assert_eq!(registry.next_coupon_date(&series), None);
+    assert_eq!(
+        registry.try_mint(&series, &holder, &100),
+        Err(Ok(Error::SeriesMatured))
+    );
+    assert_eq!(
+        registry.try_get_series(&(series + 1)),
+        Err(Ok(Error::SeriesNotFound))
+    );
+}
+
+#[test]
+fn coupons_survive_the_balance_history_wrapping() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let registry = BondRegistryClient::new(env, &env.register(BondRegistry, ()));
+    let issuer = Address::generate(env);
+    let holder = Address::generate(env);
+    let other = Address::generate(env);
+    fixture.mint_usdc(&issuer, 10_000 * TOKEN);
+
+    let maturity = env.ledger().timestamp() + 12 * MONTH;
+    let series = registry.register_series(
+        &issuer,
+        &fixture.usdc.address,
+        &(100 * TOKEN),
+        &500,
+        &maturity,
+        &MONTH,
+    );
+    registry.mint(&series, &holder, &600);
+    registry.mint(&series, &other, &400);
+
+    fixture.advance_ledgers(1);
+    fixture.advance(MONTH);
+    let coupon = registry.pay_coupon(&series);
+    assert!(coupon > 0);
+
+    // Far more transfers than the history keeps checkpoints for
+    for _ in 0..40 {
+        fixture.advance_ledgers(1);
+        registry.transfer(&series, &holder, &other, &1);
+    }
+    assert_eq!(registry.balance(&series, &holder), 560);
+    assert_eq!(
+        registry.balance_at(&series, &holder, &env.ledger().sequence()),
+        561
+    );
+
+    // The coupon recorded before them is still owed on the old balance
+    assert_eq!(
+        registry.claimable_coupon(&holder, &series),
+        coupon * 600 / 1000
+    );
+    assert_eq!(registry.claim_coupon(&holder, &series), coupon * 600 / 1000);
+    assert_eq!(fixture.usdc.balance(&holder), coupon * 600 / 1000);
+    assert_eq!(registry.claimable_coupon(&holder, &series), 0);
+
+    // The next coupon is paid on the new one
+    fixture.advance_ledgers(1);
+    fixture.advance(MONTH);
+    let next = registry.pay_coupon(&series);
+    assert_eq!(registry.claim_coupon(&holder, &series), next * 560 / 1000);
+    assert_eq!(
+        registry.claim_coupon(&other, &series),
+        coupon * 400 / 1000 + next * 440 / 1000
+    );
+}
+
+#[test]
+fn matured_bonds_redeem_from_the_escrow() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let registry = BondRegistryClient::new(env, &env.register(BondRegistry, ()));
+    let issuer = Address::generate(env);
+    let holder = Address::generate(env);
+    let other = Address::generate(env);
+    fixture.mint_usdc(&issuer, 10_000 * TOKEN);
+
+    let maturity = env.ledger().timestamp() + 2 * MONTH;
+    let series = registry.register_series(
+        &issuer,
+        &fixture.usdc.address,
+        &TOKEN,
+        &500,
+        &maturity,
+        &MONTH,
+    );
+    registry.mint(&series, &holder, &600);
+    registry.mint(&series, &other, &400);
+    assert_eq!(registry.outstanding_principal(&series), 1_000 * TOKEN);
+    registry.issuer_fund_redemption(&series, &(700 * TOKEN));
+    assert_eq!(
+        registry.try_redeem_at_maturity(&holder, &series, &600),
+        Err(Ok(Error::NotMatured))
+    );
+    assert!(!registry.is_in_default(&series));
+
+    // At maturity the last coupon is still owed, so nothing redeems yet
+    fixture.advance_ledgers(1);
+    fixture.advance(MONTH);
+    let first = registry.pay_coupon(&series);
+    fixture.advance_ledgers(1);
+    fixture.advance(MONTH);
+    assert!(registry.is_in_default(&series));
+    assert_eq!(
+        registry.try_redeem_at_maturity(&holder, &series, &600),
+        Err(Ok(Error::CouponsOutstanding))
+    );
+
+    // Once it is paid, every holder shares it on their full balance
+    let coupon = registry.pay_coupon(&series);
+    assert_eq!(
+        registry.claimable_coupon(&holder, &series),
+        first * 600 / 1000 + coupon * 600 / 1000
+    );
+    assert!(registry.is_in_default(&series));
+    assert_eq!(
+        registry.try_redeem_at_maturity(&holder, &series, &601),
+        Err(Ok(Error::InsufficientBalance))
+    );
+    assert_eq!(
+        registry.redeem_at_maturity(&holder, &series, &600),
+        600 * TOKEN
+    );
+    assert_eq!(registry.balance(&series, &holder), 0);
+    assert_eq!(registry.get_series(&series).supply, 400);
+    assert_eq!(registry.get_redemption_escrow(&series), 100 * TOKEN);
+
+    // The escrow cannot cover the rest until the issuer tops it up
+    assert_eq!(
+        registry.try_redeem_at_maturity(&other, &series, &400),
+        Err(Ok(Error::EscrowUnderfunded))
+    );
+    registry.issuer_fund_redemption(&series, &(300 * TOKEN));
+    assert!(!registry.is_in_default(&series));
+    registry.redeem_at_maturity(&other, &series, &400);
+    assert_eq!(registry.outstanding_principal(&series), 0);
+    assert_eq!(registry.get_redemption_escrow(&series), 0);
+    assert_eq!(fixture.usdc.balance(&holder), 600 * TOKEN);
+    assert_eq!(fixture.usdc.balance(&other), 400 * TOKEN);
+}
@@ -0,0 +1,304 @@
+use bridge::{Bridge, BridgeClient, Error, ReleaseProof};
+use integration_tests::{Fixture, TOKEN};
+use soroban_sdk::{testutils::Address as _, vec, Address, Bytes, Vec};
+
+/// Chain the test transfers come from and go to
+const OTHER_CHAIN: u32 = 2;
+
+/// A bridge run by the fixture's admin with a single relayer, allowing BENJI
+fn bridge<'a>(fixture: &Fixture) -> (BridgeClient<'a>, Address) {
+    let env = &fixture.env;
+    let bridge = BridgeClient::new(env, &env.register(Bridge, ()));
+    let relayer = Address::generate(env);
+    bridge.initialize(&fixture.admin, &vec![env, relayer.clone()], &1);
+    bridge.set_token_allowed(&fixture.admin, &fixture.benji.address, &true);
+    (bridge, relayer)
+}
+
+fn proof(fixture: &Fixture, nonce: u64) -> ReleaseProof {
+    ReleaseProof {
+        source_chain: OTHER_CHAIN,
+        nonce,
+        token: fixture.benji.address.clone(),
+    }
+}
+
+#[test]
+fn locked_tokens_are_released_once() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let (bridge, relayer) = bridge(&fixture);
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    let recipient = Address::generate(env);
+    let dest = Bytes::from_array(env, &[0xab; 20]);
+
+    assert_eq!(
+        bridge.lock(
+            &user,
+            &fixture.benji.address,
+            &(600 * TOKEN),
+            &OTHER_CHAIN,
+            &dest
+        ),
+        0
+    );
+    assert_eq!(
+        bridge.lock(
+            &user,
+            &fixture.benji.address,
+            &(400 * TOKEN),
+            &OTHER_CHAIN,
+            &dest
+        ),
+        1
+    );
+    assert_eq!(bridge.get_lock_nonce(), 2);
+    assert_eq!(bridge.get_locked(&fixture.benji.address), 1_000 * TOKEN);
+    assert_eq!(fixture.benji.balance(&bridge.address), 1_000 * TOKEN);
+
+    // Nothing is released before the relayers attest
+    let proof = proof(&fixture, 7);
+    assert_eq!(
+        bridge.try_release(&proof, &recipient, &(250 * TOKEN)),
+        Err(Ok(Error::InsufficientApprovals))
+    );
+    assert_eq!(
+        bridge.attest(&relayer, &proof, &recipient, &(250 * TOKEN)),
+        1
+    );
+    bridge.release(&proof, &recipient, &(250 * TOKEN));
+    assert_eq!(fixture.benji.balance(&recipient), 250 * TOKEN);
+    assert_eq!(bridge.get_locked(&fixture.benji.address), 750 * TOKEN);
+    assert!(bridge.is_processed(&OTHER_CHAIN, &7));
+
+    // The same transfer cannot be released or attested again
+    assert_eq!(
+        bridge.try_release(&proof, &recipient, &(250 * TOKEN)),
+        Err(Ok(Error::AlreadyProcessed))
+    );
+    assert_eq!(
+        bridge.try_attest(&relayer, &proof, &recipient, &(250 * TOKEN)),
+        Err(Ok(Error::AlreadyProcessed))
+    );
+    assert_eq!(fixture.benji.balance(&recipient), 250 * TOKEN);
+}
+
+#[test]
+fn release_is_limited_to_locked_tokens() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let (bridge, relayer) = bridge(&fixture);
+    let user = fixture.fund(100 * TOKEN, 0);
+    let recipient = Address::generate(env);
+    bridge.lock(
+        &user,
+        &fixture.benji.address,
+        &(100 * TOKEN),
+        &OTHER_CHAIN,
+        &Bytes::new(env),
+    );
+
+    let proof = proof(&fixture, 0);
+    assert_eq!(
+        bridge.try_release(&proof, &recipient, &0),
+        Err(Ok(Error::InvalidParameter))
+    );
+    bridge.attest(&relayer, &proof, &recipient, &(101 * TOKEN));
+    assert_eq!(
+        bridge.try_release(&proof, &recipient, &(101 * TOKEN)),
+        Err(Ok(Error::InsufficientLocked))
+    );
+    assert!(!bridge.is_processed(&OTHER_CHAIN, &0));
+    assert_eq!(bridge.get_locked(&fixture.benji.address), 100 * TOKEN);
+}
+
+#[test]
+fn only_allowed_tokens_are_locked() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let (bridge, _) = bridge(&fixture);
+    let user = fixture.fund(100 * TOKEN, 100 * TOKEN);
+
+    assert_eq!(
+        bridge.try_lock(
+            &user,
+            &fixture.benji.address,
+            &0,
+            &OTHER_CHAIN,
+            &Bytes::new(env)
+        ),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(
+        bridge.try_lock(
+            &user,
+            &fixture.usdc.address,
+            &TOKEN,
+            &OTHER_CHAIN,
+            &Bytes::new(env)
+        ),
+        Err(Ok(Error::TokenNotAllowed))
+    );
+    assert_eq!(
+        bridge.try_set_token_allowed(&user, &fixture.usdc.address, &true),
+        Err(Ok(Error::Unauthorized))
+    );
+
+    bridge.set_token_allowed(&fixture.admin, &fixture.benji.address, &false);
+    assert!(!bridge.is_token_allowed(&fixture.benji.address));
+    assert_eq!(
+        bridge.try_lock(
+            &user,
+            &fixture.benji.address,
+            &TOKEN,
+            &OTHER_CHAIN,
+            &Bytes::new(env)
+        ),
+        Err(Ok(Error::TokenNotAllowed))
+    );
+    assert_eq!(fixture.benji.balance(&user), 100 * TOKEN);
+}
+
+#[test]
+fn initialize_needs_the_admin_signature() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let bridge = BridgeClient::new(env, &env.register(Bridge, ()));
+    let relayers = vec![env, Address::generate(env)];
+
+    env.set_auths(&[]);
+    assert!(bridge
+        .try_initialize(&fixture.admin, &relayers, &1)
+        .is_err());
+
+    env.mock_all_auths();
+    bridge.initialize(&fixture.admin, &relayers, &1);
+    assert_eq!(
+        bridge.try_initialize(&fixture.admin, &relayers, &1),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+}
+
+/// A bridge allowing BENJI with 100 BENJI locked, run by three relayers of
+/// which `threshold` must approve a release
+fn multisig_bridge<'a>(fixture: &Fixture, threshold: u32) -> (BridgeClient<'a>, Vec<Address>) {
+    let env = &fixture.env;
+    let bridge = BridgeClient::new(env, &env.register(Bridge, ()));
+    let relayers = vec![
+        env,
+        Address::generate(env),
+        Address::generate(env),
+        Address::generate(env),
+    ];
+    bridge.initialize(&fixture.admin, &relayers, &threshold);
+    bridge.set_token_allowed(&fixture.admin, &fixture.benji.address, &true);
+    bridge.lock(
+        &fixture.fund(100 * TOKEN, 0),
+        &fixture.benji.address,
+        &(100 * TOKEN),
+        &OTHER_CHAIN,
+        &Bytes::new(env),
+    );
+    (bridge, relayers)
+}
+
+#[test]
+fn release_waits_for_the_threshold() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let (bridge, relayers) = multisig_bridge(&fixture, 2);
+    let recipient = Address::generate(env);
+    let proof = proof(&fixture, 0);
+    let amount = 10 * TOKEN;
+
+    assert_eq!(
+        bridge.attest(&relayers.get(0).unwrap(), &proof, &recipient, &amount),
+        1
+    );
+    assert_eq!(
+        bridge.try_attest(&relayers.get(0).unwrap(), &proof, &recipient, &amount),
+        Err(Ok(Error::AlreadyAttested))
+    );
+    assert_eq!(
+        bridge.try_attest(&recipient, &proof, &recipient, &amount),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        bridge.try_release(&proof, &recipient, &amount),
+        Err(Ok(Error::InsufficientApprovals))
+    );
+
+    // Approvals of a different amount do not count toward this one
+    bridge.attest(&relayers.get(1).unwrap(), &proof, &recipient, &(2 * amount));
+    assert_eq!(bridge.get_approvals(&proof, &recipient, &amount), 1);
+    assert_eq!(
+        bridge.try_release(&proof, &recipient, &amount),
+        Err(Ok(Error::InsufficientApprovals))
+    );
+
+    assert_eq!(
+        bridge.attest(&relayers.get(2).unwrap(), &proof, &recipient, &amount),
+        2
+    );
+    bridge.release(&proof, &recipient, &amount);
+    assert_eq!(fixture.benji.balance(&recipient), amount);
+
+    // Released requests leave no approvals behind
+    assert_eq!(bridge.get_approvals(&proof, &recipient, &amount), 0);
+}
+
+#[test]
+fn removed_relayers_no_longer_count() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let (bridge, relayers) = multisig_bridge(&fixture, 2);
+    let recipient = Address::generate(env);
+    let proof = proof(&fixture, 0);
+    let amount = 10 * TOKEN;
+
+    bridge.attest(&relayers.get(0).unwrap(), &proof, &recipient, &amount);
+    bridge.attest(&relayers.get(1).unwrap(), &proof, &recipient, &amount);
+    bridge.remove_relayer(&fixture.admin, &relayers.get(1).unwrap());
+    assert_eq!(bridge.get_relayers().len(), 2);
+    assert_eq!(bridge.get_approvals(&proof, &recipient, &amount), 1);
+    assert_eq!(
+        bridge.try_release(&proof, &recipient, &amount),
+        Err(Ok(Error::InsufficientApprovals))
+    );
+    assert_eq!(
+        bridge.try_attest(&relayers.get(1).unwrap(), &proof, &recipient, &amount),
+        Err(Ok(Error::Unauthorized))
+    );
+
+    bridge.attest(&relayers.get(2).unwrap(), &proof, &recipient, &amount);
+    bridge.release(&proof, &recipient, &amount);
+    assert_eq!(fixture.benji.balance(&recipient), amount);
+}
+
+#[test]
+fn relayer_set_keeps_the_threshold_reachable() {
+    let fixture = Fixture::new();
+    let (bridge, relayers) = multisig_bridge(&fixture, 3);
+
+    assert_eq!(
+        bridge.try_remove_relayer(&fixture.admin, &relayers.get(0).unwrap()),
+        Err(Ok(Error::InvalidThreshold))
+    );
+    assert_eq!(bridge.get_relayers(), relayers);
+    assert_eq!(
+        bridge.try_set_threshold(&fixture.admin, &4),
+        Err(Ok(Error::InvalidThreshold))
+    );
+    assert_eq!(
+        bridge.try_set_threshold(&fixture.admin, &0),
+        Err(Ok(Error::InvalidThreshold))
+    );
+
+    bridge.set_threshold(&fixture.admin, &2);
+    bridge.remove_relayer(&fixture.admin, &relayers.get(0).unwrap());
+    assert_eq!(bridge.get_threshold(), 2);
+    assert_eq!(
+        bridge.try_remove_relayer(&fixture.admin, &relayers.get(1).unwrap()),
+        Err(Ok(Error::InvalidThreshold))
+    );
+}
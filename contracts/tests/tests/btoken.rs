@@ -0,0 +1,115 @@
+use btoken::{BToken, BTokenClient};
+use credit_line::{CreditLineContract, CreditLineContractClient};
+use integration_tests::{Fixture, PRICE_ONE, TOKEN, YEAR};
+use soroban_sdk::{testutils::Address as _, Address, String};
+
+/// A market issuing bTokens, priced by the fixture's oracle
+fn market<'a>(fixture: &Fixture) -> (CreditLineContractClient<'a>, BTokenClient<'a>) {
+    let env = &fixture.env;
+    let admin = &fixture.admin;
+    let market = CreditLineContractClient::new(env, &env.register(CreditLineContract, ()));
+    market.initialize(admin, &fixture.benji.address, &fixture.usdc.address);
+    market.set_oracle(admin, &fixture.oracle.address);
+    let btoken = BTokenClient::new(env, &env.register(BToken, ()));
+    btoken.initialize(
+        &market.address,
+        &7,
+        &String::from_str(env, "BondBridge USDC"),
+        &String::from_str(env, "bUSDC"),
+    );
+    market.set_btoken(admin, &btoken.address);
+    (market, btoken)
+}
+
+#[test]
+fn only_the_credit_line_mints_and_burns() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let (market, btoken) = market(&fixture);
+    let lender = fixture.fund(0, 1_000 * TOKEN);
+
+    market.supply(&lender, &(1_000 * TOKEN));
+    assert_eq!(btoken.balance(&lender), 1_000 * TOKEN);
+    assert_eq!(btoken.total_supply(), 1_000 * TOKEN);
+
+    // Nobody else can sign for the credit line
+    env.set_auths(&[]);
+    assert!(btoken.try_mint(&lender, &TOKEN).is_err());
+    assert!(btoken.try_admin_burn(&lender, &TOKEN).is_err());
+    assert_eq!(btoken.balance(&lender), 1_000 * TOKEN);
+
+    env.mock_all_auths();
+    market.withdraw_supply(&lender, &(400 * TOKEN));
+    assert_eq!(btoken.balance(&lender), 600 * TOKEN);
+    assert_eq!(btoken.total_supply(), 600 * TOKEN);
+    assert_eq!(fixture.usdc.balance(&lender), 400 * TOKEN);
+}
+
+#[test]
+fn shares_redeem_for_more_as_interest_accrues() {
+    let fixture = Fixture::new();
+    let (market, btoken) = market(&fixture);
+    let lender = fixture.fund(0, 1_000 * TOKEN);
+    let borrower = fixture.fund(1_000 * TOKEN, 100 * TOKEN);
+
+    market.supply(&lender, &(1_000 * TOKEN));
+    let rate = market.get_exchange_rate();
+    market.deposit_collateral(
+        &borrower,
+        &0,
+        &fixture.benji.address,
+        &(1_000 * TOKEN),
+        &None,
+        &None,
+    );
+    market.borrow(&borrower, &0, &(500 * TOKEN), &None, &None);
+
+    // A year of interest lifts what every share redeems for
+    fixture.advance(YEAR);
+    fixture.set_benji_price(PRICE_ONE);
+    market.repay(
+        &borrower,
+        &0,
+        &market.get_current_debt(&borrower, &0),
+        &None,
+    );
+    assert!(market.get_exchange_rate() > rate);
+    let balance = market.get_supply_balance(&lender);
+    assert!(balance > 1_000 * TOKEN);
+
+    // Redeeming it all burns every share, bar rounding dust
+    market.withdraw_supply(&lender, &balance);
+    assert_eq!(fixture.usdc.balance(&lender), balance);
+    assert!(btoken.balance(&lender) <= 1);
+}
+
+#[test]
+fn transferred_shares_are_redeemed_by_the_new_holder() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let (market, btoken) = market(&fixture);
+    let alice = fixture.fund(0, 1_000 * TOKEN);
+    let bob = Address::generate(env);
+    let carol = Address::generate(env);
+
+    market.supply(&alice, &(1_000 * TOKEN));
+    btoken.transfer(&alice, &bob, &(300 * TOKEN));
+    assert_eq!(btoken.balance(&alice), 700 * TOKEN);
+    assert_eq!(btoken.balance(&bob), 300 * TOKEN);
+    assert_eq!(market.get_supply_shares(&bob), 300 * TOKEN);
+
+    // Allowances let a third party move shares, up to the amount approved
+    btoken.approve(&bob, &carol, &(100 * TOKEN), &1_000);
+    btoken.transfer_from(&carol, &bob, &carol, &(100 * TOKEN));
+    assert_eq!(btoken.allowance(&bob, &carol), 0);
+    assert!(btoken
+        .try_transfer_from(&carol, &bob, &carol, &TOKEN)
+        .is_err());
+
+    market.withdraw_supply(&bob, &(200 * TOKEN));
+    market.withdraw_supply(&carol, &(100 * TOKEN));
+    assert_eq!(fixture.usdc.balance(&bob), 200 * TOKEN);
+    assert_eq!(fixture.usdc.balance(&carol), 100 * TOKEN);
+    assert_eq!(btoken.total_supply(), 700 * TOKEN);
+    assert!(market.try_withdraw_supply(&bob, &TOKEN).is_err());
+}
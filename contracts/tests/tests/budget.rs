@@ -0,0 +1,79 @@
+use integration_tests::{Fixture, PRICE_ONE, TOKEN};
+
+/// Run one invocation against a fresh default budget and return the CPU
+/// instructions and memory bytes it used
+fn cost(fixture: &Fixture, call: impl FnOnce()) -> (u64, u64) {
+    let mut budget = fixture.env.cost_estimate().budget();
+    budget.reset_default();
+    call();
+    (budget.cpu_instruction_cost(), budget.memory_bytes_cost())
+}
+
+/// Ceilings sit about a quarter above today's costs, so a change that makes a
+/// hot path much dearer shows up here rather than on mainnet
+#[test]
+fn hot_paths_stay_within_their_budgets() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 100 * TOKEN);
+    let liquidator = fixture.fund(0, 1_000 * TOKEN);
+    fixture.mint_usdc(&fixture.lender, 1_000 * TOKEN);
+
+    let (cpu, mem) = cost(&fixture, || {
+        credit_line.supply(&fixture.lender, &(1_000 * TOKEN));
+    });
+    assert!(
+        cpu < 820_000 && mem < 130_000,
+        "supply: {cpu} cpu, {mem} bytes"
+    );
+
+    let (cpu, mem) = cost(&fixture, || {
+        credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    });
+    assert!(
+        cpu < 1_000_000 && mem < 180_000,
+        "deposit: {cpu} cpu, {mem} bytes"
+    );
+
+    let (cpu, mem) = cost(&fixture, || {
+        credit_line.borrow(&user, &0, &(400 * TOKEN), &None, &None);
+    });
+    assert!(
+        cpu < 1_260_000 && mem < 200_000,
+        "borrow: {cpu} cpu, {mem} bytes"
+    );
+
+    let (cpu, mem) = cost(&fixture, || {
+        credit_line.repay(&user, &0, &(100 * TOKEN), &None);
+    });
+    assert!(
+        cpu < 980_000 && mem < 150_000,
+        "repay: {cpu} cpu, {mem} bytes"
+    );
+
+    let (cpu, mem) = cost(&fixture, || {
+        credit_line.withdraw_collateral(&user, &0, benji, &(100 * TOKEN), &None);
+    });
+    assert!(
+        cpu < 1_370_000 && mem < 220_000,
+        "withdraw: {cpu} cpu, {mem} bytes"
+    );
+
+    let (cpu, mem) = cost(&fixture, || {
+        credit_line.get_health_factor(&user, &0);
+    });
+    assert!(
+        cpu < 530_000 && mem < 90_000,
+        "health factor: {cpu} cpu, {mem} bytes"
+    );
+
+    fixture.set_benji_price(PRICE_ONE / 3);
+    let (cpu, mem) = cost(&fixture, || {
+        credit_line.liquidate(&liquidator, &user, &0, benji, &(100 * TOKEN));
+    });
+    assert!(
+        cpu < 2_080_000 && mem < 350_000,
+        "liquidate: {cpu} cpu, {mem} bytes"
+    );
+}
@@ -0,0 +1,108 @@
+use debt_token::{DebtToken, DebtTokenClient};
+use integration_tests::{Fixture, PRICE_ONE, TOKEN, YEAR};
+use soroban_sdk::{testutils::Address as _, Address, String};
+
+/// A debt token minted and burned by the fixture's credit line
+fn debt_token<'a>(fixture: &Fixture) -> DebtTokenClient<'a> {
+    let env = &fixture.env;
+    let debt_token = DebtTokenClient::new(env, &env.register(DebtToken, ()));
+    debt_token.initialize(
+        &fixture.credit_line.address,
+        &7,
+        &String::from_str(env, "BondBridge USDC Debt"),
+        &String::from_str(env, "dUSDC"),
+    );
+    fixture
+        .credit_line
+        .set_debt_token(&fixture.admin, &debt_token.address);
+    debt_token
+}
+
+#[test]
+fn debt_cannot_change_hands() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let debt_token = debt_token(&fixture);
+    let borrower = fixture.fund(1_000 * TOKEN, 0);
+    let other = Address::generate(env);
+    fixture.credit_line.deposit_collateral(
+        &borrower,
+        &0,
+        &fixture.benji.address,
+        &(1_000 * TOKEN),
+        &None,
+        &None,
+    );
+    fixture
+        .credit_line
+        .borrow(&borrower, &0, &(100 * TOKEN), &None, &None);
+
+    assert!(debt_token
+        .try_transfer(&borrower, &other, &(100 * TOKEN))
+        .is_err());
+    assert!(debt_token
+        .try_approve(&borrower, &other, &(100 * TOKEN), &1_000)
+        .is_err());
+    assert!(debt_token
+        .try_transfer_from(&other, &borrower, &other, &TOKEN)
+        .is_err());
+    assert!(debt_token.try_burn(&borrower, &(100 * TOKEN)).is_err());
+    assert_eq!(debt_token.allowance(&borrower, &other), 0);
+    assert_eq!(debt_token.balance(&borrower), 100 * TOKEN);
+    assert_eq!(debt_token.balance(&other), 0);
+
+    // Only the credit line signs for mints and burns
+    env.set_auths(&[]);
+    assert!(debt_token.try_mint(&other, &TOKEN).is_err());
+    assert!(debt_token.try_admin_burn(&borrower, &TOKEN).is_err());
+}
+
+#[test]
+fn balance_tracks_debt_across_accounts() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let debt_token = debt_token(&fixture);
+    let benji = &fixture.benji.address;
+    let borrower = fixture.fund(2_000 * TOKEN, 100 * TOKEN);
+
+    credit_line.deposit_collateral(&borrower, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.deposit_collateral(&borrower, &1, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&borrower, &0, &(300 * TOKEN), &None, &None);
+    credit_line.borrow(&borrower, &1, &(200 * TOKEN), &None, &None);
+    assert_eq!(debt_token.balance(&borrower), 500 * TOKEN);
+    assert_eq!(debt_token.total_supply(), 500 * TOKEN);
+
+    credit_line.repay(&borrower, &1, &(50 * TOKEN), &None);
+    assert_eq!(debt_token.balance(&borrower), 450 * TOKEN);
+
+    // Interest shows up once it is accrued onto the account
+    fixture.advance(YEAR);
+    fixture.set_benji_price(PRICE_ONE);
+    assert_eq!(debt_token.balance(&borrower), 450 * TOKEN);
+    credit_line.accrue(&borrower, &0);
+    credit_line.accrue(&borrower, &1);
+    let debt = credit_line.get_position(&borrower, &0).borrowed
+        + credit_line.get_position(&borrower, &1).borrowed;
+    assert!(debt > 450 * TOKEN);
+    assert_eq!(debt_token.balance(&borrower), debt);
+
+    // Repaying in full clears it
+    credit_line.repay(
+        &borrower,
+        &1,
+        &credit_line.get_current_debt(&borrower, &1),
+        &None,
+    );
+    assert_eq!(
+        debt_token.balance(&borrower),
+        credit_line.get_position(&borrower, &0).borrowed
+    );
+    credit_line.repay(
+        &borrower,
+        &0,
+        &credit_line.get_current_debt(&borrower, &0),
+        &None,
+    );
+    assert_eq!(debt_token.balance(&borrower), 0);
+    assert_eq!(debt_token.total_supply(), 0);
+}
@@ -0,0 +1,237 @@
+use bondbridge_events::credit_line::{
+    Borrow, Deposit, HealthWarning, Heartbeat, LtvUpdated, ParamChanged, PauseUpdated,
+};
+use bondbridge_events::decode::to_address;
+use bondbridge_events::{CreditLineEvent, DecodeEvent};
+use credit_line::{Error, HEALTH_FACTOR_ONE};
+use integration_tests::{Fixture, TOKEN, YEAR};
+use soroban_sdk::testutils::{
+    storage::Instance as _, Address as _, Events as _, MuxedAddress as _,
+};
+use soroban_sdk::xdr::ScVal;
+use soroban_sdk::{Address, MuxedAddress, TryFromVal};
+
+/// Decode the events the credit line emitted in the last invocation
+fn credit_line_events(fixture: &Fixture) -> Vec<CreditLineEvent> {
+    let env = &fixture.env;
+    env.events()
+        .all()
+        .iter()
+        .filter(|(contract, _, _)| *contract == fixture.credit_line.address)
+        .map(|(_, topics, data)| {
+            let topics = topics
+                .iter()
+                .map(|topic| ScVal::try_from_val(env, &topic).unwrap())
+                .collect::<Vec<_>>();
+            CreditLineEvent::decode(&topics, &ScVal::try_from_val(env, &data).unwrap()).unwrap()
+        })
+        .collect()
+}
+
+fn strkey(fixture: &Fixture, address: &Address) -> String {
+    to_address(&ScVal::try_from_val(&fixture.env, &address.to_val()).unwrap()).unwrap()
+}
+
+#[test]
+fn decodes_emitted_events() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    let events = credit_line_events(&fixture);
+    assert_eq!(
+        events.last().unwrap(),
+        &CreditLineEvent::Deposit(Deposit {
+            user: strkey(&fixture, &user),
+            account_id: 0,
+            token: strkey(&fixture, benji),
+            payer: strkey(&fixture, &user),
+            muxed_id: None,
+            amount: 1_000 * TOKEN,
+            collateral: 1_000 * TOKEN,
+            borrowed: 0,
+        })
+    );
+
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    let events = credit_line_events(&fixture);
+    assert_eq!(
+        events.last().unwrap(),
+        &CreditLineEvent::Borrow(Borrow {
+            user: strkey(&fixture, &user),
+            account_id: 0,
+            recipient: strkey(&fixture, &user),
+            to: strkey(&fixture, &user),
+            muxed_id: None,
+            amount: 500 * TOKEN,
+            borrowed: 500 * TOKEN,
+        })
+    );
+    assert_eq!(events.last().unwrap().name(), "borrow");
+}
+
+#[test]
+fn decodes_admin_events() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    credit_line.set_ltv_ratio(&fixture.admin, benji, &6_500);
+    assert!(
+        credit_line_events(&fixture).contains(&CreditLineEvent::LtvUpdated(LtvUpdated {
+            token: strkey(&fixture, benji),
+            old_ratio: 7_000,
+            new_ratio: 6_500,
+        }))
+    );
+    assert!(
+        credit_line_events(&fixture).contains(&CreditLineEvent::ParamChanged(ParamChanged {
+            param: "ltv_ratio".to_string(),
+            admin: strkey(&fixture, &fixture.admin),
+            sequence: credit_line.get_param_change_count() - 1,
+            subject: Some(strkey(&fixture, benji)),
+            old_value: Some(7_000),
+            new_value: Some(6_500),
+            old_address: None,
+            new_address: None,
+        }))
+    );
+
+    credit_line.pause(&fixture.admin, &true);
+    assert!(
+        credit_line_events(&fixture).contains(&CreditLineEvent::PauseUpdated(PauseUpdated {
+            paused: true,
+            pause_repay: true,
+        }))
+    );
+}
+
+#[test]
+fn poke_accrues_interest_and_reports_a_heartbeat() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    fixture.advance(YEAR);
+
+    let borrow_index = credit_line.poke();
+    let events = credit_line_events(&fixture);
+    let total_borrowed = credit_line.get_total_borrowed();
+    assert!(total_borrowed > 500 * TOKEN);
+    assert_eq!(
+        events,
+        vec![CreditLineEvent::Heartbeat(Heartbeat {
+            borrow_index,
+            total_borrowed,
+            total_supplied: credit_line.get_total_supplied(),
+        })]
+    );
+
+    // Instance storage now lives for the next 30 days of ledgers
+    let ttl = env.as_contract(&credit_line.address, || env.storage().instance().get_ttl());
+    assert!(ttl >= 29 * 17_280);
+}
+
+#[test]
+fn positions_near_liquidation_publish_a_health_warning() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let admin = &fixture.admin;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    assert_eq!(
+        credit_line.try_set_health_warning(admin, &Some(HEALTH_FACTOR_ONE)),
+        Err(Ok(Error::InvalidParameter))
+    );
+    let threshold = HEALTH_FACTOR_ONE * 3 / 2;
+    credit_line.set_health_warning(admin, &Some(threshold));
+
+    // 800 of liquidation limit against 500 of debt is still comfortable
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    assert!(!credit_line_events(&fixture)
+        .iter()
+        .any(|event| matches!(event, CreditLineEvent::HealthWarning(_))));
+
+    credit_line.borrow(&user, &0, &(100 * TOKEN), &None, &None);
+    let events = credit_line_events(&fixture);
+    let health_factor = credit_line.get_health_factor(&user, &0);
+    assert!(health_factor < threshold);
+    assert!(
+        events.contains(&CreditLineEvent::HealthWarning(HealthWarning {
+            user: strkey(&fixture, &user),
+            account_id: 0,
+            health_factor,
+            threshold,
+        }))
+    );
+
+    // Without a threshold nothing is published
+    credit_line.set_health_warning(admin, &None);
+    credit_line.borrow(&user, &0, &TOKEN, &None, &None);
+    assert!(!credit_line_events(&fixture)
+        .iter()
+        .any(|event| matches!(event, CreditLineEvent::HealthWarning(_))));
+}
+
+#[test]
+fn muxed_users_share_their_base_account_position() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    let muxed = MuxedAddress::generate(&fixture.env);
+    let user = muxed.address();
+    fixture.mint_benji(&user, 1_000 * TOKEN);
+
+    credit_line.deposit_collateral(&muxed, &0, benji, &(1_000 * TOKEN), &None, &None);
+    let CreditLineEvent::Deposit(deposit) = credit_line_events(&fixture).pop().unwrap() else {
+        panic!("expected a deposit event");
+    };
+    assert_eq!(deposit.user, strkey(&fixture, &user));
+    assert_eq!(deposit.muxed_id, muxed.id());
+
+    // Borrowing through the muxed address draws on the base account's collateral
+    credit_line.borrow(&muxed, &0, &(500 * TOKEN), &None, &None);
+    let CreditLineEvent::Borrow(borrow) = credit_line_events(&fixture).pop().unwrap() else {
+        panic!("expected a borrow event");
+    };
+    assert_eq!(borrow.muxed_id, muxed.id());
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 500 * TOKEN);
+    assert_eq!(fixture.usdc.balance(&user), 500 * TOKEN);
+}
+
+#[test]
+fn proceeds_can_be_sent_to_another_account() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    let wallet = Address::generate(&fixture.env);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &Some(wallet.clone()));
+    let CreditLineEvent::Borrow(borrow) = credit_line_events(&fixture).pop().unwrap() else {
+        panic!("expected a borrow event");
+    };
+    assert_eq!(borrow.recipient, strkey(&fixture, &user));
+    assert_eq!(borrow.to, strkey(&fixture, &wallet));
+    assert_eq!(fixture.usdc.balance(&wallet), 500 * TOKEN);
+    assert_eq!(fixture.usdc.balance(&user), 0);
+
+    credit_line.withdraw_collateral(&user, &0, benji, &(100 * TOKEN), &Some(wallet.clone()));
+    let CreditLineEvent::Withdraw(withdraw) = credit_line_events(&fixture).pop().unwrap() else {
+        panic!("expected a withdraw event");
+    };
+    assert_eq!(withdraw.to, strkey(&fixture, &wallet));
+    assert_eq!(fixture.benji.balance(&wallet), 100 * TOKEN);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 500 * TOKEN);
+}
@@ -0,0 +1,96 @@
+use credit_line::fixed_loan::LoanTerms;
+use credit_line::{Error, HEALTH_FACTOR_ONE};
+use integration_tests::{Fixture, PRICE_ONE, TOKEN};
+
+/// Ledgers closed in a day
+const DAY: u32 = 17_280;
+
+/// Ledgers between installments of a loan longer than a month
+const INTERVAL: u32 = 30 * DAY;
+
+#[test]
+fn fixed_loans_follow_their_schedule() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(10_000 * TOKEN, 100 * TOKEN);
+    let start = fixture.env.ledger().sequence();
+
+    credit_line.deposit_collateral(&user, &0, benji, &(10_000 * TOKEN), &None, &None);
+    credit_line.borrow_fixed(&user, &0, &(100 * TOKEN), &(2 * INTERVAL));
+    let LoanTerms::Fixed(loan) = credit_line.get_position(&user, &0).terms else {
+        panic!("expected a fixed loan");
+    };
+    assert_eq!(loan.installments, 2);
+    assert_eq!(loan.outstanding, 100 * TOKEN);
+
+    // The account takes no other debt until the loan is paid off
+    assert_eq!(
+        credit_line.try_borrow(&user, &0, &TOKEN, &None, &None),
+        Err(Ok(Error::FixedLoanActive))
+    );
+    assert_eq!(
+        credit_line.try_borrow_fixed(&user, &0, &TOKEN, &INTERVAL),
+        Err(Ok(Error::FixedLoanActive))
+    );
+
+    // The first installment is half the principal plus the interest so far
+    fixture.advance_ledgers(INTERVAL);
+    fixture.set_benji_price(PRICE_ONE);
+    let (amount, due) = credit_line.get_installment_due(&user, &0);
+    assert_eq!(due, start + INTERVAL);
+    assert!(amount > 50 * TOKEN);
+    assert_eq!(credit_line.repay_installment(&user, &0, &None), amount);
+
+    // Interest accruing after the installment is paid does not make it overdue
+    fixture.advance_ledgers(1);
+    fixture.set_benji_price(PRICE_ONE);
+    assert!(credit_line.get_health_factor(&user, &0) > HEALTH_FACTOR_ONE);
+    assert!(!credit_line.is_liquidatable(&user, &0));
+    let (amount, due) = credit_line.get_installment_due(&user, &0);
+    assert_eq!(due, start + 2 * INTERVAL);
+    assert!(amount > 50 * TOKEN);
+
+    // Missing the last due ledger makes the loan liquidatable whatever its health
+    fixture.advance_ledgers(INTERVAL);
+    fixture.set_benji_price(PRICE_ONE);
+    assert!(credit_line.get_health_factor(&user, &0) > HEALTH_FACTOR_ONE);
+    assert!(credit_line.is_liquidatable(&user, &0));
+
+    // Settling it pays the loan off and reopens the credit line
+    credit_line.repay_installment(&user, &0, &None);
+    let position = credit_line.get_position(&user, &0);
+    assert_eq!(position.borrowed, 0);
+    assert_eq!(position.terms, LoanTerms::Open);
+    assert!(!credit_line.is_liquidatable(&user, &0));
+    credit_line.borrow(&user, &0, &TOKEN, &None, &None);
+}
+
+#[test]
+fn early_repayment_counts_toward_later_installments() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(10_000 * TOKEN, 100 * TOKEN);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(10_000 * TOKEN), &None, &None);
+    credit_line.borrow_fixed(&user, &0, &(100 * TOKEN), &(3 * INTERVAL));
+
+    // Two thirds of the principal repaid up front covers the first two installments
+    fixture.advance_ledgers(DAY);
+    fixture.set_benji_price(PRICE_ONE);
+    let interest = credit_line.get_current_debt(&user, &0) - 100 * TOKEN;
+    credit_line.repay(&user, &0, &(interest + 67 * TOKEN), &None);
+
+    fixture.advance_ledgers(2 * INTERVAL);
+    fixture.set_benji_price(PRICE_ONE);
+    assert!(!credit_line.is_liquidatable(&user, &0));
+    let (amount, _) = credit_line.get_installment_due(&user, &0);
+    assert_eq!(amount, credit_line.get_current_debt(&user, &0));
+
+    // Accounts without a fixed loan have no installments
+    assert_eq!(
+        credit_line.try_get_installment_due(&user, &1),
+        Err(Ok(Error::NoFixedLoan))
+    );
+}
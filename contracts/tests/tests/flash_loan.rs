@@ -0,0 +1,229 @@
+use credit_line::flash_loan::{FlashLiquidationReceiver, FlashLoanReceiver};
+use credit_line::{CreditLineContractClient, Error};
+use dex_adapter::DexAdapterClient;
+use integration_tests::{Fixture, LIQUIDITY, PRICE_ONE, TOKEN};
+use soroban_sdk::{
+    contract, contractimpl, contracttype, testutils::Address as _, token, Address, Env,
+};
+
+/// What `MockReceiver` does with a loan
+#[contracttype]
+#[derive(Clone, Copy)]
+enum Behaviour {
+    Repay,
+    Keep,
+    Reenter,
+}
+
+/// Flash loan receiver that repays the loan with its fee, keeps it, or tries
+/// to supply it back to the pool
+#[contract]
+struct MockReceiver;
+
+#[contractimpl]
+impl MockReceiver {
+    pub fn __constructor(env: Env, credit_line: Address, behaviour: Behaviour) {
+        env.storage().instance().set(&0u32, &credit_line);
+        env.storage().instance().set(&1u32, &behaviour);
+    }
+}
+
+#[contractimpl]
+impl FlashLoanReceiver for MockReceiver {
+    fn exec_op(env: Env, token: Address, amount: i128, fee: i128) {
+        let credit_line: Address = env.storage().instance().get(&0u32).unwrap();
+        let receiver = env.current_contract_address();
+        match env.storage().instance().get(&1u32).unwrap() {
+            Behaviour::Repay => {
+                token::Client::new(&env, &token).transfer(&receiver, &credit_line, &(amount + fee))
+            }
+            Behaviour::Keep => {}
+            Behaviour::Reenter => {
+                CreditLineContractClient::new(&env, &credit_line).supply(&receiver, &amount);
+            }
+        }
+    }
+}
+
+/// Contract that starts flash loans on its own authority for a separate receiver
+#[contract]
+struct MockInitiator;
+
+#[contractimpl]
+impl MockInitiator {
+    pub fn start(env: Env, credit_line: Address, receiver: Address, amount: i128) -> i128 {
+        CreditLineContractClient::new(&env, &credit_line).flash_loan(
+            &env.current_contract_address(),
+            &receiver,
+            &amount,
+        )
+    }
+}
+
+/// Flash liquidation receiver that sells the collateral it is sent on a DEX
+/// and pays the pool what it owes, or as much of it as the sale raised
+#[contract]
+struct MockLiquidator;
+
+#[contractimpl]
+impl MockLiquidator {
+    pub fn __constructor(env: Env, credit_line: Address, dex: Address, usdc: Address) {
+        env.storage().instance().set(&0u32, &credit_line);
+        env.storage().instance().set(&1u32, &dex);
+        env.storage().instance().set(&2u32, &usdc);
+    }
+}
+
+#[contractimpl]
+impl FlashLiquidationReceiver for MockLiquidator {
+    fn exec_liquidation(env: Env, token: Address, amount: i128, owed: i128) {
+        let credit_line: Address = env.storage().instance().get(&0u32).unwrap();
+        let dex: Address = env.storage().instance().get(&1u32).unwrap();
+        let usdc = token::Client::new(&env, &env.storage().instance().get(&2u32).unwrap());
+        let receiver = env.current_contract_address();
+
+        token::Client::new(&env, &token).transfer(&receiver, &dex, &amount);
+        DexAdapterClient::new(&env, &dex).swap_exact_in(
+            &token,
+            &usdc.address,
+            &amount,
+            &0,
+            &receiver,
+        );
+        usdc.transfer(&receiver, &credit_line, &owed.min(usdc.balance(&receiver)));
+    }
+}
+
+fn receiver(fixture: &Fixture, behaviour: Behaviour) -> Address {
+    let receiver = fixture.env.register(
+        MockReceiver,
+        (fixture.credit_line.address.clone(), behaviour),
+    );
+    fixture.mint_usdc(&receiver, 100 * TOKEN);
+    receiver
+}
+
+#[test]
+fn flash_loan_fee_accrues_to_suppliers() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let receiver = receiver(&fixture, Behaviour::Repay);
+    let initiator = Address::generate(&fixture.env);
+
+    // 0.09% of the loan, rounded up
+    let fee = credit_line.flash_loan(&initiator, &receiver, &(10_000 * TOKEN));
+    assert_eq!(fee, 9 * TOKEN);
+    assert_eq!(fixture.usdc.balance(&receiver), 100 * TOKEN - fee);
+    assert_eq!(fixture.usdc.balance(&credit_line.address), LIQUIDITY + fee);
+    assert_eq!(credit_line.get_total_supplied(), LIQUIDITY + fee);
+    assert_eq!(
+        credit_line.get_supply_balance(&fixture.lender),
+        LIQUIDITY + fee
+    );
+
+    // No more than the pool holds can be lent
+    assert_eq!(
+        credit_line.try_flash_loan(&initiator, &receiver, &(LIQUIDITY + fee + 1)),
+        Err(Ok(Error::InsufficientLiquidity))
+    );
+    assert_eq!(
+        credit_line.try_flash_loan(&initiator, &receiver, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+}
+
+#[test]
+fn flash_loan_must_be_repaid_within_the_call() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let initiator = Address::generate(&fixture.env);
+
+    let keeper = receiver(&fixture, Behaviour::Keep);
+    assert_eq!(
+        credit_line.try_flash_loan(&initiator, &keeper, &(1_000 * TOKEN)),
+        Err(Ok(Error::FlashLoanNotRepaid))
+    );
+    assert_eq!(fixture.usdc.balance(&keeper), 100 * TOKEN);
+
+    // The receiver cannot use the pool while it holds the loan, and its
+    // failure fails the loan
+    let reentrant = receiver(&fixture, Behaviour::Reenter);
+    assert!(credit_line
+        .try_flash_loan(&initiator, &reentrant, &(1_000 * TOKEN))
+        .is_err());
+    assert_eq!(fixture.usdc.balance(&reentrant), 100 * TOKEN);
+    assert_eq!(credit_line.get_total_supplied(), LIQUIDITY);
+    assert_eq!(fixture.usdc.balance(&credit_line.address), LIQUIDITY);
+}
+
+#[test]
+fn contract_starts_a_flash_loan_on_its_own_authority() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let receiver = receiver(&fixture, Behaviour::Repay);
+    let initiator = MockInitiatorClient::new(env, &env.register(MockInitiator, ()));
+
+    // No mocked signatures: the initiator authorizes by being the caller
+    env.set_auths(&[]);
+    let fee = initiator.start(&credit_line.address, &receiver, &(1_000 * TOKEN));
+    assert_eq!(fee, 9 * TOKEN / 10);
+    assert_eq!(fixture.usdc.balance(&receiver), 100 * TOKEN - fee);
+    assert_eq!(credit_line.get_total_supplied(), LIQUIDITY + fee);
+
+    // Anyone else needs the initiator's signature
+    assert!(credit_line
+        .try_flash_loan(&initiator.address, &receiver, &(1_000 * TOKEN))
+        .is_err());
+}
+
+#[test]
+fn liquidator_without_usdc_liquidates_with_pool_funds() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    let liquidator = Address::generate(env);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(700 * TOKEN), &None, &None);
+    fixture.set_benji_price(85 * PRICE_ONE / 100);
+    assert!(credit_line.is_liquidatable(&user, &0));
+
+    // Selling the collateral at half its worth raises less than is owed, and
+    // the liquidation is undone
+    let liquidator_at = |rate_bps: i128| {
+        env.register(
+            MockLiquidator,
+            (
+                credit_line.address.clone(),
+                fixture.mock_dex(rate_bps),
+                fixture.usdc.address.clone(),
+            ),
+        )
+    };
+    let short = liquidator_at(5_000);
+    assert_eq!(
+        credit_line.try_flash_liquidate(&liquidator, &short, &user, &0, benji, &(200 * TOKEN)),
+        Err(Ok(Error::FlashLoanNotRepaid))
+    );
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 700 * TOKEN);
+
+    // At the oracle price the sale repays the pool with its 0.09% fee and the
+    // receiver keeps the bonus
+    let receiver = liquidator_at(10_000 * 100 / 85);
+    let paid =
+        credit_line.flash_liquidate(&liquidator, &receiver, &user, &0, benji, &(200 * TOKEN));
+    let fee = 200 * TOKEN * 9 / 10_000;
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 500 * TOKEN);
+    assert_eq!(fixture.benji.balance(&receiver), 0);
+    assert_eq!(
+        fixture.usdc.balance(&receiver),
+        paid * (10_000 * 100 / 85) / 10_000 - 200 * TOKEN - fee
+    );
+    assert!(fixture.usdc.balance(&receiver) > 0);
+    assert_eq!(fixture.usdc.balance(&liquidator), 0);
+    assert_eq!(credit_line.get_total_supplied(), LIQUIDITY + fee);
+    assert!(credit_line.check_solvency().gap >= 0);
+}
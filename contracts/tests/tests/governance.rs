@@ -0,0 +1,247 @@
+use governance::{Error, Governance, GovernanceClient};
+use integration_tests::{Fixture, DAY};
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    vec, Address, Env, IntoVal, Symbol, Val, Vec,
+};
+
+/// A governor of three members needing two votes, with a day to vote and a
+/// day's queue delay
+fn governor<'a>(env: &Env) -> (GovernanceClient<'a>, Vec<Address>) {
+    let governance = GovernanceClient::new(env, &env.register(Governance, ()));
+    let members = vec![
+        env,
+        Address::generate(env),
+        Address::generate(env),
+        Address::generate(env),
+    ];
+    governance.initialize(&members, &2, &DAY, &DAY);
+    (governance, members)
+}
+
+fn args(env: &Env, arg: impl IntoVal<Env, Val>) -> Vec<Val> {
+    vec![env, arg.into_val(env)]
+}
+
+#[test]
+fn initialize_needs_every_member_and_a_reachable_quorum() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let governance = GovernanceClient::new(&env, &env.register(Governance, ()));
+    let member = Address::generate(&env);
+    let other = Address::generate(&env);
+
+    assert_eq!(
+        governance.try_initialize(&vec![&env, member.clone(), member.clone()], &1, &DAY, &0),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(
+        governance.try_initialize(&vec![&env, member.clone(), other.clone()], &3, &DAY, &0),
+        Err(Ok(Error::InvalidParameter))
+    );
+
+    governance.initialize(&vec![&env, member.clone(), other.clone()], &2, &DAY, &0);
+    let signers: std::vec::Vec<Address> = env.auths().into_iter().map(|(a, _)| a).collect();
+    assert_eq!(signers, [member.clone(), other.clone()]);
+    assert_eq!(
+        governance.try_initialize(&vec![&env, member], &1, &DAY, &0),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+}
+
+#[test]
+fn governor_takes_over_the_credit_line_after_its_delays() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let (governance, members) = governor(env);
+    let (a, b) = (members.get(0).unwrap(), members.get(1).unwrap());
+
+    credit_line.propose_admin(&fixture.admin, &governance.address);
+    let id = governance.propose(
+        &a,
+        &credit_line.address,
+        &Symbol::new(env, "accept_admin"),
+        &args(env, governance.address.clone()),
+    );
+    assert_eq!(
+        governance.try_execute(&id).err(),
+        Some(Ok(Error::QuorumNotReached))
+    );
+    assert_eq!(governance.vote(&b, &id), 2);
+    assert_eq!(governance.try_vote(&b, &id), Err(Ok(Error::AlreadyVoted)));
+
+    // Voting runs its course, then the queue delay
+    fixture.advance(DAY);
+    assert_eq!(
+        governance.try_execute(&id).err(),
+        Some(Ok(Error::TimelockNotElapsed))
+    );
+    fixture.advance(DAY);
+    governance.execute(&id);
+    assert_eq!(credit_line.get_admin(), governance.address);
+    assert_eq!(
+        governance.try_execute(&id).err(),
+        Some(Ok(Error::AlreadyExecuted))
+    );
+}
+
+#[test]
+fn governor_changes_its_own_members_and_rules() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (governance, members) = governor(&env);
+    let (a, b, c) = (
+        members.get(0).unwrap(),
+        members.get(1).unwrap(),
+        members.get(2).unwrap(),
+    );
+    let newcomer = Address::generate(&env);
+    let pass = |function: &str, arg: Val| {
+        let id = governance.propose(
+            &a,
+            &governance.address,
+            &Symbol::new(&env, function),
+            &vec![&env, arg],
+        );
+        governance.vote(&b, &id);
+        env.ledger().with_mut(|l| l.timestamp += 2 * DAY);
+        governance.try_execute(&id)
+    };
+
+    assert!(pass("add_member", newcomer.into_val(&env)).is_ok());
+    assert!(pass("remove_member", c.into_val(&env)).is_ok());
+    assert_eq!(
+        governance.get_members(),
+        vec![&env, a.clone(), b.clone(), newcomer]
+    );
+    assert_eq!(
+        governance.try_propose(
+            &c,
+            &governance.address,
+            &Symbol::new(&env, "set_quorum"),
+            &vec![&env]
+        ),
+        Err(Ok(Error::NotMember))
+    );
+
+    // The quorum must stay within reach of the members
+    assert_eq!(
+        pass("set_quorum", 4u32.into_val(&env)).err(),
+        Some(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(
+        pass("set_voting_period", 0u64.into_val(&env)).err(),
+        Some(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(
+        pass("upgrade", 0u64.into_val(&env)).err(),
+        Some(Ok(Error::InvalidParameter))
+    );
+    assert!(pass("set_quorum", 3u32.into_val(&env)).is_ok());
+    assert_eq!(governance.get_quorum(), 3);
+}
+
+#[test]
+fn proposers_can_cancel_until_execution() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (governance, members) = governor(&env);
+    let (a, b) = (members.get(0).unwrap(), members.get(1).unwrap());
+
+    let id = governance.propose(
+        &a,
+        &governance.address,
+        &Symbol::new(&env, "set_queue_delay"),
+        &args(&env, 0u64),
+    );
+    assert_eq!(governance.try_cancel(&b, &id), Err(Ok(Error::NotProposer)));
+    governance.cancel(&a, &id);
+    assert!(governance.get_proposal(&id).cancelled);
+    assert_eq!(
+        governance.try_vote(&b, &id),
+        Err(Ok(Error::ProposalCancelled))
+    );
+    assert_eq!(
+        governance.try_execute(&id).err(),
+        Some(Ok(Error::ProposalCancelled))
+    );
+    assert_eq!(
+        governance.try_cancel(&a, &id),
+        Err(Ok(Error::ProposalCancelled))
+    );
+}
+
+#[test]
+fn votes_close_with_the_voting_period() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (governance, members) = governor(&env);
+    let (a, b) = (members.get(0).unwrap(), members.get(1).unwrap());
+    let outsider = Address::generate(&env);
+
+    let id = governance.propose(
+        &a,
+        &governance.address,
+        &Symbol::new(&env, "set_queue_delay"),
+        &args(&env, 0u64),
+    );
+    assert_eq!(governance.get_proposal(&id).voters, vec![&env, a.clone()]);
+    assert!(governance.has_voted(&id, &a));
+    assert!(!governance.has_voted(&id, &b));
+    assert_eq!(
+        governance.try_vote(&outsider, &id),
+        Err(Ok(Error::NotMember))
+    );
+    assert_eq!(
+        governance.try_vote(&b, &(id + 1)),
+        Err(Ok(Error::ProposalNotFound))
+    );
+
+    // A proposal short of quorum when voting ends can never pass
+    env.ledger().with_mut(|l| l.timestamp += DAY);
+    assert_eq!(governance.try_vote(&b, &id), Err(Ok(Error::VotingClosed)));
+    env.ledger().with_mut(|l| l.timestamp += DAY);
+    assert_eq!(
+        governance.try_execute(&id).err(),
+        Some(Ok(Error::QuorumNotReached))
+    );
+}
+
+#[test]
+fn votes_of_removed_members_stop_counting() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (governance, members) = governor(&env);
+    let (a, b, c) = (
+        members.get(0).unwrap(),
+        members.get(1).unwrap(),
+        members.get(2).unwrap(),
+    );
+
+    // B votes for a change, while A and C vote B out
+    let change = governance.propose(
+        &a,
+        &governance.address,
+        &Symbol::new(&env, "set_queue_delay"),
+        &args(&env, 0u64),
+    );
+    governance.vote(&b, &change);
+    let removal = governance.propose(
+        &c,
+        &governance.address,
+        &Symbol::new(&env, "remove_member"),
+        &args(&env, b.clone()),
+    );
+    governance.vote(&a, &removal);
+
+    env.ledger().with_mut(|l| l.timestamp += 2 * DAY);
+    governance.execute(&removal);
+
+    // Without B's vote the change falls short of quorum
+    assert!(governance.has_voted(&change, &b));
+    assert_eq!(
+        governance.try_execute(&change).err(),
+        Some(Ok(Error::QuorumNotReached))
+    );
+}
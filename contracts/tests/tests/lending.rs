@@ -0,0 +1,1236 @@
+use credit_line::oracle::{self, OracleSettings, QuoteAsset};
+use credit_line::param_update::ParamUpdate;
+use credit_line::{
+    isolation::IsolationUsage, AddressChange, AmountChange, Compounding, Error, MarketConfigUpdate,
+    ProtocolCollateral,
+};
+use integration_tests::{Fixture, LIQUIDITY, PRICE_ONE, TOKEN, YEAR};
+use mock_usdc_token::UsdcTokenClient;
+use soroban_sdk::{testutils::Address as _, vec, Address, Symbol};
+
+/// Seconds in an hour
+const HOUR: u64 = 60 * 60;
+
+/// A config update that leaves every parameter as is
+fn no_changes() -> MarketConfigUpdate {
+    MarketConfigUpdate {
+        oracle: AddressChange::Keep,
+        treasury: AddressChange::Keep,
+        staking: AddressChange::Keep,
+        interest_rate: None,
+        rate_slope: None,
+        stable_rate_premium: None,
+        liquidation_bonus: None,
+        reserve_factor: None,
+        flash_loan_fee: None,
+        debt_ceiling: AmountChange::Keep,
+        min_borrow: None,
+        min_collateral: None,
+        grace_period: None,
+        max_price_age: None,
+        max_price_deviation: None,
+        twap_window: None,
+        interest_free_period: None,
+        late_penalty_rate: None,
+        close_factor: None,
+    }
+}
+
+#[test]
+fn deposit_borrow_accrue_repay_withdraw() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    assert_eq!(fixture.benji.balance(&user), 0);
+
+    // 70% LTV
+    assert_eq!(credit_line.get_available_credit(&user, &0), 700 * TOKEN);
+
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    assert_eq!(fixture.usdc.balance(&user), 500 * TOKEN);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 500 * TOKEN);
+
+    // A year later the debt has grown, though only once the position is accrued
+    fixture.advance(YEAR);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 500 * TOKEN);
+    let debt = credit_line.get_current_debt(&user, &0);
+    assert!(debt > 500 * TOKEN);
+
+    fixture.mint_usdc(&user, debt - 500 * TOKEN);
+    credit_line.repay(&user, &0, &debt, &None);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 0);
+    assert_eq!(fixture.usdc.balance(&user), 0);
+
+    credit_line.withdraw_collateral(&user, &0, benji, &(1_000 * TOKEN), &None);
+    assert_eq!(fixture.benji.balance(&user), 1_000 * TOKEN);
+
+    let solvency = credit_line.check_solvency();
+    assert!(solvency.gap >= 0);
+    assert_eq!(solvency.total_borrowed, 0);
+}
+
+#[test]
+fn deposit_pulls_collateral_through_allowance() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    let expiration = fixture.env.ledger().sequence() + 100;
+    fixture
+        .benji
+        .approve(&user, &credit_line.address, &(600 * TOKEN), &expiration);
+
+    credit_line.deposit_collateral_from(&user, &0, benji, &(400 * TOKEN), &None, &None);
+    assert_eq!(fixture.benji.balance(&user), 600 * TOKEN);
+    assert_eq!(
+        credit_line
+            .get_position(&user, &0)
+            .collateral
+            .get(benji.clone()),
+        Some(400 * TOKEN)
+    );
+    assert_eq!(
+        fixture.benji.allowance(&user, &credit_line.address),
+        200 * TOKEN
+    );
+
+    // The rest of the balance is not approved
+    assert_eq!(
+        credit_line.try_deposit_collateral_from(&user, &0, benji, &(300 * TOKEN), &None, &None),
+        Err(Ok(Error::InsufficientAllowance))
+    );
+}
+
+#[test]
+fn market_totals_track_supply_borrow_and_collateral() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    assert_eq!(credit_line.get_total_supplied(), LIQUIDITY);
+    assert_eq!(credit_line.get_total_borrowed(), 0);
+    assert_eq!(credit_line.get_utilization(), 0);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    assert_eq!(credit_line.get_total_collateral(benji), 1_000 * TOKEN);
+    assert_eq!(credit_line.get_total_borrowed(), 500 * TOKEN);
+    assert_eq!(
+        credit_line.get_utilization(),
+        credit_line.get_market_summary().utilization
+    );
+
+    // USDC sent straight to the contract is not supplied
+    fixture.mint_usdc(&credit_line.address, 100 * TOKEN);
+    assert_eq!(credit_line.get_total_supplied(), LIQUIDITY);
+
+    // Interest reaches the counter once repaid, less the reserve factor
+    fixture.advance(YEAR);
+    fixture.set_benji_price(PRICE_ONE);
+    let debt = credit_line.get_current_debt(&user, &0);
+    fixture.mint_usdc(&user, debt - 500 * TOKEN);
+    credit_line.repay(&user, &0, &debt, &None);
+    assert_eq!(credit_line.get_total_borrowed(), 0);
+    assert_eq!(
+        credit_line.get_total_supplied(),
+        LIQUIDITY + debt - 500 * TOKEN - credit_line.get_reserves()
+    );
+
+    credit_line.withdraw_collateral(&user, &0, benji, &(1_000 * TOKEN), &None);
+    assert_eq!(credit_line.get_total_collateral(benji), 0);
+}
+
+#[test]
+fn history_keeps_the_latest_snapshots() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    // Off until the admin sets a length
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    assert!(credit_line.get_history(&user, &0).is_empty());
+
+    assert_eq!(
+        credit_line.try_set_history_length(&fixture.admin, &51),
+        Err(Ok(Error::InvalidParameter))
+    );
+    credit_line.set_history_length(&fixture.admin, &2);
+
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    fixture.advance(60);
+    fixture.set_benji_price(PRICE_ONE);
+    credit_line.repay(&user, &0, &(200 * TOKEN), &None);
+    credit_line.withdraw_collateral(&user, &0, benji, &(100 * TOKEN), &None);
+
+    let history = credit_line.get_history(&user, &0);
+    assert_eq!(history.len(), 2);
+    let repaid = history.get(0).unwrap();
+    let withdrawn = history.get(1).unwrap();
+    assert_eq!(repaid.timestamp, fixture.env.ledger().timestamp());
+    assert!(repaid.borrowed > 300 * TOKEN);
+    assert_eq!(withdrawn.collateral.get(benji.clone()), Some(900 * TOKEN));
+    let position = credit_line.get_position(&user, &0);
+    assert_eq!(withdrawn.borrowed, position.borrowed);
+    assert_eq!(withdrawn.borrow_index, position.borrow_index);
+}
+
+#[test]
+fn collateral_haircut_reduces_credit_but_not_liquidation_limit() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(600 * TOKEN), &None, &None);
+    let health_factor = credit_line.get_health_factor(&user, &0);
+
+    // A 2% buffer takes 70% LTV down to 68.6%
+    credit_line.set_collateral_haircut(&fixture.admin, benji, &200);
+    assert_eq!(credit_line.get_collateral_haircut(benji), 200);
+    assert_eq!(credit_line.get_available_credit(&user, &0), 86 * TOKEN);
+    assert_eq!(credit_line.get_health_factor(&user, &0), health_factor);
+
+    assert_eq!(
+        credit_line.try_set_collateral_haircut(&fixture.admin, benji, &10_001),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(
+        credit_line.try_set_collateral_haircut(&fixture.admin, &fixture.usdc.address, &200),
+        Err(Ok(Error::UnsupportedCollateral))
+    );
+}
+
+#[test]
+fn isolated_collateral_cannot_mix_and_respects_its_ceiling() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    // A riskier token at 50% LTV, priced at 1.0
+    let risky_admin = fixture.add_collateral_token("Risky", "RSK", 5_000, 6_000);
+    let risky = risky_admin.address.clone();
+
+    credit_line.set_isolation(&fixture.admin, &risky, &Some(300 * TOKEN));
+    assert_eq!(
+        credit_line.get_isolation(&risky),
+        Some(IsolationUsage {
+            debt_ceiling: 300 * TOKEN,
+            debt: 0,
+        })
+    );
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    risky_admin.mint(&user, &(1_000 * TOKEN));
+    credit_line.deposit_collateral(&user, &0, &risky, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(200 * TOKEN), &None, &None);
+    assert_eq!(credit_line.get_isolation(&risky).unwrap().debt, 200 * TOKEN);
+
+    // The account has credit left, but the token's ceiling does not
+    assert_eq!(
+        credit_line.try_borrow(&user, &0, &(200 * TOKEN), &None, &None),
+        Err(Ok(Error::IsolationCeilingExceeded))
+    );
+    assert_eq!(
+        credit_line.try_deposit_collateral(&user, &0, benji, &(100 * TOKEN), &None, &None),
+        Err(Ok(Error::IsolatedCollateral))
+    );
+
+    // Other accounts may still hold BENJI, which cannot be isolated while held
+    credit_line.deposit_collateral(&user, &1, benji, &(100 * TOKEN), &None, &None);
+    assert_eq!(
+        credit_line.try_set_isolation(&fixture.admin, benji, &Some(0)),
+        Err(Ok(Error::InvalidParameter))
+    );
+
+    credit_line.repay(&user, &0, &(200 * TOKEN), &None);
+    assert_eq!(credit_line.get_isolation(&risky).unwrap().debt, 0);
+
+    credit_line.set_isolation(&fixture.admin, &risky, &None);
+    assert_eq!(credit_line.get_isolation(&risky), None);
+    credit_line.deposit_collateral(&user, &0, benji, &(100 * TOKEN), &None, &None);
+}
+
+#[test]
+fn max_borrowable_stops_at_the_isolation_ceiling() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+
+    let risky_admin = fixture.add_collateral_token("Risky", "RSK", 5_000, 6_000);
+    let risky = risky_admin.address.clone();
+    credit_line.set_isolation(&fixture.admin, &risky, &Some(300 * TOKEN));
+
+    let user = fixture.fund(0, 0);
+    risky_admin.mint(&user, &(1_000 * TOKEN));
+    credit_line.deposit_collateral(&user, &0, &risky, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(200 * TOKEN), &None, &None);
+
+    // The interest accrued since the last save is counted against the ceiling
+    // before the new debt, so the account's credit is not what limits it
+    fixture.advance(YEAR);
+    fixture.set_price(&risky, PRICE_ONE);
+    fixture.set_benji_price(PRICE_ONE);
+    let debt = credit_line.get_current_debt(&user, &0);
+    assert!(debt > 200 * TOKEN);
+    let max = credit_line.get_max_borrowable(&user, &0);
+    assert!(max < credit_line.get_available_credit(&user, &0));
+    assert_eq!(max, 300 * TOKEN - debt);
+
+    credit_line.borrow(&user, &0, &max, &None, &None);
+    assert_eq!(credit_line.get_isolation(&risky).unwrap().debt, 300 * TOKEN);
+    assert_eq!(credit_line.get_max_borrowable(&user, &0), 0);
+}
+
+#[test]
+fn debt_repaid_in_benji_is_owed_until_sold() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(2_000 * TOKEN, 0);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+
+    assert_eq!(
+        credit_line.try_set_repay_spread(&fixture.admin, &10_000),
+        Err(Ok(Error::InvalidParameter))
+    );
+    credit_line.set_repay_spread(&fixture.admin, &300);
+
+    // 100 BENJI at 1.0, less the 3% spread, with the debt it repays still owed
+    // to suppliers
+    let exchange_rate = credit_line.get_exchange_rate();
+    let repaid = credit_line.repay_with_collateral(&user, &0, &(100 * TOKEN));
+    assert_eq!(credit_line.get_exchange_rate(), exchange_rate);
+    assert_eq!(repaid, 97 * TOKEN);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 403 * TOKEN);
+    assert_eq!(
+        credit_line.get_protocol_collateral(),
+        ProtocolCollateral {
+            amount: 100 * TOKEN,
+            owed: 97 * TOKEN,
+        }
+    );
+    assert!(credit_line.check_solvency().gap >= 0);
+
+    // Only the BENJI needed to clear the rest of the debt is taken
+    let repaid = credit_line.repay_with_collateral(&user, &0, &(1_000 * TOKEN));
+    assert_eq!(repaid, 403 * TOKEN);
+    assert_eq!(credit_line.get_exchange_rate(), exchange_rate);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 0);
+    let protocol = credit_line.get_protocol_collateral();
+    assert_eq!(protocol.owed, 500 * TOKEN);
+    assert_eq!(
+        fixture.benji.balance(&user) + protocol.amount,
+        1_000 * TOKEN
+    );
+    assert!(protocol.amount * 97 / 100 >= 500 * TOKEN);
+    assert_eq!(
+        credit_line.try_repay_with_collateral(&user, &0, &TOKEN),
+        Err(Ok(Error::InvalidParameter))
+    );
+
+    // Selling it at the oracle price settles the pool, the spread going to reserves
+    let buyer = fixture.fund(0, 1_000 * TOKEN);
+    assert_eq!(
+        credit_line.try_buy_protocol_collateral(&buyer, &(protocol.amount + 1), &(1_000 * TOKEN)),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(
+        credit_line.try_buy_protocol_collateral(&buyer, &protocol.amount, &(protocol.amount - 1)),
+        Err(Ok(Error::MaxAmountExceeded))
+    );
+    let reserves = credit_line.get_reserves();
+    let cost = credit_line.buy_protocol_collateral(&buyer, &protocol.amount, &(1_000 * TOKEN));
+    assert_eq!(cost, protocol.amount);
+    assert_eq!(fixture.benji.balance(&buyer), protocol.amount);
+    assert_eq!(fixture.usdc.balance(&buyer), 1_000 * TOKEN - cost);
+    assert_eq!(credit_line.get_reserves(), reserves + cost - 500 * TOKEN);
+    assert_eq!(credit_line.get_exchange_rate(), exchange_rate);
+    assert_eq!(
+        credit_line.get_protocol_collateral(),
+        ProtocolCollateral { amount: 0, owed: 0 }
+    );
+    assert!(credit_line.check_solvency().gap >= 0);
+}
+
+#[test]
+fn referrers_earn_a_share_of_reserve_fees() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let referrer = Address::generate(env);
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    assert_eq!(
+        credit_line.try_set_referral_share(&fixture.admin, &10_001),
+        Err(Ok(Error::InvalidParameter))
+    );
+    credit_line.set_referral_share(&fixture.admin, &5_000);
+
+    // Only the first deposit names the referrer, and never the user themselves
+    let other = fixture.fund(100 * TOKEN, 0);
+    assert_eq!(
+        credit_line.try_deposit_collateral(
+            &other,
+            &0,
+            benji,
+            &(100 * TOKEN),
+            &None,
+            &Some(other.clone())
+        ),
+        Err(Ok(Error::InvalidParameter))
+    );
+    credit_line.deposit_collateral(
+        &user,
+        &0,
+        benji,
+        &(500 * TOKEN),
+        &None,
+        &Some(referrer.clone()),
+    );
+    credit_line.deposit_collateral(
+        &user,
+        &1,
+        benji,
+        &(500 * TOKEN),
+        &None,
+        &Some(other.clone()),
+    );
+    assert_eq!(credit_line.get_referrer(&user), Some(referrer.clone()));
+
+    // Nor can someone else name one with a deposit for the user
+    let newcomer = Address::generate(env);
+    credit_line.deposit_collateral(
+        &other,
+        &0,
+        benji,
+        &(100 * TOKEN),
+        &Some(newcomer.clone()),
+        &Some(other.clone()),
+    );
+    assert_eq!(credit_line.get_referrer(&newcomer), None);
+
+    credit_line.borrow(&user, &0, &(300 * TOKEN), &None, &None);
+    fixture.advance(YEAR);
+    fixture.set_benji_price(PRICE_ONE);
+    let debt = credit_line.get_current_debt(&user, &0);
+    fixture.mint_usdc(&user, debt - 300 * TOKEN);
+    credit_line.repay(&user, &0, &debt, &None);
+
+    // Half of the 10% reserve cut of the interest
+    let fees = credit_line.get_referral_fees(&referrer);
+    assert_eq!(fees, (debt - 300 * TOKEN) / 10 / 2);
+    assert_eq!(credit_line.get_referral_fees(&other), 0);
+
+    // The fees are not the suppliers' to withdraw
+    let supplied = credit_line.get_supply_balance(&fixture.lender);
+    let held = fixture.usdc.balance(&credit_line.address);
+    assert!(supplied + credit_line.get_reserves() + fees <= held);
+
+    assert_eq!(credit_line.claim_referral_fees(&referrer), fees);
+    assert_eq!(fixture.usdc.balance(&referrer), fees);
+    assert_eq!(credit_line.get_referral_fees(&referrer), 0);
+    assert!(credit_line.check_solvency().gap >= 0);
+}
+
+#[test]
+fn only_stray_tokens_and_surplus_usdc_can_be_rescued() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let admin = &fixture.admin;
+    let to = Address::generate(env);
+
+    // Interest leaves USDC owed to reserves and a referrer
+    credit_line.set_referral_share(admin, &5_000);
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(
+        &user,
+        &0,
+        benji,
+        &(1_000 * TOKEN),
+        &None,
+        &Some(Address::generate(env)),
+    );
+    credit_line.borrow(&user, &0, &(300 * TOKEN), &None, &None);
+    fixture.advance(YEAR);
+    fixture.set_benji_price(PRICE_ONE);
+    let debt = credit_line.get_current_debt(&user, &0);
+    fixture.mint_usdc(&user, debt - 300 * TOKEN);
+    credit_line.repay(&user, &0, &debt, &None);
+    assert!(credit_line.get_reserves() > 0);
+    assert_eq!(credit_line.get_usdc_surplus(), 0);
+
+    // A token the market does not use goes back in full
+    let stray = fixture.add_token("Stray", "STRAY");
+    stray.mint(&credit_line.address, &(25 * TOKEN));
+    credit_line.rescue_token(admin, &stray.address, &to, &(25 * TOKEN));
+    assert_eq!(stray.balance(&to), 25 * TOKEN);
+
+    // Collateral never moves, even beyond what depositors hold
+    fixture.mint_benji(&credit_line.address, 10 * TOKEN);
+    assert_eq!(
+        credit_line.try_rescue_token(admin, benji, &to, &TOKEN),
+        Err(Ok(Error::ProtectedToken))
+    );
+
+    // USDC only up to what the pool holds beyond its debts
+    fixture.mint_usdc(&credit_line.address, 50 * TOKEN);
+    assert_eq!(credit_line.get_usdc_surplus(), 50 * TOKEN);
+    let usdc = &fixture.usdc.address;
+    assert_eq!(
+        credit_line.try_rescue_token(admin, usdc, &to, &(50 * TOKEN + 1)),
+        Err(Ok(Error::InsufficientBalance))
+    );
+    credit_line.rescue_token(admin, usdc, &to, &(50 * TOKEN));
+    assert_eq!(fixture.usdc.balance(&to), 50 * TOKEN);
+    assert_eq!(credit_line.get_usdc_surplus(), 0);
+    assert!(credit_line.check_solvency().gap >= 0);
+}
+
+#[test]
+fn oracle_settings_rescale_and_combine_feeds() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let usdc = &fixture.usdc.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+
+    // A USD feed where USDC trades at 0.5 prices BENJI at 2 USDC
+    fixture.set_price(usdc, PRICE_ONE / 2);
+    let usd_feed = OracleSettings {
+        decimals: None,
+        quote: QuoteAsset::Other(oracle::Asset::Stellar(usdc.clone())),
+        invert: false,
+    };
+    let logged = credit_line.get_param_change_count();
+    credit_line.set_oracle_settings(&fixture.admin, &usd_feed);
+    assert_eq!(credit_line.get_oracle_settings(), usd_feed);
+    assert_eq!(credit_line.get_available_credit(&user, &0), 1_400 * TOKEN);
+
+    // Switching the quote asset is logged
+    let change = credit_line.get_param_changes(&logged, &1).get(0).unwrap();
+    assert_eq!(change.param, Symbol::new(env, "oracle_quote"));
+    assert_eq!(change.old_value, None);
+    assert!(change.new_value.is_some());
+
+    // The same feed quoting USD per token the other way round
+    fixture.set_benji_price(2 * PRICE_ONE);
+    fixture.set_price(usdc, 2 * PRICE_ONE);
+    credit_line.set_oracle_settings(
+        &fixture.admin,
+        &OracleSettings {
+            invert: true,
+            ..usd_feed.clone()
+        },
+    );
+    assert_eq!(credit_line.get_available_credit(&user, &0), 700 * TOKEN);
+
+    // A feed declaring 7 decimals but reporting 8
+    fixture.set_benji_price(PRICE_ONE);
+    credit_line.set_oracle_settings(
+        &fixture.admin,
+        &OracleSettings {
+            decimals: Some(8),
+            quote: QuoteAsset::Usdc,
+            invert: false,
+        },
+    );
+    assert_eq!(credit_line.get_available_credit(&user, &0), 70 * TOKEN);
+
+    assert_eq!(
+        credit_line.try_set_oracle_settings(
+            &fixture.admin,
+            &OracleSettings {
+                decimals: Some(19),
+                quote: QuoteAsset::Usdc,
+                invert: false,
+            },
+        ),
+        Err(Ok(Error::InvalidParameter))
+    );
+
+    // USDC's price in the quote asset is held to the same checks as the token's
+    credit_line.set_oracle_settings(&fixture.admin, &usd_feed);
+    credit_line.update_config(
+        &fixture.admin,
+        &MarketConfigUpdate {
+            max_price_deviation: Some(2_000),
+            ..no_changes()
+        },
+    );
+    fixture.advance(60);
+    fixture.set_benji_price(PRICE_ONE);
+    fixture.set_price(usdc, 2 * PRICE_ONE);
+    assert!(credit_line.get_available_credit(&user, &0) > 0);
+    fixture.advance(60);
+    fixture.set_price(usdc, PRICE_ONE);
+    assert_eq!(
+        credit_line.try_get_available_credit(&user, &0),
+        Err(Ok(Error::OracleStale))
+    );
+}
+
+#[test]
+fn collateral_yield_offsets_the_net_rate() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    // Only the admin and the oracle may report the yield
+    assert_eq!(
+        credit_line.try_set_collateral_yield(&user, &400),
+        Err(Ok(Error::Unauthorized))
+    );
+    credit_line.set_collateral_yield(&fixture.oracle.address, &400);
+    assert_eq!(credit_line.get_collateral_yield(), 400);
+
+    // Without debt there is nothing to offset
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    let rate = credit_line.get_market_summary().borrow_rate as i128;
+    assert_eq!(credit_line.get_net_rate(&user, &0), rate);
+
+    // 4% on 1,000 of BENJI offsets 8% on a 500 debt
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    let rate = credit_line.get_market_summary().borrow_rate as i128;
+    assert_eq!(credit_line.get_net_rate(&user, &0), rate - 800);
+
+    credit_line.set_collateral_yield(&fixture.admin, &0);
+    assert_eq!(credit_line.get_net_rate(&user, &0), rate);
+}
+
+#[test]
+fn borrow_is_limited_by_collateral_and_liquidity() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    assert!(credit_line
+        .try_borrow(&user, &0, &(701 * TOKEN), &None, &None)
+        .is_err());
+
+    // Collateral worth more than the pool holds still cannot drain it
+    let whale = fixture.fund(1_000_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&whale, &0, benji, &(1_000_000 * TOKEN), &None, &None);
+    assert!(credit_line
+        .try_borrow(&whale, &0, &(LIQUIDITY + TOKEN), &None, &None)
+        .is_err());
+}
+
+#[test]
+fn compounding_follows_the_configured_frequency() {
+    // Debt on 500 after a year at a flat 10% APR
+    let debt_after_a_year = |compounding| {
+        let fixture = Fixture::new();
+        let credit_line = &fixture.credit_line;
+        let user = fixture.fund(1_000 * TOKEN, 0);
+        credit_line.multi_set(
+            &fixture.admin,
+            &vec![
+                &fixture.env,
+                ParamUpdate::InterestRate(1_000),
+                ParamUpdate::RateSlope(0),
+                ParamUpdate::Compounding(compounding),
+            ],
+        );
+        assert_eq!(credit_line.get_config().compounding, compounding);
+
+        let benji = &fixture.benji.address;
+        credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+        credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+        fixture.advance(YEAR);
+        fixture.set_benji_price(PRICE_ONE);
+        credit_line.get_current_debt(&user, &0)
+    };
+
+    let linear = debt_after_a_year(Compounding::Linear);
+    let daily = debt_after_a_year(Compounding::Daily);
+    let per_ledger = debt_after_a_year(Compounding::PerLedger);
+    assert_eq!(linear, 550 * TOKEN);
+
+    // 500 * (1 + 0.1 / 365)^365 and 500 * e^0.1, to within rounding
+    assert!((daily - 5_525_778_908).abs() < 100, "daily: {daily}");
+    assert!(
+        (per_ledger - 5_525_854_588).abs() < 100,
+        "per ledger: {per_ledger}"
+    );
+    assert!(linear < daily && daily < per_ledger);
+}
+
+#[test]
+fn daily_compounding_does_not_depend_on_how_often_debt_is_touched() {
+    // Debt on 5,000 after three and a half days at a flat 10% APR, with the
+    // market brought up to date every `touch` seconds
+    let debt_touched_every = |touch: u64| {
+        let fixture = Fixture::new();
+        let credit_line = &fixture.credit_line;
+        let user = fixture.fund(10_000 * TOKEN, 0);
+        credit_line.multi_set(
+            &fixture.admin,
+            &vec![
+                &fixture.env,
+                ParamUpdate::InterestRate(1_000),
+                ParamUpdate::RateSlope(0),
+                ParamUpdate::Compounding(Compounding::Daily),
+            ],
+        );
+        let benji = &fixture.benji.address;
+        credit_line.deposit_collateral(&user, &0, benji, &(10_000 * TOKEN), &None, &None);
+        credit_line.borrow(&user, &0, &(5_000 * TOKEN), &None, &None);
+
+        let mut elapsed = 0;
+        while elapsed < 84 * HOUR {
+            fixture.advance(touch.min(84 * HOUR - elapsed));
+            elapsed += touch;
+            fixture.set_benji_price(PRICE_ONE);
+            credit_line.poke();
+        }
+        (credit_line.poke(), credit_line.get_current_debt(&user, &0))
+    };
+
+    let (hourly_index, hourly_debt) = debt_touched_every(HOUR);
+    let (daily_index, daily_debt) = debt_touched_every(24 * HOUR);
+    let (once_index, once_debt) = debt_touched_every(84 * HOUR);
+    assert_eq!(hourly_debt, daily_debt);
+    assert_eq!(daily_debt, once_debt);
+    assert!((hourly_index - once_index).abs() < 1_000_000);
+    assert!((daily_index - once_index).abs() < 1_000_000);
+}
+
+#[test]
+fn blocked_pool_halts_usdc_but_not_collateral() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let usdc = UsdcTokenClient::new(&fixture.env, &fixture.usdc.address);
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(500 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(100 * TOKEN), &None, &None);
+
+    // With the pool blocked no USDC moves in or out of it
+    usdc.block(&credit_line.address);
+    fixture.mint_usdc(&fixture.lender, 100 * TOKEN);
+    assert!(credit_line
+        .try_supply(&fixture.lender, &(100 * TOKEN))
+        .is_err());
+    assert!(credit_line
+        .try_withdraw_supply(&fixture.lender, &TOKEN)
+        .is_err());
+    assert!(credit_line
+        .try_borrow(&user, &0, &(100 * TOKEN), &None, &None)
+        .is_err());
+    assert!(credit_line
+        .try_repay(&user, &0, &(50 * TOKEN), &None)
+        .is_err());
+
+    // Collateral is BENJI, so it still comes and goes
+    credit_line.deposit_collateral(&user, &0, benji, &(500 * TOKEN), &None, &None);
+    credit_line.withdraw_collateral(&user, &0, benji, &(100 * TOKEN), &None);
+    assert_eq!(fixture.benji.balance(&user), 100 * TOKEN);
+
+    usdc.unblock(&credit_line.address);
+    credit_line.repay(&user, &0, &(50 * TOKEN), &None);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 50 * TOKEN);
+}
+
+#[test]
+fn repaying_more_than_the_debt_clears_it() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let user = fixture.fund(1_000 * TOKEN, 100 * TOKEN);
+
+    credit_line.deposit_collateral(
+        &user,
+        &0,
+        &fixture.benji.address,
+        &(1_000 * TOKEN),
+        &None,
+        &None,
+    );
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    fixture.advance(YEAR);
+    fixture.set_benji_price(PRICE_ONE);
+
+    // Only the debt accrued to this ledger is taken
+    let debt = credit_line.get_current_debt(&user, &0);
+    assert!(debt > 500 * TOKEN && debt < 600 * TOKEN);
+    assert_eq!(
+        credit_line
+            .preview_repay(&user, &0, &(600 * TOKEN))
+            .position
+            .borrowed,
+        0
+    );
+    credit_line.repay(&user, &0, &(600 * TOKEN), &None);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 0);
+    assert_eq!(fixture.usdc.balance(&user), 600 * TOKEN - debt);
+    assert!(credit_line.try_repay(&user, &0, &TOKEN, &None).is_err());
+}
+
+#[test]
+fn collateral_yield_is_shared_pro_rata() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let alice = fixture.fund(1_000 * TOKEN, 0);
+    let bob = fixture.fund(3_000 * TOKEN, 0);
+    let carol = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&alice, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.deposit_collateral(&bob, &0, benji, &(3_000 * TOKEN), &None, &None);
+
+    // BENJI paid to the contract as a dividend on the 4,000 it holds
+    fixture.mint_benji(&credit_line.address, 400 * TOKEN);
+    let claimed = credit_line.claim_collateral_yield(&alice, &0);
+    assert_eq!(claimed.get(benji.clone()), Some(100 * TOKEN));
+    assert_eq!(fixture.benji.balance(&alice), 100 * TOKEN);
+
+    // A later depositor only shares in yield paid after they joined
+    credit_line.deposit_collateral(&carol, &0, benji, &(1_000 * TOKEN), &None, &None);
+    fixture.mint_benji(&credit_line.address, 500 * TOKEN);
+    credit_line.claim_collateral_yield(&alice, &0);
+    credit_line.claim_collateral_yield(&bob, &0);
+    credit_line.claim_collateral_yield(&carol, &0);
+    assert_eq!(fixture.benji.balance(&alice), 200 * TOKEN);
+    assert_eq!(fixture.benji.balance(&bob), 600 * TOKEN);
+    assert_eq!(fixture.benji.balance(&carol), 100 * TOKEN);
+
+    // Nothing is left to claim, and the collateral itself is untouched
+    assert!(credit_line.claim_collateral_yield(&bob, &0).is_empty());
+    assert_eq!(fixture.benji.balance(&credit_line.address), 5_000 * TOKEN);
+    assert_eq!(
+        credit_line
+            .get_position(&bob, &0)
+            .collateral
+            .get(benji.clone()),
+        Some(3_000 * TOKEN)
+    );
+}
+
+#[test]
+fn benji_taken_in_repayment_is_not_paid_out_as_yield() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let alice = fixture.fund(1_000 * TOKEN, 0);
+    let bob = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&alice, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.deposit_collateral(&bob, &0, benji, &(500 * TOKEN), &None, &None);
+    credit_line.borrow(&bob, &0, &(200 * TOKEN), &None, &None);
+
+    // Bob's BENJI repays his debt and stays in the contract as the protocol's
+    credit_line.repay_with_collateral(&bob, &0, &(100 * TOKEN));
+    assert!(credit_line.claim_collateral_yield(&alice, &0).is_empty());
+    assert_eq!(fixture.benji.balance(&alice), 0);
+
+    // A dividend on the 1,500 deposited is still shared between depositors
+    fixture.mint_benji(&credit_line.address, 150 * TOKEN);
+    let claimed = credit_line.claim_collateral_yield(&alice, &0);
+    assert_eq!(claimed.get(benji.clone()), Some(100 * TOKEN));
+}
+
+#[test]
+fn delegated_borrows_are_capped_and_charged_to_the_delegator() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let delegator = fixture.fund(1_000 * TOKEN, 0);
+    let delegatee = Address::generate(env);
+    let stranger = Address::generate(env);
+    credit_line.deposit_collateral(&delegator, &0, benji, &(1_000 * TOKEN), &None, &None);
+
+    assert_eq!(
+        credit_line.try_approve_delegation(&delegator, &0, &delegatee, &-1),
+        Err(Ok(Error::InvalidParameter))
+    );
+    credit_line.approve_delegation(&delegator, &0, &delegatee, &(300 * TOKEN));
+
+    // The delegatee is paid and the delegator owes it
+    let on_behalf_of = Some(delegator.clone());
+    credit_line.borrow(&delegatee, &0, &(200 * TOKEN), &on_behalf_of, &None);
+    assert_eq!(fixture.usdc.balance(&delegatee), 200 * TOKEN);
+    assert_eq!(
+        credit_line.get_position(&delegator, &0).borrowed,
+        200 * TOKEN
+    );
+    assert!(credit_line.get_accounts(&delegatee).is_empty());
+    assert_eq!(
+        credit_line.get_delegation(&delegator, &0, &delegatee),
+        100 * TOKEN
+    );
+
+    // Nothing past the allowance, and nothing without one
+    assert_eq!(
+        credit_line.try_borrow(&delegatee, &0, &(101 * TOKEN), &on_behalf_of, &None),
+        Err(Ok(Error::InsufficientDelegation))
+    );
+    assert_eq!(
+        credit_line.try_borrow(&stranger, &0, &TOKEN, &on_behalf_of, &None),
+        Err(Ok(Error::InsufficientDelegation))
+    );
+    credit_line.borrow(&delegatee, &0, &(100 * TOKEN), &on_behalf_of, &None);
+    assert_eq!(credit_line.get_delegation(&delegator, &0, &delegatee), 0);
+    assert_eq!(
+        credit_line.get_position(&delegator, &0).borrowed,
+        300 * TOKEN
+    );
+
+    // A new approval replaces what was left, and zero revokes it
+    credit_line.approve_delegation(&delegator, &0, &delegatee, &(50 * TOKEN));
+    credit_line.approve_delegation(&delegator, &0, &delegatee, &0);
+    assert_eq!(
+        credit_line.try_borrow(&delegatee, &0, &TOKEN, &on_behalf_of, &None),
+        Err(Ok(Error::InsufficientDelegation))
+    );
+}
+
+#[test]
+fn price_jumps_past_the_deviation_limit_are_refused() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(300 * TOKEN), &None, &None);
+
+    // 25% up on the last update, past the 20% limit
+    fixture.advance(60);
+    fixture.set_benji_price(PRICE_ONE * 5 / 4);
+    assert_eq!(
+        credit_line.try_borrow(&user, &0, &TOKEN, &None, &None),
+        Err(Ok(Error::OracleStale))
+    );
+    assert_eq!(
+        credit_line.try_get_available_credit(&user, &0),
+        Err(Ok(Error::OracleStale))
+    );
+
+    // A move within the limit is accepted again
+    fixture.advance(60);
+    fixture.set_benji_price(PRICE_ONE * 11 / 10);
+    credit_line.borrow(&user, &0, &TOKEN, &None, &None);
+    let borrowed = credit_line.get_position(&user, &0).borrowed;
+    assert!(borrowed > 301 * TOKEN && borrowed < 302 * TOKEN);
+}
+
+#[test]
+fn stale_prices_block_borrows_and_withdrawals_but_not_repays() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_100 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(300 * TOKEN), &None, &None);
+
+    // Two hours without an update, past the one hour limit
+    fixture.advance(2 * HOUR);
+    assert_eq!(
+        credit_line.try_borrow(&user, &0, &TOKEN, &None, &None),
+        Err(Ok(Error::OracleStale))
+    );
+    assert_eq!(
+        credit_line.try_withdraw_collateral(&user, &0, benji, &TOKEN, &None),
+        Err(Ok(Error::OracleStale))
+    );
+
+    // Paying down debt and adding collateral still go through
+    credit_line.repay(&user, &0, &(100 * TOKEN), &None);
+    credit_line.deposit_collateral(&user, &0, benji, &(100 * TOKEN), &None, &None);
+    let position = credit_line.get_position(&user, &0);
+    assert!(position.borrowed < 201 * TOKEN);
+    assert_eq!(fixture.usdc.balance(&user), 200 * TOKEN);
+
+    fixture.set_benji_price(PRICE_ONE);
+    credit_line.withdraw_collateral(&user, &0, benji, &(100 * TOKEN), &None);
+    assert_eq!(fixture.benji.balance(&user), 100 * TOKEN);
+}
+
+#[test]
+fn repayments_use_up_interest_free_tranches_oldest_first() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.update_config(
+        &fixture.admin,
+        &MarketConfigUpdate {
+            interest_free_period: Some(YEAR / 12),
+            ..no_changes()
+        },
+    );
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+
+    // A borrow is free for the period, and repaying it uses up its tranche
+    credit_line.borrow(&user, &0, &(100 * TOKEN), &None, &None);
+    fixture.advance(YEAR / 24);
+    fixture.set_benji_price(PRICE_ONE);
+    assert_eq!(credit_line.get_current_debt(&user, &0), 100 * TOKEN);
+    credit_line.repay(&user, &0, &(40 * TOKEN), &None);
+    let tranches = credit_line.get_position(&user, &0).free_tranches;
+    assert_eq!(tranches.len(), 1);
+    assert_eq!(tranches.get_unchecked(0).amount, 60 * TOKEN);
+
+    // Borrowing again opens a tranche for the new debt only
+    credit_line.borrow(&user, &0, &(40 * TOKEN), &None, &None);
+    credit_line.repay(&user, &0, &(70 * TOKEN), &None);
+    let tranches = credit_line.get_position(&user, &0).free_tranches;
+    assert_eq!(tranches.len(), 1);
+    assert_eq!(tranches.get_unchecked(0).amount, 30 * TOKEN);
+
+    // The rest is the newer debt, free until its own tranche runs out
+    fixture.advance(YEAR / 24);
+    fixture.set_benji_price(PRICE_ONE);
+    assert_eq!(credit_line.get_current_debt(&user, &0), 30 * TOKEN);
+    fixture.advance(YEAR / 12);
+    fixture.set_benji_price(PRICE_ONE);
+    assert!(credit_line.get_current_debt(&user, &0) > 30 * TOKEN);
+
+    // A position keeps at most eight tranches, later borrows joining the newest
+    for _ in 0..9 {
+        credit_line.borrow(&user, &0, &(10 * TOKEN), &None, &None);
+    }
+    let tranches = credit_line.get_position(&user, &0).free_tranches;
+    assert_eq!(tranches.len(), 8);
+    assert_eq!(tranches.last_unchecked().amount, 20 * TOKEN);
+}
+
+#[test]
+fn update_config_sets_and_clears_optional_parameters() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let treasury = Address::generate(&fixture.env);
+    let oracle = credit_line.get_config().oracle;
+
+    credit_line.update_config(
+        &fixture.admin,
+        &MarketConfigUpdate {
+            treasury: AddressChange::Set(treasury.clone()),
+            debt_ceiling: AmountChange::Set(1_000 * TOKEN),
+            ..no_changes()
+        },
+    );
+    let config = credit_line.get_config();
+    assert_eq!(config.treasury, Some(treasury));
+    assert_eq!(config.debt_ceiling, Some(1_000 * TOKEN));
+
+    // Clearing takes a parameter back to unset, and `Keep` leaves it be
+    credit_line.update_config(
+        &fixture.admin,
+        &MarketConfigUpdate {
+            treasury: AddressChange::Clear,
+            debt_ceiling: AmountChange::Clear,
+            ..no_changes()
+        },
+    );
+    let config = credit_line.get_config();
+    assert_eq!(config.treasury, None);
+    assert_eq!(config.debt_ceiling, None);
+    assert_eq!(config.oracle, oracle);
+    assert!(oracle.is_some());
+}
+
+#[test]
+fn update_config_rejects_out_of_range_rates_and_bonus() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let config = credit_line.get_config();
+
+    for update in [
+        MarketConfigUpdate {
+            interest_rate: Some(10_001),
+            ..no_changes()
+        },
+        MarketConfigUpdate {
+            rate_slope: Some(10_001),
+            ..no_changes()
+        },
+        MarketConfigUpdate {
+            stable_rate_premium: Some(10_001),
+            ..no_changes()
+        },
+        MarketConfigUpdate {
+            late_penalty_rate: Some(10_001),
+            ..no_changes()
+        },
+        MarketConfigUpdate {
+            liquidation_bonus: Some(2_001),
+            ..no_changes()
+        },
+    ] {
+        assert_eq!(
+            credit_line.try_update_config(&fixture.admin, &update),
+            Err(Ok(Error::InvalidParameter))
+        );
+    }
+    assert_eq!(credit_line.get_config(), config);
+
+    credit_line.update_config(
+        &fixture.admin,
+        &MarketConfigUpdate {
+            interest_rate: Some(10_000),
+            liquidation_bonus: Some(2_000),
+            ..no_changes()
+        },
+    );
+    assert_eq!(credit_line.get_config().liquidation_bonus, 2_000);
+}
+
+#[test]
+fn twap_rides_out_a_one_ledger_price_crash() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(550 * TOKEN), &None, &None);
+
+    // Average over an hour, and let any single move through
+    credit_line.update_config(
+        &fixture.admin,
+        &MarketConfigUpdate {
+            max_price_deviation: Some(0),
+            twap_window: Some(HOUR),
+            ..no_changes()
+        },
+    );
+    assert_eq!(credit_line.get_config().twap_window, HOUR);
+    fixture.advance(HOUR);
+    fixture.set_benji_price(PRICE_ONE);
+    let available = credit_line.get_available_credit(&user, &0);
+
+    // BENJI halves for a single ledger, which at the spot price would put
+    // the debt past the 80% liquidation threshold
+    fixture.advance(1);
+    fixture.set_benji_price(PRICE_ONE / 2);
+    fixture.advance_ledgers(1);
+    assert!(!credit_line.is_liquidatable(&user, &0));
+    assert!(credit_line.get_available_credit(&user, &0) > available - TOKEN);
+
+    // Once it recovers the crash fades out of the window
+    fixture.set_benji_price(PRICE_ONE);
+    fixture.advance(HOUR);
+    fixture.set_benji_price(PRICE_ONE);
+    assert_eq!(
+        credit_line.get_available_credit(&user, &0),
+        700 * TOKEN - credit_line.get_current_debt(&user, &0)
+    );
+
+    // A lasting fall is priced in as it fills the window
+    fixture.set_benji_price(PRICE_ONE / 2);
+    fixture.advance(HOUR / 2);
+    fixture.set_benji_price(PRICE_ONE / 2);
+    assert!(!credit_line.is_liquidatable(&user, &0));
+    fixture.advance(HOUR / 2);
+    fixture.set_benji_price(PRICE_ONE / 2);
+    assert!(credit_line.is_liquidatable(&user, &0));
+}
+
+#[test]
+fn borrow_cap_spans_all_of_a_users_accounts() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    let user = fixture.fund(2_000 * TOKEN, 0);
+    credit_line.set_borrow_cap(&fixture.admin, &user, &Some(500 * TOKEN));
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.deposit_collateral(&user, &1, benji, &(1_000 * TOKEN), &None, &None);
+
+    // A second account does not open a second cap
+    credit_line.borrow(&user, &0, &(400 * TOKEN), &None, &None);
+    assert_eq!(credit_line.get_max_borrowable(&user, &1), 100 * TOKEN);
+    assert_eq!(
+        credit_line.try_borrow(&user, &1, &(101 * TOKEN), &None, &None),
+        Err(Ok(Error::UserBorrowCapReached))
+    );
+    credit_line.borrow(&user, &1, &(100 * TOKEN), &None, &None);
+}
+
+#[test]
+fn accounts_per_user_are_limited() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    // Others may fund a user's accounts, but only open one with the user's consent
+    let user = fixture.fund(100 * TOKEN, 0);
+    let payer = fixture.fund(100 * TOKEN, 0);
+    credit_line.deposit_collateral(&payer, &7, benji, &TOKEN, &Some(user.clone()), &None);
+    let signers: std::vec::Vec<Address> = env.auths().into_iter().map(|(a, _)| a).collect();
+    assert_eq!(signers, [payer.clone(), user.clone()]);
+    credit_line.deposit_collateral(&payer, &7, benji, &TOKEN, &Some(user.clone()), &None);
+    let signers: std::vec::Vec<Address> = env.auths().into_iter().map(|(a, _)| a).collect();
+    assert_eq!(signers, [payer]);
+
+    for account_id in 0..16 {
+        credit_line.deposit_collateral(&user, &account_id, benji, &TOKEN, &None, &None);
+    }
+    assert_eq!(credit_line.get_accounts(&user).len(), 16);
+    assert_eq!(
+        credit_line.try_deposit_collateral(&user, &16, benji, &TOKEN, &None, &None),
+        Err(Ok(Error::TooManyAccounts))
+    );
+    credit_line.deposit_collateral(&user, &3, benji, &TOKEN, &None, &None);
+}
+
+#[test]
+fn deposits_stop_at_the_supply_cap() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let admin = &fixture.admin;
+    let first = fixture.fund(600 * TOKEN, 0);
+    let second = fixture.fund(600 * TOKEN, 0);
+
+    // The cap counts every position's collateral together
+    credit_line.set_supply_cap(admin, benji, &Some(1_000 * TOKEN));
+    credit_line.deposit_collateral(&first, &0, benji, &(600 * TOKEN), &None, &None);
+    assert_eq!(
+        credit_line.try_deposit_collateral(&second, &0, benji, &(401 * TOKEN), &None, &None),
+        Err(Ok(Error::SupplyCapExceeded))
+    );
+    credit_line.deposit_collateral(&second, &0, benji, &(400 * TOKEN), &None, &None);
+
+    // Withdrawals make room again, and clearing the cap lifts it
+    credit_line.withdraw_collateral(&first, &0, benji, &(100 * TOKEN), &None);
+    credit_line.deposit_collateral(&second, &0, benji, &(100 * TOKEN), &None, &None);
+    credit_line.set_supply_cap(admin, benji, &None);
+    credit_line.deposit_collateral(&second, &0, benji, &(100 * TOKEN), &None, &None);
+    assert_eq!(credit_line.get_total_collateral(benji), 1_100 * TOKEN);
+}
+
+#[test]
+fn donations_do_not_move_the_share_price() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let rate = credit_line.get_exchange_rate();
+
+    // USDC sent straight to the pool is surplus, not supplier assets
+    fixture.mint_usdc(&credit_line.address, 10_000 * TOKEN);
+    assert_eq!(credit_line.get_exchange_rate(), rate);
+    assert_eq!(credit_line.get_supply_balance(&fixture.lender), LIQUIDITY);
+    assert_eq!(credit_line.get_usdc_surplus(), 10_000 * TOKEN);
+
+    // So the next supplier's shares are worth what they paid
+    let lender = Address::generate(&fixture.env);
+    fixture.mint_usdc(&lender, 100 * TOKEN);
+    credit_line.supply(&lender, &(100 * TOKEN));
+    assert_eq!(credit_line.get_supply_balance(&lender), 100 * TOKEN);
+    assert_eq!(credit_line.get_exchange_rate(), rate);
+}
@@ -0,0 +1,317 @@
+use credit_line::{CollateralConfig, Error};
+use integration_tests::{Fixture, DAY, PRICE_ONE, TOKEN};
+use mock_usdc_token::UsdcTokenClient;
+use soroban_sdk::{testutils::Address as _, Address};
+
+#[test]
+fn price_drop_makes_position_liquidatable() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(700 * TOKEN), &None, &None);
+
+    let liquidator = fixture.fund(0, 1_000 * TOKEN);
+    assert_eq!(
+        credit_line.try_liquidate(&liquidator, &user, &0, benji, &(100 * TOKEN)),
+        Err(Ok(Error::PositionHealthy))
+    );
+
+    // BENJI falls 15%, leaving 850 of collateral against a 700 debt
+    fixture.set_benji_price(PRICE_ONE * 85 / 100);
+
+    assert_eq!(
+        credit_line.try_liquidate(&liquidator, &user, &0, benji, &0),
+        Err(Ok(Error::InvalidParameter))
+    );
+
+    let seized = credit_line.liquidate(&liquidator, &user, &0, benji, &(200 * TOKEN));
+    assert!(seized > 200 * TOKEN * 100 / 85);
+    assert_eq!(fixture.benji.balance(&liquidator), seized);
+    assert_eq!(fixture.usdc.balance(&liquidator), 800 * TOKEN);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 500 * TOKEN);
+    assert!(credit_line.check_solvency().gap >= 0);
+}
+
+#[test]
+fn treasury_takes_its_share_of_the_liquidation_bonus() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let admin = &fixture.admin;
+    let treasury = Address::generate(env);
+
+    assert_eq!(
+        credit_line.try_set_liquidation_fee_share(admin, &5_000),
+        Err(Ok(Error::TreasuryNotSet))
+    );
+    credit_line.set_treasury(admin, &treasury);
+    assert_eq!(
+        credit_line.try_set_liquidation_fee_share(admin, &10_001),
+        Err(Ok(Error::InvalidParameter))
+    );
+    credit_line.set_liquidation_fee_share(admin, &5_000);
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(700 * TOKEN), &None, &None);
+    fixture.set_benji_price(85 * PRICE_ONE / 100);
+
+    let liquidator = fixture.fund(0, 1_000 * TOKEN);
+    let paid = credit_line.liquidate(&liquidator, &user, &0, benji, &(200 * TOKEN));
+    let fee = fixture.benji.balance(&treasury);
+    assert_eq!(fixture.benji.balance(&liquidator), paid);
+    assert_eq!(credit_line.get_liquidation_fees(benji), fee);
+
+    // Collateral worth the repaid debt goes to the liquidator, and the 5% bonus
+    // on top of it is split evenly
+    let seized = paid + fee;
+    let collateral = credit_line.get_position(&user, &0).collateral;
+    assert_eq!(collateral.get(benji.clone()), Some(1_000 * TOKEN - seized));
+    let bonus = seized - seized * 10_000 / 10_500;
+    assert!((fee - bonus / 2).abs() <= 1);
+    assert!(credit_line.check_solvency().gap >= 0);
+}
+
+#[test]
+fn borrower_without_usdc_repays_from_collateral() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(700 * TOKEN), &None, &None);
+    fixture
+        .usdc
+        .transfer(&user, &fixture.lender, &(700 * TOKEN));
+
+    // At 0.85 the position is open to liquidation
+    fixture.set_benji_price(85 * PRICE_ONE / 100);
+    assert!(credit_line.is_liquidatable(&user, &0));
+
+    let surrendered = credit_line.repay_from_collateral(&user, &0, &(200 * TOKEN));
+    assert_eq!(surrendered, 200 * TOKEN * 100 / 85 + 1);
+    let position = credit_line.get_position(&user, &0);
+    assert_eq!(position.borrowed, 500 * TOKEN);
+    assert_eq!(
+        position.collateral.get(benji.clone()),
+        Some(1_000 * TOKEN - surrendered)
+    );
+    assert!(!credit_line.is_liquidatable(&user, &0));
+
+    let protocol = credit_line.get_protocol_collateral();
+    assert_eq!(protocol.amount, surrendered);
+    assert_eq!(protocol.owed, 200 * TOKEN);
+    assert!(credit_line.check_solvency().gap >= 0);
+
+    // Collateral worth less than the debt cannot cover all of it
+    fixture.set_benji_price(PRICE_ONE / 2);
+    assert_eq!(
+        credit_line.try_repay_from_collateral(&user, &0, &(500 * TOKEN)),
+        Err(Ok(Error::InsufficientCollateral))
+    );
+
+    // Nor can it pay down part of the debt and leave the position underwater
+    assert_eq!(
+        credit_line.try_repay_from_collateral(&user, &0, &(100 * TOKEN)),
+        Err(Ok(Error::InsufficientCollateral))
+    );
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 500 * TOKEN);
+
+    // The suppliers' share price holds while the debt is owed for the BENJI
+    fixture.set_benji_price(PRICE_ONE);
+    let exchange_rate = credit_line.get_exchange_rate();
+    credit_line.repay_from_collateral(&user, &0, &(100 * TOKEN));
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 400 * TOKEN);
+    assert_eq!(credit_line.get_exchange_rate(), exchange_rate);
+}
+
+#[test]
+fn collateral_surrendered_for_debt_is_not_paid_out_as_yield() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    let depositor = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.deposit_collateral(&depositor, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+
+    // The surrendered BENJI leaves the deposits but not the contract
+    let surrendered = credit_line.repay_from_collateral(&user, &0, &(200 * TOKEN));
+    assert_eq!(fixture.benji.balance(&credit_line.address), 2_000 * TOKEN);
+    assert_eq!(credit_line.get_protocol_collateral().amount, surrendered);
+    assert!(credit_line
+        .claim_collateral_yield(&depositor, &0)
+        .is_empty());
+    assert!(credit_line.claim_collateral_yield(&user, &0).is_empty());
+    assert_eq!(fixture.benji.balance(&depositor), 0);
+}
+
+#[test]
+fn blocked_borrowers_can_still_be_liquidated() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let usdc = UsdcTokenClient::new(&fixture.env, &fixture.usdc.address);
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    let liquidator = fixture.fund(0, 1_000 * TOKEN);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(600 * TOKEN), &None, &None);
+
+    // A blocked borrower can neither draw nor repay USDC
+    usdc.block(&user);
+    assert!(credit_line
+        .try_borrow(&user, &0, &(50 * TOKEN), &None, &None)
+        .is_err());
+    assert!(credit_line
+        .try_repay(&user, &0, &(50 * TOKEN), &None)
+        .is_err());
+
+    // The position stays open to liquidation, which moves no USDC of theirs
+    fixture.set_benji_price(70 * PRICE_ONE / 100);
+    assert!(credit_line.is_liquidatable(&user, &0));
+    credit_line.liquidate(&liquidator, &user, &0, benji, &(200 * TOKEN));
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 400 * TOKEN);
+    assert!(credit_line.check_solvency().gap >= 0);
+
+    usdc.unblock(&user);
+    credit_line.repay(&user, &0, &(50 * TOKEN), &None);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 350 * TOKEN);
+}
+
+#[test]
+fn lowered_threshold_waits_out_the_grace_period() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    let liquidator = fixture.fund(0, 1_000 * TOKEN);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(650 * TOKEN), &None, &None);
+    assert_eq!(credit_line.get_grace_deadline(&user, &0), None);
+
+    // A 60% threshold puts the 650 debt past the limit, but not until the
+    // three day grace period is over
+    credit_line.set_collateral_config(
+        &fixture.admin,
+        benji,
+        &CollateralConfig {
+            ltv_ratio: 5_000,
+            liquidation_threshold: 6_000,
+        },
+    );
+    let deadline = fixture.env.ledger().timestamp() + 3 * DAY;
+    assert_eq!(credit_line.get_grace_deadline(&user, &0), Some(deadline));
+    assert!(!credit_line.is_liquidatable(&user, &0));
+    assert_eq!(
+        credit_line.try_liquidate(&liquidator, &user, &0, benji, &(100 * TOKEN)),
+        Err(Ok(Error::PositionHealthy))
+    );
+
+    // Nor can the position borrow more in the meantime
+    assert_eq!(credit_line.get_max_borrowable(&user, &0), 0);
+    assert_eq!(
+        credit_line.try_borrow(&user, &0, &TOKEN, &None, &None),
+        Err(Ok(Error::ExceedsCreditLimit))
+    );
+
+    fixture.advance(3 * DAY);
+    fixture.set_benji_price(PRICE_ONE);
+    assert_eq!(credit_line.get_grace_deadline(&user, &0), None);
+    assert!(credit_line.is_liquidatable(&user, &0));
+    credit_line.liquidate(&liquidator, &user, &0, benji, &(100 * TOKEN));
+    assert!(credit_line.get_position(&user, &0).borrowed < 560 * TOKEN);
+}
+
+#[test]
+fn close_factor_caps_each_liquidation_until_the_rest_would_be_dust() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    let liquidator = fixture.fund(0, 1_000 * TOKEN);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(700 * TOKEN), &None, &None);
+    credit_line.set_position_minimums(&fixture.admin, &(200 * TOKEN), &0);
+    fixture.set_benji_price(PRICE_ONE * 85 / 100);
+    fixture.advance(30 * 60);
+    fixture.set_benji_price(PRICE_ONE * 85 / 100);
+
+    // At 50%, one call may repay half the debt and no more
+    let debt = credit_line.get_current_debt(&user, &0);
+    assert_eq!(
+        credit_line.try_liquidate(&liquidator, &user, &0, benji, &(debt / 2 + TOKEN)),
+        Err(Ok(Error::CloseFactorExceeded))
+    );
+    assert_eq!(
+        credit_line.try_liquidate(&liquidator, &user, &0, benji, &debt),
+        Err(Ok(Error::CloseFactorExceeded))
+    );
+    credit_line.liquidate(&liquidator, &user, &0, benji, &(debt / 2));
+    assert_eq!(
+        credit_line.get_position(&user, &0).borrowed,
+        debt - debt / 2
+    );
+
+    // Half of what is left would leave less than the minimum borrow, so the
+    // close factor gives way to closing out the whole debt
+    fixture.set_benji_price(PRICE_ONE * 60 / 100);
+    fixture.advance(30 * 60);
+    fixture.set_benji_price(PRICE_ONE * 60 / 100);
+    assert!(credit_line.is_liquidatable(&user, &0));
+    let debt = credit_line.get_current_debt(&user, &0);
+    assert_eq!(
+        credit_line.try_liquidate(&liquidator, &user, &0, benji, &(debt / 2)),
+        Err(Ok(Error::BelowMinimum))
+    );
+    credit_line.liquidate(&liquidator, &user, &0, benji, &debt);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 0);
+    assert!(credit_line.check_solvency().gap >= 0);
+}
+
+#[test]
+fn dust_close_out_seizes_only_what_the_debt_is_worth() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(10_000 * TOKEN, 0);
+    let liquidator = fixture.fund(0, 100 * TOKEN);
+
+    // A fixed loan left overdue is liquidatable however well collateralized
+    credit_line.deposit_collateral(&user, &0, benji, &(10_000 * TOKEN), &None, &None);
+    credit_line.borrow_fixed(&user, &0, &(100 * TOKEN), &(10 * 17_280));
+    credit_line.repay(&user, &0, &(50 * TOKEN), &None);
+    fixture.advance_ledgers(10 * 17_280 + 1);
+    fixture.set_benji_price(PRICE_ONE);
+    assert!(credit_line.is_liquidatable(&user, &0));
+
+    // Half the debt would leave less than the minimum borrow, so all of it goes
+    // in one call, and a liquidator may ask for more than is owed to be sure of
+    // covering the interest
+    credit_line.set_position_minimums(&fixture.admin, &(30 * TOKEN), &0);
+    let debt = credit_line.get_current_debt(&user, &0);
+    assert_eq!(
+        credit_line.try_liquidate(&liquidator, &user, &0, benji, &(debt / 2)),
+        Err(Ok(Error::BelowMinimum))
+    );
+    let seized = credit_line.liquidate(&liquidator, &user, &0, benji, &(debt + TOKEN));
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 0);
+    assert_eq!(fixture.usdc.balance(&liquidator), 100 * TOKEN - debt);
+
+    // The liquidator takes the debt's worth plus the bonus, not the whole balance
+    assert_eq!(seized, debt * 105 / 100);
+    assert_eq!(
+        credit_line
+            .get_position(&user, &0)
+            .collateral
+            .get(benji.clone()),
+        Some(10_000 * TOKEN - seized)
+    );
+}
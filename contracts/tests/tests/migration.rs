@@ -0,0 +1,93 @@
+use credit_line::{CreditLineContract, CreditLineContractClient, Error};
+use integration_tests::{Fixture, LIQUIDITY, PRICE_ONE, TOKEN, YEAR};
+
+#[test]
+fn positions_move_to_the_successor_with_their_debt() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let admin = &fixture.admin;
+    let old = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    // A second market on the same tokens and oracle, with its own lenders
+    let new = CreditLineContractClient::new(env, &env.register(CreditLineContract, ()));
+    new.initialize(admin, benji, &fixture.usdc.address);
+    new.set_oracle(admin, &fixture.oracle.address);
+    fixture.mint_usdc(&fixture.lender, LIQUIDITY);
+    new.supply(&fixture.lender, &LIQUIDITY);
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    old.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    old.borrow(&user, &0, &(400 * TOKEN), &None, &None);
+    fixture.advance(YEAR);
+    fixture.set_benji_price(PRICE_ONE);
+
+    // Both admins must agree on the route
+    assert_eq!(
+        old.try_migrate_position(&user, &0, &new.address),
+        Err(Ok(Error::Unauthorized))
+    );
+    old.set_successor(admin, &Some(new.address.clone()));
+    assert!(old.try_migrate_position(&user, &0, &new.address).is_err());
+    new.set_predecessor(admin, &Some(old.address.clone()));
+
+    let debt = old.get_current_debt(&user, &0);
+    assert_eq!(old.migrate_position(&user, &0, &new.address), debt);
+
+    // The old pool holds the debt in USDC and nothing of the position
+    let position = old.get_position(&user, &0);
+    assert_eq!(position.borrowed, 0);
+    assert!(position.collateral.is_empty());
+    assert_eq!(old.get_total_borrowed(), 0);
+    assert_eq!(old.get_total_collateral(benji), 0);
+    assert_eq!(
+        fixture.usdc.balance(&old.address),
+        LIQUIDITY - 400 * TOKEN + debt
+    );
+    assert!(old.check_solvency().gap >= 0);
+
+    // The new pool lent the debt to the old one and holds the collateral
+    let position = new.get_position(&user, &0);
+    assert_eq!(position.borrowed, debt);
+    assert_eq!(position.collateral.get(benji.clone()), Some(1_000 * TOKEN));
+    assert_eq!(new.get_total_collateral(benji), 1_000 * TOKEN);
+    assert_eq!(fixture.benji.balance(&new.address), 1_000 * TOKEN);
+    assert_eq!(fixture.benji.balance(&old.address), 0);
+    assert_eq!(fixture.usdc.balance(&new.address), LIQUIDITY - debt);
+    assert!(new.check_solvency().gap >= 0);
+
+    // The borrower carries on in the new market
+    fixture.mint_usdc(&user, debt - 400 * TOKEN);
+    new.repay(&user, &0, &debt, &None);
+    new.withdraw_collateral(&user, &0, benji, &(1_000 * TOKEN), &None);
+    assert_eq!(fixture.benji.balance(&user), 1_000 * TOKEN);
+}
+
+#[test]
+fn migrated_collateral_respects_the_successors_supply_cap() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let admin = &fixture.admin;
+    let old = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    let new = CreditLineContractClient::new(env, &env.register(CreditLineContract, ()));
+    new.initialize(admin, benji, &fixture.usdc.address);
+    new.set_oracle(admin, &fixture.oracle.address);
+    old.set_successor(admin, &Some(new.address.clone()));
+    new.set_predecessor(admin, &Some(old.address.clone()));
+    new.set_supply_cap(admin, benji, &Some(500 * TOKEN));
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    old.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    assert!(old.try_migrate_position(&user, &0, &new.address).is_err());
+    assert_eq!(
+        old.get_position(&user, &0).collateral.get(benji.clone()),
+        Some(1_000 * TOKEN)
+    );
+    assert_eq!(new.get_total_collateral(benji), 0);
+
+    new.set_supply_cap(admin, benji, &Some(1_000 * TOKEN));
+    old.migrate_position(&user, &0, &new.address);
+    assert_eq!(new.get_total_collateral(benji), 1_000 * TOKEN);
+}
@@ -0,0 +1,189 @@
+use integration_tests::{Fixture, DAY, TOKEN};
+use mock_usdc_token::UsdcTokenClient;
+use soroban_sdk::testutils::{Address as _, Events as _};
+use soroban_sdk::xdr::ScVal;
+use soroban_sdk::{Address, Env, IntoVal, Symbol, TryFromVal, Val};
+
+/// Topics and data of the last event the mock USDC emitted, as XDR
+fn last_usdc_event(fixture: &Fixture) -> (Vec<ScVal>, ScVal) {
+    let env = &fixture.env;
+    let (_, topics, data) = env
+        .events()
+        .all()
+        .iter()
+        .filter(|(contract, _, _)| *contract == fixture.usdc.address)
+        .last()
+        .unwrap();
+    let topics = topics
+        .iter()
+        .map(|topic| ScVal::try_from_val(env, &topic).unwrap())
+        .collect();
+    (topics, ScVal::try_from_val(env, &data).unwrap())
+}
+
+/// An event with the given name, address topics and single-value data
+fn event(
+    fixture: &Fixture,
+    name: &str,
+    addresses: &[&Address],
+    data: impl IntoVal<Env, Val>,
+) -> (Vec<ScVal>, ScVal) {
+    let env = &fixture.env;
+    let name: Val = Symbol::new(env, name).into_val(env);
+    let topics = core::iter::once(name)
+        .chain(addresses.iter().map(|address| address.to_val()))
+        .map(|topic| ScVal::try_from_val(env, &topic).unwrap())
+        .collect();
+    (
+        topics,
+        ScVal::try_from_val(env, &data.into_val(env)).unwrap(),
+    )
+}
+
+#[test]
+fn emits_standard_token_events() {
+    let fixture = Fixture::new();
+    let usdc = UsdcTokenClient::new(&fixture.env, &fixture.usdc.address);
+    let admin = &fixture.admin;
+    let alice = Address::generate(&fixture.env);
+    let bob = Address::generate(&fixture.env);
+
+    usdc.mint(&alice, &(100 * TOKEN));
+    assert_eq!(
+        last_usdc_event(&fixture),
+        event(&fixture, "mint", &[&alice], 100 * TOKEN)
+    );
+
+    usdc.faucet(&bob, &TOKEN);
+    assert_eq!(
+        last_usdc_event(&fixture),
+        event(&fixture, "mint", &[&bob], TOKEN)
+    );
+
+    usdc.approve(&alice, &bob, &(50 * TOKEN), &1_000);
+    let (topics, _) = last_usdc_event(&fixture);
+    assert_eq!(topics, event(&fixture, "approve", &[&alice, &bob], ()).0);
+
+    usdc.transfer_from(&bob, &alice, &bob, &(10 * TOKEN));
+    assert_eq!(
+        last_usdc_event(&fixture),
+        event(&fixture, "transfer", &[&alice, &bob], 10 * TOKEN)
+    );
+
+    usdc.burn_from(&bob, &alice, &(10 * TOKEN));
+    assert_eq!(
+        last_usdc_event(&fixture),
+        event(&fixture, "burn", &[&alice], 10 * TOKEN)
+    );
+
+    usdc.burn(&bob, &TOKEN);
+    assert_eq!(
+        last_usdc_event(&fixture),
+        event(&fixture, "burn", &[&bob], TOKEN)
+    );
+
+    usdc.clawback(&alice, &(10 * TOKEN));
+    assert_eq!(
+        last_usdc_event(&fixture),
+        event(&fixture, "clawback", &[&alice], 10 * TOKEN)
+    );
+
+    usdc.freeze(&alice);
+    assert_eq!(
+        last_usdc_event(&fixture),
+        event(&fixture, "set_authorized", &[&alice], false)
+    );
+    usdc.unfreeze(&alice);
+    assert_eq!(
+        last_usdc_event(&fixture),
+        event(&fixture, "set_authorized", &[&alice], true)
+    );
+
+    usdc.set_admin(&bob);
+    assert_eq!(
+        last_usdc_event(&fixture),
+        event(&fixture, "set_admin", &[admin], bob.clone())
+    );
+}
+
+#[test]
+fn faucet_pays_out_up_to_a_daily_limit_per_address() {
+    let fixture = Fixture::new();
+    let usdc = UsdcTokenClient::new(&fixture.env, &fixture.usdc.address);
+    let alice = Address::generate(&fixture.env);
+    let bob = Address::generate(&fixture.env);
+    assert_eq!(usdc.faucet_limit(), 1_000 * TOKEN);
+
+    usdc.faucet(&alice, &(600 * TOKEN));
+    usdc.faucet(&alice, &(400 * TOKEN));
+    assert_eq!(fixture.usdc.balance(&alice), 1_000 * TOKEN);
+    assert!(usdc.try_faucet(&alice, &1).is_err());
+
+    // Each address has its own allowance, and it resets the next day
+    usdc.faucet(&bob, &(1_000 * TOKEN));
+    fixture.advance(DAY);
+    usdc.faucet(&alice, &(1_000 * TOKEN));
+    assert_eq!(fixture.usdc.balance(&alice), 2_000 * TOKEN);
+
+    // Only the admin may change the limit, and zero turns the faucet off
+    assert!(usdc.try_set_faucet_limit(&alice, &0).is_err());
+    usdc.set_faucet_limit(&fixture.admin, &0);
+    fixture.advance(DAY);
+    assert!(usdc.try_faucet(&bob, &TOKEN).is_err());
+}
+
+#[test]
+fn mints_stop_at_the_max_supply() {
+    let fixture = Fixture::new();
+    let usdc = UsdcTokenClient::new(&fixture.env, &fixture.usdc.address);
+    let alice = Address::generate(&fixture.env);
+    let supply = usdc.total_supply();
+
+    assert!(usdc
+        .try_set_max_supply(&alice, &Some(supply + 100 * TOKEN))
+        .is_err());
+    usdc.set_max_supply(&fixture.admin, &Some(supply + 100 * TOKEN));
+    usdc.mint(&alice, &(60 * TOKEN));
+    usdc.faucet(&alice, &(40 * TOKEN));
+    assert!(usdc.try_mint(&alice, &1).is_err());
+    assert!(usdc.try_faucet(&alice, &1).is_err());
+
+    // Burning makes room again, and lifting the cap removes the limit
+    usdc.burn(&alice, &TOKEN);
+    usdc.mint(&alice, &TOKEN);
+    usdc.set_max_supply(&fixture.admin, &None);
+    usdc.mint(&alice, &(i128::MAX - supply - 100 * TOKEN));
+    assert_eq!(usdc.total_supply(), i128::MAX);
+
+    // Overflow panics instead of wrapping
+    assert!(usdc.try_mint(&alice, &1).is_err());
+    assert!(usdc.try_faucet(&alice, &1).is_err());
+}
+
+#[test]
+fn blocked_accounts_cannot_use_the_token() {
+    let fixture = Fixture::new();
+    let usdc = UsdcTokenClient::new(&fixture.env, &fixture.usdc.address);
+    let alice = Address::generate(&fixture.env);
+    let bob = Address::generate(&fixture.env);
+    usdc.mint(&alice, &(100 * TOKEN));
+    usdc.approve(&alice, &bob, &(100 * TOKEN), &1_000);
+
+    usdc.block(&alice);
+    assert!(usdc.is_blocked(&alice));
+    assert!(usdc.try_transfer(&alice, &bob, &TOKEN).is_err());
+    assert!(usdc.try_transfer(&bob, &alice, &0).is_err());
+    assert!(usdc.try_transfer_from(&bob, &alice, &bob, &TOKEN).is_err());
+    assert!(usdc.try_approve(&alice, &bob, &TOKEN, &1_000).is_err());
+    assert!(usdc.try_burn(&alice, &TOKEN).is_err());
+    assert!(usdc.try_mint(&alice, &TOKEN).is_err());
+    assert!(usdc.try_faucet(&alice, &TOKEN).is_err());
+
+    // The admin can still claw funds back from a blocked account
+    usdc.clawback(&alice, &TOKEN);
+    assert_eq!(fixture.usdc.balance(&alice), 99 * TOKEN);
+
+    usdc.unblock(&alice);
+    usdc.transfer(&alice, &bob, &TOKEN);
+    assert_eq!(fixture.usdc.balance(&bob), TOKEN);
+}
@@ -0,0 +1,309 @@
+use credit_line::param_log::ParamChange;
+use credit_line::param_update::ParamUpdate;
+use credit_line::rewards::RewardPool;
+use credit_line::Error;
+use integration_tests::{Fixture, TOKEN};
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{vec, Address, Symbol};
+
+#[test]
+fn admin_changes_are_logged_with_old_and_new_values() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let admin = &fixture.admin;
+
+    // Setting up the market writes the first config, which is not a change
+    let start = credit_line.get_param_change_count();
+
+    credit_line.set_ltv_ratio(admin, benji, &6_000);
+    credit_line.set_debt_ceiling(admin, &Some(50_000 * TOKEN));
+    credit_line.set_supply_cap(admin, benji, &Some(10_000 * TOKEN));
+    // Unchanged values are not logged
+    credit_line.set_debt_ceiling(admin, &Some(50_000 * TOKEN));
+    assert_eq!(credit_line.get_param_change_count(), start + 3);
+
+    let timestamp = env.ledger().timestamp();
+    let changes = credit_line.get_param_changes(&start, &10);
+    assert_eq!(changes.len(), 3);
+    assert_eq!(
+        changes.get(0).unwrap(),
+        ParamChange {
+            param: Symbol::new(env, "ltv_ratio"),
+            subject: Some(benji.clone()),
+            old_value: Some(7_000),
+            new_value: Some(6_000),
+            old_address: None,
+            new_address: None,
+            admin: admin.clone(),
+            timestamp,
+        }
+    );
+    assert_eq!(
+        changes.get(1).unwrap(),
+        ParamChange {
+            param: Symbol::new(env, "debt_ceiling"),
+            subject: None,
+            old_value: None,
+            new_value: Some(50_000 * TOKEN),
+            old_address: None,
+            new_address: None,
+            admin: admin.clone(),
+            timestamp,
+        }
+    );
+
+    // Pages start at a sequence number
+    let page = credit_line.get_param_changes(&(start + 2), &10);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().param, Symbol::new(env, "supply_cap"));
+    assert_eq!(credit_line.get_param_changes(&(start + 3), &10).len(), 0);
+}
+
+#[test]
+fn batched_updates_land_together_or_not_at_all() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let admin = &fixture.admin;
+
+    // Alone, the higher LTV would sit above the current threshold
+    assert_eq!(
+        credit_line.try_set_ltv_ratio(admin, benji, &8_500),
+        Err(Ok(Error::InvalidParameter))
+    );
+    credit_line.multi_set(
+        admin,
+        &vec![
+            env,
+            ParamUpdate::LiquidationThreshold(benji.clone(), 9_000),
+            ParamUpdate::LtvRatio(benji.clone(), 8_500),
+            ParamUpdate::SupplyCap(benji.clone(), Some(10_000 * TOKEN)),
+            ParamUpdate::InterestRate(300),
+        ],
+    );
+    let collateral = credit_line.get_collateral_config(benji);
+    assert_eq!(collateral.ltv_ratio, 8_500);
+    assert_eq!(collateral.liquidation_threshold, 9_000);
+    assert_eq!(credit_line.get_config().interest_rate, 300);
+
+    // One bad update reverts the ones before it
+    let start = credit_line.get_param_change_count();
+    assert_eq!(
+        credit_line.try_multi_set(
+            admin,
+            &vec![
+                env,
+                ParamUpdate::LtvRatio(benji.clone(), 6_000),
+                ParamUpdate::ReserveFactor(20_000),
+            ],
+        ),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(credit_line.get_collateral_config(benji).ltv_ratio, 8_500);
+    assert_eq!(credit_line.get_param_change_count(), start);
+
+    assert_eq!(
+        credit_line.try_multi_set(
+            &Address::generate(env),
+            &vec![env, ParamUpdate::InterestRate(0)],
+        ),
+        Err(Ok(Error::Unauthorized))
+    );
+}
+
+#[test]
+fn access_and_contract_changes_are_logged() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let admin = &fixture.admin;
+    let user = Address::generate(env);
+    let treasury = Address::generate(env);
+    let start = credit_line.get_param_change_count();
+
+    credit_line.set_allowlist_enabled(admin, &true);
+    credit_line.allow(admin, &user);
+    credit_line.deny(admin, &user);
+    credit_line.set_history_length(admin, &10);
+    credit_line.set_borrow_cap(admin, &user, &Some(TOKEN));
+    credit_line.set_treasury(admin, &treasury);
+    credit_line.set_flash_loan_fee(admin, &5);
+    credit_line.set_position_minimums(admin, &TOKEN, &0);
+    // Unchanged values are not logged
+    credit_line.deny(admin, &user);
+    credit_line.set_treasury(admin, &treasury);
+
+    let changes = credit_line.get_param_changes(&start, &20);
+    let summary: std::vec::Vec<_> = changes
+        .iter()
+        .map(|change| {
+            (
+                change.param,
+                change.subject,
+                change.old_value,
+                change.new_value,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (
+                Symbol::new(env, "allowlist_enabled"),
+                None,
+                Some(0),
+                Some(1)
+            ),
+            (
+                Symbol::new(env, "allowlisted"),
+                Some(user.clone()),
+                Some(0),
+                Some(1)
+            ),
+            (
+                Symbol::new(env, "allowlisted"),
+                Some(user.clone()),
+                Some(1),
+                Some(0)
+            ),
+            (Symbol::new(env, "history_length"), None, Some(0), Some(10)),
+            (
+                Symbol::new(env, "borrow_cap"),
+                Some(user.clone()),
+                None,
+                Some(TOKEN)
+            ),
+            (Symbol::new(env, "treasury"), None, None, None),
+            (Symbol::new(env, "flash_loan_fee"), None, Some(9), Some(5)),
+            (Symbol::new(env, "min_borrow"), None, Some(0), Some(TOKEN)),
+        ]
+    );
+
+    // Contracts are logged by address
+    let change = changes.get(5).unwrap();
+    assert_eq!(change.old_address, None);
+    assert_eq!(change.new_address, Some(treasury));
+    assert_eq!(change.admin, *admin);
+
+    assert_eq!(
+        credit_line.try_set_history_length(admin, &1_000),
+        Err(Ok(Error::InvalidParameter))
+    );
+}
+
+#[test]
+fn every_admin_setter_is_logged_with_its_caller() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let admin = &fixture.admin;
+    let new_admin = Address::generate(env);
+    let successor = Address::generate(env);
+    let predecessor = Address::generate(env);
+    let reward = Address::generate(env);
+    let start = credit_line.get_param_change_count();
+
+    credit_line.pause(admin, &false);
+    credit_line.unpause(admin);
+    credit_line.set_reward_token(admin, &reward);
+    credit_line.set_emission_rate(admin, &RewardPool::Borrow, &10);
+    credit_line.set_successor(admin, &Some(successor.clone()));
+    credit_line.set_predecessor(admin, &Some(predecessor.clone()));
+    credit_line.enable_emergency_mode(admin);
+    credit_line.propose_admin(admin, &new_admin);
+    credit_line.accept_admin(&new_admin);
+    // Config changes are credited to whoever made them, not the admin of record
+    credit_line.set_debt_ceiling(&new_admin, &Some(TOKEN));
+
+    let changes = credit_line.get_param_changes(&start, &20);
+    let summary: std::vec::Vec<_> = changes
+        .iter()
+        .map(|change| {
+            (
+                change.param,
+                change.old_value,
+                change.new_value,
+                change.new_address,
+                change.admin,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (
+                Symbol::new(env, "paused"),
+                Some(0),
+                Some(1),
+                None,
+                admin.clone()
+            ),
+            (
+                Symbol::new(env, "paused"),
+                Some(1),
+                Some(0),
+                None,
+                admin.clone()
+            ),
+            (
+                Symbol::new(env, "reward_token"),
+                None,
+                None,
+                Some(reward),
+                admin.clone()
+            ),
+            (
+                Symbol::new(env, "borrow_emission_rate"),
+                Some(0),
+                Some(10),
+                None,
+                admin.clone()
+            ),
+            (
+                Symbol::new(env, "successor"),
+                None,
+                None,
+                Some(successor),
+                admin.clone()
+            ),
+            (
+                Symbol::new(env, "predecessor"),
+                None,
+                None,
+                Some(predecessor),
+                admin.clone()
+            ),
+            (
+                Symbol::new(env, "emergency_mode"),
+                Some(0),
+                Some(1),
+                None,
+                admin.clone()
+            ),
+            (
+                Symbol::new(env, "pending_admin"),
+                None,
+                None,
+                Some(new_admin.clone()),
+                admin.clone()
+            ),
+            (
+                Symbol::new(env, "admin"),
+                None,
+                None,
+                Some(new_admin.clone()),
+                new_admin.clone()
+            ),
+            (
+                Symbol::new(env, "debt_ceiling"),
+                None,
+                Some(TOKEN),
+                None,
+                new_admin.clone()
+            ),
+        ]
+    );
+}
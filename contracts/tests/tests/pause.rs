@@ -0,0 +1,158 @@
+use credit_line::Error;
+use integration_tests::{Fixture, PRICE_ONE, TOKEN};
+
+#[test]
+fn pause_halts_the_market_but_can_allow_repayment() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(100 * TOKEN), &None, &None);
+
+    credit_line.pause(&fixture.admin, &false);
+    assert!(credit_line.is_paused());
+    assert!(credit_line
+        .try_borrow(&user, &0, &(100 * TOKEN), &None, &None)
+        .is_err());
+    assert!(credit_line
+        .try_deposit_collateral(&user, &0, benji, &TOKEN, &None, &None)
+        .is_err());
+    assert!(credit_line
+        .try_withdraw_supply(&fixture.lender, &TOKEN)
+        .is_err());
+
+    // Borrowers can still pay down debt
+    credit_line.repay(&user, &0, &(50 * TOKEN), &None);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 50 * TOKEN);
+
+    credit_line.unpause(&fixture.admin);
+    credit_line.borrow(&user, &0, &(50 * TOKEN), &None, &None);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 100 * TOKEN);
+}
+
+#[test]
+fn pause_can_halt_repayment_too() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(
+        &user,
+        &0,
+        &fixture.benji.address,
+        &(1_000 * TOKEN),
+        &None,
+        &None,
+    );
+    credit_line.borrow(&user, &0, &(100 * TOKEN), &None, &None);
+
+    credit_line.pause(&fixture.admin, &true);
+    assert!(credit_line
+        .try_repay(&user, &0, &(50 * TOKEN), &None)
+        .is_err());
+}
+
+#[test]
+fn only_admin_can_pause() {
+    let fixture = Fixture::new();
+    let outsider = fixture.fund(0, 0);
+
+    assert!(fixture.credit_line.try_pause(&outsider, &false).is_err());
+    assert!(!fixture.credit_line.is_paused());
+}
+
+#[test]
+fn emergency_withdrawal_keeps_collateral_worth_the_debt() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+
+    // A second collateral token, priced at 1.0
+    let other_admin = fixture.add_collateral_token("Other", "OTH", 7_000, 8_000);
+    let other = other_admin.address.clone();
+
+    let user = fixture.fund(500 * TOKEN, 0);
+    other_admin.mint(&user, &(500 * TOKEN));
+    credit_line.deposit_collateral(&user, &0, benji, &(500 * TOKEN), &None, &None);
+    credit_line.deposit_collateral(&user, &0, &other, &(500 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(600 * TOKEN), &None, &None);
+    credit_line.enable_emergency_mode(&fixture.admin);
+
+    // The other token covers 500 of the debt, so BENJI worth the other 100
+    // stays behind and nothing is written off
+    assert_eq!(
+        credit_line.emergency_withdraw(&user, &0, benji),
+        400 * TOKEN
+    );
+    let position = credit_line.get_position(&user, &0);
+    assert_eq!(position.borrowed, 600 * TOKEN);
+    assert_eq!(position.collateral.get(benji.clone()), Some(100 * TOKEN));
+    assert_eq!(credit_line.get_bad_debt(), 0);
+
+    // All of the other token is still needed
+    assert_eq!(credit_line.emergency_withdraw(&user, &0, &other), 0);
+    assert_eq!(
+        credit_line
+            .get_position(&user, &0)
+            .collateral
+            .get(other.clone()),
+        Some(500 * TOKEN)
+    );
+
+    // Repaying the debt frees the rest
+    credit_line.repay(&user, &0, &(600 * TOKEN), &None);
+    assert_eq!(
+        credit_line.emergency_withdraw(&user, &0, &other),
+        500 * TOKEN
+    );
+    assert_eq!(
+        credit_line.emergency_withdraw(&user, &0, benji),
+        100 * TOKEN
+    );
+    assert_eq!(fixture.benji.balance(&user), 500 * TOKEN);
+    assert_eq!(credit_line.get_bad_debt(), 0);
+}
+
+#[test]
+fn bad_debt_comes_off_the_share_price_when_recorded() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let lender = &fixture.lender;
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    fixture.set_benji_price(30 * PRICE_ONE / 100);
+    credit_line.enable_emergency_mode(&fixture.admin);
+
+    // Collateral now worth 300 leaves 200 of the debt unrecoverable, and
+    // suppliers bear that loss at once, not when the admin gets to it
+    let supplied = credit_line.get_supply_balance(lender);
+    assert_eq!(credit_line.emergency_withdraw(&user, &0, benji), 0);
+    assert_eq!(credit_line.get_bad_debt(), 200 * TOKEN);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 300 * TOKEN);
+    assert_eq!(
+        credit_line.get_supply_balance(lender),
+        supplied - 200 * TOKEN
+    );
+
+    // Socializing it only settles the books
+    let total_supplied = credit_line.get_total_supplied();
+    assert_eq!(
+        credit_line.try_socialize_bad_debt(&fixture.admin, &0),
+        Err(Ok(Error::InvalidParameter))
+    );
+    credit_line.socialize_bad_debt(&fixture.admin, &(200 * TOKEN));
+    assert_eq!(credit_line.get_bad_debt(), 0);
+    assert_eq!(
+        credit_line.get_total_supplied(),
+        total_supplied - 200 * TOKEN
+    );
+    assert_eq!(
+        credit_line.get_supply_balance(lender),
+        supplied - 200 * TOKEN
+    );
+}
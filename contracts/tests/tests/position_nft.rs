@@ -0,0 +1,180 @@
+use credit_line::rewards::RewardPool;
+use credit_line::HEALTH_FACTOR_ONE;
+use integration_tests::{Fixture, DAY, TOKEN};
+use position_nft::{Error, PositionNft, PositionNftClient};
+use position_vault::PositionVault;
+use soroban_sdk::{
+    contract, contractimpl, testutils::Address as _, token::TokenClient, Address, Bytes, BytesN,
+    Env,
+};
+
+/// Registers a native `PositionVault` at an address, then fails so the
+/// registration is rolled back while the host keeps its code. A vault later
+/// deployed there from the empty test wasm runs that code, standing in for
+/// the vault wasm these tests cannot build.
+#[contract]
+struct VaultLoader;
+
+#[contractimpl]
+impl VaultLoader {
+    pub fn load(env: Env, at: Address, nft: Address, credit_line: Address, usdc: Address) {
+        env.register_at(&at, PositionVault, (nft, credit_line, usdc));
+        panic!("roll back the registration");
+    }
+}
+
+/// A position NFT on the fixture's market, with native vault code ready for
+/// the first `vaults` tokens it mints
+fn position_nft<'a>(fixture: &Fixture, vaults: u64) -> PositionNftClient<'a> {
+    let env = &fixture.env;
+    let nft = PositionNftClient::new(env, &env.register(PositionNft, ()));
+    let test_wasm = env.deployer().upload_contract_wasm(Bytes::new(env));
+    nft.initialize(
+        &fixture.credit_line.address,
+        &fixture.usdc.address,
+        &test_wasm,
+    );
+
+    let loader = VaultLoaderClient::new(env, &env.register(VaultLoader, ()));
+    for token_id in 0..vaults {
+        let mut salt = [0u8; 32];
+        salt[24..].copy_from_slice(&token_id.to_be_bytes());
+        let vault = env
+            .deployer()
+            .with_address(nft.address.clone(), BytesN::from_array(env, &salt))
+            .deployed_address();
+        assert!(loader
+            .try_load(
+                &vault,
+                &nft.address,
+                &fixture.credit_line.address,
+                &fixture.usdc.address,
+            )
+            .is_err());
+    }
+    nft
+}
+
+#[test]
+fn token_holder_controls_the_vault_position() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let nft = position_nft(&fixture, 1);
+    let owner = fixture.fund(1_000 * TOKEN, 0);
+    let stranger = fixture.fund(1_000 * TOKEN, 1_000 * TOKEN);
+
+    let token_id = nft.mint(&owner, &fixture.benji.address, &(500 * TOKEN));
+    let vault = nft.vault_of(&token_id);
+    assert_eq!(nft.owner_of(&token_id), owner);
+    assert_eq!(nft.balance(&owner), 1);
+    assert_eq!(nft.total_supply(), 1);
+    assert_eq!(
+        credit_line
+            .get_position(&vault, &0)
+            .collateral
+            .get(fixture.benji.address.clone()),
+        Some(500 * TOKEN)
+    );
+
+    // Anyone may add collateral or repay, but only the holder draws funds
+    nft.deposit(&stranger, &token_id, &fixture.benji.address, &(500 * TOKEN));
+    nft.borrow(&owner, &token_id, &(300 * TOKEN));
+    assert_eq!(fixture.usdc.balance(&owner), 300 * TOKEN);
+    assert_eq!(credit_line.get_position(&vault, &0).borrowed, 300 * TOKEN);
+    assert_eq!(
+        nft.try_borrow(&stranger, &token_id, &TOKEN),
+        Err(Ok(Error::NotOwner))
+    );
+    assert_eq!(
+        nft.try_withdraw(&stranger, &token_id, &fixture.benji.address, &TOKEN),
+        Err(Ok(Error::NotOwner))
+    );
+    nft.repay(&stranger, &token_id, &(100 * TOKEN));
+    assert_eq!(credit_line.get_position(&vault, &0).borrowed, 200 * TOKEN);
+
+    nft.withdraw(&owner, &token_id, &fixture.benji.address, &(100 * TOKEN));
+    assert_eq!(fixture.benji.balance(&owner), 600 * TOKEN);
+    assert!(credit_line.get_health_factor(&vault, &0) > HEALTH_FACTOR_ONE);
+}
+
+#[test]
+fn transferring_the_token_hands_over_the_position() {
+    let fixture = Fixture::new();
+    let nft = position_nft(&fixture, 1);
+    let seller = fixture.fund(1_000 * TOKEN, 0);
+    let buyer = Address::generate(&fixture.env);
+
+    let token_id = nft.mint(&seller, &fixture.benji.address, &(1_000 * TOKEN));
+    nft.borrow(&seller, &token_id, &(200 * TOKEN));
+    assert_eq!(
+        nft.try_transfer(&buyer, &seller, &token_id),
+        Err(Ok(Error::NotOwner))
+    );
+
+    nft.transfer(&seller, &buyer, &token_id);
+    assert_eq!(nft.owner_of(&token_id), buyer);
+    assert_eq!(nft.balance(&seller), 0);
+    assert_eq!(nft.balance(&buyer), 1);
+    assert_eq!(
+        nft.try_borrow(&seller, &token_id, &TOKEN),
+        Err(Ok(Error::NotOwner))
+    );
+
+    // The debt moves with the token
+    nft.borrow(&buyer, &token_id, &(100 * TOKEN));
+    assert_eq!(fixture.usdc.balance(&buyer), 100 * TOKEN);
+    assert_eq!(
+        fixture
+            .credit_line
+            .get_position(&nft.vault_of(&token_id), &0)
+            .borrowed,
+        300 * TOKEN
+    );
+    assert_eq!(nft.try_owner_of(&1), Err(Ok(Error::TokenNotFound)));
+}
+
+#[test]
+fn token_holder_claims_the_vault_yield_and_rewards() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let nft = position_nft(&fixture, 1);
+    let owner = fixture.fund(1_000 * TOKEN, 0);
+    let stranger = Address::generate(env);
+
+    // Borrower rewards paid in a token of their own
+    let reward_admin = fixture.add_token("Reward", "RWD");
+    let reward = reward_admin.address.clone();
+    reward_admin.mint(&credit_line.address, &(10_000 * TOKEN));
+    credit_line.set_reward_token(&fixture.admin, &reward);
+    credit_line.set_emission_rate(&fixture.admin, &RewardPool::Borrow, &10);
+
+    let token_id = nft.mint(&owner, benji, &(1_000 * TOKEN));
+    nft.borrow(&owner, &token_id, &(100 * TOKEN));
+    fixture.advance(DAY);
+
+    // A BENJI dividend on the vault's collateral
+    fixture.mint_benji(&credit_line.address, 50 * TOKEN);
+
+    assert_eq!(
+        nft.try_claim_collateral_yield(&stranger, &token_id),
+        Err(Ok(Error::NotOwner))
+    );
+    assert_eq!(
+        nft.try_claim_rewards(&stranger, &token_id),
+        Err(Ok(Error::NotOwner))
+    );
+
+    let claimed = nft.claim_collateral_yield(&owner, &token_id);
+    assert_eq!(claimed.get(benji.clone()), Some(50 * TOKEN));
+    assert_eq!(fixture.benji.balance(&owner), 50 * TOKEN);
+
+    let rewards = nft.claim_rewards(&owner, &token_id);
+    assert_eq!(rewards, 10 * DAY as i128);
+    assert_eq!(TokenClient::new(env, &reward).balance(&owner), rewards);
+    assert_eq!(
+        TokenClient::new(env, &reward).balance(&nft.vault_of(&token_id)),
+        0
+    );
+}
@@ -0,0 +1,53 @@
+use integration_tests::{Fixture, TOKEN};
+use position_vault::{PositionVault, PositionVaultClient};
+use soroban_sdk::{testutils::Address as _, Address};
+
+#[test]
+fn only_the_nft_contract_moves_vault_funds() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let nft = Address::generate(env);
+    let vault = PositionVaultClient::new(
+        env,
+        &env.register(
+            PositionVault,
+            (
+                nft.clone(),
+                credit_line.address.clone(),
+                fixture.usdc.address.clone(),
+            ),
+        ),
+    );
+    let payer = fixture.fund(1_000 * TOKEN, 0);
+    let holder = Address::generate(env);
+    credit_line.deposit_collateral(
+        &payer,
+        &0,
+        &fixture.benji.address,
+        &(1_000 * TOKEN),
+        &Some(vault.address.clone()),
+        &None,
+    );
+
+    // The vault signs for its own position, on the NFT contract's say-so
+    vault.borrow(&(200 * TOKEN), &holder);
+    assert_eq!(env.auths()[0].0, nft);
+    assert_eq!(fixture.usdc.balance(&holder), 200 * TOKEN);
+    assert_eq!(
+        credit_line.get_position(&vault.address, &0).borrowed,
+        200 * TOKEN
+    );
+
+    vault.withdraw(&fixture.benji.address, &(100 * TOKEN), &holder);
+    assert_eq!(env.auths()[0].0, nft);
+    assert_eq!(fixture.benji.balance(&holder), 100 * TOKEN);
+
+    // Without it, nothing leaves the vault
+    env.set_auths(&[]);
+    assert!(vault.try_borrow(&TOKEN, &holder).is_err());
+    assert!(vault
+        .try_withdraw(&fixture.benji.address, &TOKEN, &holder)
+        .is_err());
+    assert_eq!(fixture.usdc.balance(&holder), 200 * TOKEN);
+}
@@ -0,0 +1,160 @@
+use credit_line::math::{compound, ray_mul, Rounding, RAY};
+use integration_tests::{Fixture, PRICE_ONE, TOKEN};
+use proptest::prelude::*;
+
+/// BENJI LTV set by `initialize`, in basis points
+const LTV: i128 = 7000;
+
+/// Up to a week between observations
+const MAX_STEP: u64 = 7 * 24 * 60 * 60;
+
+/// A user with `collateral` BENJI deposited and `borrowed` USDC drawn against it
+fn open_position(fixture: &Fixture, collateral: i128, borrowed: i128) -> soroban_sdk::Address {
+    let user = fixture.fund(collateral, 0);
+    fixture.credit_line.deposit_collateral(
+        &user,
+        &0,
+        &fixture.benji.address,
+        &collateral,
+        &None,
+        &None,
+    );
+    if borrowed > 0 {
+        fixture
+            .credit_line
+            .borrow(&user, &0, &borrowed, &None, &None);
+    }
+    user
+}
+
+proptest! {
+    // Every case deploys a fresh market, so keep the case count modest
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn withdraw_never_leaves_debt_above_credit_limit(
+        collateral in 1..10_000 * TOKEN,
+        borrow_bps in 0..=LTV,
+        withdraw_bps in 1..=10_000i128,
+    ) {
+        let fixture = Fixture::new();
+        let borrowed = collateral * borrow_bps / 10_000;
+        let user = open_position(&fixture, collateral, borrowed);
+
+        let withdraw = (collateral * withdraw_bps / 10_000).max(1);
+        let _ = fixture
+            .credit_line
+            .try_withdraw_collateral(&user, &0, &fixture.benji.address, &withdraw, &None);
+
+        // BENJI is priced at 1.0, so the credit limit is the collateral at LTV
+        let position = fixture.credit_line.get_position(&user, &0);
+        let remaining = position.collateral.get(fixture.benji.address.clone()).unwrap_or(0);
+        prop_assert!(position.borrowed * 10_000 <= remaining * LTV);
+    }
+
+    #[test]
+    fn repay_then_borrow_leaves_health_unchanged(
+        collateral in TOKEN..10_000 * TOKEN,
+        borrow_bps in 1..=LTV,
+        repay_bps in 1..=10_000i128,
+    ) {
+        let fixture = Fixture::new();
+        let borrowed = (collateral * borrow_bps / 10_000).max(1);
+        let user = open_position(&fixture, collateral, borrowed);
+        let health = fixture.credit_line.get_health_factor(&user, &0);
+
+        let amount = (borrowed * repay_bps / 10_000).max(1);
+        fixture.credit_line.repay(&user, &0, &amount, &None);
+        fixture.credit_line.borrow(&user, &0, &amount, &None, &None);
+
+        prop_assert_eq!(fixture.credit_line.get_position(&user, &0).borrowed, borrowed);
+        prop_assert_eq!(fixture.credit_line.get_health_factor(&user, &0), health);
+    }
+
+    #[test]
+    fn interest_accrual_is_monotone(
+        borrow_bps in 1..=LTV,
+        steps in proptest::collection::vec(0..MAX_STEP, 1..8),
+    ) {
+        let fixture = Fixture::new();
+        let collateral = 10_000 * TOKEN;
+        let user = open_position(&fixture, collateral, collateral * borrow_bps / 10_000);
+
+        let mut debt = fixture.credit_line.get_current_debt(&user, &0);
+        for step in steps {
+            fixture.advance(step);
+            let next = fixture.credit_line.get_current_debt(&user, &0);
+            prop_assert!(next >= debt);
+            debt = next;
+        }
+    }
+
+    #[test]
+    fn supply_round_trips_never_pay_out_more_than_supplied(
+        amount in 1..1_000 * TOKEN,
+        elapsed in 1..MAX_STEP,
+    ) {
+        // Interest leaves pool shares worth an uneven amount of USDC
+        let fixture = Fixture::new();
+        open_position(&fixture, 10_000 * TOKEN, 5_000 * TOKEN);
+        fixture.advance(elapsed);
+        fixture.set_benji_price(PRICE_ONE);
+
+        let lender = fixture.fund(0, amount);
+        let shares = fixture.credit_line.supply(&lender, &amount);
+        let balance = fixture.credit_line.get_supply_balance(&lender);
+        prop_assert!(balance <= amount);
+
+        if balance > 0 {
+            let burned = fixture.credit_line.withdraw_supply(&lender, &balance);
+            prop_assert!(burned <= shares);
+        }
+        prop_assert!(fixture.usdc.balance(&lender) <= amount);
+        prop_assert!(fixture.credit_line.check_solvency().gap >= 0);
+    }
+
+    #[test]
+    fn dust_borrows_repay_at_least_what_was_lent(
+        amount in 1..1_000i128,
+        steps in proptest::collection::vec(1..MAX_STEP, 1..6),
+    ) {
+        let fixture = Fixture::new();
+        let user = open_position(&fixture, TOKEN, 0);
+        fixture.mint_usdc(&user, TOKEN);
+
+        // Each cycle lends a few stroops and takes back the accrued debt
+        for step in steps {
+            fixture.credit_line.borrow(&user, &0, &amount, &None, &None);
+            fixture.advance(step);
+            fixture.set_benji_price(PRICE_ONE);
+
+            let debt = fixture.credit_line.get_current_debt(&user, &0);
+            prop_assert!(debt >= amount);
+            fixture.credit_line.repay(&user, &0, &debt, &None);
+            prop_assert_eq!(fixture.credit_line.get_position(&user, &0).borrowed, 0);
+        }
+        prop_assert!(fixture.usdc.balance(&user) <= TOKEN);
+        prop_assert!(fixture.credit_line.check_solvency().gap >= 0);
+    }
+
+    #[test]
+    fn compound_matches_repeated_multiplication(
+        rate in 0..RAY / 100,
+        periods in 0u64..400,
+    ) {
+        // One period at a time, rounding each way
+        let (mut low, mut high) = (RAY, RAY);
+        for _ in 0..periods {
+            low = ray_mul(low, RAY + rate, Rounding::Down).unwrap();
+            high = ray_mul(high, RAY + rate, Rounding::Up).unwrap();
+        }
+
+        let up = compound(rate, periods, Rounding::Up).unwrap();
+        let down = compound(rate, periods, Rounding::Down).unwrap();
+        // Relative error of 2 units of RAY scale per period
+        let slack = 2 * periods as i128 * (high / RAY + 1);
+        prop_assert!(down <= up);
+        prop_assert!(up >= low && up <= high + slack, "{low} {up} {high}");
+        prop_assert!(down <= high && down >= low - slack, "{low} {down} {high}");
+    }
+}
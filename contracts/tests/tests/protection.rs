@@ -0,0 +1,164 @@
+use credit_line::protection::{Protection, Protector};
+use credit_line::{Error, HEALTH_FACTOR_ONE};
+use integration_tests::{Fixture, TOKEN};
+use soroban_sdk::{contract, contractimpl, token, Address, Env};
+
+/// Protector paying the credit line what it asks for, or nothing when built
+/// with `pays` unset
+#[contract]
+struct MockProtector;
+
+#[contractimpl]
+impl MockProtector {
+    pub fn __constructor(env: Env, credit_line: Address, pays: bool) {
+        env.storage().instance().set(&0u32, &credit_line);
+        env.storage().instance().set(&1u32, &pays);
+    }
+}
+
+#[contractimpl]
+impl Protector for MockProtector {
+    fn on_protect(env: Env, _user: Address, _account_id: u32, token: Address, amount: i128) {
+        let credit_line: Address = env.storage().instance().get(&0u32).unwrap();
+        let pays: bool = env.storage().instance().get(&1u32).unwrap();
+        if pays {
+            token::Client::new(&env, &token).transfer(
+                &env.current_contract_address(),
+                &credit_line,
+                &amount,
+            );
+        }
+    }
+}
+
+/// A user with 1,000 BENJI against 600 USDC, at a health factor of 1.33
+fn borrower(fixture: &Fixture) -> Address {
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    fixture.credit_line.deposit_collateral(
+        &user,
+        &0,
+        &fixture.benji.address,
+        &(1_000 * TOKEN),
+        &None,
+        &None,
+    );
+    fixture
+        .credit_line
+        .borrow(&user, &0, &(600 * TOKEN), &None, &None);
+    user
+}
+
+fn protector(fixture: &Fixture, pays: bool) -> Address {
+    let protector = fixture
+        .env
+        .register(MockProtector, (fixture.credit_line.address.clone(), pays));
+    fixture.mint_usdc(&protector, 1_000 * TOKEN);
+    protector
+}
+
+#[test]
+fn protector_repays_debt_back_to_the_trigger() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let user = borrower(&fixture);
+    let protector = protector(&fixture, true);
+
+    // The trigger must sit above the liquidation line
+    assert_eq!(
+        credit_line.try_set_protection(&user, &0, &protector, &HEALTH_FACTOR_ONE, &(100 * TOKEN)),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(
+        credit_line.try_set_protection(&user, &0, &protector, &(2 * HEALTH_FACTOR_ONE), &0),
+        Err(Ok(Error::InvalidParameter))
+    );
+
+    // Nothing to do while the position is above its trigger
+    let trigger = 12 * HEALTH_FACTOR_ONE / 10;
+    credit_line.set_protection(&user, &0, &protector, &trigger, &(100 * TOKEN));
+    assert_eq!(
+        credit_line.get_protection(&user, &0),
+        Protection {
+            protector: protector.clone(),
+            trigger_health_factor: trigger,
+            max_repay: 100 * TOKEN,
+        }
+    );
+    assert_eq!(
+        credit_line.try_protect(&user, &0),
+        Err(Ok(Error::PositionHealthy))
+    );
+
+    // Below it, anyone may have the protector pay down just enough debt
+    let trigger = 15 * HEALTH_FACTOR_ONE / 10;
+    credit_line.set_protection(&user, &0, &protector, &trigger, &(100 * TOKEN));
+    let repaid = credit_line.protect(&user, &0);
+    assert!(repaid > 66 * TOKEN && repaid < 67 * TOKEN);
+    assert_eq!(
+        credit_line.get_position(&user, &0).borrowed,
+        600 * TOKEN - repaid
+    );
+    assert_eq!(fixture.usdc.balance(&protector), 1_000 * TOKEN - repaid);
+    assert!(credit_line.get_health_factor(&user, &0) >= trigger);
+    assert_eq!(
+        credit_line.try_protect(&user, &0),
+        Err(Ok(Error::PositionHealthy))
+    );
+    assert!(credit_line.check_solvency().gap >= 0);
+}
+
+#[test]
+fn protector_repays_at_most_max_repay() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let user = borrower(&fixture);
+    let protector = protector(&fixture, true);
+
+    // Reaching 2.0 would take 200 USDC, but one call draws only 100
+    credit_line.set_protection(
+        &user,
+        &0,
+        &protector,
+        &(2 * HEALTH_FACTOR_ONE),
+        &(100 * TOKEN),
+    );
+    assert_eq!(credit_line.protect(&user, &0), 100 * TOKEN);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 500 * TOKEN);
+    assert!(credit_line.get_health_factor(&user, &0) < 2 * HEALTH_FACTOR_ONE);
+}
+
+#[test]
+fn protect_fails_unless_the_protector_pays() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let user = borrower(&fixture);
+    let protector = protector(&fixture, false);
+
+    credit_line.set_protection(
+        &user,
+        &0,
+        &protector,
+        &(2 * HEALTH_FACTOR_ONE),
+        &(100 * TOKEN),
+    );
+    assert_eq!(
+        credit_line.try_protect(&user, &0),
+        Err(Ok(Error::ProtectorNotPaid))
+    );
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 600 * TOKEN);
+
+    // Once removed, the protection is gone for good
+    credit_line.remove_protection(&user, &0);
+    assert_eq!(
+        credit_line.try_get_protection(&user, &0),
+        Err(Ok(Error::NoProtection))
+    );
+    assert_eq!(
+        credit_line.try_protect(&user, &0),
+        Err(Ok(Error::NoProtection))
+    );
+    assert_eq!(
+        credit_line.try_remove_protection(&user, &0),
+        Err(Ok(Error::NoProtection))
+    );
+}
@@ -0,0 +1,85 @@
+use credit_line::fixed_loan::LoanTerms;
+use credit_line::RateMode;
+use integration_tests::{Fixture, PRICE_ONE, TOKEN, YEAR};
+
+/// Ledgers between installments of a loan longer than a month
+const INTERVAL: u32 = 30 * 17_280;
+
+#[test]
+fn stable_debt_keeps_its_rate_while_utilization_moves() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    let whale = fixture.fund(100_000 * TOKEN, 0);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    let locked = credit_line.get_market_summary().stable_borrow_rate;
+    assert_eq!(credit_line.swap_rate_mode(&user, &0), RateMode::Stable);
+    let position = credit_line.get_position(&user, &0);
+    assert_eq!(position.stable_borrowed, 500 * TOKEN);
+    assert_eq!(position.stable_rate, locked);
+
+    // Heavy borrowing lifts the variable rate past the locked one
+    credit_line.deposit_collateral(&whale, &0, benji, &(100_000 * TOKEN), &None, &None);
+    credit_line.borrow(&whale, &0, &(50_000 * TOKEN), &None, &None);
+    assert!(credit_line.get_market_summary().borrow_rate > locked);
+
+    // A year of simple interest at the locked rate
+    fixture.advance(YEAR);
+    fixture.set_benji_price(PRICE_ONE);
+    let debt = credit_line.get_current_debt(&user, &0);
+    assert!((debt - (500 * TOKEN + 500 * TOKEN * locked as i128 / 10_000)).abs() <= 1);
+
+    // Swapping back moves all of it to the variable rate
+    assert_eq!(credit_line.swap_rate_mode(&user, &0), RateMode::Variable);
+    let position = credit_line.get_position(&user, &0);
+    assert_eq!(position.borrowed, debt);
+    assert_eq!(position.stable_borrowed, 0);
+    assert_eq!(position.stable_rate, 0);
+    assert_eq!(
+        credit_line.get_net_rate(&user, &0),
+        credit_line.get_market_summary().borrow_rate as i128
+    );
+}
+
+#[test]
+fn fixed_loan_leaves_variable_debt_variable() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let user = fixture.fund(10_000 * TOKEN, 100 * TOKEN);
+
+    credit_line.deposit_collateral(&user, &0, benji, &(10_000 * TOKEN), &None, &None);
+    credit_line.borrow(&user, &0, &(100 * TOKEN), &None, &None);
+    let variable_rate = credit_line.get_market_summary().borrow_rate;
+    let stable_rate = credit_line.get_market_summary().stable_borrow_rate;
+
+    // Only the loan takes the stable rate
+    credit_line.borrow_fixed(&user, &0, &(50 * TOKEN), &(2 * INTERVAL));
+    let position = credit_line.get_position(&user, &0);
+    assert_eq!(position.borrowed, 150 * TOKEN);
+    assert_eq!(position.stable_borrowed, 50 * TOKEN);
+    assert_eq!(position.rate_mode, RateMode::Variable);
+    assert!(position.stable_rate >= stable_rate);
+    assert!(position.stable_rate > variable_rate);
+
+    // Installments are worked out on the loan alone and paid off it first
+    fixture.advance_ledgers(INTERVAL);
+    fixture.set_benji_price(PRICE_ONE);
+    let (amount, _) = credit_line.get_installment_due(&user, &0);
+    assert!(amount > 25 * TOKEN && amount < 26 * TOKEN);
+    credit_line.repay_installment(&user, &0, &None);
+    let position = credit_line.get_position(&user, &0);
+    assert!(position.stable_borrowed <= 25 * TOKEN);
+    assert!(position.borrowed - position.stable_borrowed > 100 * TOKEN);
+
+    // Paying off the loan leaves the variable debt and reopens the line
+    credit_line.repay(&user, &0, &position.stable_borrowed, &None);
+    let position = credit_line.get_position(&user, &0);
+    assert_eq!(position.terms, LoanTerms::Open);
+    assert_eq!(position.stable_borrowed, 0);
+    assert!(position.borrowed > 100 * TOKEN && position.borrowed < 101 * TOKEN);
+    credit_line.borrow(&user, &0, &TOKEN, &None, &None);
+}
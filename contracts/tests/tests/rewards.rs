@@ -0,0 +1,66 @@
+use btoken::{BToken, BTokenClient};
+use credit_line::rewards::{RewardPool, MAX_EMISSION_RATE};
+use credit_line::{CreditLineContract, CreditLineContractClient, Error};
+use integration_tests::{Fixture, DAY, TOKEN};
+use soroban_sdk::{testutils::Address as _, Address, String};
+
+#[test]
+fn supply_rewards_follow_btokens_that_change_hands() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let admin = &fixture.admin;
+
+    // A market issuing bTokens, whose supply rewards are paid in BENJI
+    let market = CreditLineContractClient::new(env, &env.register(CreditLineContract, ()));
+    market.initialize(admin, &fixture.benji.address, &fixture.usdc.address);
+    let btoken = BTokenClient::new(env, &env.register(BToken, ()));
+    btoken.initialize(
+        &market.address,
+        &7,
+        &String::from_str(env, "BondBridge USDC"),
+        &String::from_str(env, "bUSDC"),
+    );
+    market.set_btoken(admin, &btoken.address);
+    market.set_reward_token(admin, &fixture.benji.address);
+    market.set_emission_rate(admin, &RewardPool::Supply, &1_000);
+    fixture.mint_benji(&market.address, 1_000_000 * TOKEN);
+
+    let alice = fixture.fund(0, 1_000 * TOKEN);
+    let bob = Address::generate(env);
+    let carol = fixture.fund(0, 1_000 * TOKEN);
+    market.supply(&alice, &(1_000 * TOKEN));
+    market.supply(&carol, &(1_000 * TOKEN));
+
+    // Alice hands her shares to Bob, who redeems them
+    btoken.transfer(&alice, &bob, &(1_000 * TOKEN));
+    market.withdraw_supply(&bob, &(1_000 * TOKEN));
+    assert_eq!(fixture.usdc.balance(&bob), 1_000 * TOKEN);
+
+    // Only Carol still holds shares, so she earns the whole emission
+    fixture.advance(DAY);
+    assert_eq!(market.get_claimable_rewards(&alice), 0);
+    assert_eq!(market.get_claimable_rewards(&bob), 0);
+    assert_eq!(market.get_claimable_rewards(&carol), 1_000 * DAY as i128);
+
+    // Burning shares stops their rewards too
+    btoken.burn(&carol, &(1_000 * TOKEN));
+    fixture.advance(DAY);
+    assert_eq!(market.get_claimable_rewards(&carol), 1_000 * DAY as i128);
+}
+
+#[test]
+fn emission_rates_are_bounded() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let admin = &fixture.admin;
+
+    assert_eq!(
+        credit_line.try_set_emission_rate(admin, &RewardPool::Supply, &-1),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(
+        credit_line.try_set_emission_rate(admin, &RewardPool::Supply, &(MAX_EMISSION_RATE + 1)),
+        Err(Ok(Error::InvalidParameter))
+    );
+    credit_line.set_emission_rate(admin, &RewardPool::Supply, &MAX_EMISSION_RATE);
+}
@@ -0,0 +1,153 @@
+use dex_adapter::DexAdapterClient;
+use integration_tests::{Fixture, TOKEN};
+use router::{Error, Router, RouterClient, PRICE_ONE};
+use soroban_sdk::{contract, contractimpl, token, vec, Address, Env, Vec};
+use soroswap_adapter::SoroswapAdapter;
+
+/// Health factor of 1.0, in 7-decimal fixed point
+const HEALTH_FACTOR_ONE: i128 = 10_000_000;
+
+/// Soroswap router whose single pool is itself, charging the 0.3% AMM fee on a 1:1 price
+#[contract]
+struct MockSoroswap;
+
+#[contractimpl]
+impl MockSoroswap {
+    pub fn router_pair_for(env: Env, _token_a: Address, _token_b: Address) -> Address {
+        env.current_contract_address()
+    }
+
+    pub fn router_get_amounts_out(env: Env, amount_in: i128, _path: Vec<Address>) -> Vec<i128> {
+        vec![&env, amount_in, amount_in * 997 / 1000]
+    }
+
+    pub fn swap_exact_tokens_for_tokens(
+        env: Env,
+        amount_in: i128,
+        amount_out_min: i128,
+        path: Vec<Address>,
+        to: Address,
+        _deadline: u64,
+    ) -> Vec<i128> {
+        to.require_auth();
+        let pool = env.current_contract_address();
+        let amounts = Self::router_get_amounts_out(env.clone(), amount_in, path.clone());
+        let out = amounts.get(1).unwrap();
+        assert!(out >= amount_out_min);
+
+        token::Client::new(&env, &path.get(0).unwrap()).transfer(&to, &pool, &amount_in);
+        token::Client::new(&env, &path.get(1).unwrap()).transfer(&pool, &to, &out);
+        amounts
+    }
+}
+
+fn deploy<'a>(fixture: &Fixture<'a>, adapter: &Address) -> RouterClient<'a> {
+    let env = &fixture.env;
+    let router = RouterClient::new(env, &env.register(Router, ()));
+    router.initialize(
+        &fixture.admin,
+        &fixture.credit_line.address,
+        &fixture.benji.address,
+        &fixture.usdc.address,
+        adapter,
+    );
+    router
+}
+
+#[test]
+fn leverage_then_deleverage() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let router = deploy(&fixture, &fixture.mock_dex(10_000));
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    let borrowed = router.leverage(&user, &0, &(1_000 * TOKEN), &3, &HEALTH_FACTOR_ONE);
+
+    // Every borrowed USDC came back as BENJI collateral at 1:1
+    let position = credit_line.get_position(&user, &0);
+    assert!(borrowed > 1_400 * TOKEN);
+    assert_eq!(position.borrowed, borrowed);
+    assert_eq!(
+        position.collateral.get(benji.clone()),
+        Some(1_000 * TOKEN + borrowed)
+    );
+    assert_eq!(fixture.benji.balance(&user), 0);
+    assert_eq!(fixture.usdc.balance(&user), 0);
+    assert!(credit_line.get_available_credit(&user, &0) > 0);
+
+    // A DEX paying 1:1 clears any minimum price up to 1.0
+    assert_eq!(
+        router.try_deleverage(&user, &0, &10, &-1),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert!(router
+        .try_deleverage(&user, &0, &10, &(PRICE_ONE + 1))
+        .is_err());
+    assert_eq!(router.deleverage(&user, &0, &10, &PRICE_ONE), 0);
+
+    // Selling at 1:1 leaves the initial collateral behind, less what was
+    // withdrawn past the debt and paid out as USDC
+    let position = credit_line.get_position(&user, &0);
+    let left = position.collateral.get(benji.clone()).unwrap();
+    assert_eq!(position.borrowed, 0);
+    assert_eq!(left + fixture.usdc.balance(&user), 1_000 * TOKEN);
+}
+
+#[test]
+fn leverage_fails_below_the_minimum_health_factor() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    // A venue keeping half of every swap
+    let router = deploy(&fixture, &fixture.mock_dex(5_000));
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    assert_eq!(
+        router.try_leverage(
+            &user,
+            &0,
+            &(1_000 * TOKEN),
+            &3,
+            &(12 * HEALTH_FACTOR_ONE / 10)
+        ),
+        Err(Ok(Error::HealthFactorTooLow))
+    );
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 0);
+    assert_eq!(fixture.benji.balance(&user), 1_000 * TOKEN);
+
+    assert_eq!(
+        router.try_leverage(&user, &0, &(1_000 * TOKEN), &11, &0),
+        Err(Ok(Error::InvalidParameter))
+    );
+}
+
+#[test]
+fn leverage_through_soroswap() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let usdc = &fixture.usdc.address;
+
+    let soroswap = fixture.env.register(MockSoroswap, ());
+    fixture.mint_benji(&soroswap, 10_000 * TOKEN);
+    fixture.mint_usdc(&soroswap, 10_000 * TOKEN);
+    let adapter = fixture.env.register(SoroswapAdapter, (soroswap.clone(),));
+    let router = deploy(&fixture, &adapter);
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    let quote = DexAdapterClient::new(&fixture.env, &adapter).quote(usdc, benji, &(100 * TOKEN));
+    assert_eq!(quote, 997 * TOKEN / 10);
+
+    let borrowed = router.leverage(&user, &0, &(1_000 * TOKEN), &1, &HEALTH_FACTOR_ONE);
+    assert_eq!(borrowed, 665 * TOKEN);
+    assert_eq!(
+        credit_line
+            .get_position(&user, &0)
+            .collateral
+            .get(benji.clone()),
+        Some(1_000 * TOKEN + borrowed * 997 / 1000)
+    );
+    // Nothing is left behind in the adapter
+    assert_eq!(fixture.usdc.balance(&adapter), 0);
+    assert_eq!(fixture.benji.balance(&adapter), 0);
+}
@@ -0,0 +1,80 @@
+use credit_line::rewards::RewardPool;
+use integration_tests::{Fixture, PRICE_ONE, TOKEN, YEAR};
+use savings_vault::{Error, SavingsVault, SavingsVaultClient};
+use soroban_sdk::{testutils::Address as _, token::TokenClient, Address};
+
+#[test]
+fn deposits_earn_interest_and_compounded_rewards() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+
+    // Supply rewards paid in a token of their own, which the DEX buys at 1:1
+    let reward_admin = fixture.add_token("Reward", "RWD");
+    let reward = reward_admin.address.clone();
+    reward_admin.mint(&credit_line.address, &(10_000 * TOKEN));
+    credit_line.set_reward_token(&fixture.admin, &reward);
+    credit_line.set_emission_rate(&fixture.admin, &RewardPool::Supply, &1_000);
+
+    let vault = SavingsVaultClient::new(env, &env.register(SavingsVault, ()));
+    vault.initialize(
+        &fixture.admin,
+        &credit_line.address,
+        &fixture.usdc.address,
+        &reward,
+        &fixture.mock_dex(10_000),
+    );
+
+    let lender = fixture.fund(0, 1_000 * TOKEN);
+    let shares = vault.deposit(&lender, &(1_000 * TOKEN));
+    assert_eq!(shares, 1_000 * TOKEN);
+    assert_eq!(vault.total_assets(), 1_000 * TOKEN);
+    assert_eq!(
+        credit_line.get_supply_balance(&vault.address),
+        1_000 * TOKEN
+    );
+
+    // A year of borrower interest raises what the shares are worth
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(
+        &user,
+        &0,
+        &fixture.benji.address,
+        &(1_000 * TOKEN),
+        &None,
+        &None,
+    );
+    credit_line.borrow(&user, &0, &(500 * TOKEN), &None, &None);
+    fixture.advance(YEAR);
+    fixture.set_benji_price(PRICE_ONE);
+    let debt = credit_line.get_current_debt(&user, &0);
+    fixture.mint_usdc(&user, debt - 500 * TOKEN);
+    credit_line.repay(&user, &0, &debt, &None);
+
+    let with_interest = vault.convert_to_assets(&shares);
+    assert!(with_interest > 1_000 * TOKEN);
+
+    // Only the admin or its keeper harvests, since the caller picks the
+    // least the swap may pay
+    let keeper = Address::generate(env);
+    assert_eq!(vault.try_harvest(&keeper, &0), Err(Ok(Error::Unauthorized)));
+    vault.set_keeper(&fixture.admin, &Some(keeper.clone()));
+    assert_eq!(vault.get_keeper(), Some(keeper.clone()));
+
+    // Harvesting swaps the vault's rewards to USDC and supplies it
+    let compounded = vault.harvest(&keeper, &0);
+    assert!(compounded > 0);
+    assert_eq!(TokenClient::new(env, &reward).balance(&vault.address), 0);
+    assert!(vault.convert_to_assets(&shares) >= with_interest + compounded - 1);
+
+    assert_eq!(
+        vault.try_withdraw(&lender, &(2_000 * TOKEN)),
+        Err(Ok(Error::InsufficientShares))
+    );
+
+    let redeemed = vault.redeem(&lender, &shares);
+    assert_eq!(fixture.usdc.balance(&lender), redeemed);
+    assert!(redeemed > with_interest);
+    assert_eq!(vault.balance(&lender), 0);
+    assert_eq!(vault.total_shares(), 0);
+}
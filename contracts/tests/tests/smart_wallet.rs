@@ -0,0 +1,241 @@
+use credit_line::Error;
+use integration_tests::{invocation, wallet_auth, Fixture, PolicyWallet, PRICE_ONE, TOKEN};
+use soroban_sdk::{Address, IntoVal};
+
+/// Seconds in a 30-day month
+const MONTH: u64 = 30 * 24 * 60 * 60;
+
+#[test]
+fn policy_wallet_signs_deposits_within_its_limit() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let wallet = env.register(PolicyWallet, (1_000 * TOKEN,));
+    fixture.mint_benji(&wallet, 2_000 * TOKEN);
+
+    // The wallet sees the BENJI transfer under the deposit and refuses it
+    let deposit = |amount: i128| {
+        wallet_auth(
+            env,
+            &wallet,
+            invocation(
+                env,
+                &credit_line.address,
+                "deposit_collateral",
+                (
+                    wallet.clone(),
+                    0u32,
+                    benji.clone(),
+                    amount,
+                    None::<Address>,
+                    None::<Address>,
+                )
+                    .into_val(env),
+                vec![invocation(
+                    env,
+                    benji,
+                    "transfer",
+                    (wallet.clone(), credit_line.address.clone(), amount).into_val(env),
+                    vec![],
+                )],
+            ),
+        )
+    };
+    assert!(credit_line
+        .set_auths(&[deposit(1_500 * TOKEN)])
+        .try_deposit_collateral(&wallet, &0, benji, &(1_500 * TOKEN), &None, &None)
+        .is_err());
+
+    credit_line
+        .set_auths(&[deposit(1_000 * TOKEN)])
+        .deposit_collateral(&wallet, &0, benji, &(1_000 * TOKEN), &None, &None);
+    assert_eq!(
+        credit_line
+            .get_position(&wallet, &0)
+            .collateral
+            .get(benji.clone()),
+        Some(1_000 * TOKEN)
+    );
+
+    let borrow = wallet_auth(
+        env,
+        &wallet,
+        invocation(
+            env,
+            &credit_line.address,
+            "borrow",
+            (
+                wallet.clone(),
+                0u32,
+                400 * TOKEN,
+                None::<Address>,
+                None::<Address>,
+            )
+                .into_val(env),
+            vec![],
+        ),
+    );
+    credit_line
+        .set_auths(&[borrow])
+        .borrow(&wallet, &0, &(400 * TOKEN), &None, &None);
+    assert_eq!(fixture.usdc.balance(&wallet), 400 * TOKEN);
+}
+
+#[test]
+fn closing_transfers_the_signed_maximum_and_refunds_the_rest() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let usdc = &fixture.usdc.address;
+    let wallet = env.register(PolicyWallet, (1_000 * TOKEN,));
+    fixture.mint_benji(&wallet, 1_000 * TOKEN);
+    fixture.mint_usdc(&wallet, 100 * TOKEN);
+
+    credit_line.deposit_collateral(&wallet, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&wallet, &0, &(400 * TOKEN), &None, &None);
+    fixture.advance(MONTH);
+    fixture.set_benji_price(PRICE_ONE);
+
+    // Signed against the debt now, with room for interest until it executes
+    let debt = credit_line.get_current_debt(&wallet, &0);
+    let max_repay = debt + 10 * TOKEN;
+    assert_eq!(
+        credit_line.try_close_position(&wallet, &0, &(debt - 1)),
+        Err(Ok(Error::MaxAmountExceeded))
+    );
+    let close = wallet_auth(
+        env,
+        &wallet,
+        invocation(
+            env,
+            &credit_line.address,
+            "close_position",
+            (wallet.clone(), 0u32, max_repay).into_val(env),
+            vec![invocation(
+                env,
+                usdc,
+                "transfer",
+                (wallet.clone(), credit_line.address.clone(), max_repay).into_val(env),
+                vec![],
+            )],
+        ),
+    );
+
+    fixture.advance(60 * 60);
+    fixture.set_benji_price(PRICE_ONE);
+    let repaid = credit_line
+        .set_auths(&[close])
+        .close_position(&wallet, &0, &max_repay);
+    assert!(repaid > debt);
+    assert_eq!(fixture.usdc.balance(&wallet), 500 * TOKEN - repaid);
+    assert_eq!(fixture.benji.balance(&wallet), 1_000 * TOKEN);
+    assert_eq!(credit_line.get_position(&wallet, &0).borrowed, 0);
+    assert!(credit_line.check_solvency().gap >= 0);
+}
+
+#[test]
+fn repaying_from_an_allowance_signs_no_transfer() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let wallet = env.register(PolicyWallet, (1_000 * TOKEN,));
+    fixture.mint_benji(&wallet, 1_000 * TOKEN);
+
+    credit_line.deposit_collateral(&wallet, &0, benji, &(1_000 * TOKEN), &None, &None);
+    credit_line.borrow(&wallet, &0, &(400 * TOKEN), &None, &None);
+    let expiration = env.ledger().sequence() + 100;
+    fixture
+        .usdc
+        .approve(&wallet, &credit_line.address, &(150 * TOKEN), &expiration);
+
+    // The wallet signs the repayment alone, with nothing nested under it
+    let repay = wallet_auth(
+        env,
+        &wallet,
+        invocation(
+            env,
+            &credit_line.address,
+            "repay_from",
+            (wallet.clone(), 0u32, 100 * TOKEN, None::<Address>).into_val(env),
+            vec![],
+        ),
+    );
+    credit_line
+        .set_auths(&[repay])
+        .repay_from(&wallet, &0, &(100 * TOKEN), &None);
+    assert_eq!(credit_line.get_position(&wallet, &0).borrowed, 300 * TOKEN);
+    assert_eq!(fixture.usdc.balance(&wallet), 300 * TOKEN);
+    assert_eq!(
+        fixture.usdc.allowance(&wallet, &credit_line.address),
+        50 * TOKEN
+    );
+
+    // Beyond the allowance nothing moves
+    assert_eq!(
+        credit_line.try_repay_from(&wallet, &0, &(100 * TOKEN), &None),
+        Err(Ok(Error::InsufficientAllowance))
+    );
+}
+
+#[test]
+fn liquidators_pay_at_most_what_they_sign() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let credit_line = &fixture.credit_line;
+    let benji = &fixture.benji.address;
+    let usdc = &fixture.usdc.address;
+    let user = fixture.fund(10_000 * TOKEN, 0);
+    let wallet = env.register(PolicyWallet, (100 * TOKEN,));
+    fixture.mint_usdc(&wallet, 100 * TOKEN);
+
+    // An overdue fixed loan small enough to be closed out in one call
+    credit_line.deposit_collateral(&user, &0, benji, &(10_000 * TOKEN), &None, &None);
+    credit_line.borrow_fixed(&user, &0, &(50 * TOKEN), &(10 * 17_280));
+    fixture.advance_ledgers(10 * 17_280 + 1);
+    fixture.set_benji_price(PRICE_ONE);
+    credit_line.set_position_minimums(&fixture.admin, &(30 * TOKEN), &0);
+    let debt = credit_line.get_current_debt(&user, &0);
+
+    let liquidate = |amount: i128| {
+        wallet_auth(
+            env,
+            &wallet,
+            invocation(
+                env,
+                &credit_line.address,
+                "liquidate",
+                (wallet.clone(), user.clone(), 0u32, benji.clone(), amount).into_val(env),
+                vec![invocation(
+                    env,
+                    usdc,
+                    "transfer",
+                    (wallet.clone(), credit_line.address.clone(), amount).into_val(env),
+                    vec![],
+                )],
+            ),
+        )
+    };
+
+    // Repaying half would leave dust, and is refused rather than enlarged
+    assert_eq!(
+        credit_line.set_auths(&[liquidate(debt / 2)]).try_liquidate(
+            &wallet,
+            &user,
+            &0,
+            benji,
+            &(debt / 2)
+        ),
+        Err(Ok(Error::BelowMinimum))
+    );
+
+    // Signing for more than the debt pays the debt and refunds the rest
+    let max_repay = debt + 10 * TOKEN;
+    credit_line
+        .set_auths(&[liquidate(max_repay)])
+        .liquidate(&wallet, &user, &0, benji, &max_repay);
+    assert_eq!(fixture.usdc.balance(&wallet), 100 * TOKEN - debt);
+    assert_eq!(credit_line.get_position(&user, &0).borrowed, 0);
+}
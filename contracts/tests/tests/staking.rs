@@ -0,0 +1,153 @@
+use integration_tests::{Fixture, DAY, TOKEN};
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::Address;
+use staking::{BoostParams, Error, Staking, StakingClient, MAX_LOCKS};
+
+/// Staking on the fixture's BENJI, with 1,000 BENJI locked for 100 days
+/// earning the full 5 point boost
+fn staking<'a>(fixture: &Fixture) -> StakingClient<'a> {
+    let env = &fixture.env;
+    let staking = StakingClient::new(env, &env.register(Staking, ()));
+    staking.initialize(
+        &fixture.admin,
+        &fixture.benji.address,
+        &BoostParams {
+            max_boost: 500,
+            full_boost_amount: 1_000 * TOKEN,
+            max_lock: 100 * DAY,
+        },
+    );
+    staking
+}
+
+#[test]
+fn locks_are_capped_per_user() {
+    let fixture = Fixture::new();
+    let staking = staking(&fixture);
+    let user = fixture.fund(1_000 * TOKEN, 0);
+
+    for day in 1..=MAX_LOCKS as u64 {
+        staking.lock(&user, &TOKEN, &(day * DAY));
+    }
+    assert_eq!(
+        staking.try_lock(&user, &TOKEN, &(50 * DAY)),
+        Err(Ok(Error::TooManyLocks))
+    );
+
+    // A lock ending with an existing one joins it
+    staking.lock(&user, &TOKEN, &DAY);
+    let locks = staking.get_locks(&user);
+    assert_eq!(locks.len(), MAX_LOCKS);
+    assert_eq!(locks.get(0).unwrap().amount, 2 * TOKEN);
+
+    // Unlocking expired locks frees their slots
+    fixture.advance(DAY);
+    assert_eq!(staking.unlock(&user), 2 * TOKEN);
+    staking.lock(&user, &(900 * TOKEN), &(100 * DAY));
+    assert_eq!(staking.get_locks(&user).len(), MAX_LOCKS);
+    assert!(staking.get_boost(&user) >= 450);
+}
+
+#[test]
+fn boost_decays_as_locks_near_expiry() {
+    let fixture = Fixture::new();
+    let staking = staking(&fixture);
+    let user = fixture.fund(2_000 * TOKEN, 0);
+    assert_eq!(staking.get_boost(&user), 0);
+    assert_eq!(
+        staking.try_lock(&user, &TOKEN, &(101 * DAY)),
+        Err(Ok(Error::InvalidParameter))
+    );
+
+    // Half the amount for the full term earns half the boost
+    let unlock_at = staking.lock(&user, &(500 * TOKEN), &(100 * DAY));
+    assert_eq!(unlock_at, fixture.env.ledger().timestamp() + 100 * DAY);
+    assert_eq!(staking.get_boost(&user), 250);
+    assert_eq!(fixture.benji.balance(&staking.address), 500 * TOKEN);
+
+    // Boost never passes the cap, however much is locked
+    staking.lock(&user, &(1_500 * TOKEN), &(100 * DAY));
+    assert_eq!(staking.get_boost(&user), 500);
+
+    fixture.advance(50 * DAY);
+    assert_eq!(staking.get_boost(&user), 500);
+    fixture.advance(40 * DAY);
+    assert_eq!(staking.get_boost(&user), 100);
+    assert_eq!(staking.try_unlock(&user), Err(Ok(Error::NothingToUnlock)));
+
+    fixture.advance(10 * DAY);
+    assert_eq!(staking.get_boost(&user), 0);
+    assert_eq!(staking.unlock(&user), 2_000 * TOKEN);
+    assert_eq!(fixture.benji.balance(&user), 2_000 * TOKEN);
+    assert!(staking.get_locks(&user).is_empty());
+}
+
+#[test]
+fn boost_raises_credit_line_ltv() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+    let staking = staking(&fixture);
+    let user = fixture.fund(2_000 * TOKEN, 0);
+    credit_line.deposit_collateral(
+        &user,
+        &0,
+        &fixture.benji.address,
+        &(1_000 * TOKEN),
+        &None,
+        &None,
+    );
+    let unboosted = credit_line.get_max_borrowable(&user, &0);
+
+    staking.lock(&user, &(1_000 * TOKEN), &(100 * DAY));
+    assert_eq!(credit_line.get_max_borrowable(&user, &0), unboosted);
+    credit_line.set_staking(&fixture.admin, &Some(staking.address.clone()));
+    let ltv = credit_line
+        .get_collateral_config(&fixture.benji.address)
+        .ltv_ratio as i128;
+    assert_eq!(
+        credit_line.get_max_borrowable(&user, &0),
+        unboosted * (ltv + 500) / ltv
+    );
+}
+
+#[test]
+fn only_the_admin_sets_sound_params() {
+    let fixture = Fixture::new();
+    let staking = staking(&fixture);
+    let params = BoostParams {
+        max_boost: 200,
+        full_boost_amount: 100 * TOKEN,
+        max_lock: 10 * DAY,
+    };
+
+    assert_eq!(
+        staking.try_set_params(&Address::generate(&fixture.env), &params),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        staking.try_set_params(
+            &fixture.admin,
+            &BoostParams {
+                max_boost: 10_001,
+                ..params.clone()
+            }
+        ),
+        Err(Ok(Error::InvalidParameter))
+    );
+    assert_eq!(
+        staking.try_set_params(
+            &fixture.admin,
+            &BoostParams {
+                max_lock: 0,
+                ..params.clone()
+            }
+        ),
+        Err(Ok(Error::InvalidParameter))
+    );
+    staking.set_params(&fixture.admin, &params);
+    assert_eq!(staking.get_params(), params);
+    assert_eq!(
+        staking.try_initialize(&fixture.admin, &fixture.benji.address, &params),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+}
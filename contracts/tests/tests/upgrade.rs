@@ -0,0 +1,145 @@
+use credit_line::{CreditLineContract, CreditLineContractClient, Error};
+use integration_tests::{Fixture, PRICE_ONE, TOKEN};
+use soroban_sdk::{contracttype, vec, Address, BytesN};
+
+/// Storage keys as the first release of the credit line wrote them
+#[contracttype]
+enum BaselineKey {
+    Admin,
+    BenjiToken,
+    UsdcToken,
+    UserPosition(Address),
+    LtvRatio,
+}
+
+/// A position as the first release stored it
+#[contracttype]
+struct BaselinePosition {
+    collateral: i128,
+    borrowed: i128,
+    last_update: u64,
+}
+
+#[test]
+fn only_admin_can_upgrade() {
+    let fixture = Fixture::new();
+    let outsider = fixture.fund(0, 0);
+    let wasm_hash = BytesN::from_array(&fixture.env, &[1; 32]);
+
+    assert!(fixture
+        .credit_line
+        .try_upgrade(&outsider, &wasm_hash)
+        .is_err());
+}
+
+#[test]
+fn upgrade_to_unknown_code_is_rejected() {
+    let fixture = Fixture::new();
+    let wasm_hash = BytesN::from_array(&fixture.env, &[1; 32]);
+
+    // No code has been uploaded under this hash
+    assert!(fixture
+        .credit_line
+        .try_upgrade(&fixture.admin, &wasm_hash)
+        .is_err());
+    assert_eq!(fixture.credit_line.version(), 3);
+}
+
+#[test]
+fn migrate_keeps_positions_and_is_idempotent() {
+    let fixture = Fixture::new();
+    let credit_line = &fixture.credit_line;
+
+    let user = fixture.fund(1_000 * TOKEN, 0);
+    credit_line.deposit_collateral(
+        &user,
+        &0,
+        &fixture.benji.address,
+        &(1_000 * TOKEN),
+        &None,
+        &None,
+    );
+    credit_line.borrow(&user, &0, &(300 * TOKEN), &None, &None);
+    let position = credit_line.get_position(&user, &0);
+
+    assert_eq!(credit_line.migrate(&fixture.admin), 3);
+    assert_eq!(credit_line.migrate(&fixture.admin), 3);
+
+    assert_eq!(credit_line.get_position(&user, &0), position);
+    assert!(credit_line.try_migrate(&user).is_err());
+}
+
+#[test]
+fn migrate_brings_the_first_release_up_to_date() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let admin = &fixture.admin;
+    let user = fixture.fund(0, 0);
+
+    // A market left by the first release: 1,000 BENJI held against 300 USDC lent
+    let credit_line = CreditLineContractClient::new(env, &env.register(CreditLineContract, ()));
+    fixture.mint_benji(&credit_line.address, 1_000 * TOKEN);
+    fixture.mint_usdc(&credit_line.address, 700 * TOKEN);
+    env.as_contract(&credit_line.address, || {
+        let storage = env.storage();
+        storage.instance().set(&BaselineKey::Admin, admin);
+        storage
+            .instance()
+            .set(&BaselineKey::BenjiToken, &fixture.benji.address);
+        storage
+            .instance()
+            .set(&BaselineKey::UsdcToken, &fixture.usdc.address);
+        storage.instance().set(&BaselineKey::LtvRatio, &7000_u32);
+        storage.persistent().set(
+            &BaselineKey::UserPosition(user.clone()),
+            &BaselinePosition {
+                collateral: 1_000 * TOKEN,
+                borrowed: 300 * TOKEN,
+                last_update: env.ledger().timestamp(),
+            },
+        );
+    });
+    assert_eq!(credit_line.version(), 0);
+
+    // Positions move only once the config has
+    assert_eq!(
+        credit_line.try_migrate_positions(admin, &vec![env, user.clone()]),
+        Err(Ok(Error::NotInitialized))
+    );
+    assert_eq!(credit_line.migrate(admin), 3);
+    credit_line.set_oracle(admin, &fixture.oracle.address);
+    assert_eq!(
+        credit_line.migrate_positions(admin, &vec![env, user.clone(), fixture.lender.clone()]),
+        1
+    );
+    assert_eq!(
+        credit_line.migrate_positions(admin, &vec![env, user.clone()]),
+        0
+    );
+
+    let position = credit_line.get_position(&user, &0);
+    assert_eq!(
+        position.collateral.get(fixture.benji.address.clone()),
+        Some(1_000 * TOKEN)
+    );
+    assert_eq!(position.borrowed, 300 * TOKEN);
+    assert_eq!(credit_line.get_accounts(&user), vec![env, 0]);
+    assert_eq!(credit_line.list_users(&0, &10), vec![env, user.clone()]);
+
+    let summary = credit_line.get_market_summary();
+    assert_eq!(summary.total_borrowed, 300 * TOKEN);
+    assert_eq!(summary.total_supplied, 1_000 * TOKEN);
+    assert_eq!(
+        summary.total_collateral.get(fixture.benji.address.clone()),
+        Some(1_000 * TOKEN)
+    );
+    assert_eq!(credit_line.check_solvency().gap, 0);
+
+    // The migrated position works like any other
+    fixture.set_benji_price(PRICE_ONE);
+    credit_line.borrow(&user, &0, &(100 * TOKEN), &None, &None);
+    fixture.mint_usdc(&user, 300 * TOKEN);
+    credit_line.repay(&user, &0, &(400 * TOKEN), &None);
+    credit_line.withdraw_collateral(&user, &0, &fixture.benji.address, &(1_000 * TOKEN), &None);
+    assert_eq!(fixture.benji.balance(&user), 1_000 * TOKEN);
+}